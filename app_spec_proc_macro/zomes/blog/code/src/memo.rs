@@ -117,6 +117,7 @@ mod tests {
         let validation_data = ValidationData {
             package: ValidationPackage::only_header(test_chain_header()),
             lifecycle: EntryLifecycle::Chain,
+            chain_forks: Vec::new(),
         };
         assert_eq!(
             (memo_definition.validator)(EntryValidationData::Create {
@@ -175,6 +175,7 @@ mod tests {
         let validation_data = ValidationData {
             package: ValidationPackage::only_header(test_chain_header()),
             lifecycle: EntryLifecycle::Chain,
+            chain_forks: Vec::new(),
         };
         assert_eq!(
             (post_definition.validator)(EntryValidationData::Create {
@@ -196,6 +197,7 @@ mod tests {
         let validation_data = ValidationData {
             package: ValidationPackage::only_header(test_chain_header()),
             lifecycle: EntryLifecycle::Chain,
+            chain_forks: Vec::new(),
         };
         assert_eq!(
             (post_definition.validator)(EntryValidationData::Create {
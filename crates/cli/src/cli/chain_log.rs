@@ -1,94 +1,332 @@
-use crate::error::DefaultResult;
-use colored::*;
+use crate::{error::DefaultResult, ChainFormat};
+use holochain_conductor_lib::{
+    config::{default_persistence_dir, StorageConfiguration},
+    storage::InstanceStorage,
+};
 use holochain_core::{
     agent::{
-        chain_store::ChainStore,
-        state::{AgentState, AgentStateSnapshot},
+        chain_store::{ChainStore, ChainStoreQueryOptions, ChainStoreQueryResult},
+        chain_verifier::verify_chain,
+        state::{AgentStateSnapshot, AGENT_SNAPSHOT_ADDRESS},
     },
     content_store::GetContent,
+    persister::migrate_snapshot,
+};
+use holochain_core_types::{chain_header::ChainHeader, entry::Entry, time::Iso8601};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
 };
-use holochain_core_types::{chain_header::ChainHeader, entry::Entry};
-use holochain_locksmith::RwLock;
-use holochain_persistence_api::cas::content::Address;
-use holochain_persistence_file::cas::file::FilesystemStorage;
-use std::{fs, path::PathBuf};
 
-// TODO: use system-agnostic default path
-const DEFAULT_CHAIN_PATH: &str = "TODO";
+/// Which headers of a chain `hc chain` shows
+pub struct ChainFilter {
+    /// Names of the entry types to show, which can be globs. All types if empty
+    pub entry_types: Vec<String>,
+    /// Only headers with this timestamp or a later one
+    pub since: Option<Iso8601>,
+    /// Only headers with this timestamp or an earlier one
+    pub until: Option<Iso8601>,
+    /// How many of the matching headers to skip, counting from the latest,
+    /// and how many to show after that (all for 0)
+    pub range: ChainStoreQueryOptions,
+}
 
+/// A chain header together with its entry, as printed by `hc chain`
+#[derive(Debug, Serialize)]
+pub struct ChainRecord {
+    /// Position of the header in the chain, the DNA entry being at 0
+    pub seq: usize,
+    pub address: Address,
+    pub header: ChainHeader,
+    /// None if the entry is missing from the CAS
+    pub entry: Option<Entry>,
+    /// Previous versions of the entry, found by following `link_update_delete`,
+    /// latest first. Only set when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<ChainRecord>>,
+}
+
+/// File storage of the given instance in the given directory, which defaults to the
+/// storage directory of a conductor with the default persistence_dir
+pub fn instance_file_storage(path: Option<PathBuf>, instance_id: &str) -> StorageConfiguration {
+    let path = path.unwrap_or_else(default_storage_dir).join(instance_id);
+    StorageConfiguration::File {
+        path: path.to_string_lossy().to_string(),
+    }
+}
+
+fn default_storage_dir() -> PathBuf {
+    default_persistence_dir().join("storage")
+}
+
+/// Prints the headers of a chain that match the filter, with their entries, as one JSON
+/// array or as one JSON object per line
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
-#[allow(clippy::toplevel_ref_arg)]
-pub fn chain_log(storage_path: Option<PathBuf>, instance_id: String) -> DefaultResult<()> {
-    let storage_path = storage_path.ok_or_else(|| {
-        format_err!("Please specify the path to CAS storage with the --path option.")
-    })?;
-    let cas_path = storage_path.join(instance_id).join("cas");
-    let chain_store = ChainStore::new(std::sync::Arc::new(RwLock::new(
-        FilesystemStorage::new(cas_path.clone()).expect("Could not create chain store"),
-    )));
-
-    let agent = chain_store
-        .get_raw(&Address::from("AgentState"))?
-        .ok_or("Chain does not exist or has not been initialized")
-        .and_then(|snapshot_json| {
-            AgentStateSnapshot::from_json_str(&snapshot_json.to_string())
-                .map_err(|_| "AgentState is malformed")
-        })
-        .map(|snapshot| {
-            let top_header = snapshot.top_chain_header().to_owned();
-            AgentState::new_with_top_chain_header(
-                chain_store.clone(),
-                top_header.cloned(),
-                Address::new(),
-            )
-        })
-        .map_err(|err| {
-            format_err!(
-                "Could not display chain for '{}': {}",
-                cas_path.to_string_lossy(),
-                err.to_string()
-            )
-        })?;
-
-    println!(
-        "\nChain entries for '{}' (latest on top):\n",
-        cas_path.to_string_lossy()
-    );
-    for ref header in agent.iter_chain() {
-        let entry = chain_store
-            .get(header.entry_address())
-            .expect("Panic while fetching from CAS!")
-            .ok_or_else(|| {
-                println!(
-                    "{:?} referenced in header but not found in CAS!",
-                    header.entry_address(),
-                )
-            })
-            .unwrap();
-        display_header(&header, &entry);
+pub fn chain_log(
+    storage: StorageConfiguration,
+    filter: ChainFilter,
+    format: ChainFormat,
+    history: bool,
+) -> DefaultResult<()> {
+    let chain_store = open_chain_store(&storage)?;
+    let top_header = top_chain_header(&chain_store)?;
+    let records = chain_records(&chain_store, &top_header, &filter, history)?;
+    match format {
+        ChainFormat::Json => println!("{}", serde_json::to_string_pretty(&records)?),
+        ChainFormat::Ndjson => {
+            for record in records {
+                println!("{}", serde_json::to_string(&record)?);
+            }
+        }
     }
+    Ok(())
+}
 
+/// Verifies the source chain in the given storage from the top header of its saved
+/// agent state and prints the report as JSON. Fails if the chain has any breaks,
+/// so scripts can tell from the exit code.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
+pub fn chain_verify(storage: StorageConfiguration) -> DefaultResult<()> {
+    let chain_store = open_chain_store(&storage)?;
+    let report = verify_chain(&chain_store, top_chain_header(&chain_store)?);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if !report.is_intact() {
+        bail!("Found {} breaks in the chain", report.breaks.len());
+    }
     Ok(())
 }
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
-pub fn chain_list(path: Option<PathBuf>) {
-    let path = path.unwrap_or_else(|| PathBuf::from(DEFAULT_CHAIN_PATH));
+pub fn chain_list(path: Option<PathBuf>) -> DefaultResult<()> {
+    let path = path.unwrap_or_else(default_storage_dir);
     println!("Please specify an instance ID to view its chain.");
     println!("Available instances for '{}':\n", path.to_string_lossy());
-    for entry in fs::read_dir(path).unwrap() {
-        let name = entry.unwrap().file_name();
-        println!("- {}", name.to_string_lossy());
+    for entry in fs::read_dir(path)? {
+        println!("- {}", entry?.file_name().to_string_lossy());
     }
+    Ok(())
 }
 
-#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
-fn display_header(header: &ChainHeader, entry: &Entry) {
-    println!(
-        "{} {}",
-        header.timestamp().to_string().bright_black(),
-        // format!("{:?}", header.entry_type()).blue().bold(),
-        header.entry_address().to_string().yellow(),
-    );
-    println!("{:#?}", entry);
+fn open_chain_store(storage: &StorageConfiguration) -> DefaultResult<ChainStore> {
+    let path = storage
+        .path()
+        .ok_or_else(|| format_err!("Memory storage can't be opened offline"))?;
+    if !Path::new(path).exists() {
+        bail!("Storage '{}' does not exist", path);
+    }
+    Ok(ChainStore::new(InstanceStorage::open(storage)?.cas))
+}
+
+/// The top header from the agent state the instance saved last
+fn top_chain_header(chain_store: &ChainStore) -> DefaultResult<Option<ChainHeader>> {
+    let snapshot = chain_store
+        .get_raw(&Address::from(AGENT_SNAPSHOT_ADDRESS))?
+        .ok_or_else(|| format_err!("Chain does not exist or has not been initialized"))?;
+    let snapshot = AgentStateSnapshot::from_json_str(&String::from(migrate_snapshot(&snapshot)?))?;
+    Ok(snapshot.top_chain_header().cloned())
+}
+
+fn chain_records(
+    chain_store: &ChainStore,
+    top_header: &Option<ChainHeader>,
+    filter: &ChainFilter,
+    history: bool,
+) -> DefaultResult<Vec<ChainRecord>> {
+    let chain: Vec<ChainHeader> = chain_store.iter(top_header).collect();
+    let seqs: HashMap<Address, usize> = chain
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(seq, header)| (header.address(), seq))
+        .collect();
+
+    let entry_types: Vec<&str> = filter.entry_types.iter().map(String::as_str).collect();
+    let options = ChainStoreQueryOptions {
+        headers: true,
+        ..Default::default()
+    };
+    let headers = match chain_store
+        .query(top_header, &entry_types, options)
+        .map_err(|code| format_err!("Invalid entry type filter: {:?}", code))?
+    {
+        ChainStoreQueryResult::Headers(headers) => headers,
+        ChainStoreQueryResult::Addresses(_) => unreachable!(),
+    };
+    let limit = match filter.range.limit {
+        0 => usize::max_value(),
+        limit => limit,
+    };
+
+    headers
+        .into_iter()
+        .filter(|header| {
+            filter.since.iter().all(|since| header.timestamp() >= since)
+                && filter.until.iter().all(|until| header.timestamp() <= until)
+        })
+        .skip(filter.range.start)
+        .take(limit)
+        .map(|header| {
+            let history = if history {
+                Some(entry_history(chain_store, &chain, &seqs, &header)?)
+            } else {
+                None
+            };
+            chain_record(chain_store, &seqs, header, history)
+        })
+        .collect()
+}
+
+/// Headers of the earlier versions of the header's entry, each one the latest header
+/// below the previous one whose entry is the one `link_update_delete` points to
+fn entry_history(
+    chain_store: &ChainStore,
+    chain: &[ChainHeader],
+    seqs: &HashMap<Address, usize>,
+    header: &ChainHeader,
+) -> DefaultResult<Vec<ChainRecord>> {
+    let mut history = Vec::new();
+    let mut current = header.clone();
+    while let Some(previous_entry) = current.link_update_delete() {
+        let seq = seqs[&current.address()];
+        let previous = chain.iter().find(|header| {
+            seqs[&header.address()] < seq && *header.entry_address() == previous_entry
+        });
+        match previous {
+            Some(previous) => {
+                history.push(chain_record(chain_store, seqs, previous.clone(), None)?);
+                current = previous.clone();
+            }
+            None => break,
+        }
+    }
+    Ok(history)
+}
+
+fn chain_record(
+    chain_store: &ChainStore,
+    seqs: &HashMap<Address, usize>,
+    header: ChainHeader,
+    history: Option<Vec<ChainRecord>>,
+) -> DefaultResult<ChainRecord> {
+    Ok(ChainRecord {
+        seq: seqs[&header.address()],
+        address: header.address(),
+        entry: chain_store.get(header.entry_address())?,
+        header,
+        history,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core::content_store::AddContent;
+    use holochain_core_types::{
+        chain_header::test_provenances,
+        entry::{test_entry, test_entry_b, test_entry_c},
+    };
+    use tempfile::tempdir;
+
+    /// test_entry(), test_entry_b() and test_entry_c() as an update of test_entry_b(),
+    /// one second apart
+    fn test_chain(chain_store: &mut ChainStore) -> Vec<ChainHeader> {
+        let mut headers: Vec<ChainHeader> = Vec::new();
+        for (i, entry) in vec![test_entry(), test_entry_b(), test_entry_c()]
+            .into_iter()
+            .enumerate()
+        {
+            let link_update_delete = if i == 2 {
+                Some(test_entry_b().address())
+            } else {
+                None
+            };
+            let header = ChainHeader::new(
+                &entry.entry_type(),
+                &entry.address(),
+                &test_provenances("sig"),
+                &headers.last().map(|header| header.address()),
+                &None,
+                &link_update_delete,
+                &Iso8601::from(i as i64 + 1),
+            );
+            chain_store.add(&entry).unwrap();
+            chain_store.add(&header).unwrap();
+            headers.push(header);
+        }
+        headers
+    }
+
+    fn filter() -> ChainFilter {
+        ChainFilter {
+            entry_types: Vec::new(),
+            since: None,
+            until: None,
+            range: ChainStoreQueryOptions::default(),
+        }
+    }
+
+    fn seqs(records: &[ChainRecord]) -> Vec<usize> {
+        records.iter().map(|record| record.seq).collect()
+    }
+
+    #[test]
+    fn filters_chain_records() {
+        let dir = tempdir().unwrap();
+        let storage = StorageConfiguration::File {
+            path: dir.path().to_string_lossy().to_string(),
+        };
+        let mut chain_store = open_chain_store(&storage).unwrap();
+        let headers = test_chain(&mut chain_store);
+        let top = headers.last().cloned();
+
+        let records = chain_records(&chain_store, &top, &filter(), false).unwrap();
+        assert_eq!(seqs(&records), vec![2, 1, 0]);
+        assert_eq!(records[0].entry, Some(test_entry_c()));
+        assert!(records[0].history.is_none());
+
+        let by_type = ChainFilter {
+            entry_types: vec!["*B".to_string()],
+            ..filter()
+        };
+        let records = chain_records(&chain_store, &top, &by_type, false).unwrap();
+        assert_eq!(seqs(&records), vec![2, 1]);
+
+        let by_time = ChainFilter {
+            since: Some(Iso8601::from(1)),
+            until: Some(Iso8601::from(2)),
+            ..filter()
+        };
+        let records = chain_records(&chain_store, &top, &by_time, false).unwrap();
+        assert_eq!(seqs(&records), vec![1, 0]);
+
+        let by_range = ChainFilter {
+            range: ChainStoreQueryOptions {
+                start: 1,
+                limit: 1,
+                headers: false,
+            },
+            ..filter()
+        };
+        let records = chain_records(&chain_store, &top, &by_range, false).unwrap();
+        assert_eq!(seqs(&records), vec![1]);
+    }
+
+    #[test]
+    fn follows_entry_history() {
+        let dir = tempdir().unwrap();
+        let storage = StorageConfiguration::File {
+            path: dir.path().to_string_lossy().to_string(),
+        };
+        let mut chain_store = open_chain_store(&storage).unwrap();
+        let headers = test_chain(&mut chain_store);
+
+        let records =
+            chain_records(&chain_store, &headers.last().cloned(), &filter(), true).unwrap();
+        let history = records[0].history.as_ref().unwrap();
+        assert_eq!(seqs(history), vec![1]);
+        assert_eq!(history[0].entry, Some(test_entry_b()));
+        assert_eq!(records[1].history.as_ref().map(Vec::len), Some(0));
+    }
 }
@@ -0,0 +1,42 @@
+use crate::error::DefaultResult;
+use holochain_conductor_lib::{config::StorageConfiguration, storage::dht_shard::DhtShard};
+use std::path::Path;
+
+/// Prints the held entries, holding workflows and holding map mismatches of the DHT shard
+/// in the given storage as JSON. Fails if there are mismatches, so scripts can tell from
+/// the exit code. With `repair` it rebuilds the holding map from the CAS instead and
+/// prints the mismatches that got fixed.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
+pub fn dht_shard(storage: StorageConfiguration, repair: bool) -> DefaultResult<()> {
+    let path = storage
+        .path()
+        .ok_or_else(|| format_err!("Memory storage can't be opened offline"))?;
+    if !Path::new(path).exists() {
+        bail!("Storage '{}' does not exist", path);
+    }
+    let mut shard = DhtShard::open(&storage)?;
+
+    if repair {
+        let fixed = shard.repair()?;
+        println!("{}", serde_json::to_string_pretty(&fixed)?);
+        if fixed.is_empty() {
+            println!("The holding map matches the CAS, nothing to repair.");
+        } else {
+            println!(
+                "Rebuilt the holding map, fixing {} entries. The instance announces it to the network the next time it starts.",
+                fixed.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let report = shard.report()?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if !report.is_consistent() {
+        bail!(
+            "Holding map does not match the CAS for {} entries, run with --repair to rebuild it",
+            report.mismatches.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use error::DefaultResult;
+use failure::err_msg;
+use holochain_conductor_lib::{
+    config::{
+        load_configuration, AgentConfiguration, Configuration, DnaConfiguration,
+        InstanceConfiguration, StorageConfiguration,
+    },
+    storage::archive::{export_instance, InstanceArchive},
+};
+use holochain_core_types::dna::Dna;
+use std::{convert::TryFrom, fs, path::PathBuf};
+
+/// Writes the data of an instance of the given conductor config, together with its DNA
+/// and agent, to a single archive. The instance must not be running.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
+pub fn instance_export(
+    config_path: PathBuf,
+    instance_id: String,
+    output: PathBuf,
+) -> DefaultResult<()> {
+    let config: Configuration = load_configuration(&fs::read_to_string(&config_path)?)?;
+    let instance = config
+        .instance_by_id(&instance_id)
+        .ok_or_else(|| err_msg(format!("No instance \"{}\" in config", instance_id)))?;
+    let dna_config = config
+        .dna_by_id(&instance.dna)
+        .ok_or_else(|| err_msg(format!("No DNA \"{}\" in config", instance.dna)))?;
+    let uuid = dna_config.uuid.clone();
+    let mut dna = Dna::try_from(dna_config)?;
+    if let Some(uuid) = uuid {
+        dna.uuid = uuid;
+    }
+    let agent = config
+        .agent_by_id(&instance.agent)
+        .ok_or_else(|| err_msg(format!("No agent \"{}\" in config", instance.agent)))?;
+
+    let manifest = export_instance(&instance_id, &dna, &agent, &instance.storage, &output)?;
+    println!(
+        "Exported {} CAS items and {} EAVIs of instance \"{}\" to {}",
+        manifest.cas_items,
+        manifest.eavis,
+        instance_id,
+        output.display()
+    );
+    Ok(())
+}
+
+/// The parts of a conductor config that an imported instance needs
+#[derive(Serialize)]
+struct ImportedConfig {
+    agents: Vec<AgentConfiguration>,
+    dnas: Vec<DnaConfiguration>,
+    instances: Vec<InstanceConfiguration>,
+}
+
+/// Restores the data of an archive into the given, empty storage and writes the DNA
+/// and keystore next to it. Prints the config that runs the restored instance.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
+pub fn instance_import(archive_path: PathBuf, storage: StorageConfiguration) -> DefaultResult<()> {
+    let archive = InstanceArchive::read(&archive_path)?;
+    let storage_dir = match &storage {
+        StorageConfiguration::File { path }
+        | StorageConfiguration::Pickle { path }
+        | StorageConfiguration::Lmdb { path, .. }
+        | StorageConfiguration::Sqlite { path } => PathBuf::from(path),
+        StorageConfiguration::Memory => {
+            return Err(err_msg("Can't import into memory storage"));
+        }
+    };
+    archive.restore_storage(&storage)?;
+
+    let dna_file = storage_dir.join("dna.json");
+    fs::write(&dna_file, serde_json::to_string_pretty(&archive.dna)?)?;
+    let mut agent = archive.agent.clone();
+    if let Some(ref keystore) = archive.keystore {
+        let keystore_file = storage_dir.join("keystore");
+        fs::write(&keystore_file, keystore)?;
+        agent.keystore_file = keystore_file.to_string_lossy().to_string();
+    }
+
+    let imported_config = ImportedConfig {
+        dnas: vec![DnaConfiguration {
+            id: archive.manifest.dna_hash.clone(),
+            file: dna_file.to_string_lossy().to_string(),
+            hash: archive.manifest.dna_hash.clone(),
+            uuid: None,
+        }],
+        instances: vec![InstanceConfiguration {
+            id: archive.manifest.instance_id.clone(),
+            dna: archive.manifest.dna_hash.clone(),
+            agent: agent.id.clone(),
+            storage,
+            wasm_limits: Default::default(),
+            wasm_engine: None,
+            journal: false,
+            storage_quota: None,
+            membrane_proof: None,
+        }],
+        agents: vec![agent],
+    };
+    println!(
+        "Restored {} CAS items and {} EAVIs of instance \"{}\".",
+        archive.manifest.cas_items, archive.manifest.eavis, archive.manifest.instance_id
+    );
+    println!(
+        "Add this to your conductor config to run it, unless it has the DNA or agent already:\n"
+    );
+    println!("{}", toml::to_string(&imported_config)?);
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::error::DefaultResult;
+use colored::*;
+use holochain_conductor_lib::{
+    context_builder::ContextBuilder,
+    interface::ConductorApiBuilder,
+    key_loaders::mock_passphrase_manager,
+    keystore::{Keystore, PRIMARY_KEYBUNDLE_ID},
+};
+use holochain_core::{
+    agent::state::AgentStateSnapshot,
+    context::Context,
+    dht::dht_store::DhtStoreSnapshot,
+    journal::{read_journal, JournalRecord},
+    nucleus::state::NucleusStateSnapshot,
+    state::{State, StateWrapper},
+};
+use holochain_core_types::agent::AgentId;
+use holochain_locksmith::Mutex;
+use serde_json::{self, Value};
+use std::{path::PathBuf, sync::Arc};
+
+/// Replays the actions of a journal one by one on an empty state in memory and prints
+/// how each of them changed the parts of the state that get persisted.
+/// The journal only holds what happened since the instance last saved its state, so
+/// committed headers that link to an earlier part of the source chain don't get put back.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
+pub fn journal_replay(journal_path: PathBuf, until: Option<u64>, all: bool) -> DefaultResult<()> {
+    let records = read_journal(&journal_path)?;
+    let mut state = State::new(replay_context()?);
+    let mut snapshot = state_snapshot(&state)?;
+    let mut replayed = 0;
+
+    for record in records
+        .iter()
+        .take_while(|record| until.map(|seq| record.seq() <= seq).unwrap_or(true))
+    {
+        match (record.replayable_action()?, record) {
+            (Some(action_wrapper), _) => {
+                println!(
+                    "{} {}",
+                    format!("#{}", record.seq()).bold(),
+                    record.action_type().unwrap_or_default().bold()
+                );
+                state = state.reduce(action_wrapper);
+                let new_snapshot = state_snapshot(&state)?;
+                let changes = json_diff("", &snapshot, &new_snapshot);
+                if changes.is_empty() {
+                    println!("  (no change)");
+                }
+                for change in changes {
+                    println!("  {}", change);
+                }
+                snapshot = new_snapshot;
+                replayed += 1;
+            }
+            (None, JournalRecord::Snapshot { seq }) if all => {
+                println!("{}", format!("#{} state saved", seq).dimmed());
+            }
+            (None, _) if all => {
+                println!(
+                    "{}",
+                    format!(
+                        "#{} {} (not replayed)",
+                        record.seq(),
+                        record.action_type().unwrap_or_default()
+                    )
+                    .dimmed()
+                );
+            }
+            (None, _) => (),
+        }
+    }
+
+    println!(
+        "\nReplayed {} of {} journal records from '{}'.",
+        replayed,
+        records.len(),
+        journal_path.to_string_lossy()
+    );
+    Ok(())
+}
+
+/// A context with memory storage and an agent that can sign, which is all the
+/// reducers need
+fn replay_context() -> DefaultResult<Arc<Context>> {
+    let (mut keystore, pub_key) =
+        Keystore::new_standalone(mock_passphrase_manager("replay".to_string()), None)?;
+    let keybundle = keystore.get_keybundle(PRIMARY_KEYBUNDLE_ID)?;
+    let agent = AgentId::new("journal-replay", pub_key);
+    let conductor_api = ConductorApiBuilder::new()
+        .with_agent_signature_callback(Arc::new(Mutex::new(keybundle)))
+        .spawn();
+    Ok(Arc::new(
+        ContextBuilder::new()
+            .with_agent(agent)
+            .with_conductor_api(conductor_api)
+            .with_memory_storage()
+            .with_instance_name("journal-replay")
+            .spawn(),
+    ))
+}
+
+/// The snapshots the persister would save for the given state
+fn state_snapshot(state: &State) -> DefaultResult<Value> {
+    let state = StateWrapper::from(state.clone());
+    Ok(json!({
+        "agent": serde_json::to_value(AgentStateSnapshot::from(&state))?,
+        "nucleus": serde_json::to_value(NucleusStateSnapshot::from(&state))?,
+        "dht": serde_json::to_value(DhtStoreSnapshot::from(&state))?,
+    }))
+}
+
+/// Lists the values that differ between the two JSON documents by their path,
+/// removed ones in red and added ones in green
+fn json_diff(path: &str, before: &Value, after: &Value) -> Vec<String> {
+    match (before, after) {
+        (Value::Object(before_map), Value::Object(after_map)) => {
+            let mut keys: Vec<&String> = before_map.keys().chain(after_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .flat_map(|key| {
+                    json_diff(
+                        &format!("{}/{}", path, key),
+                        before_map.get(key).unwrap_or(&Value::Null),
+                        after_map.get(key).unwrap_or(&Value::Null),
+                    )
+                })
+                .collect()
+        }
+        (Value::Array(before_items), Value::Array(after_items)) => {
+            let length = before_items.len().max(after_items.len());
+            (0..length)
+                .flat_map(|i| {
+                    json_diff(
+                        &format!("{}/{}", path, i),
+                        before_items.get(i).unwrap_or(&Value::Null),
+                        after_items.get(i).unwrap_or(&Value::Null),
+                    )
+                })
+                .collect()
+        }
+        _ if before == after => Vec::new(),
+        _ => {
+            let mut changes = Vec::new();
+            if !before.is_null() {
+                changes.push(format!("- {}: {}", path, before).red().to_string());
+            }
+            if !after.is_null() {
+                changes.push(format!("+ {}: {}", path, after).green().to_string());
+            }
+            changes
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_json_by_path() {
+        colored::control::set_override(false);
+        let before = json!({"agent": {"top_chain_header": null}, "dht": {"queue": [1, 2]}});
+        let after = json!({"agent": {"top_chain_header": "Qm"}, "dht": {"queue": [1]}});
+        assert_eq!(
+            json_diff("", &before, &after),
+            vec![
+                "+ /agent/top_chain_header: \"Qm\"".to_string(),
+                "- /dht/queue/1: 2".to_string(),
+            ]
+        );
+        assert!(json_diff("", &before, &before).is_empty());
+    }
+}
@@ -1,21 +1,29 @@
 mod chain_log;
+mod dht;
 mod generate;
 mod hash_dna;
 pub mod init;
+mod instance_archive;
+mod journal;
 mod keygen;
 pub mod package;
 pub mod run;
 mod sim2h_client;
+mod storage;
 pub mod test;
 
 pub use self::{
-    chain_log::{chain_list, chain_log},
+    chain_log::{chain_list, chain_log, chain_verify, instance_file_storage, ChainFilter},
+    dht::dht_shard,
     generate::generate,
     hash_dna::hash_dna,
     init::init,
+    instance_archive::{instance_export, instance_import},
+    journal::journal_replay,
     keygen::keygen,
     package::package,
     run::{get_interface_type_string, hc_run_bundle_configuration, hc_run_configuration, run},
     sim2h_client::sim2h_client,
+    storage::storage_migrate,
     test::{test, TEST_DIR_NAME},
 };
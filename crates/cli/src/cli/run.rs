@@ -42,7 +42,7 @@ pub fn run(
         .boot_from_config()
         .map_err(|err| format_err!("{}", err))?;
 
-    conductor.start_all_interfaces();
+    conductor.start_all_interfaces().map_err(failure::err_msg)?;
     conductor.start_all_instances()?;
     conductor
         .start_all_static_servers()
@@ -197,6 +197,11 @@ fn instance_configuration(storage: StorageConfiguration) -> InstanceConfiguratio
         dna: DNA_CONFIG_ID.into(),
         agent: AGENT_CONFIG_ID.into(),
         storage,
+        wasm_limits: Default::default(),
+        wasm_engine: None,
+        journal: false,
+        storage_quota: None,
+        membrane_proof: None,
     }
 }
 
@@ -225,6 +230,8 @@ fn interface_configuration(
             alias: None,
         }],
         choose_free_port: None,
+        bind_address: default_address(),
+        tls: None,
     })
 }
 
@@ -375,6 +382,11 @@ mod tests {
                 dna: "hc-run-dna".to_string(),
                 agent: "hc-run-agent".to_string(),
                 storage: StorageConfiguration::Memory,
+                wasm_limits: Default::default(),
+                wasm_engine: None,
+                journal: false,
+                storage_quota: None,
+                membrane_proof: None,
             }
         )
     }
@@ -393,6 +405,8 @@ mod tests {
                     alias: None,
                 }],
                 choose_free_port: None,
+                bind_address: "127.0.0.1".to_string(),
+                tls: None,
             }
         );
 
@@ -409,6 +423,8 @@ mod tests {
                     alias: None,
                 }],
                 choose_free_port: None,
+                bind_address: "127.0.0.1".to_string(),
+                tls: None,
             }
         );
 
@@ -0,0 +1,20 @@
+use error::DefaultResult;
+use holochain_conductor_lib::{config::StorageConfiguration, storage::migration::migrate_storage};
+
+/// Copies all CAS and EAV data from one storage into another, empty one.
+/// Used to switch the storage backend of an instance that is not running.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CLI)]
+pub fn storage_migrate(from: StorageConfiguration, to: StorageConfiguration) -> DefaultResult<()> {
+    let report = migrate_storage(&from, &to)?;
+    println!(
+        "Migrated {} CAS items and {} EAVIs.",
+        report.cas_items, report.eavis
+    );
+    if report.snapshots.is_empty() {
+        println!("No state snapshots found.");
+    } else {
+        println!("State snapshots: {}", report.snapshots.join(", "));
+    }
+    println!("The source storage was left untouched; point the instance config to the new storage to use it.");
+    Ok(())
+}
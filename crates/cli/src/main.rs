@@ -8,7 +8,6 @@ extern crate holochain_json_api;
 extern crate holochain_locksmith;
 extern crate holochain_net;
 extern crate holochain_persistence_api;
-extern crate holochain_persistence_file;
 extern crate json_patch;
 extern crate lib3h_crypto_api;
 extern crate lib3h_protocol;
@@ -41,7 +40,9 @@ mod error;
 mod util;
 
 use crate::error::{HolochainError, HolochainResult};
-use holochain_conductor_lib::happ_bundle::HappBundle;
+use holochain_conductor_lib::{config::StorageConfiguration, happ_bundle::HappBundle};
+use holochain_core::agent::chain_store::ChainStoreQueryOptions;
+use holochain_core_types::time::Iso8601;
 use std::{fs::File, io::Read, path::PathBuf, str::FromStr};
 use structopt::{clap::arg_enum, StructOpt};
 new_relic_setup!("NEW_RELIC_LICENSE_KEY");
@@ -134,17 +135,71 @@ enum Cli {
         nullpass: bool,
     },
     #[structopt(name = "chain")]
-    /// View the contents of a source chain
+    /// View the contents of a source chain as JSON
     ChainLog {
         #[structopt(name = "INSTANCE")]
-        /// Instance ID to view
+        /// Instance ID to view, for file storage in the directory given by --path
         instance_id: Option<String>,
         #[structopt(long, short, parse(from_os_str))]
-        /// Location of chain storage
+        /// Directory with the storage of all instances [default: the conductor's default persistence_dir/storage]
         path: Option<PathBuf>,
+        #[structopt(long)]
+        /// Storage of any type to view instead of an instance's file storage, as <file|pickle|lmdb|sqlite>:<path>
+        storage: Option<StorageConfiguration>,
         #[structopt(long, short)]
         /// List available instances
         list: bool,
+        #[structopt(long = "entry-type", short = "t")]
+        /// Only show entries of this type, can be a glob like 'my_*' and given more than once
+        entry_types: Vec<String>,
+        #[structopt(long)]
+        /// Only show headers with this ISO 8601 timestamp or a later one
+        since: Option<Iso8601>,
+        #[structopt(long)]
+        /// Only show headers with this ISO 8601 timestamp or an earlier one
+        until: Option<Iso8601>,
+        #[structopt(long, default_value = "0")]
+        /// Number of matching headers to skip, counting from the latest
+        start: usize,
+        #[structopt(long, default_value = "0")]
+        /// Maximum number of headers to show, 0 for all
+        limit: usize,
+        #[structopt(long, short, default_value = "json", possible_values = &ChainFormat::variants(), case_insensitive = true)]
+        /// Print one JSON array or one JSON object per line
+        format: ChainFormat,
+        #[structopt(long)]
+        /// Also show the earlier versions of updated and deleted entries
+        history: bool,
+        #[structopt(subcommand)]
+        command: Option<ChainCommand>,
+    },
+    #[structopt(name = "journal")]
+    /// Inspect the action journal of an instance
+    Journal {
+        #[structopt(subcommand)]
+        command: JournalCommand,
+    },
+    #[structopt(name = "instance")]
+    /// Back up and restore instances
+    Instance {
+        #[structopt(subcommand)]
+        command: InstanceCommand,
+    },
+    #[structopt(name = "storage")]
+    /// Work on the storage of an instance that is not running
+    Storage {
+        #[structopt(subcommand)]
+        command: StorageCommand,
+    },
+    #[structopt(name = "dht")]
+    /// Inspect the DHT shard of an instance that is not running and check its holding map against the CAS
+    Dht {
+        #[structopt(name = "STORAGE")]
+        /// Storage of the instance, as <file|pickle|lmdb|sqlite>:<path>
+        storage: StorageConfiguration,
+        #[structopt(long)]
+        /// Rebuild the holding map from the CAS so the instance announces what it actually holds
+        repair: bool,
     },
     #[structopt(name = "hash")]
     /// Parse and hash a DNA file to determine its unique network hash
@@ -172,6 +227,83 @@ enum Cli {
         files: bool,
     },
 }
+#[derive(StructOpt)]
+enum InstanceCommand {
+    #[structopt(name = "export")]
+    /// Writes the chain, DHT shard, state snapshots, DNA and agent of an instance that is not running to a single archive
+    Export {
+        #[structopt(name = "INSTANCE")]
+        /// Instance ID to export
+        instance_id: String,
+        #[structopt(long, short, parse(from_os_str))]
+        /// Conductor config file the instance is configured in
+        config: PathBuf,
+        #[structopt(long, short, parse(from_os_str))]
+        /// Archive file to write
+        output: PathBuf,
+    },
+    #[structopt(name = "import")]
+    /// Restores an archive into a new, empty storage and prints the config to run it
+    Import {
+        #[structopt(name = "ARCHIVE", parse(from_os_str))]
+        /// Archive file written by `hc instance export`
+        archive: PathBuf,
+        #[structopt(long)]
+        /// Storage to restore to, as <file|pickle|lmdb|sqlite>:<path>
+        to: StorageConfiguration,
+    },
+}
+
+#[derive(StructOpt)]
+enum ChainCommand {
+    #[structopt(name = "verify")]
+    /// Checks header links, entry addresses, signatures and timestamps of a source chain and prints a JSON report of any breaks
+    Verify {
+        #[structopt(name = "STORAGE")]
+        /// Storage of an instance that is not running, as <file|pickle|lmdb|sqlite>:<path>
+        storage: StorageConfiguration,
+    },
+}
+
+#[derive(StructOpt)]
+enum JournalCommand {
+    #[structopt(name = "replay")]
+    /// Replays a journal step by step on an empty state, printing how every action changes the state
+    Replay {
+        #[structopt(name = "JOURNAL", parse(from_os_str))]
+        /// Path to the journal.jsonl in the storage directory of an instance
+        journal: PathBuf,
+        #[structopt(long)]
+        /// Stop after the record with this sequence number
+        until: Option<u64>,
+        #[structopt(long, short)]
+        /// Also list the records that don't get replayed
+        all: bool,
+    },
+}
+
+#[derive(StructOpt)]
+enum StorageCommand {
+    #[structopt(name = "migrate")]
+    /// Copies all data of an instance's storage into a new, empty storage, e.g. to switch from file to lmdb storage
+    Migrate {
+        #[structopt(long)]
+        /// Storage to copy from, as <file|pickle|lmdb|sqlite>:<path>
+        from: StorageConfiguration,
+        #[structopt(long)]
+        /// Storage to copy to, as <file|pickle|lmdb|sqlite>:<path>
+        to: StorageConfiguration,
+    },
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    pub enum ChainFormat {
+        Json,
+        Ndjson,
+    }
+}
+
 arg_enum! {
     #[derive(Debug)]
     pub enum NetworkingType {
@@ -311,21 +443,76 @@ fn run() -> HolochainResult<()> {
                 .map_err(|e| HolochainError::Default(format_err!("{}", e)))?
         }
 
+        Cli::ChainLog {
+            command: Some(ChainCommand::Verify { storage }),
+            ..
+        } => {
+            cli::chain_verify(storage).map_err(|e| HolochainError::Default(format_err!("{}", e)))?
+        }
         Cli::ChainLog {
             instance_id,
-            list,
             path,
-        } => match (list, instance_id) {
-            (true, _) => cli::chain_list(path),
-            (false, None) => {
-                Cli::clap().print_help().expect("Couldn't print help!");
-                println!("\n\nTry `hc help chain` for more info");
-            }
-            (false, Some(instance_id)) => {
-                cli::chain_log(path, instance_id)
-                    .map_err(|e| HolochainError::Default(format_err!("{}", e)))?;
-            }
-        },
+            storage,
+            list,
+            entry_types,
+            since,
+            until,
+            start,
+            limit,
+            format,
+            history,
+            command: None,
+        } => {
+            let storage = match (list, storage, instance_id) {
+                (true, _, _) => {
+                    return cli::chain_list(path)
+                        .map_err(|e| HolochainError::Default(format_err!("{}", e)))
+                }
+                (false, Some(storage), _) => storage,
+                (false, None, Some(instance_id)) => cli::instance_file_storage(path, &instance_id),
+                (false, None, None) => {
+                    Cli::clap().print_help().expect("Couldn't print help!");
+                    println!("\n\nTry `hc help chain` for more info");
+                    return Ok(());
+                }
+            };
+            let filter = cli::ChainFilter {
+                entry_types,
+                since,
+                until,
+                range: ChainStoreQueryOptions {
+                    start,
+                    limit,
+                    headers: true,
+                },
+            };
+            cli::chain_log(storage, filter, format, history)
+                .map_err(|e| HolochainError::Default(format_err!("{}", e)))?
+        }
+        Cli::Journal {
+            command:
+                JournalCommand::Replay {
+                    journal,
+                    until,
+                    all,
+                },
+        } => cli::journal_replay(journal, until, all)
+            .map_err(|e| HolochainError::Default(format_err!("{}", e)))?,
+        Cli::Instance { command } => match command {
+            InstanceCommand::Export {
+                instance_id,
+                config,
+                output,
+            } => cli::instance_export(config, instance_id, output),
+            InstanceCommand::Import { archive, to } => cli::instance_import(archive, to),
+        }
+        .map_err(|e| HolochainError::Default(format_err!("{}", e)))?,
+        Cli::Storage {
+            command: StorageCommand::Migrate { from, to },
+        } => cli::storage_migrate(from, to)
+            .map_err(|e| HolochainError::Default(format_err!("{}", e)))?,
+        Cli::Dht { storage, repair } => cli::dht_shard(storage, repair)
+            .map_err(|e| HolochainError::Default(format_err!("{}", e)))?,
         Cli::HashDna {
             path,
             property,
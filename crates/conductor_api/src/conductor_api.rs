@@ -1,9 +1,11 @@
-use holochain_core_types::error::HolochainError;
+use holochain_core_types::{
+    agent::Base32, entry::encrypted_entry::SealedData, error::HolochainError,
+};
 use holochain_locksmith::RwLock;
 use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
 use jsonrpc_core::IoHandler;
 use jsonrpc_lite::JsonRpc;
-use serde_json::json;
+use serde_json::{json, Value};
 use snowflake::ProcessUniqueId;
 use std::{fmt, sync::Arc};
 
@@ -15,6 +17,16 @@ pub fn send_json_rpc(
     handle: Arc<RwLock<IoHandler>>,
     payload: String,
     request_reponse: (String, String),
+) -> Result<String, HolochainError> {
+    send_json_rpc_with_params(handle, json!({ "payload": payload }), request_reponse)
+}
+
+/// Like `send_json_rpc()` but for methods that take more params than just the payload
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CONDUCTOR_API)]
+pub fn send_json_rpc_with_params(
+    handle: Arc<RwLock<IoHandler>>,
+    params: Value,
+    request_reponse: (String, String),
 ) -> Result<String, HolochainError> {
     let handler = handle.write().unwrap();
     let method = format!("agent/{}", request_reponse.0);
@@ -22,9 +34,7 @@ pub fn send_json_rpc(
     let request = json!({
         "jsonrpc": "2.0",
         "method": method,
-        "params": {
-            "payload": payload,
-        },
+        "params": params,
         "id": id,
     })
     .to_string();
@@ -74,6 +84,35 @@ impl ConductorApi {
         send_json_rpc(self.0.clone(), encoded_payload, request_response)
     }
 
+    /// Encrypts the payload so that only the holders of the given public encrypting keys
+    /// can decrypt it (through `decrypt_sealed()` of their conductor).
+    pub fn encrypt_for(
+        &self,
+        payload: String,
+        recipients: Vec<Base32>,
+    ) -> Result<SealedData, HolochainError> {
+        let params = json!({
+            "payload": base64::encode(&payload),
+            "recipients": recipients,
+        });
+        let sealed = send_json_rpc_with_params(
+            self.0.clone(),
+            params,
+            (String::from("encrypt_for"), String::from("sealed")),
+        )?;
+        Ok(serde_json::from_str(&sealed)?)
+    }
+
+    /// Decrypts data that was encrypted for this agent with `encrypt_for()`
+    pub fn decrypt_sealed(&self, sealed: &SealedData) -> Result<String, HolochainError> {
+        let params = json!({ "sealed": serde_json::to_string(sealed)? });
+        send_json_rpc_with_params(
+            self.0.clone(),
+            params,
+            (String::from("decrypt_sealed"), String::from("message")),
+        )
+    }
+
     pub fn get(&self) -> &Arc<RwLock<IoHandler>> {
         &self.0
     }
@@ -1,13 +1,18 @@
 use crate::{
     conductor::{base::notify, Conductor},
     config::{
-        AgentConfiguration, Bridge, DnaConfiguration, InstanceConfiguration,
+        AgentConfiguration, Bridge, Configuration, DnaConfiguration, InstanceConfiguration,
         InstanceReferenceConfiguration, InterfaceConfiguration, StorageConfiguration,
     },
     dpki_instance::DpkiInstance,
     keystore::{Keystore, PRIMARY_KEYBUNDLE_ID},
+    storage::{
+        archive::{self, ArchiveManifest, InstanceArchive},
+        migration::{migrate_storage, MigrationReport},
+    },
 };
 use holochain_core_types::error::HolochainError;
+use holochain_dpki::CODEC_HCS0;
 use holochain_locksmith::RwLock;
 use holochain_persistence_api::{cas::content::AddressableContent, hash::HashString};
 
@@ -15,6 +20,7 @@ use json_patch;
 use std::{
     fs::{self, create_dir_all},
     path::PathBuf,
+    str::FromStr,
     sync::Arc,
     thread::sleep,
     time::Duration,
@@ -41,8 +47,26 @@ pub trait ConductorAdmin {
         dna_id: &String,
         agent_id: &String,
         storage: Option<&str>,
+        membrane_proof: Option<String>,
     ) -> Result<(), HolochainError>;
     fn remove_instance(&mut self, id: &String) -> Result<(), HolochainError>;
+    fn migrate_instance_storage(
+        &mut self,
+        id: &String,
+        storage: &str,
+        path: Option<String>,
+    ) -> Result<MigrationReport, HolochainError>;
+    fn export_instance(
+        &mut self,
+        id: &String,
+        path: &PathBuf,
+    ) -> Result<ArchiveManifest, HolochainError>;
+    fn import_instance(
+        &mut self,
+        path: &PathBuf,
+        id: Option<String>,
+        storage: Option<&str>,
+    ) -> Result<ArchiveManifest, HolochainError>;
     fn add_interface(&mut self, new_instance: InterfaceConfiguration)
         -> Result<(), HolochainError>;
     fn remove_interface(&mut self, id: &String) -> Result<(), HolochainError>;
@@ -198,42 +222,21 @@ impl ConductorAdmin for Conductor {
         dna_id: &String,
         agent_id: &String,
         storage: Option<&str>,
+        membrane_proof: Option<String>,
     ) -> Result<(), HolochainError> {
         let mut new_config = self.config.clone();
-        let storage_path = self
-            .instance_storage_dir_path()
-            .join(id.clone())
-            .to_str()
-            .ok_or_else(|| {
-                HolochainError::ConfigError(format!(
-                    "invalid path {:?}",
-                    self.instance_storage_dir_path().join(id.clone())
-                ))
-            })?
-            .into();
-
-        fs::create_dir_all(&storage_path)?;
-        let storage_config = match storage {
-            Some("memory") => StorageConfiguration::Memory,
-            Some("file") => StorageConfiguration::File { path: storage_path },
-            Some("pickle") => StorageConfiguration::Pickle { path: storage_path },
-            None | Some("lmdb") => StorageConfiguration::Lmdb {
-                path: storage_path,
-                initial_mmap_bytes: None,
-            },
-            Some(s) => {
-                return Err(HolochainError::ConfigError(format!(
-                    "Invalid storage option: {}",
-                    s
-                )))
-            }
-        };
+        let storage_config = self.instance_storage_config(id, storage)?;
 
         let new_instance_config = InstanceConfiguration {
             id: id.to_string(),
             dna: dna_id.to_string(),
             agent: agent_id.to_string(),
             storage: storage_config,
+            wasm_limits: Default::default(),
+            wasm_engine: None,
+            journal: false,
+            storage_quota: None,
+            membrane_proof,
         };
         new_config.instances.push(new_instance_config);
         new_config.check_consistency(&mut self.dna_loader)?;
@@ -276,6 +279,122 @@ impl ConductorAdmin for Conductor {
         Ok(())
     }
 
+    /// Copies all data of the instance given by id into a new storage of the given type
+    /// and switches the instance over to it. The instance has to be stopped.
+    /// Without a path the new storage goes into `<id>-<storage>` in the storage directory.
+    /// The old storage is left untouched so it can be deleted once the migration proved good.
+    fn migrate_instance_storage(
+        &mut self,
+        id: &String,
+        storage: &str,
+        path: Option<String>,
+    ) -> Result<MigrationReport, HolochainError> {
+        let path = match path {
+            Some(path) => path,
+            None => path_to_string(
+                self.instance_storage_dir_path()
+                    .join(format!("{}-{}", id, storage)),
+            )?,
+        };
+        let new_storage = StorageConfiguration::from_str(&format!("{}:{}", storage, path))?;
+
+        let report = self.with_instance_taken_down(id, |conductor, instance_config| {
+            let report = migrate_storage(&instance_config.storage, &new_storage)?;
+            let mut new_config = conductor.config.clone();
+            new_config
+                .instances
+                .iter_mut()
+                .filter(|instance| instance.id == *id)
+                .for_each(|instance| instance.storage = new_storage.clone());
+            new_config.check_consistency(&mut conductor.dna_loader)?;
+            conductor.config = new_config;
+            conductor.save_config()?;
+            Ok(report)
+        })?;
+
+        notify(format!(
+            "Migrated storage of instance \"{}\" to {}.",
+            id, path
+        ));
+        Ok(report)
+    }
+
+    /// Writes all data of the instance given by id, together with its DNA and agent,
+    /// to a single archive file. The instance has to be stopped.
+    fn export_instance(
+        &mut self,
+        id: &String,
+        path: &PathBuf,
+    ) -> Result<ArchiveManifest, HolochainError> {
+        let manifest = self.with_instance_taken_down(id, |conductor, instance_config| {
+            let dna_config = conductor
+                .config
+                .dna_by_id(&instance_config.dna)
+                .ok_or_else(|| HolochainError::ConfigError("DNA not found".into()))?;
+            let mut dna =
+                Arc::get_mut(&mut conductor.dna_loader).unwrap()(&PathBuf::from(&dna_config.file))?;
+            if let Some(uuid) = dna_config.uuid {
+                dna.uuid = uuid;
+            }
+            let agent_config = conductor
+                .config
+                .agent_by_id(&instance_config.agent)
+                .ok_or_else(|| HolochainError::ConfigError("Agent not found".into()))?;
+            archive::export_instance(id, &dna, &agent_config, &instance_config.storage, path)
+        })?;
+
+        notify(format!("Exported instance \"{}\" to {:?}.", id, path));
+        Ok(manifest)
+    }
+
+    /// Creates a new instance from an archive written by export_instance().
+    /// DNA and agent get added to the config unless it already has them.
+    /// The instance gets the ID it was exported with unless another one is given,
+    /// and storage of the given type, like add_instance().
+    /// It does not get started nor added to an interface.
+    fn import_instance(
+        &mut self,
+        path: &PathBuf,
+        id: Option<String>,
+        storage: Option<&str>,
+    ) -> Result<ArchiveManifest, HolochainError> {
+        let archive = InstanceArchive::read(path)?;
+        let id = id.unwrap_or_else(|| archive.manifest.instance_id.clone());
+        let mut new_config = self.config.clone();
+        if new_config.instance_by_id(&id).is_some() {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Instance with ID '{}' already exists",
+                id
+            )));
+        }
+
+        // Whatever got written for an import that fails gets removed again,
+        // so that it can be retried.
+        let mut written = Vec::new();
+        if let Err(error) =
+            self.restore_archive(&archive, &id, storage, &mut new_config, &mut written)
+        {
+            for path in written.iter().rev() {
+                let _ = if path.is_dir() {
+                    fs::remove_dir_all(path)
+                } else {
+                    fs::remove_file(path)
+                };
+            }
+            return Err(error);
+        }
+
+        self.config = new_config;
+        let instance = self.instantiate_from_config(&id)?;
+        self.instances
+            .insert(id.clone(), Arc::new(RwLock::new(instance)));
+        self.save_config()?;
+        let _ = self.start_signal_multiplexer();
+
+        notify(format!("Imported instance \"{}\" from {:?}.", id, path));
+        Ok(archive.manifest)
+    }
+
     fn add_interface(&mut self, interface: InterfaceConfiguration) -> Result<(), HolochainError> {
         let mut new_config = self.config.clone();
         if new_config.interfaces.iter().any(|i| i.id == interface.id) {
@@ -616,6 +735,175 @@ impl ConductorAdmin for Conductor {
     }
 }
 
+/// Agent addresses from an archive name the keystore file we write for them,
+/// so they have to be an actual public key and not a path.
+fn check_agent_address(address: &str) -> Result<(), HolochainError> {
+    if address.contains(|c| c == '/' || c == '\\') || CODEC_HCS0.decode(address).is_err() {
+        return Err(HolochainError::ConfigError(format!(
+            "Invalid agent address in archive: {}",
+            address
+        )));
+    }
+    Ok(())
+}
+
+fn path_to_string(path: PathBuf) -> Result<String, HolochainError> {
+    path.to_str()
+        .map(String::from)
+        .ok_or_else(|| HolochainError::ConfigError(format!("invalid path {:?}", path)))
+}
+
+impl Conductor {
+    /// Adds the DNA, agent and instance of the given archive to new_config and restores its
+    /// storage, pushing every file and directory it creates to written.
+    fn restore_archive(
+        &mut self,
+        archive: &InstanceArchive,
+        id: &str,
+        storage: Option<&str>,
+        new_config: &mut Configuration,
+        written: &mut Vec<PathBuf>,
+    ) -> Result<(), HolochainError> {
+        let dna_id = match new_config
+            .dnas
+            .iter()
+            .find(|dna| dna.hash == archive.manifest.dna_hash)
+        {
+            Some(dna) => dna.id.clone(),
+            None => {
+                let dna_file = self.save_dna(&archive.dna)?;
+                if !new_config
+                    .dnas
+                    .iter()
+                    .any(|dna| PathBuf::from(&dna.file) == dna_file)
+                {
+                    written.push(dna_file.clone());
+                }
+                new_config.dnas.push(DnaConfiguration {
+                    id: archive.manifest.dna_hash.clone(),
+                    file: path_to_string(dna_file)?,
+                    hash: archive.manifest.dna_hash.clone(),
+                    uuid: None,
+                });
+                archive.manifest.dna_hash.clone()
+            }
+        };
+
+        let agent_id = match new_config
+            .agents
+            .iter()
+            .find(|agent| agent.public_address == archive.agent.public_address)
+        {
+            Some(agent) => agent.id.clone(),
+            None => {
+                if new_config.agent_by_id(&archive.agent.id).is_some() {
+                    return Err(HolochainError::ErrorGeneric(format!(
+                        "Agent with ID '{}' already exists with another key",
+                        archive.agent.id
+                    )));
+                }
+                let mut agent = archive.agent.clone();
+                if let Some(ref keystore) = archive.keystore {
+                    check_agent_address(&agent.public_address)?;
+                    let keystore_file = self
+                        .instance_storage_dir_path()
+                        .join(agent.public_address.clone());
+                    create_dir_all(self.instance_storage_dir_path())?;
+                    if !keystore_file.exists() {
+                        written.push(keystore_file.clone());
+                    }
+                    fs::write(&keystore_file, keystore)?;
+                    agent.keystore_file = path_to_string(keystore_file)?;
+                }
+                new_config.agents.push(agent);
+                archive.agent.id.clone()
+            }
+        };
+
+        let storage_dir = self.instance_storage_dir_path().join(id);
+        if !storage_dir.exists() {
+            written.push(storage_dir);
+        }
+        let storage_config = self.instance_storage_config(id, storage)?;
+        new_config.instances.push(InstanceConfiguration {
+            id: id.to_string(),
+            dna: dna_id,
+            agent: agent_id,
+            storage: storage_config.clone(),
+            wasm_limits: Default::default(),
+            wasm_engine: None,
+            journal: false,
+            storage_quota: None,
+            membrane_proof: None,
+        });
+        new_config.check_consistency(&mut self.dna_loader)?;
+        archive.restore_storage(&storage_config)
+    }
+
+    /// Storage of the given type in the default storage directory for the instance given by id.
+    /// Defaults to lmdb.
+    fn instance_storage_config(
+        &self,
+        id: &str,
+        storage: Option<&str>,
+    ) -> Result<StorageConfiguration, HolochainError> {
+        let storage_path = path_to_string(self.instance_storage_dir_path().join(id))?;
+        fs::create_dir_all(&storage_path)?;
+        match storage {
+            Some("memory") => Ok(StorageConfiguration::Memory),
+            Some("file") => Ok(StorageConfiguration::File { path: storage_path }),
+            Some("pickle") => Ok(StorageConfiguration::Pickle { path: storage_path }),
+            Some("sqlite") => Ok(StorageConfiguration::Sqlite { path: storage_path }),
+            None | Some("lmdb") => Ok(StorageConfiguration::Lmdb {
+                path: storage_path,
+                initial_mmap_bytes: None,
+            }),
+            Some(s) => Err(HolochainError::ConfigError(format!(
+                "Invalid storage option: {}",
+                s
+            ))),
+        }
+    }
+
+    /// Drops the stopped instance given by id so that its storage gets flushed and closed,
+    /// runs f on the conductor and the instance's config, and instantiates the instance again
+    /// with whatever config f left behind.
+    #[allow(clippy::ptr_arg)]
+    fn with_instance_taken_down<T, F>(&mut self, id: &String, f: F) -> Result<T, HolochainError>
+    where
+        F: FnOnce(&mut Conductor, &InstanceConfiguration) -> Result<T, HolochainError>,
+    {
+        let instance_config = self.config.instance_by_id(id).ok_or_else(|| {
+            HolochainError::ConfigError(format!("Instance with ID {} not found", id))
+        })?;
+        if instance_config.storage == StorageConfiguration::Memory {
+            return Err(HolochainError::ConfigError(format!(
+                "Instance {} uses memory storage which only exists while it runs",
+                id
+            )));
+        }
+        if let Some(instance) = self.instances.get(id) {
+            if instance.read().unwrap().active() {
+                return Err(HolochainError::ErrorGeneric(format!(
+                    "Instance {} has to be stopped first",
+                    id
+                )));
+            }
+        }
+        if let Some(instance) = self.instances.remove(id) {
+            instance.write().unwrap().kill();
+        }
+
+        let result = f(self, &instance_config);
+
+        let instance = self.instantiate_from_config(id)?;
+        self.instances
+            .insert(id.clone(), Arc::new(RwLock::new(instance)));
+        let _ = self.start_signal_multiplexer();
+        result
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -737,6 +1025,7 @@ trace = false"#
         format!(
             r#"[[interfaces]]
 admin = true
+bind_address = '127.0.0.1'
 id = 'websocket interface'
 
 [[interfaces.instances]]
@@ -1160,6 +1449,7 @@ id = 'new-dna'"#,
             &String::from("new-dna"),
             &String::from("test-agent-1"),
             None,
+            None,
         );
 
         assert_eq!(add_result, Ok(()));
@@ -1212,6 +1502,150 @@ id = 'new-instance'"#,
         assert_eq!(config_contents, toml,);
     }
 
+    #[test]
+    fn test_migrate_instance_storage() {
+        let test_name = "test_migrate_instance_storage";
+        let mut conductor = create_test_conductor(test_name, 3017);
+        let storage_path = conductor.instance_storage_dir_path();
+        let _ = remove_dir_all(storage_path.clone());
+
+        assert!(conductor
+            .migrate_instance_storage(&String::from("test-instance-1"), "lmdb", None)
+            .is_err());
+
+        conductor
+            .install_dna_from_file(
+                PathBuf::from("new-dna.dna.json"),
+                String::from("new-dna"),
+                false,
+                None,
+                None,
+                None,
+            )
+            .expect("Could not install DNA");
+        conductor
+            .add_instance(
+                &String::from("new-instance"),
+                &String::from("new-dna"),
+                &String::from("test-agent-1"),
+                Some("file"),
+                None,
+            )
+            .expect("Could not add instance");
+
+        conductor
+            .migrate_instance_storage(&String::from("new-instance"), "sqlite", None)
+            .expect("Could not migrate storage");
+
+        assert_eq!(
+            conductor
+                .config
+                .instance_by_id("new-instance")
+                .unwrap()
+                .storage,
+            StorageConfiguration::Sqlite {
+                path: storage_path
+                    .join("new-instance-sqlite")
+                    .to_str()
+                    .unwrap()
+                    .to_string(),
+            }
+        );
+        assert!(conductor.instances.contains_key("new-instance"));
+
+        // the target already has data now
+        assert!(conductor
+            .migrate_instance_storage(
+                &String::from("new-instance"),
+                "sqlite",
+                Some(
+                    storage_path
+                        .join("new-instance-sqlite")
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                ),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_export_and_import_instance() {
+        let test_name = "test_export_and_import_instance";
+        let mut conductor = create_test_conductor(test_name, 3018);
+        let _ = remove_dir_all(conductor.instance_storage_dir_path());
+        let archive_path = conductor.config.persistence_dir.join("new-instance.tar.gz");
+
+        conductor
+            .install_dna_from_file(
+                PathBuf::from("new-dna.dna.json"),
+                String::from("new-dna"),
+                false,
+                None,
+                None,
+                None,
+            )
+            .expect("Could not install DNA");
+        conductor
+            .add_instance(
+                &String::from("new-instance"),
+                &String::from("new-dna"),
+                &String::from("test-agent-1"),
+                Some("file"),
+                None,
+            )
+            .expect("Could not add instance");
+
+        let manifest = conductor
+            .export_instance(&String::from("new-instance"), &archive_path)
+            .expect("Could not export instance");
+        assert_eq!(manifest.instance_id, "new-instance");
+        assert!(conductor.instances.contains_key("new-instance"));
+
+        let dnas = conductor.config.dnas.len();
+        let agents = conductor.config.agents.len();
+        assert_eq!(
+            conductor
+                .import_instance(
+                    &archive_path,
+                    Some(String::from("imported")),
+                    Some("sqlite")
+                )
+                .map(|imported| imported.entries),
+            Ok(manifest.entries)
+        );
+        let imported = conductor.config.instance_by_id("imported").unwrap();
+        assert_eq!(imported.agent, "test-agent-1");
+        assert_eq!(conductor.config.dnas.len(), dnas);
+        assert_eq!(conductor.config.agents.len(), agents);
+        assert!(conductor.instances.contains_key("imported"));
+
+        // IDs of instances are unique
+        assert!(conductor
+            .import_instance(&archive_path, Some(String::from("imported")), None)
+            .is_err());
+
+        // Failed imports leave nothing behind
+        assert!(conductor
+            .import_instance(&archive_path, Some(String::from("failed")), Some("bogus"))
+            .is_err());
+        assert!(!conductor
+            .instance_storage_dir_path()
+            .join("failed")
+            .exists());
+        assert!(conductor.config.instance_by_id("failed").is_none());
+    }
+
+    #[test]
+    fn test_only_public_keys_name_imported_keystores() {
+        assert!(check_agent_address(&test_keybundle(1).get_id()).is_ok());
+        assert!(check_agent_address("../../conductor-config.toml").is_err());
+        assert!(check_agent_address(
+            "HcScIkRaAaaaaaaaaaAaaaAAAAaaaaaaaaAaaaaAaaaaaaaaAaaaaaaaaaaaaaa/.."
+        )
+        .is_err());
+    }
+
     #[test]
     /// Tests if the removed instance is gone from the config file
     /// as well as the mentions of the removed instance are gone from the interfaces
@@ -1243,6 +1677,7 @@ id = 'new-instance'"#,
             String::from(
                 r#"[[interfaces]]
 admin = true
+bind_address = '127.0.0.1'
 id = 'websocket interface'
 
 [[interfaces.instances]]
@@ -1294,6 +1729,7 @@ type = 'websocket'"#,
             String::from(
                 r#"[[interfaces]]
 admin = true
+bind_address = '127.0.0.1'
 id = 'websocket interface'
 instances = []
 
@@ -1321,6 +1757,8 @@ type = 'websocket'"#,
             admin: false,
             instances: Vec::new(),
             choose_free_port: None,
+            bind_address: String::from("127.0.0.1"),
+            tls: None,
         };
 
         assert_eq!(conductor.add_interface(interface_config), Ok(()),);
@@ -1343,6 +1781,7 @@ type = 'websocket'"#,
             String::from(
                 r#"[[interfaces]]
 admin = false
+bind_address = '127.0.0.1'
 id = 'new-interface'
 instances = []
 
@@ -1364,7 +1803,7 @@ type = 'http'"#,
         let test_name = "test_remove_interface";
         let mut conductor = create_test_conductor(test_name, 3006);
 
-        conductor.start_all_interfaces();
+        conductor.start_all_interfaces().unwrap();
         assert!(conductor
             .interface_threads
             .get("websocket interface")
@@ -1445,6 +1884,7 @@ type = 'http'"#,
                 &String::from("new-dna"),
                 &String::from("test-agent-1"),
                 None,
+                None,
             ),
             Ok(())
         );
@@ -1502,6 +1942,7 @@ id = 'new-instance-2'"#,
             String::from(
                 r#"[[interfaces]]
 admin = true
+bind_address = '127.0.0.1'
 id = 'websocket interface'
 
 [[interfaces.instances]]
@@ -1562,6 +2003,7 @@ type = 'websocket'"#,
             String::from(
                 r#"[[interfaces]]
 admin = true
+bind_address = '127.0.0.1'
 id = 'websocket interface'
 
 [[interfaces.instances]]
@@ -1657,6 +2099,7 @@ public_address = '{}'"#,
             String::from(
                 r#"[[interfaces]]
 admin = true
+bind_address = '127.0.0.1'
 id = 'websocket interface'
 
 [[interfaces.instances]]
@@ -2,7 +2,7 @@ use crate::{
     conductor::broadcaster::Broadcaster,
     config::{
         serialize_configuration, Configuration, InterfaceConfiguration, InterfaceDriver,
-        NetworkConfig, StorageConfiguration,
+        InterfaceTlsConfiguration, NetworkConfig, StorageConfiguration,
     },
     context_builder::ContextBuilder,
     dpki_instance::DpkiInstance,
@@ -15,28 +15,37 @@ use crate::{
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use holochain_common::paths::DNA_EXTENSION;
 use holochain_core::{
-    logger::Logger, network::handler::fetch::fetch_aspects_for_entry, signal::Signal,
+    agent::chain_verifier::{verify_chain, ChainVerificationReport},
+    logger::Logger,
+    network::{handler::fetch::fetch_aspects_for_entry, validation_receipt::ValidationReceipt},
+    signal::Signal,
+    wasm_engine::engine::WasmEngineConfig,
 };
 use holochain_core_types::{
     agent::AgentId,
     dna::Dna,
     error::{HcResult, HolochainError},
-    network::entry_aspect::EntryAspect,
+    network::{entry_aspect::EntryAspect, warrant::Warrant},
 };
 use holochain_dpki::{key_bundle::KeyBundle, password_encryption::PwHashConfig};
 use holochain_json_api::json::JsonString;
 use holochain_locksmith::{Mutex, RwLock};
 use holochain_logging::{rule::RuleFilter, FastLogger, FastLoggerBuilder};
-use holochain_persistence_api::{cas::content::AddressableContent, hash::HashString};
+use holochain_persistence_api::{
+    cas::content::{Address, AddressableContent},
+    hash::HashString,
+};
 use holochain_tracing as ht;
+use in_stream::TlsCertificate;
 use jsonrpc_ws_server::jsonrpc_core::IoHandler;
 use lib3h_protocol::types::AspectHash;
 use std::{
     clone::Clone,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     convert::TryFrom,
     fs::{self, File},
     io::prelude::*,
+    net::IpAddr,
     option::NoneError,
     path::PathBuf,
     str::FromStr,
@@ -53,6 +62,7 @@ use crate::{
     },
     config::{AgentConfiguration, PassphraseServiceConfig, TracingConfiguration},
     interface::{ConductorApiBuilder, InstanceMap, Interface},
+    interface_auth::InterfaceAuthorizer,
     port_utils::get_free_port,
     signal_wrapper::SignalWrapper,
     static_file_server::ConductorStaticFileServer,
@@ -138,6 +148,7 @@ pub struct Conductor {
     pub(in crate::conductor) static_servers: HashMap<String, StaticServer>,
     pub(in crate::conductor) interface_threads: HashMap<String, Sender<()>>,
     pub(in crate::conductor) interface_broadcasters: Arc<RwLock<HashMap<String, Broadcaster>>>,
+    interface_factories: HashMap<String, InterfaceFactory>,
     signal_multiplexer_kill_switch: Option<Sender<()>>,
     stats_thread_kill_switch: Option<Sender<()>>,
     stats_signal_receiver: Option<Receiver<HashMap<String, InstanceStats>>>,
@@ -179,6 +190,10 @@ pub type KeyLoader = Arc<
 pub type DnaLoader = Arc<Box<dyn FnMut(&PathBuf) -> Result<Dna, HolochainError> + Send + Sync>>;
 pub type UiDirCopier =
     Arc<Box<dyn FnMut(&PathBuf, &PathBuf) -> Result<(), HolochainError> + Send + Sync>>;
+/// Creates the interface for a custom interface driver from the driver's TOML table
+/// (i.e. everything in `[interfaces.driver]` besides `type = "custom"`).
+pub type InterfaceFactory =
+    Arc<Box<dyn Fn(&toml::Value) -> Result<Box<dyn Interface>, String> + Send + Sync>>;
 
 /// preparing for having conductor notifiers go to one of the log streams
 pub fn notify(msg: String) {
@@ -243,6 +258,7 @@ impl Conductor {
             interface_threads: HashMap::new(),
             static_servers: HashMap::new(),
             interface_broadcasters: Arc::new(RwLock::new(HashMap::new())),
+            interface_factories: HashMap::new(),
             signal_multiplexer_kill_switch: None,
             stats_thread_kill_switch: None,
             stats_signal_receiver: None,
@@ -320,6 +336,27 @@ impl Conductor {
         self
     }
 
+    /// Registers a factory for interfaces with driver `type = "custom"` whose `name`
+    /// matches the given name. This is how embedding applications can provide their own
+    /// transports without them having to be part of this crate.
+    pub fn add_interface_factory<S, F>(&mut self, name: S, factory: F)
+    where
+        S: Into<String>,
+        F: Fn(&toml::Value) -> Result<Box<dyn Interface>, String> + Send + Sync + 'static,
+    {
+        self.interface_factories
+            .insert(name.into(), Arc::new(Box::new(factory)));
+    }
+
+    pub fn with_interface_factory<S, F>(mut self, name: S, factory: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&toml::Value) -> Result<Box<dyn Interface>, String> + Send + Sync + 'static,
+    {
+        self.add_interface_factory(name, factory);
+        self
+    }
+
     pub fn config(&self) -> Configuration {
         self.config.clone()
     }
@@ -470,15 +507,15 @@ impl Conductor {
             .map(|kill_switch| kill_switch.send(()));
     }
 
-    pub fn start_all_interfaces(&mut self) {
-        self.interface_threads = self
-            .config
-            .interfaces
-            .iter()
-            .map(|ic| (ic.id.clone(), self.spawn_interface_thread(ic.clone())))
-            .collect();
+    pub fn start_all_interfaces(&mut self) -> Result<(), String> {
+        for interface_config in self.config.interfaces.clone() {
+            let kill_switch = self.spawn_interface_thread(interface_config.clone())?;
+            self.interface_threads
+                .insert(interface_config.id, kill_switch);
+        }
 
         self.start_signal_multiplexer();
+        Ok(())
     }
 
     pub fn stop_all_interfaces(&mut self) {
@@ -569,6 +606,40 @@ impl Conductor {
         Ok(response)
     }
 
+    /// Checks the integrity of an instance's source chain from its current top header
+    pub fn instance_verify_chain(
+        &self,
+        id: &String,
+    ) -> Result<ChainVerificationReport, HolochainInstanceError> {
+        let instance = self.instances.get(id)?.read().unwrap();
+        let state = instance.state()?;
+        Ok(verify_chain(
+            &state.agent().chain_store(),
+            state.agent().top_chain_header(),
+        ))
+    }
+
+    /// The validation receipts that holders sent for an entry the instance's agent authored
+    pub fn instance_validation_receipts(
+        &self,
+        id: &String,
+        entry_address: &Address,
+    ) -> Result<Vec<ValidationReceipt>, HolochainInstanceError> {
+        let instance = self.instances.get(id)?.read().unwrap();
+        let state = instance.state()?;
+        Ok(state.dht().get_validation_receipts(entry_address)?)
+    }
+
+    /// The agents the instance blocked, with the warrants it confirmed against them
+    pub fn instance_blocked_agents(
+        &self,
+        id: &String,
+    ) -> Result<BTreeMap<Address, Vec<Warrant>>, HolochainInstanceError> {
+        let instance = self.instances.get(id)?.read().unwrap();
+        let state = instance.state()?;
+        Ok(state.dht().blocked_agents()?)
+    }
+
     pub fn start_instance(&mut self, id: &String) -> Result<(), HolochainInstanceError> {
         let mut instance = self.instances.get(id)?.write().unwrap();
         notify(format!("Starting instance \"{}\"...", id));
@@ -907,6 +978,16 @@ impl Conductor {
                     self.trace_reporters.write().unwrap().insert(instance_name.clone(), (span_rx, reporter));
                 }
 
+                // Journal:
+                if instance_config.journal {
+                    let path = instance_config.storage.path().ok_or_else(|| {
+                        String::from("Journal needs a storage with a path")
+                    })?;
+                    context_builder = context_builder
+                        .with_journal(path)
+                        .map_err(|hc_err| format!("Error creating context: {}", hc_err.to_string()))?;
+                }
+
                 // Storage:
                 match instance_config.storage {
                     StorageConfiguration::File { path } => {
@@ -934,8 +1015,36 @@ impl Conductor {
                                     format!("Error creating context: {}", hc_err.to_string())
                                 })?
                     }
+                    StorageConfiguration::Sqlite { path } => {
+                        context_builder =
+                            context_builder
+                                .with_sqlite_storage(path)
+                                .map_err(|hc_err| {
+                                    format!("Error creating context: {}", hc_err.to_string())
+                                })?
+                    }
                 }
 
+                context_builder = context_builder
+                    .with_wasm_limits(instance_config.wasm_limits.clone())
+                    .with_storage_quota(instance_config.storage_quota)
+                    .with_membrane_proof(instance_config.membrane_proof.clone());
+
+                // WASM engine:
+                let wasm_engine = match instance_config
+                    .wasm_engine
+                    .clone()
+                    .unwrap_or_else(|| self.config.wasm_engine.clone())
+                {
+                    WasmEngineConfig::Wasmer { cache_dir: None } => WasmEngineConfig::Wasmer {
+                        cache_dir: Some(self.config.persistence_dir.join("wasm_cache")),
+                    },
+                    config => config,
+                };
+                context_builder = context_builder
+                    .with_wasm_engine(&wasm_engine)
+                    .map_err(|hc_err| format!("Error creating context: {}", hc_err.to_string()))?;
+
                 // Conductor API
                 let api = self.build_conductor_api(instance_config.id)?;
                 context_builder = context_builder.with_conductor_api(api);
@@ -1141,8 +1250,10 @@ impl Conductor {
             AgentId::new(&agent_config.name, agent_config.public_address.clone())
         } else {
             let keybundle_arc = self.get_keybundle_for_agent(&agent_config.id)?;
-            let keybundle = keybundle_arc.lock().unwrap();
+            let mut keybundle = keybundle_arc.lock().unwrap();
+            let enc_key_signature = keybundle.sign_enc_key()?;
             AgentId::new(&agent_config.name, keybundle.get_id())
+                .with_enc_key(keybundle.enc_keys.public.clone(), enc_key_signature)
         })
     }
 
@@ -1325,7 +1436,7 @@ impl Conductor {
             return Err(format!("Interface {} already started!", config.id));
         }
         notify(format!("Starting interface '{}'.", config.id));
-        let handle = self.spawn_interface_thread(config.clone());
+        let handle = self.spawn_interface_thread(config.clone())?;
         self.interface_threads.insert(config.id.clone(), handle);
         Ok(())
     }
@@ -1395,23 +1506,39 @@ impl Conductor {
                 .with_debug_functions();
         }
 
-        conductor_api_builder.spawn()
+        let io = conductor_api_builder.spawn();
+        match self.config.interface_auth {
+            Some(ref auth_config) if interface_config.admin => {
+                InterfaceAuthorizer::new(interface_config.id.clone(), auth_config.clone())
+                    .protect(io)
+            }
+            _ => io,
+        }
     }
 
-    fn spawn_interface_thread(&self, interface_config: InterfaceConfiguration) -> Sender<()> {
+    /// Fails if the interface can't be run, e.g. because its port is taken or there is
+    /// no factory registered for its custom driver.
+    fn spawn_interface_thread(
+        &self,
+        interface_config: InterfaceConfiguration,
+    ) -> Result<Sender<()>, String> {
         let dispatcher = self.make_interface_handler(&interface_config);
         // The "kill switch" is the channel which allows the interface to be stopped from outside its thread
         let (kill_switch_tx, kill_switch_rx) = unbounded();
 
-        let (broadcaster, _handle) = run_interface(&interface_config, dispatcher, kill_switch_rx)
-            .map_err(|error| {
-                error!(
-                    "conductor: Error running interface, even after {} attempts '{}': {}",
-                    INTERFACE_CONNECT_ATTEMPTS_MAX, interface_config.id, error
-                );
-                error
-            })
-            .unwrap();
+        let (broadcaster, _handle) = run_interface(
+            &interface_config,
+            dispatcher,
+            kill_switch_rx,
+            &self.interface_factories,
+        )
+        .map_err(|error| {
+            error!(
+                "conductor: Error running interface, even after {} attempts '{}': {}",
+                INTERFACE_CONNECT_ATTEMPTS_MAX, interface_config.id, error
+            );
+            error
+        })?;
 
         debug!("conductor: adding broadcaster to map {:?}", broadcaster);
 
@@ -1422,7 +1549,7 @@ impl Conductor {
                 .insert(interface_config.id, broadcaster);
         }
 
-        kill_switch_tx
+        Ok(kill_switch_tx)
     }
 
     pub fn dna_dir_path(&self) -> PathBuf {
@@ -1535,14 +1662,51 @@ impl Conductor {
     }
 }
 
-/// This can eventually be dependency injected for third party Interface definitions
-fn _make_interface(interface_config: &InterfaceConfiguration) -> Box<dyn Interface> {
-    use crate::interface_impls::{http::HttpInterface, websocket::WebsocketInterface};
-    match interface_config.driver {
-        InterfaceDriver::Websocket { port } => Box::new(WebsocketInterface::new(port)),
-        InterfaceDriver::Http { port } => Box::new(HttpInterface::new(port)),
-        _ => unimplemented!(),
-    }
+/// Parses the address a websocket or HTTP interface should bind to and loads
+/// its TLS certificate if it is configured to use TLS.
+fn interface_binding(
+    interface_config: &InterfaceConfiguration,
+) -> Result<(IpAddr, Option<TlsCertificate>), String> {
+    let bind_address = interface_config
+        .bind_address
+        .parse::<IpAddr>()
+        .map_err(|e| {
+            format!(
+                "Invalid bind address {}: {}",
+                interface_config.bind_address, e
+            )
+        })?;
+    let tls_certificate = match interface_config.tls {
+        Some(InterfaceTlsConfiguration::Pem {
+            ref certificate_file,
+            ref key_file,
+        }) => Some(
+            TlsCertificate::from_pem_files(certificate_file, key_file)
+                .map_err(|e| format!("Could not load TLS certificate: {}", e))?,
+        ),
+        Some(InterfaceTlsConfiguration::Dev) => Some(TlsCertificate::generate_dev()),
+        None => None,
+    };
+    Ok((bind_address, tls_certificate))
+}
+
+/// Looks up the factory registered for the `name` given in a custom driver's TOML table
+/// and has it create the interface.
+fn make_custom_interface(
+    value: &toml::Value,
+    interface_factories: &HashMap<String, InterfaceFactory>,
+) -> Result<Box<dyn Interface>, String> {
+    let name = value
+        .get("name")
+        .and_then(|name| name.as_str())
+        .ok_or_else(|| String::from("Custom interface driver needs a `name` string"))?;
+    let factory = interface_factories.get(name).ok_or_else(|| {
+        format!(
+            "No factory registered for custom interface driver '{}'",
+            name
+        )
+    })?;
+    factory(value)
 }
 
 #[allow(dead_code)]
@@ -1567,6 +1731,7 @@ fn run_interface(
     interface_config: &InterfaceConfiguration,
     handler: IoHandler,
     kill_switch: Receiver<()>,
+    interface_factories: &HashMap<String, InterfaceFactory>,
 ) -> Result<(Broadcaster, thread::JoinHandle<()>), String> {
     use crate::interface_impls::{http::HttpInterface, websocket::WebsocketInterface};
 
@@ -1577,8 +1742,12 @@ fn run_interface(
             } else {
                 port
             };
-            let mut interface = WebsocketInterface::new(port);
-            let r = interface.run(handler, kill_switch);
+            let (bind_address, tls_certificate) = interface_binding(interface_config)?;
+            let mut interface = WebsocketInterface::new(bind_address, port);
+            if let Some(certificate) = tls_certificate {
+                interface = interface.with_tls(certificate);
+            }
+            let r = interface.run(handler, kill_switch)?;
             let addr = interface
                 .bound_address()
                 .expect("Could not bind interface to address");
@@ -1586,7 +1755,7 @@ fn run_interface(
                 "{}",
                 magic_port_binding_string(&interface_config.id, addr.port())
             );
-            r
+            Ok(r)
         }
         InterfaceDriver::Http { port } => {
             let port = if interface_config.choose_free_port.unwrap_or(false) {
@@ -1594,8 +1763,12 @@ fn run_interface(
             } else {
                 port
             };
-            let mut interface = HttpInterface::new(port);
-            let r = interface.run(handler, kill_switch);
+            let (bind_address, tls_certificate) = interface_binding(interface_config)?;
+            let mut interface = HttpInterface::new(bind_address, port);
+            if let Some(certificate) = tls_certificate {
+                interface = interface.with_tls(certificate);
+            }
+            let r = interface.run(handler, kill_switch)?;
             let addr = interface
                 .bound_address()
                 .expect("Could not bind interface to address");
@@ -1603,9 +1776,40 @@ fn run_interface(
                 "{}",
                 magic_port_binding_string(&interface_config.id, addr.port())
             );
+            Ok(r)
+        }
+        #[cfg(unix)]
+        InterfaceDriver::DomainSocket {
+            ref file,
+            permissions,
+        } => {
+            use crate::interface_impls::domain_socket::DomainSocketInterface;
+            let mut interface = DomainSocketInterface::new(PathBuf::from(file), permissions);
+            let r = interface.run(handler, kill_switch);
+            if r.is_ok() {
+                notify(format!(
+                    "*** Bound interface '{}' to socket: {}",
+                    interface_config.id,
+                    interface.file().display()
+                ));
+            }
+            r
+        }
+        InterfaceDriver::Custom(ref value) => {
+            let mut interface = make_custom_interface(value, interface_factories)?;
+            let r = interface.run(handler, kill_switch);
+            if r.is_ok() {
+                notify(format!(
+                    "*** Started custom interface '{}'",
+                    interface_config.id
+                ));
+            }
             r
         }
-        _ => unimplemented!(),
+        #[cfg(not(unix))]
+        InterfaceDriver::DomainSocket { .. } => Err(String::from(
+            "domain socket interfaces are not supported on this platform",
+        )),
     }
 }
 
@@ -1923,7 +2127,7 @@ pub mod tests {
         assert_eq!(conductor.instances.len(), 3);
 
         conductor.start_all_instances().unwrap();
-        conductor.start_all_interfaces();
+        conductor.start_all_interfaces().unwrap();
         conductor.stop_all_instances().unwrap();
     }
 
@@ -2608,7 +2812,7 @@ pub mod tests {
     fn test_signals_through_admin_websocket() {
         let mut conductor = test_conductor(10031, 10032);
         let _ = conductor.start_all_instances();
-        conductor.start_all_interfaces();
+        conductor.start_all_interfaces().unwrap();
         thread::sleep(Duration::from_secs(2));
         // parking_lot::Mutex is an alternative Mutex that does not get poisoned if one of the
         // threads panic. Here it helps getting the causing assertion panic to be printed
@@ -2666,6 +2870,74 @@ pub mod tests {
         ));
     }
 
+    struct TestCustomInterface {
+        option: i64,
+        ran_with: Arc<Mutex<Option<i64>>>,
+    }
+
+    impl Interface for TestCustomInterface {
+        fn run(
+            &mut self,
+            _handler: IoHandler,
+            kill_switch: Receiver<()>,
+        ) -> Result<(Broadcaster, thread::JoinHandle<()>), String> {
+            *self.ran_with.lock().unwrap() = Some(self.option);
+            let handle = thread::spawn(move || {
+                let _ = kill_switch.recv();
+            });
+            Ok((Broadcaster::Noop, handle))
+        }
+    }
+
+    #[test]
+    fn test_custom_interface_factory() {
+        let ran_with = Arc::new(Mutex::new(None));
+        let ran_with_clone = ran_with.clone();
+        let conductor = test_conductor(10071, 10072).with_interface_factory(
+            "test-transport",
+            move |value: &toml::Value| -> Result<Box<dyn Interface>, String> {
+                Ok(Box::new(TestCustomInterface {
+                    option: value.get("option").and_then(|o| o.as_integer()).unwrap(),
+                    ran_with: ran_with_clone.clone(),
+                }))
+            },
+        );
+
+        let mut interface_config = conductor.config().interfaces[0].clone();
+        interface_config.driver = InterfaceDriver::Custom(
+            toml::from_str("name = \"test-transport\"\noption = 42").unwrap(),
+        );
+        let (kill_switch_tx, kill_switch_rx) = unbounded();
+        let (_, handle) = run_interface(
+            &interface_config,
+            IoHandler::new(),
+            kill_switch_rx,
+            &conductor.interface_factories,
+        )
+        .unwrap();
+        assert_eq!(*ran_with.lock().unwrap(), Some(42));
+        kill_switch_tx.send(()).unwrap();
+        handle.join().unwrap();
+
+        interface_config.driver =
+            InterfaceDriver::Custom(toml::from_str("name = \"unknown-transport\"").unwrap());
+        let (_kill_switch_tx, kill_switch_rx) = unbounded();
+        assert_eq!(
+            run_interface(
+                &interface_config,
+                IoHandler::new(),
+                kill_switch_rx,
+                &conductor.interface_factories,
+            )
+            .err(),
+            Some(
+                "No factory registered for custom interface driver 'unknown-transport'".to_string()
+            )
+        );
+        // the conductor hands the error back instead of panicking
+        assert!(conductor.spawn_interface_thread(interface_config).is_err());
+    }
+
     #[test]
     fn test_start_stop_instance() {
         let mut conductor = test_conductor(10051, 10052);
@@ -2690,4 +2962,57 @@ pub mod tests {
             Err(HolochainInstanceError::InstanceNotActiveYet),
         );
     }
+
+    #[test]
+    fn test_instance_verify_chain() {
+        let conductor = test_conductor(10053, 10054);
+        let report = conductor
+            .instance_verify_chain(&String::from("test-instance-1"))
+            .expect("Could not verify chain");
+        // the DNA and the AgentId entry
+        assert_eq!(report.headers_checked, 2);
+        assert_eq!(report.breaks, Vec::new());
+        assert_eq!(
+            conductor
+                .instance_verify_chain(&String::from("non-existant-id"))
+                .map(|_| ()),
+            Err(HolochainInstanceError::NoSuchInstance),
+        );
+    }
+
+    #[test]
+    fn test_instance_blocked_agents() {
+        let conductor = test_conductor(10057, 10058);
+        let blocked = conductor
+            .instance_blocked_agents(&String::from("test-instance-1"))
+            .expect("Could not list blocked agents");
+        assert!(blocked.is_empty());
+        assert_eq!(
+            conductor
+                .instance_blocked_agents(&String::from("non-existant-id"))
+                .map(|_| ()),
+            Err(HolochainInstanceError::NoSuchInstance),
+        );
+    }
+
+    #[test]
+    fn test_instance_validation_receipts() {
+        let conductor = test_conductor(10055, 10056);
+        let receipts = conductor
+            .instance_validation_receipts(
+                &String::from("test-instance-1"),
+                &Address::from("QmNotPublished"),
+            )
+            .expect("Could not get validation receipts");
+        assert_eq!(receipts, Vec::new());
+        assert_eq!(
+            conductor
+                .instance_validation_receipts(
+                    &String::from("non-existant-id"),
+                    &Address::from("QmNotPublished"),
+                )
+                .map(|_| ()),
+            Err(HolochainInstanceError::NoSuchInstance),
+        );
+    }
 }
@@ -1,11 +1,17 @@
+#[cfg(unix)]
+use crate::interface_impls::domain_socket::DomainSocketClients;
 use holochain_core_types::error::HolochainError;
 use holochain_json_api::json::JsonString;
 use jsonrpc_ws_server::ws;
+#[cfg(unix)]
+use std::{io::Write, net::Shutdown, sync::Arc};
 
 /// An abstraction which represents the ability to (maybe) send a message to the client
 /// over the existing connection.
 pub enum Broadcaster {
     Ws(jsonrpc_ws_server::Broadcaster),
+    #[cfg(unix)]
+    DomainSocket(DomainSocketClients),
     Noop,
 }
 
@@ -13,6 +19,8 @@ impl std::fmt::Debug for Broadcaster {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let variant = match self {
             Broadcaster::Ws(_) => "Ws",
+            #[cfg(unix)]
+            Broadcaster::DomainSocket(_) => "DomainSocket",
             Broadcaster::Noop => "Noop",
         };
         write!(f, "Broadcaster::{}", variant)
@@ -31,6 +39,26 @@ impl Broadcaster {
                 .map_err(|e| {
                     HolochainError::ErrorGeneric(format!("Broadcaster::Ws -- {}", e.to_string()))
                 })?,
+            #[cfg(unix)]
+            Broadcaster::DomainSocket(clients) => {
+                let msg = msg.into().to_string();
+                // Writing happens outside of the lock on the list, so a client that is slow
+                // to read only holds up this broadcast, and at most for its write timeout.
+                let receivers = clients.lock().unwrap().clone();
+                let failed: Vec<_> = receivers
+                    .into_iter()
+                    .filter(|client| writeln!(client.lock().unwrap(), "{}", msg).is_err())
+                    .collect();
+                // Clients that can't be written to anymore have disconnected or are stuck
+                // and get dropped from the list.
+                for client in failed.iter() {
+                    let _ = client.lock().unwrap().shutdown(Shutdown::Both);
+                }
+                clients
+                    .lock()
+                    .unwrap()
+                    .retain(|client| !failed.iter().any(|other| Arc::ptr_eq(other, client)));
+            }
             Broadcaster::Noop => (),
         }
         Ok(())
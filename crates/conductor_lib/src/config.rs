@@ -11,6 +11,7 @@ use crate::{conductor::base::DnaLoader, logger::LogRules};
 ///   the conductor
 /// * bridges, which are
 use boolinator::*;
+use holochain_core::wasm_engine::{engine::WasmEngineConfig, metering::WasmLimits};
 use holochain_core_types::{
     agent::{AgentId, Base32},
     dna::{
@@ -32,8 +33,9 @@ use std::{
     convert::TryFrom,
     fs::File,
     io::prelude::*,
-    net::Ipv4Addr,
+    net::{IpAddr, Ipv4Addr},
     path::PathBuf,
+    str::FromStr,
     sync::Arc,
 };
 use toml;
@@ -57,6 +59,10 @@ pub struct Configuration {
     /// List of interfaces any UI can use to access zome functions. Optional.
     #[serde(default)]
     pub interfaces: Vec<InterfaceConfiguration>,
+    /// Credentials required for calling admin functions on admin interfaces. Optional.
+    /// If not set, admin interfaces don't check who is calling.
+    #[serde(default)]
+    pub interface_auth: Option<InterfaceAuthConfiguration>,
 
     /// List of bridges between instances. Optional.
     #[serde(default)]
@@ -121,6 +127,11 @@ pub struct Configuration {
 
     #[serde(default)]
     pub metric_publisher: Option<MetricPublisherConfig>,
+
+    /// The engine that runs zome code of all instances that don't configure their own.
+    /// Defaults to the wasmi interpreter.
+    #[serde(default)]
+    pub wasm_engine: WasmEngineConfig,
 }
 
 /// The default passphrase service is `Cmd` which will ask for a passphrase via stdout stdin.
@@ -263,6 +274,12 @@ impl Configuration {
                     instance.dna, instance.id
                 )
             })?;
+            (!instance.journal || instance.storage.path().is_some()).ok_or_else(|| {
+                format!(
+                    "Instance \"{}\" has a journal, which needs a storage with a path",
+                    instance.id
+                )
+            })?;
             let dna_config = dna_config.unwrap();
             let dna =
                 Arc::get_mut(&mut dna_loader).unwrap()(&PathBuf::from(dna_config.file.clone()))
@@ -288,6 +305,24 @@ impl Configuration {
         }
 
         for ref interface in self.interfaces.iter() {
+            interface.bind_address.parse::<IpAddr>().map_err(|_| {
+                format!(
+                    "Invalid bind address \"{}\" for interface \"{}\"",
+                    interface.bind_address, interface.id
+                )
+            })?;
+            if let InterfaceDriver::Custom(ref value) = interface.driver {
+                value
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .is_some()
+                    .ok_or_else(|| {
+                        format!(
+                            "Custom driver of interface \"{}\" needs a `name` string",
+                            interface.id
+                        )
+                    })?;
+            }
             for ref instance in interface.instances.iter() {
                 self.instance_by_id(&instance.id).is_some().ok_or_else(|| {
                     format!(
@@ -298,6 +333,15 @@ impl Configuration {
             }
         }
 
+        if let Some(ref auth) = self.interface_auth {
+            detect_dupes("auth token", auth.tokens.iter().map(|c| &c.id))?;
+            for token in auth.tokens.iter() {
+                (!token.token.is_empty()).ok_or_else(|| {
+                    format!("Token of interface auth token \"{}\" is empty", token.id)
+                })?;
+            }
+        }
+
         for bridge in self.bridges.iter() {
             self.check_bridge_requirements(bridge, dna_loader)?;
         }
@@ -344,6 +388,14 @@ impl Configuration {
                     "Passphrase service type 'unixsocket' is not available on non-Unix systems",
                 ));
             }
+            for interface in self.interfaces.iter() {
+                if let InterfaceDriver::DomainSocket { .. } = interface.driver {
+                    return Err(format!(
+                        "Interface '{}': driver type 'domainsocket' is not available on non-Unix systems",
+                        interface.id
+                    ));
+                }
+            }
         }
 
         Ok(())
@@ -647,7 +699,8 @@ impl Configuration {
             .filter_map(|stg_config| match stg_config.storage {
                 StorageConfiguration::File { ref path }
                 | StorageConfiguration::Lmdb { ref path, .. }
-                | StorageConfiguration::Pickle { ref path } => Some(path.as_str()),
+                | StorageConfiguration::Pickle { ref path }
+                | StorageConfiguration::Sqlite { ref path } => Some(path.as_str()),
                 _ => None,
             })
             .collect();
@@ -720,15 +773,41 @@ pub struct InstanceConfiguration {
     pub dna: String,
     pub agent: String,
     pub storage: StorageConfiguration,
+    /// Limits on instructions, memory and duration of every zome and callback call. Optional.
+    #[serde(default, skip_serializing_if = "WasmLimits::is_unlimited")]
+    pub wasm_limits: WasmLimits,
+    /// The engine that runs the zomes of this instance. Optional,
+    /// falls back to the conductor wide `wasm_engine`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm_engine: Option<WasmEngineConfig>,
+    /// If true, every dispatched action gets written to a journal next to the storage
+    /// and replayed on top of the stored state when the instance gets loaded.
+    /// Needs a storage with a path. Optional, defaults to false.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub journal: bool,
+    /// Bytes of DHT data the instance holds for other agents before it starts evicting
+    /// the entries furthest from its location. Optional, unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_quota: Option<usize>,
+    /// Proof that the agent may join the DNA's network, usually JSON, like an invite
+    /// signed by an existing member. Gets committed with the agent entry when the
+    /// instance is initialized. Optional.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub membrane_proof: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
 /// This configures the Content Addressable Storage (CAS) that
 /// the instance uses to store source chain and DHT shard in.
-/// There are two storage implementations in cas_implementations so far:
+/// There are these storage implementations so far:
 /// * memory
 /// * file
-///
-/// Projected are various DB adapters.
+/// * pickle
+/// * lmdb
+/// * sqlite
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum StorageConfiguration {
@@ -743,6 +822,66 @@ pub enum StorageConfiguration {
         path: String,
         initial_mmap_bytes: Option<usize>,
     },
+    Sqlite {
+        path: String,
+    },
+}
+
+impl StorageConfiguration {
+    /// The directory of the storage, None for memory storage
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            StorageConfiguration::Memory => None,
+            StorageConfiguration::File { path }
+            | StorageConfiguration::Pickle { path }
+            | StorageConfiguration::Lmdb { path, .. }
+            | StorageConfiguration::Sqlite { path } => Some(path.as_str()),
+        }
+    }
+
+    /// The same kind of storage in another directory
+    pub fn with_path(&self, new_path: String) -> Self {
+        match self {
+            StorageConfiguration::Memory => StorageConfiguration::Memory,
+            StorageConfiguration::File { .. } => StorageConfiguration::File { path: new_path },
+            StorageConfiguration::Pickle { .. } => StorageConfiguration::Pickle { path: new_path },
+            StorageConfiguration::Lmdb {
+                initial_mmap_bytes, ..
+            } => StorageConfiguration::Lmdb {
+                path: new_path,
+                initial_mmap_bytes: *initial_mmap_bytes,
+            },
+            StorageConfiguration::Sqlite { .. } => StorageConfiguration::Sqlite { path: new_path },
+        }
+    }
+}
+
+/// Parses the `<type>:<path>` notation the CLI uses for storage, e.g. `lmdb:/path/to/storage`.
+/// Memory storage has no path and is just `memory`.
+impl FromStr for StorageConfiguration {
+    type Err = HolochainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let storage_type = parts.next().unwrap_or_default();
+        let path = parts
+            .next()
+            .filter(|path| !path.is_empty())
+            .map(String::from);
+        match (storage_type, path) {
+            ("memory", None) => Ok(StorageConfiguration::Memory),
+            ("file", Some(path)) => Ok(StorageConfiguration::File { path }),
+            ("pickle", Some(path)) => Ok(StorageConfiguration::Pickle { path }),
+            ("lmdb", Some(path)) => Ok(StorageConfiguration::Lmdb {
+                path,
+                initial_mmap_bytes: None,
+            }),
+            ("sqlite", Some(path)) => Ok(StorageConfiguration::Sqlite { path }),
+            _ => Err(HolochainError::ConfigError(format!(
+                "Invalid storage '{}', expected <memory|file|pickle|lmdb|sqlite>:<path>",
+                s
+            ))),
+        }
+    }
 }
 
 /// Here, interfaces are user facing and make available zome functions to
@@ -750,9 +889,7 @@ pub enum StorageConfiguration {
 /// We currently have:
 /// * websockets
 /// * HTTP
-///
-/// We will also soon develop
-/// * Unix domain sockets
+/// * Unix domain sockets (Unix only)
 ///
 /// The instances (referenced by ID) that are to be made available via that interface should be listed.
 /// An admin flag will enable conductor functions for programatically changing the configuration
@@ -771,17 +908,93 @@ pub struct InterfaceConfiguration {
     /// is not aware of this logic and is not tracking the new port (which gets printed on stdout).
     /// Use at your own risk...
     pub choose_free_port: Option<bool>,
+
+    #[serde(default = "default_address")]
+    /// Address to bind to (websocket and http only)
+    /// Can be either ip4 or ip6
+    /// Default = "127.0.0.1"
+    pub bind_address: String,
+
+    /// Serve the interface via TLS, i.e. as wss:// or https:// (websocket and http only)
+    /// (Optional)
+    #[serde(default)]
+    pub tls: Option<InterfaceTlsConfiguration>,
+}
+
+/// Where an interface gets its TLS certificate from.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum InterfaceTlsConfiguration {
+    /// PEM encoded certificate (chain, leaf certificate first) and private key files
+    Pem {
+        certificate_file: String,
+        key_file: String,
+    },
+    /// Self-signed certificate that gets generated on every start.
+    /// Clients can't verify it, so this is only meant for development.
+    Dev,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum InterfaceDriver {
-    Websocket { port: u16 },
-    Http { port: u16 },
-    DomainSocket { file: String },
+    Websocket {
+        port: u16,
+    },
+    Http {
+        port: u16,
+    },
+    /// Newline-delimited JSON-RPC over a Unix domain socket at the path given by `file`.
+    /// `permissions` optionally sets the mode of the socket file (e.g. `0o660`)
+    /// which is what controls who can connect.
+    DomainSocket {
+        file: String,
+        #[serde(default)]
+        permissions: Option<u32>,
+    },
     Custom(toml::value::Value),
 }
 
+/// Who may call which of the protected methods of admin interfaces.
+/// Callers authenticate either with one of the configured bearer `tokens` or by signing
+/// a challenge (see `auth/challenge`) with the key of one of the configured `agents`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct InterfaceAuthConfiguration {
+    /// Method name patterns that require credentials, where a trailing `*` matches any suffix.
+    /// Default = ["admin/*", "debug/*", "test/*"]
+    #[serde(default = "default_protected_methods")]
+    pub protected_methods: Vec<String>,
+    #[serde(default)]
+    pub tokens: Vec<AuthTokenConfiguration>,
+    #[serde(default)]
+    pub agents: Vec<AuthAgentConfiguration>,
+}
+
+pub fn default_protected_methods() -> Vec<String> {
+    vec![
+        String::from("admin/*"),
+        String::from("debug/*"),
+        String::from("test/*"),
+    ]
+}
+
+/// A bearer token that is passed as `auth_token` param.
+/// `permissions` lists the method name patterns it may be used for.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AuthTokenConfiguration {
+    pub id: String,
+    pub token: String,
+    pub permissions: Vec<String>,
+}
+
+/// An agent that authenticates by signing challenges with the key of `public_address`.
+/// `permissions` lists the method name patterns it may call.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AuthAgentConfiguration {
+    pub public_address: Base32,
+    pub permissions: Vec<String>,
+}
+
 /// An instance reference makes an instance available in the scope
 /// of an interface.
 /// Since UIs usually hard-code the name with which they reference an instance,
@@ -863,7 +1076,7 @@ fn default_reroute() -> bool {
     true
 }
 
-fn default_address() -> String {
+pub fn default_address() -> String {
     Ipv4Addr::LOCALHOST.to_string()
 }
 
@@ -1006,9 +1219,17 @@ pub mod tests {
     id = "app spec instance"
     dna = "app spec rust"
     agent = "test agent"
+    journal = true
+    storage_quota = 100000000
+    membrane_proof = '{"invite":"signed by bob"}'
         [instances.storage]
         type = "file"
         path = "app_spec_storage"
+        [instances.wasm_limits]
+        max_instructions = 1000000
+        max_duration_ms = 500
+        [instances.wasm_engine]
+        type = "wasmer"
 
     [[interfaces]]
     id = "app spec websocket interface"
@@ -1031,6 +1252,7 @@ pub mod tests {
         [interfaces.driver]
         type = "domainsocket"
         file = "/tmp/holochain.sock"
+        permissions = 0o660
         [[interfaces.instances]]
         id = "app spec instance"
 
@@ -1059,7 +1281,31 @@ pub mod tests {
         assert_eq!(instance_config.id, "app spec instance");
         assert_eq!(instance_config.dna, "app spec rust");
         assert_eq!(instance_config.agent, "test agent");
+        assert_eq!(
+            instance_config.wasm_limits,
+            WasmLimits::default()
+                .with_max_instructions(1_000_000)
+                .with_max_duration_ms(500)
+        );
+        assert_eq!(
+            instance_config.wasm_engine,
+            Some(WasmEngineConfig::Wasmer { cache_dir: None })
+        );
+        assert!(instance_config.journal);
+        assert_eq!(instance_config.storage_quota, Some(100_000_000));
+        assert_eq!(
+            instance_config.membrane_proof,
+            Some(String::from(r#"{"invite":"signed by bob"}"#))
+        );
+        assert_eq!(config.wasm_engine, WasmEngineConfig::Wasmi);
         assert_eq!(config.logger.logger_level, "debug");
+        assert_eq!(
+            config.interfaces.get(2).unwrap().driver,
+            InterfaceDriver::DomainSocket {
+                file: "/tmp/holochain.sock".to_string(),
+                permissions: Some(0o660),
+            }
+        );
         assert_eq!(format!("{:?}", config.metric_publisher), "Some(CloudWatchLogs(CloudWatchLogsConfig { region: None, log_group_name: Some(\"holochain\"), log_stream_name: Some(\"2019-11-22_20-53-31.sim2h_public\"), assume_role_arn: None }))");
         assert_eq!(
             config.network.unwrap(),
@@ -1275,6 +1521,139 @@ pub mod tests {
         );
     }
 
+    fn custom_interface_toml(driver: &str) -> String {
+        format!(
+            r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    keystore_file = "holo_tester.key"
+
+    [[interfaces]]
+    id = "custom interface"
+        [interfaces.driver]
+        type = "custom"
+        {}
+    "#,
+            driver
+        )
+    }
+
+    #[test]
+    fn test_custom_interface_config() {
+        let toml = custom_interface_toml(
+            r#"name = "grpc"
+        port = 5000"#,
+        );
+        let config = load_configuration::<Configuration>(&toml).unwrap();
+        assert_eq!(config.check_consistency(&mut test_dna_loader()), Ok(()));
+        match config.interfaces.get(0).unwrap().driver {
+            InterfaceDriver::Custom(ref value) => {
+                assert_eq!(value.get("name").unwrap().as_str(), Some("grpc"));
+                assert_eq!(value.get("port").unwrap().as_integer(), Some(5000));
+            }
+            ref other => panic!("Expected custom driver, got {:?}", other),
+        }
+
+        let toml = custom_interface_toml("port = 5000");
+        let config = load_configuration::<Configuration>(&toml).unwrap();
+        assert_eq!(
+            config.check_consistency(&mut test_dna_loader()),
+            Err(
+                "Custom driver of interface \"custom interface\" needs a `name` string".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_interface_bind_address_and_tls() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    keystore_file = "holo_tester.key"
+
+    [[interfaces]]
+    id = "default interface"
+        [interfaces.driver]
+        type = "websocket"
+        port = 8888
+
+    [[interfaces]]
+    id = "tls interface"
+    bind_address = "::"
+        [interfaces.driver]
+        type = "http"
+        port = 4000
+        [interfaces.tls]
+        type = "pem"
+        certificate_file = "cert.pem"
+        key_file = "key.pem"
+    "#;
+        let config = load_configuration::<Configuration>(toml).unwrap();
+        assert_eq!(config.check_consistency(&mut test_dna_loader()), Ok(()));
+        assert_eq!(config.interfaces[0].bind_address, "127.0.0.1");
+        assert_eq!(config.interfaces[0].tls, None);
+        assert_eq!(config.interfaces[1].bind_address, "::");
+        assert_eq!(
+            config.interfaces[1].tls,
+            Some(InterfaceTlsConfiguration::Pem {
+                certificate_file: "cert.pem".to_string(),
+                key_file: "key.pem".to_string(),
+            })
+        );
+
+        let mut config = config;
+        config.interfaces[0].bind_address = "localhost".to_string();
+        assert_eq!(
+            config.check_consistency(&mut test_dna_loader()),
+            Err(
+                "Invalid bind address \"localhost\" for interface \"default interface\""
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_interface_auth() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    keystore_file = "holo_tester.key"
+
+    [interface_auth]
+        [[interface_auth.tokens]]
+        id = "monitoring"
+        token = "some secret"
+        permissions = ["debug/*", "admin/instance/list"]
+
+        [[interface_auth.agents]]
+        public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+        permissions = ["*"]
+    "#;
+        let config = load_configuration::<Configuration>(toml).unwrap();
+        assert_eq!(config.check_consistency(&mut test_dna_loader()), Ok(()));
+        let auth = config.interface_auth.clone().unwrap();
+        assert_eq!(auth.protected_methods, default_protected_methods());
+        assert_eq!(auth.tokens[0].id, "monitoring");
+        assert_eq!(
+            auth.tokens[0].permissions,
+            vec!["debug/*".to_string(), "admin/instance/list".to_string()]
+        );
+        assert_eq!(auth.agents[0].permissions, vec!["*".to_string()]);
+
+        let mut config = config;
+        config.interface_auth.as_mut().unwrap().tokens[0].token = String::new();
+        assert_eq!(
+            config.check_consistency(&mut test_dna_loader()),
+            Err("Token of interface auth token \"monitoring\" is empty".to_string())
+        );
+    }
+
     #[test]
     fn test_invalid_toml_1() {
         let toml = &format!(
@@ -1675,4 +2054,27 @@ pub mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_parse_storage_configuration() {
+        assert_eq!(
+            StorageConfiguration::from_str("lmdb:/tmp/storage"),
+            Ok(StorageConfiguration::Lmdb {
+                path: "/tmp/storage".to_string(),
+                initial_mmap_bytes: None,
+            })
+        );
+        assert_eq!(
+            StorageConfiguration::from_str("file:C:/storage"),
+            Ok(StorageConfiguration::File {
+                path: "C:/storage".to_string(),
+            })
+        );
+        assert_eq!(
+            StorageConfiguration::from_str("memory"),
+            Ok(StorageConfiguration::Memory)
+        );
+        assert!(StorageConfiguration::from_str("sqlite").is_err());
+        assert!(StorageConfiguration::from_str("redis:/tmp/storage").is_err());
+    }
 }
@@ -1,6 +1,19 @@
-use holochain_core::{context::Context, persister::SimplePersister, signal::SignalSender};
+use crate::storage::{
+    sqlite::{EavSqliteStorage, SqliteStorage},
+    InstanceStorage,
+};
+use holochain_core::{
+    context::Context,
+    journal::Journal,
+    persister::SimplePersister,
+    signal::SignalSender,
+    wasm_engine::{
+        engine::{create_wasm_engine, WasmEngine, WasmEngineConfig},
+        metering::WasmLimits,
+    },
+};
 use holochain_core_types::{agent::AgentId, eav::Attribute, error::HolochainError};
-use holochain_locksmith::RwLock;
+use holochain_locksmith::{Mutex, RwLock};
 use holochain_net::p2p_config::P2pConfig;
 use holochain_persistence_api::{
     cas::storage::ContentAddressableStorage, eav::EntityAttributeValueStorage,
@@ -43,6 +56,11 @@ pub struct ContextBuilder {
     tracer: Option<holochain_tracing::Tracer>,
     state_dump_logging: bool,
     metric_publisher: Option<Arc<RwLock<dyn MetricPublisher>>>,
+    wasm_limits: WasmLimits,
+    wasm_engine: Option<Arc<dyn WasmEngine>>,
+    journal: Option<Journal>,
+    storage_quota: Option<usize>,
+    membrane_proof: Option<String>,
 }
 
 impl ContextBuilder {
@@ -60,6 +78,11 @@ impl ContextBuilder {
             tracer: None,
             state_dump_logging: false,
             metric_publisher: None,
+            wasm_limits: WasmLimits::default(),
+            wasm_engine: None,
+            journal: None,
+            storage_quota: None,
+            membrane_proof: None,
         }
     }
 
@@ -143,6 +166,31 @@ impl ContextBuilder {
         Ok(self)
     }
 
+    /// Sets all three storages, chain, DHT and EAV storage, to persistent SQLite based implementations.
+    /// All of them get stored in one database file inside the given directory.
+    /// Chain and DHT storages get set to the same SQLite CAS.
+    /// Returns an error if the database could not be opened or created.
+    pub fn with_sqlite_storage<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HolochainError> {
+        fs::create_dir_all(path.as_ref())?;
+
+        let cas_storage = Arc::new(RwLock::new(SqliteStorage::new(path.as_ref())?));
+        let eav_storage = Arc::new(RwLock::new(EavSqliteStorage::new(path.as_ref())?));
+        self.chain_storage = Some(cas_storage.clone());
+        self.dht_storage = Some(cas_storage);
+        self.eav_storage = Some(eav_storage);
+        Ok(self)
+    }
+
+    /// Sets all three storages, chain, DHT and EAV storage, to the ones of an instance storage
+    /// that was already opened, so tools can work on it through a context.
+    /// Chain and DHT storages get set to its CAS.
+    pub fn with_instance_storage(mut self, storage: &InstanceStorage) -> Self {
+        self.chain_storage = Some(storage.cas.clone());
+        self.dht_storage = Some(storage.cas.clone());
+        self.eav_storage = Some(storage.eav.clone());
+        self
+    }
+
     /// Sets the network config.
     pub fn with_p2p_config(mut self, p2p_config: P2pConfig) -> Self {
         self.p2p_config = Some(p2p_config);
@@ -174,6 +222,41 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets the limits every zome and callback call of the instance has to stay within.
+    pub fn with_wasm_limits(mut self, wasm_limits: WasmLimits) -> Self {
+        self.wasm_limits = wasm_limits;
+        self
+    }
+
+    /// Sets the engine that runs the zomes of the instance.
+    /// Returns an error if the engine could not be created, e.g. because its cache
+    /// directory is not accessible.
+    pub fn with_wasm_engine(mut self, config: &WasmEngineConfig) -> Result<Self, HolochainError> {
+        self.wasm_engine = Some(create_wasm_engine(config)?);
+        Ok(self)
+    }
+
+    /// Switches on the journal of dispatched actions, kept in the given storage directory.
+    /// Returns an error if the journal file could not be opened.
+    pub fn with_journal<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HolochainError> {
+        self.journal = Some(Journal::open(path)?);
+        Ok(self)
+    }
+
+    /// Sets the number of bytes of DHT data the instance holds for others
+    /// before it starts evicting entries.
+    pub fn with_storage_quota(mut self, storage_quota: Option<usize>) -> Self {
+        self.storage_quota = storage_quota;
+        self
+    }
+
+    /// Sets the proof that the agent may join the network.
+    /// It gets committed with the agent entry when the instance gets initialized.
+    pub fn with_membrane_proof(mut self, membrane_proof: Option<String>) -> Self {
+        self.membrane_proof = membrane_proof;
+        self
+    }
+
     pub fn with_metric_publisher(mut self, config: &MetricPublisherConfig) -> Self {
         let config = match &config {
             MetricPublisherConfig::CloudWatchLogs(config) => {
@@ -213,7 +296,7 @@ impl ContextBuilder {
             .metric_publisher
             .unwrap_or_else(|| Arc::new(RwLock::new(DefaultMetricPublisher::default())));
 
-        Context::new(
+        let mut context = Context::new(
             &self
                 .instance_name
                 .unwrap_or_else(|| "Anonymous-instance".to_string()),
@@ -234,7 +317,15 @@ impl ContextBuilder {
                 self.tracer
                     .unwrap_or_else(|| holochain_tracing::null_tracer()),
             ),
-        )
+        );
+        context.wasm_limits = self.wasm_limits;
+        context.storage_quota = self.storage_quota;
+        context.membrane_proof = self.membrane_proof;
+        if let Some(wasm_engine) = self.wasm_engine {
+            context.wasm_engine = wasm_engine;
+        }
+        context.journal = self.journal.map(|journal| Arc::new(Mutex::new(journal)));
+        context
     }
 }
 
@@ -292,5 +383,11 @@ mod tests {
             .expect("Filestorage should get instantiated with tempdir")
             .with_conductor_api(mock_conductor_api(AgentId::generate_fake("alice")))
             .spawn();
+        let temp = tempdir().expect("test was supposed to create temp dir");
+        let _ = ContextBuilder::new()
+            .with_sqlite_storage(temp.path())
+            .expect("SQLite storage should get instantiated with tempdir")
+            .with_conductor_api(mock_conductor_api(AgentId::generate_fake("alice")))
+            .spawn();
     }
 }
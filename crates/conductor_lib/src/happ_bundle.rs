@@ -125,6 +125,11 @@ impl HappBundle {
                 dna: happ_instance.id.clone(),
                 agent: agent_config.id.clone(),
                 storage: storage.clone(),
+                wasm_limits: Default::default(),
+                wasm_engine: None,
+                journal: false,
+                storage_quota: None,
+                membrane_proof: None,
             })
             .collect::<Vec<_>>();
 
@@ -146,6 +151,8 @@ impl HappBundle {
                 driver: InterfaceDriver::Websocket { port },
                 admin: false,
                 choose_free_port: Some(false),
+                bind_address: default_address(),
+                tls: None,
                 instances: ui
                     .instance_references
                     .iter()
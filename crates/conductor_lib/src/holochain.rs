@@ -95,6 +95,7 @@ use crate::error::{HolochainInstanceError, HolochainResult};
 use holochain_core::{
     context::Context,
     instance::Instance,
+    journal::replay,
     nucleus::{call_zome_function, ZomeFnCall},
     persister::{Persister, SimplePersister},
     wasm_engine::{run_dna, WasmCallData},
@@ -175,11 +176,29 @@ impl Holochain {
         }
     }
 
+    /// Loads the instance from the state snapshots in its storage.
+    /// If the context has a journal, the actions that were journaled after the last
+    /// saved snapshot get replayed and the resulting state gets saved.
     pub fn load(context: Arc<Context>) -> Result<Self, HolochainError> {
-        let persister = SimplePersister::new(context.dht_storage.clone());
-        let loaded_state = persister.load(context.clone())?.ok_or_else(|| {
+        let mut persister = SimplePersister::new(context.dht_storage.clone());
+        let mut loaded_state = persister.load(context.clone())?.ok_or_else(|| {
             HolochainError::ErrorGeneric("State could not be loaded due to NoneError".to_string())
         })?;
+        if let Some(journal) = &context.journal {
+            let mut journal = journal.lock().unwrap();
+            let (state, replayed) = replay(loaded_state, &journal.unsaved_records()?)?;
+            if replayed > 0 {
+                persister.save(&StateWrapper::from(state.clone()))?;
+                journal.mark_snapshot()?;
+                log_info!(
+                    context,
+                    "conductor: replayed {} actions from journal {}",
+                    replayed,
+                    journal.path().display()
+                );
+            }
+            loaded_state = state;
+        }
         let mut instance = Instance::from_state(loaded_state, context.clone());
         let new_context = instance.initialize(None, context)?;
         Ok(Holochain {
@@ -322,13 +341,14 @@ mod tests {
     use super::*;
     use crate::context_builder::ContextBuilder;
     use holochain_core::{
-        action::Action,
+        action::{Action, ActionWrapper},
         context::Context,
+        journal::Journal,
         logger::{test_logger, TestLogger},
         nucleus::actions::call_zome_function::make_cap_request_for_call,
         signal::{signal_channel, SignalReceiver},
     };
-    use holochain_core_types::dna::capabilities::CapabilityRequest;
+    use holochain_core_types::{dna::capabilities::CapabilityRequest, entry::test_entry};
     use holochain_json_api::json::RawString;
     use holochain_locksmith::Mutex;
     use holochain_persistence_api::cas::content::{Address, AddressableContent};
@@ -473,6 +493,44 @@ mod tests {
         assert_eq!(network_state.dna_address.is_some(), true);
     }
 
+    #[test]
+    fn replays_journal_on_load() {
+        let temp = tempdir().unwrap();
+        let storage_dir = temp.path().to_str().unwrap();
+        let agent = registered_test_agent("journal");
+        let context = || {
+            Arc::new(
+                ContextBuilder::new()
+                    .with_agent(agent.clone())
+                    .with_conductor_api(mock_conductor_api(agent.clone()))
+                    .with_file_storage(storage_dir)
+                    .unwrap()
+                    .with_journal(storage_dir)
+                    .unwrap()
+                    .spawn(),
+            )
+        };
+
+        let mut hc = Holochain::new(create_arbitrary_test_dna(), context()).unwrap();
+        let state = hc.state().unwrap();
+        hc.kill();
+        // Simulates a crash after a commit got reduced and journaled,
+        // before the resulting state got saved
+        let commit = ActionWrapper::new(Action::Commit((test_entry(), None, Vec::new())));
+        let committed_state = state.reduce(commit.clone());
+        let mut journal = Journal::open(storage_dir).unwrap();
+        journal.append_action(&commit).unwrap();
+        journal
+            .append_commits(&commit, &state.agent(), &committed_state.agent())
+            .unwrap();
+
+        let hc = Holochain::load(context()).unwrap();
+        assert_eq!(
+            hc.state().unwrap().agent().top_chain_header(),
+            committed_state.agent().top_chain_header()
+        );
+    }
+
     #[test]
     fn fails_instantiate_if_init_fails() {
         let dna = create_test_dna_with_wat(
@@ -7,7 +7,10 @@ use holochain_core::{
 
 use crate::Holochain;
 use holochain_core_types::{
-    agent::AgentId, dna::capabilities::CapabilityRequest, signature::Provenance,
+    agent::{AgentId, Base32},
+    dna::capabilities::CapabilityRequest,
+    entry::encrypted_entry::SealedData,
+    signature::Provenance,
 };
 use holochain_dpki::key_bundle::KeyBundle;
 use holochain_json_api::json::JsonString;
@@ -24,7 +27,7 @@ use crate::{
         CONDUCTOR,
     },
     config::{
-        AgentConfiguration, Bridge, DnaConfiguration, InstanceConfiguration,
+        default_address, AgentConfiguration, Bridge, DnaConfiguration, InstanceConfiguration,
         InterfaceConfiguration, InterfaceDriver, UiBundleConfiguration, UiInterfaceConfiguration,
     },
     keystore::{KeyType, Keystore, Secret},
@@ -400,6 +403,10 @@ impl ConductorApiBuilder {
     ///     * `id`: [string] Name for the new instance
     ///     * `agent_id`: [string] Agent to run this instance with
     ///     * `dna_id`: [string] DNA to run in this instance
+    ///     * `storage`: [string] Optional, one of `memory`, `file`, `pickle`, `lmdb` (default)
+    ///       or `sqlite`
+    ///     * `membrane_proof`: [string] Optional proof that the agent may join the DNA's
+    ///       network, committed with its agent entry
     ///
     ///  * `admin/instance/remove`
     ///     Removes an instance. Also remove its any uses of it in interfaces.
@@ -416,12 +423,59 @@ impl ConductorApiBuilder {
     ///     Params:
     ///     * `id`: [string] Which instance to stop?
     ///
+    ///  * `admin/instance/migrate_storage`
+    ///     Copies all data of a stopped instance into a new, empty storage and switches
+    ///     the instance over to it. The old storage is kept.
+    ///     Returns the number of CAS items and EAVIs copied and which state snapshots were found.
+    ///     Params:
+    ///     * `id`: [string] Which instance to migrate?
+    ///     * `storage`: [string] One of `file`, `pickle`, `lmdb` or `sqlite`
+    ///     * `path`: [string] Optional, directory of the new storage.
+    ///       Defaults to `<id>-<storage>` in the storage directory of the conductor
+    ///
+    ///  * `admin/instance/export`
+    ///     Writes all data of a stopped instance, together with its DNA and agent,
+    ///     to a single compressed archive. Returns the archive's manifest.
+    ///     Params:
+    ///     * `id`: [string] Which instance to export?
+    ///     * `path`: [string] Path of the archive file to write
+    ///
+    ///  * `admin/instance/import`
+    ///     Creates a new instance from an archive written by `admin/instance/export`,
+    ///     adding its DNA and agent unless they are configured already.
+    ///     Does not start the instance nor add it to an interface.
+    ///     Returns the archive's manifest.
+    ///     Params:
+    ///     * `path`: [string] Path of the archive file
+    ///     * `id`: [string] Optional, ID of the new instance. Defaults to the exported one
+    ///     * `storage`: [string] Optional, one of `file`, `pickle`, `lmdb` (default) or `sqlite`
+    ///
     ///  * `admin/instance/get_meta`
     ///     Gets meta-data about a hash on an instance
     ///     Params:
     ///     * `id`: [string] Which instance to get data from?
     ///     * `hash`: [string] hash to get data about
     ///
+    ///  * `admin/instance/verify_chain`
+    ///     Checks the integrity of an instance's source chain: that all header links
+    ///     resolve, that entries match their addresses, that signatures verify and
+    ///     that timestamps are monotonic. Returns a report listing every break found.
+    ///     Params:
+    ///     * `id`: [string] Which instance to verify?
+    ///
+    ///  * `admin/instance/validation_receipts`
+    ///     Returns the number of validators that confirmed holding an entry the instance's
+    ///     agent authored, together with their signed receipts.
+    ///     Params:
+    ///     * `id`: [string] Which instance authored the entry?
+    ///     * `entry_address`: [string] Address of the entry
+    ///
+    ///  * `admin/instance/blocked_agents`
+    ///     Returns an object mapping every agent the instance blocked to the warrants
+    ///     it confirmed against that agent.
+    ///     Params:
+    ///     * `id`: [string] Which instance to get the blocked agents of?
+    ///
     ///  * `admin/instance/list`
     ///     Returns an array of all instances that are configured.
     ///
@@ -558,11 +612,13 @@ impl ConductorApiBuilder {
             let dna_id = Self::get_as_string("dna_id", &params_map)?;
             let agent_id = Self::get_as_string("agent_id", &params_map)?;
             let storage = Self::get_as_string("storage", &params_map).ok();
+            let membrane_proof = Self::get_as_string("membrane_proof", &params_map).ok();
             conductor_call!(|c| c.add_instance(
                 &id,
                 &dna_id,
                 &agent_id,
-                storage.as_ref().map(String::as_str)
+                storage.as_ref().map(String::as_str),
+                membrane_proof
             ))?;
             Ok(json!({"success": true}))
         });
@@ -574,6 +630,44 @@ impl ConductorApiBuilder {
             Ok(json!({"success": true}))
         });
 
+        self.io
+            .add_method("admin/instance/migrate_storage", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                let storage = Self::get_as_string("storage", &params_map)?;
+                let path = Self::get_as_string("path", &params_map).ok();
+                let report = conductor_call!(|c| c.migrate_instance_storage(&id, &storage, path))?;
+                Ok(serde_json::to_value(report)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?)
+            });
+
+        self.io.add_method("admin/instance/export", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let id = Self::get_as_string("id", &params_map)?;
+            let path = PathBuf::from(Self::get_as_string("path", &params_map)?);
+            let manifest = conductor_call!(|c| c.export_instance(&id, &path))?;
+            Ok(
+                serde_json::to_value(manifest)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?,
+            )
+        });
+
+        self.io.add_method("admin/instance/import", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let path = PathBuf::from(Self::get_as_string("path", &params_map)?);
+            let id = Self::get_as_string("id", &params_map).ok();
+            let storage = Self::get_as_string("storage", &params_map).ok();
+            let manifest = conductor_call!(|c| c.import_instance(
+                &path,
+                id,
+                storage.as_ref().map(String::as_str)
+            ))?;
+            Ok(
+                serde_json::to_value(manifest)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?,
+            )
+        });
+
         self.io.add_method("admin/instance/start", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let id = Self::get_as_string("id", &params_map)?;
@@ -592,6 +686,39 @@ impl ConductorApiBuilder {
                     .map_err(|_| jsonrpc_core::Error::internal_error())?)
             });
 
+        self.io
+            .add_method("admin/instance/verify_chain", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                let report = conductor_call!(|c| c.instance_verify_chain(&id))?;
+                Ok(serde_json::to_value(report)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?)
+            });
+
+        self.io
+            .add_method("admin/instance/blocked_agents", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                let blocked = conductor_call!(|c| c.instance_blocked_agents(&id))?;
+                Ok(serde_json::to_value(blocked)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?)
+            });
+
+        self.io
+            .add_method("admin/instance/validation_receipts", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                let entry_address = Self::get_as_string("entry_address", &params_map)?;
+                let receipts = conductor_call!(
+                    |c| c.instance_validation_receipts(&id, &entry_address.into())
+                )?;
+                Ok(json!({
+                    "count": receipts.len(),
+                    "receipts": serde_json::to_value(receipts)
+                        .map_err(|_| jsonrpc_core::Error::internal_error())?,
+                }))
+            });
+
         self.io.add_method("admin/instance/stop", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let id = Self::get_as_string("id", &params_map)?;
@@ -651,26 +778,42 @@ impl ConductorApiBuilder {
             let id = Self::get_as_string("id", &params_map)?;
             let admin = Self::get_as_bool("admin", &params_map)?;
             let driver_type = Self::get_as_string("type", &params_map)?;
-            let port = u16::try_from(Self::get_as_int("port", &params_map)?).map_err(|_| {
-                jsonrpc_core::Error::invalid_params(String::from(
-                    "`port` has to be a 16bit integer",
-                ))
-            })?;
+            let get_port = || {
+                u16::try_from(Self::get_as_int("port", &params_map)?).map_err(|_| {
+                    jsonrpc_core::Error::invalid_params(String::from(
+                        "`port` has to be a 16bit integer",
+                    ))
+                })
+            };
 
             let new_interface = InterfaceConfiguration {
                 id,
                 admin,
                 driver: match driver_type.as_ref() {
-                    "websocket" => InterfaceDriver::Websocket { port },
-                    "http" => InterfaceDriver::Http { port },
+                    "websocket" => InterfaceDriver::Websocket { port: get_port()? },
+                    "http" => InterfaceDriver::Http { port: get_port()? },
+                    #[cfg(unix)]
+                    "domainsocket" => InterfaceDriver::DomainSocket {
+                        file: Self::get_as_string("file", &params_map)?,
+                        permissions: None,
+                    },
+                    #[cfg(not(unix))]
+                    "domainsocket" => {
+                        return Err(jsonrpc_core::Error::invalid_params(String::from(
+                            "domain socket interfaces are not supported on this platform",
+                        )));
+                    }
                     _ => {
                         return Err(jsonrpc_core::Error::invalid_params(String::from(
-                            "`type` has to be either `websocket` or `http`",
+                            "`type` has to be either `websocket`, `http` or `domainsocket`",
                         )));
                     }
                 },
                 instances: Vec::new(),
                 choose_free_port: None,
+                bind_address: Self::get_as_string("bind_address", &params_map)
+                    .unwrap_or_else(|_| default_address()),
+                tls: None,
             };
 
             conductor_call!(|c| c.add_interface(new_interface))?;
@@ -1016,6 +1159,31 @@ impl ConductorApiBuilder {
     }
 
     pub fn with_agent_encryption_callback(mut self, keybundle: Arc<Mutex<KeyBundle>>) -> Self {
+        let k = keybundle.clone();
+        self.io.add_method("agent/encrypt_for", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let payload = Self::get_as_crypto_string("payload", &params_map)?;
+            let recipients: Vec<Base32> = params_map
+                .get("recipients")
+                .and_then(|recipients| serde_json::from_value(recipients.clone()).ok())
+                .ok_or_else(|| {
+                    jsonrpc_core::Error::invalid_params(
+                        "`recipients` has to be a list of public encrypting keys",
+                    )
+                })?;
+            let mut message = SecBuf::with_insecure_from_string(payload);
+
+            let sealed = k
+                .lock()
+                .unwrap()
+                .encrypt_for(&mut message, &recipients)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            let sealed = serde_json::to_string(&sealed)
+                .map_err(|_| jsonrpc_core::Error::new(jsonrpc_core::ErrorCode::InternalError))?;
+
+            Ok(json!({ "sealed": sealed }))
+        });
+
         self.io.add_method("agent/encrypt", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let payload = Self::get_as_crypto_string("payload", &params_map)?;
@@ -1040,6 +1208,25 @@ impl ConductorApiBuilder {
     }
 
     pub fn with_agent_decryption_callback(mut self, keybundle: Arc<Mutex<KeyBundle>>) -> Self {
+        let k = keybundle.clone();
+        self.io.add_method("agent/decrypt_sealed", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let sealed: SealedData =
+                serde_json::from_str(&Self::get_as_string("sealed", &params_map)?)
+                    .map_err(|_| jsonrpc_core::Error::invalid_params("`sealed` is not valid"))?;
+
+            let mut decrypted_buf = k
+                .lock()
+                .unwrap()
+                .decrypt_sealed(&sealed)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+
+            let decrypted_bytes = decrypted_buf.read_lock();
+            let decrypted_string = std::str::from_utf8(&**decrypted_bytes)
+                .map_err(|_| jsonrpc_core::Error::new(jsonrpc_core::ErrorCode::InternalError))?;
+            Ok(json!({ "message": decrypted_string }))
+        });
+
         self.io.add_method("agent/decrypt", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let payload = Self::get_as_crypto_string("payload", &params_map)?;
@@ -0,0 +1,355 @@
+//! Authorization of calls to protected (i.e. admin) methods of conductor interfaces.
+//!
+//! If `Configuration::interface_auth` is set, every call to a method that matches one of
+//! the configured `protected_methods` patterns on an admin interface has to carry credentials
+//! in its params map. Those are either
+//! * a bearer token: `auth_token`, or
+//! * a signed challenge: `auth_agent`, `auth_challenge` and `auth_signature`, where the
+//!   challenge was obtained by calling `auth/challenge` before and signed with the key of
+//!   the agent.
+//!
+//! Each token and each agent comes with a list of method patterns it is permitted to call.
+//! The credential params get removed before the call is passed on to the actual method.
+use crate::config::InterfaceAuthConfiguration;
+use holochain_core_types::signature::Signature;
+use holochain_dpki::utils::{generate_random_buf, verify};
+use holochain_locksmith::Mutex;
+use holochain_persistence_api::cas::content::Address;
+use jsonrpc_core::{
+    futures::future, types::params::Params, BoxFuture, IoHandler, RemoteProcedure, RpcMethod, Value,
+};
+use serde_json::map::Map;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub const AUTH_TOKEN_PARAM: &str = "auth_token";
+pub const AUTH_AGENT_PARAM: &str = "auth_agent";
+pub const AUTH_CHALLENGE_PARAM: &str = "auth_challenge";
+pub const AUTH_SIGNATURE_PARAM: &str = "auth_signature";
+
+/// The method clients call to get a challenge to sign
+pub const AUTH_CHALLENGE_METHOD: &str = "auth/challenge";
+
+/// JSON-RPC error code of rejected calls (in the range reserved for server errors)
+pub const UNAUTHORIZED_ERROR_CODE: i64 = -32001;
+
+/// How long a challenge can be used after it was handed out
+const CHALLENGE_TIMEOUT_SECS: u64 = 60;
+
+const CHALLENGE_SIZE: usize = 32;
+
+/// How many unused challenges are kept at most, the oldest get dropped beyond that
+const MAX_CHALLENGES: usize = 1024;
+
+/// Returns true if `method` matches `pattern`, which is either an exact method name,
+/// a prefix followed by `*` (e.g. `admin/*`) or just `*`.
+pub fn method_matches(pattern: &str, method: &str) -> bool {
+    if pattern.ends_with('*') {
+        method.starts_with(&pattern[..pattern.len() - 1])
+    } else {
+        pattern == method
+    }
+}
+
+fn any_matches(patterns: &[String], method: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| method_matches(pattern, method))
+}
+
+/// Compares without short-circuiting so the time it takes doesn't leak
+/// how much of a guessed token was right.
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn unauthorized(reason: &str) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::types::error::ErrorCode::ServerError(UNAUTHORIZED_ERROR_CODE),
+        message: format!("Unauthorized: {}", reason),
+        data: None,
+    }
+}
+
+fn take_string(params_map: &mut Map<String, Value>, key: &str) -> Option<String> {
+    params_map
+        .remove(key)
+        .and_then(|value| value.as_str().map(String::from))
+}
+
+/// Checks the credentials of calls to protected methods of one interface.
+pub struct InterfaceAuthorizer {
+    interface_id: String,
+    config: InterfaceAuthConfiguration,
+    /// Challenges handed out through `auth/challenge` that were not used yet
+    challenges: Mutex<HashMap<String, Instant>>,
+}
+
+impl InterfaceAuthorizer {
+    pub fn new(interface_id: String, config: InterfaceAuthConfiguration) -> Self {
+        InterfaceAuthorizer {
+            interface_id,
+            config,
+            challenges: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_protected(&self, method: &str) -> bool {
+        any_matches(&self.config.protected_methods, method)
+    }
+
+    /// Creates a random challenge that can be used once within `CHALLENGE_TIMEOUT_SECS`.
+    /// Drops expired challenges and, if there are still `MAX_CHALLENGES`, the oldest one.
+    pub fn new_challenge(&self) -> String {
+        let mut buf = generate_random_buf(CHALLENGE_SIZE);
+        let challenge = base64::encode(&**buf.read_lock());
+        let timeout = Duration::from_secs(CHALLENGE_TIMEOUT_SECS);
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, issued| issued.elapsed() < timeout);
+        while challenges.len() >= MAX_CHALLENGES {
+            let oldest = challenges
+                .iter()
+                .min_by_key(|(_, issued)| **issued)
+                .map(|(challenge, _)| challenge.clone())
+                .unwrap();
+            challenges.remove(&oldest);
+        }
+        challenges.insert(challenge.clone(), Instant::now());
+        challenge
+    }
+
+    /// Consumes the challenge, returns false if it was never issued or has expired
+    fn use_challenge(&self, challenge: &str) -> bool {
+        self.challenges
+            .lock()
+            .unwrap()
+            .remove(challenge)
+            .map(|issued| issued.elapsed() < Duration::from_secs(CHALLENGE_TIMEOUT_SECS))
+            .unwrap_or(false)
+    }
+
+    /// Checks the credentials found in `params` for a call to `method` and returns
+    /// the params without them.
+    pub fn authorize(&self, method: &str, params: Params) -> Result<Params, jsonrpc_core::Error> {
+        let result = self.check_credentials(method, params);
+        if let Err(ref error) = result {
+            warn!(
+                "interface {}: rejected call to {}: {}",
+                self.interface_id, method, error.message
+            );
+        }
+        result
+    }
+
+    fn check_credentials(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> Result<Params, jsonrpc_core::Error> {
+        let mut params_map = match params {
+            Params::Map(map) => map,
+            _ => return Err(unauthorized("credentials missing, expected params map")),
+        };
+
+        let permissions = if let Some(token) = take_string(&mut params_map, AUTH_TOKEN_PARAM) {
+            self.config
+                .tokens
+                .iter()
+                .find(|configured| constant_time_eq(&configured.token, &token))
+                .map(|configured| &configured.permissions)
+                .ok_or_else(|| unauthorized("unknown token"))?
+        } else if let Some(agent) = take_string(&mut params_map, AUTH_AGENT_PARAM) {
+            let challenge = take_string(&mut params_map, AUTH_CHALLENGE_PARAM)
+                .ok_or_else(|| unauthorized("`auth_challenge` missing"))?;
+            let signature = take_string(&mut params_map, AUTH_SIGNATURE_PARAM)
+                .ok_or_else(|| unauthorized("`auth_signature` missing"))?;
+            let configured = self
+                .config
+                .agents
+                .iter()
+                .find(|configured| configured.public_address == agent)
+                .ok_or_else(|| unauthorized("unknown agent"))?;
+            if !self.use_challenge(&challenge) {
+                return Err(unauthorized("unknown or expired challenge"));
+            }
+            let valid = verify(Address::from(agent), challenge, Signature::from(signature))
+                .unwrap_or(false);
+            if !valid {
+                return Err(unauthorized("invalid signature"));
+            }
+            &configured.permissions
+        } else {
+            return Err(unauthorized("credentials missing"));
+        };
+
+        if !any_matches(permissions, method) {
+            return Err(unauthorized("method not permitted"));
+        }
+        Ok(Params::Map(params_map))
+    }
+
+    /// Wraps all protected methods of the given handler with a credentials check
+    /// and adds the `auth/challenge` method.
+    pub fn protect(self, io: IoHandler) -> IoHandler {
+        let authorizer = Arc::new(self);
+        let mut protected_io = IoHandler::new();
+        for (name, procedure) in io {
+            match procedure {
+                RemoteProcedure::Method(ref method) if authorizer.is_protected(&name) => {
+                    let authorizer = authorizer.clone();
+                    let method = method.clone();
+                    let method_name = name.clone();
+                    protected_io.add_method_with_meta(&name, move |params: Params, meta: ()| {
+                        match authorizer.authorize(&method_name, params) {
+                            Ok(params) => method.call(params, meta),
+                            Err(error) => Box::new(future::err(error)) as BoxFuture<Value>,
+                        }
+                    });
+                }
+                procedure => protected_io.extend_with(vec![(name, procedure)]),
+            }
+        }
+
+        protected_io.add_method(AUTH_CHALLENGE_METHOD, move |_params| {
+            Ok(json!({ "challenge": authorizer.new_challenge() }))
+        });
+        protected_io
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        conductor::base::tests::test_keybundle,
+        config::{AuthAgentConfiguration, AuthTokenConfiguration},
+    };
+    use lib3h_sodium::secbuf::SecBuf;
+
+    fn test_authorizer() -> InterfaceAuthorizer {
+        InterfaceAuthorizer::new(
+            "test-interface".to_string(),
+            InterfaceAuthConfiguration {
+                protected_methods: vec!["admin/*".to_string(), "debug/*".to_string()],
+                tokens: vec![AuthTokenConfiguration {
+                    id: "debugger".to_string(),
+                    token: "secret".to_string(),
+                    permissions: vec!["debug/*".to_string()],
+                }],
+                agents: vec![AuthAgentConfiguration {
+                    public_address: test_keybundle(1).get_id(),
+                    permissions: vec!["*".to_string()],
+                }],
+            },
+        )
+    }
+
+    fn test_io() -> IoHandler {
+        let mut io = IoHandler::new();
+        io.add_method("admin/agent/list", |params: Params| {
+            Ok(json!({ "params": params }))
+        });
+        io.add_method("debug/running_instances", |_| Ok(json!([])));
+        io.add_method("info/instances", |_| Ok(json!([])));
+        io
+    }
+
+    fn call(io: &IoHandler, method: &str, params: Value) -> Value {
+        let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+        let response = io.handle_request_sync(&request.to_string()).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_method_matches() {
+        assert!(method_matches("admin/*", "admin/agent/add"));
+        assert!(method_matches("*", "debug/state_dump"));
+        assert!(method_matches("info/instances", "info/instances"));
+        assert!(!method_matches("admin/*", "debug/state_dump"));
+        assert!(!method_matches("admin/agent", "admin/agent/add"));
+    }
+
+    #[test]
+    fn test_token_permissions() {
+        let io = test_authorizer().protect(test_io());
+
+        // unprotected methods need no credentials
+        assert_eq!(call(&io, "info/instances", json!({}))["result"], json!([]));
+
+        let response = call(&io, "debug/running_instances", json!({}));
+        assert_eq!(response["error"]["code"], json!(UNAUTHORIZED_ERROR_CODE));
+
+        let response = call(
+            &io,
+            "debug/running_instances",
+            json!({ "auth_token": "secret" }),
+        );
+        assert_eq!(response["result"], json!([]));
+
+        let response = call(&io, "admin/agent/list", json!({ "auth_token": "secret" }));
+        assert_eq!(
+            response["error"]["message"],
+            json!("Unauthorized: method not permitted")
+        );
+
+        let response = call(
+            &io,
+            "debug/running_instances",
+            json!({ "auth_token": "wrong" }),
+        );
+        assert_eq!(
+            response["error"]["message"],
+            json!("Unauthorized: unknown token")
+        );
+    }
+
+    #[test]
+    fn test_signed_challenge() {
+        let io = test_authorizer().protect(test_io());
+        let challenge = call(&io, AUTH_CHALLENGE_METHOD, json!({}))["result"]["challenge"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let mut keybundle = test_keybundle(1);
+        let mut message = SecBuf::with_insecure_from_string(challenge.clone());
+        let mut signature = keybundle.sign(&mut message).unwrap();
+        let signature = base64::encode(&**signature.read_lock());
+
+        let params = json!({
+            "auth_agent": keybundle.get_id(),
+            "auth_challenge": challenge,
+            "auth_signature": signature,
+            "some_param": 42,
+        });
+        let response = call(&io, "admin/agent/list", params.clone());
+        // credentials get stripped before the call is passed on
+        assert_eq!(response["result"], json!({"params": {"some_param": 42}}));
+
+        // challenges can only be used once
+        let response = call(&io, "admin/agent/list", params);
+        assert_eq!(
+            response["error"]["message"],
+            json!("Unauthorized: unknown or expired challenge")
+        );
+    }
+
+    #[test]
+    fn test_challenges_are_capped() {
+        let authorizer = test_authorizer();
+        let first = authorizer.new_challenge();
+        for _ in 0..MAX_CHALLENGES {
+            authorizer.new_challenge();
+        }
+        assert_eq!(authorizer.challenges.lock().unwrap().len(), MAX_CHALLENGES);
+        // the oldest challenge made room for the newer ones
+        assert!(!authorizer.use_challenge(&first));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{conductor::broadcaster::Broadcaster, interface::Interface};
+use crossbeam_channel::{Receiver, TryRecvError};
+use holochain_locksmith::Mutex;
+use jsonrpc_core::IoHandler;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// How long the accept loop sleeps between polling for new connections and the kill switch
+const ACCEPT_POLL_INTERVAL_MS: u64 = 50;
+
+/// How long writing a response or signal to a client may block before the client gets dropped
+const WRITE_TIMEOUT_SECS: u64 = 10;
+
+pub type DomainSocketClient = Arc<Mutex<UnixStream>>;
+pub type DomainSocketClients = Arc<Mutex<Vec<DomainSocketClient>>>;
+
+/// A JSON-RPC interface over a Unix domain socket.
+/// Requests and responses (as well as broadcasted signals) are newline-delimited
+/// JSON strings, so every line a client writes to the socket is treated as one request.
+/// Access control is done through the file permissions of the socket file.
+pub struct DomainSocketInterface {
+    file: PathBuf,
+    permissions: Option<u32>,
+}
+
+impl DomainSocketInterface {
+    pub fn new(file: PathBuf, permissions: Option<u32>) -> Self {
+        DomainSocketInterface { file, permissions }
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+}
+
+/// Removes a socket file that was left behind by a previous run of the conductor.
+/// Fails if the path exists but is not a socket, or if some other process is still
+/// accepting connections on it.
+fn remove_stale_socket(path: &Path) -> Result<(), String> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(()),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(format!(
+            "Could not bind domain socket interface: {} exists and is not a socket",
+            path.display()
+        ));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(format!(
+            "Could not bind domain socket interface: {} is in use by another process",
+            path.display()
+        ));
+    }
+    debug!("Removing stale domain socket file {}", path.display());
+    fs::remove_file(path).map_err(|e| e.to_string())
+}
+
+/// Binds the socket in a directory only we can access and moves it into place once it has
+/// the configured permissions, so nobody can connect while it still has the default ones.
+fn bind_socket(path: &Path, permissions: Option<u32>) -> Result<UnixListener, String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("{} is not a valid socket path", path.display()))?;
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let private_dir = parent.join(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)
+        .map_err(|e| e.to_string())?;
+    let private_path = private_dir.join(file_name);
+    let result = UnixListener::bind(&private_path)
+        .and_then(|listener| {
+            if let Some(mode) = permissions {
+                fs::set_permissions(&private_path, fs::Permissions::from_mode(mode))?;
+            }
+            fs::rename(&private_path, path)?;
+            Ok(listener)
+        })
+        .map_err(|e| e.to_string());
+    let _ = fs::remove_file(&private_path);
+    let _ = fs::remove_dir(&private_dir);
+    result
+}
+
+/// Reads newline-delimited requests from one client and writes the responses back
+/// until the client disconnects.
+fn serve_client(handler: Arc<IoHandler>, client: DomainSocketClient, clients: DomainSocketClients) {
+    let reader = client
+        .lock()
+        .unwrap()
+        .try_clone()
+        .map(BufReader::new)
+        .map_err(|e| error!("domain socket interface: could not clone stream: {}", e));
+    if let Ok(reader) = reader {
+        for line in reader.lines() {
+            let request = match line {
+                Ok(request) => request,
+                Err(_) => break,
+            };
+            if request.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = handler.handle_request_sync(&request) {
+                let mut stream = client.lock().unwrap();
+                if writeln!(stream, "{}", response).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    clients
+        .lock()
+        .unwrap()
+        .retain(|other| !Arc::ptr_eq(other, &client));
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CONDUCTOR_LIB)]
+impl Interface for DomainSocketInterface {
+    fn run(
+        &mut self,
+        handler: IoHandler,
+        kill_switch: Receiver<()>,
+    ) -> Result<(Broadcaster, thread::JoinHandle<()>), String> {
+        remove_stale_socket(&self.file)?;
+        let listener = bind_socket(&self.file, self.permissions)?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+        let handler = Arc::new(handler);
+        let clients: DomainSocketClients = Arc::new(Mutex::new(Vec::new()));
+        let broadcaster = Broadcaster::DomainSocket(clients.clone());
+        let file = self.file.clone();
+        let handle = thread::Builder::new()
+            .name(format!("domain_socket_interface/{}", file.display()))
+            .spawn(move || {
+                loop {
+                    match kill_switch.try_recv() {
+                        Err(TryRecvError::Empty) => (),
+                        _ => break,
+                    }
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let configured = stream.set_nonblocking(false).and_then(|_| {
+                                stream.set_write_timeout(Some(Duration::from_secs(
+                                    WRITE_TIMEOUT_SECS,
+                                )))
+                            });
+                            if let Err(e) = configured {
+                                error!("domain socket interface: {}", e);
+                                continue;
+                            }
+                            let client = Arc::new(Mutex::new(stream));
+                            clients.lock().unwrap().push(client.clone());
+                            let handler = handler.clone();
+                            let clients = clients.clone();
+                            let _ = thread::Builder::new()
+                                .name(format!("domain_socket_client/{}", file.display()))
+                                .spawn(move || serve_client(handler, client, clients));
+                        }
+                        Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MS))
+                        }
+                        Err(e) => error!("domain socket interface: accept failed: {}", e),
+                    }
+                }
+                for client in clients.lock().unwrap().drain(..) {
+                    let _ = client.lock().unwrap().shutdown(std::net::Shutdown::Both);
+                }
+                let _ = fs::remove_file(&file);
+            })
+            .expect("Could not spawn thread for domain socket interface");
+        Ok((broadcaster, handle))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+    use jsonrpc_core::Value;
+    use tempfile::tempdir;
+
+    fn test_handler() -> IoHandler {
+        let mut handler = IoHandler::new();
+        handler.add_method("ping", |_| Ok(Value::String("pong".into())));
+        handler
+    }
+
+    fn read_line(reader: &mut BufReader<UnixStream>) -> String {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        line
+    }
+
+    #[test]
+    fn test_domain_socket_call_and_broadcast() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("holochain.sock");
+        let mut interface = DomainSocketInterface::new(file.clone(), Some(0o600));
+        let (kill_switch_tx, kill_switch_rx) = unbounded();
+        let (broadcaster, handle) = interface.run(test_handler(), kill_switch_rx).unwrap();
+
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // the directory the socket got bound in is gone
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let mut stream = UnixStream::connect(&file).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        writeln!(stream, r#"{{"jsonrpc":"2.0","method":"ping","id":1}}"#).unwrap();
+        assert_eq!(
+            read_line(&mut reader),
+            "{\"jsonrpc\":\"2.0\",\"result\":\"pong\",\"id\":1}\n"
+        );
+
+        broadcaster.send("\"some signal\"").unwrap();
+        assert_eq!(read_line(&mut reader), "\"some signal\"\n");
+
+        kill_switch_tx.send(()).unwrap();
+        handle.join().unwrap();
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_domain_socket_removes_stale_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("stale.sock");
+        // A listener that is dropped without removing its file leaves a stale socket behind
+        drop(UnixListener::bind(&file).unwrap());
+        assert!(file.exists());
+
+        let mut interface = DomainSocketInterface::new(file.clone(), None);
+        let (kill_switch_tx, kill_switch_rx) = unbounded();
+        let (_, handle) = interface.run(test_handler(), kill_switch_rx).unwrap();
+
+        // A second interface on the same live socket has to fail
+        let mut other = DomainSocketInterface::new(file.clone(), None);
+        let (_other_tx, other_rx) = unbounded();
+        assert!(other.run(test_handler(), other_rx).is_err());
+
+        kill_switch_tx.send(()).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_domain_socket_refuses_regular_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("not_a_socket");
+        fs::write(&file, "data").unwrap();
+        let mut interface = DomainSocketInterface::new(file, None);
+        let (_kill_switch_tx, kill_switch_rx) = unbounded();
+        assert!(interface.run(test_handler(), kill_switch_rx).is_err());
+    }
+}
@@ -1,23 +1,44 @@
-use crate::{conductor::broadcaster::Broadcaster, interface::Interface};
+use crate::{
+    conductor::broadcaster::Broadcaster,
+    interface::Interface,
+    interface_impls::tls::{self, TlsProxy},
+};
 use crossbeam_channel::Receiver;
+use in_stream::TlsCertificate;
 use jsonrpc_core::IoHandler;
-use jsonrpc_http_server::ServerBuilder;
-use std::{net::SocketAddr, thread};
+use jsonrpc_http_server::{
+    hyper::{Body, Request, Response, StatusCode},
+    RequestMiddlewareAction, ServerBuilder,
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    thread,
+};
 use tokio::runtime::Runtime;
 
 pub struct HttpInterface {
+    bind_address: IpAddr,
     port: u16,
+    tls_certificate: Option<TlsCertificate>,
     bound_address: Option<SocketAddr>,
 }
 
 impl HttpInterface {
-    pub fn new(port: u16) -> Self {
+    pub fn new(bind_address: IpAddr, port: u16) -> Self {
         HttpInterface {
+            bind_address,
             port,
+            tls_certificate: None,
             bound_address: None,
         }
     }
 
+    /// Serve https:// with the given certificate instead of plain http://
+    pub fn with_tls(mut self, certificate: TlsCertificate) -> Self {
+        self.tls_certificate = Some(certificate);
+        self
+    }
+
     pub fn bound_address(&self) -> Option<SocketAddr> {
         self.bound_address
     }
@@ -30,19 +51,56 @@ impl Interface for HttpInterface {
         handler: IoHandler,
         kill_switch: Receiver<()>,
     ) -> Result<(Broadcaster, thread::JoinHandle<()>), String> {
-        let url = format!("0.0.0.0:{}", self.port);
+        let address = SocketAddr::new(self.bind_address, self.port);
+        let server_address = match self.tls_certificate {
+            Some(_) => tls::backend_address(),
+            None => address,
+        };
+        let secret = tls::new_proxy_secret();
         let runtime = Runtime::new().map_err(|e| e.to_string())?;
-        let server = ServerBuilder::new(handler)
-            .event_loop_executor(runtime.executor())
-            .start_http(&url.parse().expect("Invalid URL!"))
+        let mut builder = ServerBuilder::new(handler).event_loop_executor(runtime.executor());
+        if self.tls_certificate.is_some() {
+            // The proxy only adds its secret to the first request of a connection
+            let secret = secret.clone();
+            builder =
+                builder
+                    .keep_alive(false)
+                    .request_middleware(move |request: Request<Body>| {
+                        let header = request.headers().get(tls::PROXY_SECRET_HEADER);
+                        if tls::is_proxied(header.map(|value| value.as_bytes()), &secret) {
+                            RequestMiddlewareAction::from(request)
+                        } else {
+                            let mut response = Response::new(Body::empty());
+                            *response.status_mut() = StatusCode::FORBIDDEN;
+                            RequestMiddlewareAction::from(response)
+                        }
+                    });
+        }
+        let server = builder
+            .start_http(&server_address)
             .map_err(|e| e.to_string())?;
-        self.bound_address = Some(*server.address());
+        let proxy = match self.tls_certificate {
+            Some(ref certificate) => Some(TlsProxy::spawn(
+                address,
+                *server.address(),
+                certificate,
+                secret,
+            )?),
+            None => None,
+        };
+        self.bound_address = Some(
+            proxy
+                .as_ref()
+                .map(|proxy| proxy.address())
+                .unwrap_or_else(|| *server.address()),
+        );
         let broadcaster = Broadcaster::Noop;
         let handle = thread::Builder::new()
-            .name(format!("http_interface/{}", url))
+            .name(format!("http_interface/{}", address))
             .spawn(move || {
                 let _ = server; // move `server` into this thread
                 let _ = runtime; // move tokio runtime for RPC futures into this thread
+                let _ = proxy; // move TLS proxy into this thread, it stops when dropped
                 let _ = kill_switch.recv();
             })
             .expect("Could not spawn thread for HTTP interface");
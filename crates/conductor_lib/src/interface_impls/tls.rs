@@ -0,0 +1,293 @@
+use crate::interface_auth::constant_time_eq;
+use crossbeam_channel::{select, unbounded, Receiver, Sender, TryRecvError};
+use holochain_dpki::utils::generate_random_buf;
+use holochain_locksmith::Mutex;
+use in_stream::TlsCertificate;
+use native_tls::{Identity, TlsAcceptor, TlsStream};
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// How long the accept loop sleeps between polling for new connections and the kill switch
+const ACCEPT_POLL_INTERVAL_MS: u64 = 50;
+
+const PIPE_BUFFER_SIZE: usize = 16 * 1024;
+
+/// The longest request line the proxy reads before it gives up on a connection
+const MAX_REQUEST_LINE_SIZE: usize = 8 * 1024;
+
+const PROXY_SECRET_SIZE: usize = 32;
+
+/// Header the proxy adds to the first request of every connection it forwards.
+/// The backend refuses requests without it, so the plain text port on localhost can't
+/// be used by other local processes to get around TLS.
+pub const PROXY_SECRET_HEADER: &str = "X-Holochain-Proxy-Secret";
+
+/// Terminates TLS in front of one of our plain text interface servers.
+/// jsonrpc-ws-server and jsonrpc-http-server can't serve TLS themselves,
+/// so they get bound to an ephemeral port on localhost instead and this proxy
+/// accepts TLS connections on the configured address and forwards the decrypted
+/// stream to them, together with the secret the backend checks for.
+///
+/// The proxy stops accepting and closes all its connections when it gets dropped.
+pub struct TlsProxy {
+    address: SocketAddr,
+    // Never sent on, dropping it disconnects all the receivers in the proxy threads.
+    _kill_switch: Sender<()>,
+}
+
+impl TlsProxy {
+    pub fn spawn(
+        bind_address: SocketAddr,
+        backend: SocketAddr,
+        certificate: &TlsCertificate,
+        secret: String,
+    ) -> Result<Self, String> {
+        let identity = Identity::from_pkcs12(&certificate.pkcs12_data, &certificate.passphrase)
+            .map_err(|e| format!("Invalid TLS certificate: {}", e))?;
+        let acceptor = Arc::new(TlsAcceptor::new(identity).map_err(|e| e.to_string())?);
+        let listener = TcpListener::bind(bind_address).map_err(|e| e.to_string())?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+        let (kill_switch_tx, kill_switch_rx) = unbounded();
+
+        thread::Builder::new()
+            .name(format!("tls_proxy/{}", address))
+            .spawn(move || loop {
+                if is_killed(&kill_switch_rx) {
+                    break;
+                }
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        let acceptor = acceptor.clone();
+                        let secret = secret.clone();
+                        let kill_switch = kill_switch_rx.clone();
+                        let _ = thread::Builder::new()
+                            .name(format!("tls_proxy_connection/{}", peer))
+                            .spawn(move || {
+                                if let Err(e) = proxy_connection(
+                                    stream,
+                                    backend,
+                                    &acceptor,
+                                    &secret,
+                                    kill_switch,
+                                ) {
+                                    debug!("tls proxy: connection from {} closed: {}", peer, e);
+                                }
+                            });
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MS))
+                    }
+                    Err(e) => error!("tls proxy: accept failed: {}", e),
+                }
+            })
+            .map_err(|e| e.to_string())?;
+
+        Ok(TlsProxy {
+            address,
+            _kill_switch: kill_switch_tx,
+        })
+    }
+
+    /// The address the proxy accepts TLS connections on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+/// Where to bind a plain text server that only gets reached through a `TlsProxy`
+pub fn backend_address() -> SocketAddr {
+    SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)
+}
+
+/// A random secret for a proxy and its backend to share
+pub fn new_proxy_secret() -> String {
+    base64::encode(&**generate_random_buf(PROXY_SECRET_SIZE).read_lock())
+}
+
+/// Whether the value of the `PROXY_SECRET_HEADER` of a request to a backend
+/// shows that it came through the proxy sharing the given secret
+pub fn is_proxied(header: Option<&[u8]>, secret: &str) -> bool {
+    header
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .map(|value| constant_time_eq(value.trim(), secret))
+        .unwrap_or(false)
+}
+
+fn is_killed(kill_switch: &Receiver<()>) -> bool {
+    match kill_switch.try_recv() {
+        Err(TryRecvError::Empty) => false,
+        _ => true,
+    }
+}
+
+fn proxy_connection(
+    stream: TcpStream,
+    backend: SocketAddr,
+    acceptor: &TlsAcceptor,
+    secret: &str,
+    kill_switch: Receiver<()>,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut client = acceptor
+        .accept(stream)
+        .map_err(|e| io::Error::new(io::ErrorKind::ConnectionAborted, e.to_string()))?;
+    let mut server = TcpStream::connect(backend)?;
+    forward_request_line(&mut client, &mut server, secret)?;
+
+    let client_socket = client.get_ref().try_clone()?;
+    let server_socket = server.try_clone()?;
+    let client = Arc::new(Mutex::new(client));
+    let (done_tx, done_rx) = unbounded();
+    let upstream = thread::Builder::new().spawn({
+        let client = client.clone();
+        let client_socket = client_socket.try_clone()?;
+        let mut server = server.try_clone()?;
+        let done = done_tx.clone();
+        move || {
+            let _ = client_to_server(&client_socket, &client, &mut server);
+            let _ = done.send(());
+        }
+    })?;
+    let downstream = thread::Builder::new().spawn(move || {
+        let _ = server_to_client(&mut server, &client);
+        let _ = done_tx.send(());
+    })?;
+
+    // Once one direction is done or the proxy gets dropped, shutting down both sockets
+    // wakes up whatever is still blocked on reading from them
+    select! {
+        recv(kill_switch) -> _ => (),
+        recv(done_rx) -> _ => (),
+    }
+    let _ = client_socket.shutdown(Shutdown::Both);
+    let _ = server_socket.shutdown(Shutdown::Both);
+    let _ = upstream.join();
+    let _ = downstream.join();
+    Ok(())
+}
+
+/// Forwards the first line of the client's request and adds the secret header after it
+fn forward_request_line<R: Read>(
+    client: &mut R,
+    server: &mut TcpStream,
+    secret: &str,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    let mut byte = [0u8];
+    while line.last() != Some(&b'\n') {
+        if line.len() >= MAX_REQUEST_LINE_SIZE || client.read(&mut byte)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no request line",
+            ));
+        }
+        line.push(byte[0]);
+    }
+    server.write_all(&line)?;
+    server.write_all(format!("{}: {}\r\n", PROXY_SECRET_HEADER, secret).as_bytes())?;
+    server.flush()
+}
+
+/// Forwards everything the client sends to the backend.
+/// Waits for encrypted data on the client socket without holding the TLS stream,
+/// so the other direction can write to the client in the meantime.
+fn client_to_server(
+    client_socket: &TcpStream,
+    client: &Mutex<TlsStream<TcpStream>>,
+    server: &mut TcpStream,
+) -> io::Result<()> {
+    let mut buffer = [0u8; PIPE_BUFFER_SIZE];
+    loop {
+        let buffered = client.lock().unwrap().buffered_read_size().unwrap_or(0);
+        if buffered == 0 && client_socket.peek(&mut [0u8])? == 0 {
+            return Ok(());
+        }
+        let n = client.lock().unwrap().read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        server.write_all(&buffer[..n])?;
+        server.flush()?;
+    }
+}
+
+/// Forwards everything the backend sends to the client
+fn server_to_client(
+    server: &mut TcpStream,
+    client: &Mutex<TlsStream<TcpStream>>,
+) -> io::Result<()> {
+    let mut buffer = [0u8; PIPE_BUFFER_SIZE];
+    loop {
+        let n = server.read(&mut buffer)?;
+        if n == 0 {
+            let _ = client.lock().unwrap().shutdown();
+            return Ok(());
+        }
+        let mut client = client.lock().unwrap();
+        client.write_all(&buffer[..n])?;
+        client.flush()?;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use native_tls::TlsConnector;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn test_tls_proxy_forwards_both_ways() {
+        let backend = TcpListener::bind("127.0.0.1:0").unwrap();
+        let backend_address = backend.local_addr().unwrap();
+        let echo = thread::spawn(move || {
+            let (stream, _) = backend.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            assert_eq!(request_line, "HELLO\r\n");
+            let mut secret_header = String::new();
+            reader.read_line(&mut secret_header).unwrap();
+            assert_eq!(
+                secret_header,
+                format!("{}: secret\r\n", PROXY_SECRET_HEADER)
+            );
+            let mut buffer = [0u8; 5];
+            reader.read_exact(&mut buffer).unwrap();
+            (&stream).write_all(&buffer).unwrap();
+        });
+
+        let proxy = TlsProxy::spawn(
+            "127.0.0.1:0".parse().unwrap(),
+            backend_address,
+            &TlsCertificate::with_fake_certificate(),
+            String::from("secret"),
+        )
+        .unwrap();
+
+        let connector = TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()
+            .unwrap();
+        let stream = TcpStream::connect(proxy.address()).unwrap();
+        let mut stream = connector.connect("localhost", stream).unwrap();
+        stream.write_all(b"HELLO\r\nworld").unwrap();
+        let mut response = [0u8; 5];
+        stream.read_exact(&mut response).unwrap();
+        assert_eq!(&response, b"world");
+        echo.join().unwrap();
+    }
+
+    #[test]
+    fn test_is_proxied() {
+        assert!(is_proxied(Some(&b"secret"[..]), "secret"));
+        assert!(!is_proxied(Some(&b"guess"[..]), "secret"));
+        assert!(!is_proxied(None, "secret"));
+    }
+}
@@ -1,23 +1,41 @@
-use crate::{conductor::broadcaster::Broadcaster, interface::Interface};
+use crate::{
+    conductor::broadcaster::Broadcaster,
+    interface::Interface,
+    interface_impls::tls::{self, TlsProxy},
+};
 use crossbeam_channel::Receiver;
+use in_stream::TlsCertificate;
 use jsonrpc_core::IoHandler;
-use jsonrpc_ws_server::ServerBuilder;
-use std::{net::SocketAddr, thread};
+use jsonrpc_ws_server::{ws, ServerBuilder};
+use std::{
+    net::{IpAddr, SocketAddr},
+    thread,
+};
 use tokio::runtime::Runtime;
 
 pub struct WebsocketInterface {
+    bind_address: IpAddr,
     port: u16,
+    tls_certificate: Option<TlsCertificate>,
     bound_address: Option<SocketAddr>,
 }
 
 impl WebsocketInterface {
-    pub fn new(port: u16) -> Self {
+    pub fn new(bind_address: IpAddr, port: u16) -> Self {
         WebsocketInterface {
+            bind_address,
             port,
+            tls_certificate: None,
             bound_address: None,
         }
     }
 
+    /// Serve wss:// with the given certificate instead of plain ws://
+    pub fn with_tls(mut self, certificate: TlsCertificate) -> Self {
+        self.tls_certificate = Some(certificate);
+        self
+    }
+
     pub fn bound_address(&self) -> Option<SocketAddr> {
         self.bound_address
     }
@@ -30,19 +48,48 @@ impl Interface for WebsocketInterface {
         handler: IoHandler,
         kill_switch: Receiver<()>,
     ) -> Result<(Broadcaster, thread::JoinHandle<()>), String> {
-        let url = format!("0.0.0.0:{}", self.port);
+        let address = SocketAddr::new(self.bind_address, self.port);
+        let server_address = match self.tls_certificate {
+            Some(_) => tls::backend_address(),
+            None => address,
+        };
+        let secret = tls::new_proxy_secret();
         let runtime = Runtime::new().map_err(|e| e.to_string())?;
-        let server = ServerBuilder::new(handler)
-            .event_loop_executor(runtime.executor())
-            .start(&url.parse().expect("Invalid URL!"))
-            .map_err(|e| e.to_string())?;
-        self.bound_address = Some(*server.addr());
+        let mut builder = ServerBuilder::new(handler).event_loop_executor(runtime.executor());
+        if self.tls_certificate.is_some() {
+            let secret = secret.clone();
+            builder = builder.request_middleware(move |request: &ws::Request| {
+                let header = request.header(tls::PROXY_SECRET_HEADER);
+                if tls::is_proxied(header.map(Vec::as_slice), &secret) {
+                    None
+                } else {
+                    Some(ws::Response::new(403, "Forbidden", Vec::new()))
+                }
+            });
+        }
+        let server = builder.start(&server_address).map_err(|e| e.to_string())?;
+        let proxy = match self.tls_certificate {
+            Some(ref certificate) => Some(TlsProxy::spawn(
+                address,
+                *server.addr(),
+                certificate,
+                secret,
+            )?),
+            None => None,
+        };
+        self.bound_address = Some(
+            proxy
+                .as_ref()
+                .map(|proxy| proxy.address())
+                .unwrap_or_else(|| *server.addr()),
+        );
         let broadcaster = Broadcaster::Ws(server.broadcaster());
         let handle = thread::Builder::new()
-            .name(format!("websocket_interface/{}", url))
+            .name(format!("websocket_interface/{}", address))
             .spawn(move || {
                 let _ = server; // move `server` into this thread
                 let _ = runtime; // move tokio runtime for RPC futures into this thread
+                let _ = proxy; // move TLS proxy into this thread, it stops when dropped
                 let _ = kill_switch.recv();
             })
             .expect("Could not spawn thread for websocket interface");
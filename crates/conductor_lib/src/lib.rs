@@ -184,6 +184,13 @@ pub mod interface;
     clippy::let_and_return,
     clippy::collapsible_if
 )]
+pub mod interface_auth;
+#[allow(
+    clippy::suspicious_else_formatting,
+    clippy::redundant_closure,
+    clippy::let_and_return,
+    clippy::collapsible_if
+)]
 pub mod interface_impls;
 #[allow(
     clippy::suspicious_else_formatting,
@@ -234,6 +241,13 @@ pub mod static_file_server;
     clippy::collapsible_if
 )]
 pub mod static_server_impls;
+#[allow(
+    clippy::suspicious_else_formatting,
+    clippy::redundant_closure,
+    clippy::let_and_return,
+    clippy::collapsible_if
+)]
+pub mod storage;
 
 pub use crate::holochain::Holochain;
 
@@ -0,0 +1,373 @@
+//! Backups of a single instance as one compressed archive.
+//!
+//! An archive is a gzipped tar file with these entries:
+//! * `manifest.json`: what the archive holds and the hash of every other entry
+//! * `dna.json`: the DNA the instance runs
+//! * `agent.json`: the agent's configuration, without the path to its keystore
+//! * `keystore`: the agent's encrypted keystore file, if it has one
+//! * `cas.json`: every CAS item with its address, i.e. the source chain's headers and entries,
+//!   the DHT shard and the state snapshots SimplePersister writes, which include the agent
+//!   state and the holding map of the DHT store
+//! * `eav.json`: every EAVI, i.e. the meta data of the chain and the DHT shard
+//!
+//! Reading an archive checks every entry against the hash in the manifest.
+
+use crate::{
+    config::{AgentConfiguration, StorageConfiguration},
+    storage::{snapshots_in, InstanceStorage, StoredContent},
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use holochain_core_types::{dna::Dna, eav::Attribute, error::HolochainError};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::{
+    cas::content::{Address, AddressableContent},
+    eav::EntityAttributeValueIndex,
+    hash::HashString,
+};
+use multihash::Hash;
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+/// Version of the archive layout, increased on changes old conductors can't read
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DNA_ENTRY: &str = "dna.json";
+const AGENT_ENTRY: &str = "agent.json";
+const KEYSTORE_ENTRY: &str = "keystore";
+const CAS_ENTRY: &str = "cas.json";
+const EAV_ENTRY: &str = "eav.json";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub instance_id: String,
+    pub dna_hash: String,
+    pub agent_address: String,
+    pub cas_items: usize,
+    pub eavis: usize,
+    /// Addresses of the state snapshots found in the CAS
+    pub snapshots: Vec<String>,
+    /// Hash of every other entry of the archive, by entry name
+    pub entries: BTreeMap<String, String>,
+}
+
+/// The verified content of an archive
+pub struct InstanceArchive {
+    pub manifest: ArchiveManifest,
+    pub dna: Dna,
+    /// The archived agent, with an empty keystore_file
+    pub agent: AgentConfiguration,
+    pub keystore: Option<Vec<u8>>,
+    cas: Vec<(Address, String)>,
+    eavis: Vec<EntityAttributeValueIndex<Attribute>>,
+}
+
+fn archive_error(message: String) -> HolochainError {
+    HolochainError::ErrorGeneric(format!("Instance archive: {}", message))
+}
+
+fn entry_hash(data: &[u8]) -> String {
+    HashString::encode_from_bytes(data, Hash::SHA2256).to_string()
+}
+
+/// Writes the data of the stopped instance to an archive at the given path.
+/// The instance's storage is only read.
+pub fn export_instance(
+    instance_id: &str,
+    dna: &Dna,
+    agent: &AgentConfiguration,
+    storage: &StorageConfiguration,
+    path: &Path,
+) -> Result<ArchiveManifest, HolochainError> {
+    let source = InstanceStorage::open(storage)?;
+    let addresses = source.cas_addresses()?;
+    let mut cas = Vec::new();
+    {
+        let source_cas = source.cas.read().unwrap();
+        for address in addresses.iter() {
+            let content = source_cas
+                .fetch(address)?
+                .ok_or_else(|| archive_error(format!("could not read {} from storage", address)))?;
+            cas.push((address.clone(), String::from(content)));
+        }
+    }
+    let eavis: Vec<_> = source.all_eavis()?.into_iter().collect();
+
+    let keystore = if agent.holo_remote_key.unwrap_or(false)
+        || agent.test_agent.unwrap_or(false)
+        || !Path::new(&agent.keystore_file).is_file()
+    {
+        None
+    } else {
+        Some(fs::read(&agent.keystore_file)?)
+    };
+    let archived_agent = AgentConfiguration {
+        keystore_file: String::new(),
+        ..agent.clone()
+    };
+
+    let mut entries = vec![
+        (DNA_ENTRY, serde_json::to_vec_pretty(dna)?),
+        (AGENT_ENTRY, serde_json::to_vec_pretty(&archived_agent)?),
+        (CAS_ENTRY, serde_json::to_vec(&cas)?),
+        (EAV_ENTRY, serde_json::to_vec(&eavis)?),
+    ];
+    if let Some(keystore) = keystore {
+        entries.push((KEYSTORE_ENTRY, keystore));
+    }
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        instance_id: instance_id.to_string(),
+        dna_hash: dna.address().to_string(),
+        agent_address: agent.public_address.clone(),
+        cas_items: cas.len(),
+        eavis: eavis.len(),
+        snapshots: snapshots_in(&addresses),
+        entries: entries
+            .iter()
+            .map(|(name, data)| (name.to_string(), entry_hash(data)))
+            .collect(),
+    };
+    entries.insert(0, (MANIFEST_ENTRY, serde_json::to_vec_pretty(&manifest)?));
+    write_archive(path, &entries)?;
+    Ok(manifest)
+}
+
+pub(crate) fn write_archive(
+    path: &Path,
+    entries: &[(&str, Vec<u8>)],
+) -> Result<(), HolochainError> {
+    let mut builder =
+        tar::Builder::new(GzEncoder::new(File::create(path)?, Compression::default()));
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o600);
+        header.set_cksum();
+        builder.append_data(&mut header, name, data.as_slice())?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+impl InstanceArchive {
+    /// Reads the archive at the given path and checks all entries against the manifest
+    pub fn read(path: &Path) -> Result<Self, HolochainError> {
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+        let mut entries = BTreeMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().to_string();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            entries.insert(name, data);
+        }
+
+        let manifest: ArchiveManifest = serde_json::from_slice(
+            &entries
+                .remove(MANIFEST_ENTRY)
+                .ok_or_else(|| archive_error("manifest is missing".to_string()))?,
+        )?;
+        if manifest.format_version != ARCHIVE_FORMAT_VERSION {
+            return Err(archive_error(format!(
+                "unsupported format version {}",
+                manifest.format_version
+            )));
+        }
+        if entries.keys().ne(manifest.entries.keys()) {
+            return Err(archive_error(
+                "entries don't match the manifest".to_string(),
+            ));
+        }
+        for (name, hash) in manifest.entries.iter() {
+            if entry_hash(&entries[name]) != *hash {
+                return Err(archive_error(format!("{} is corrupted", name)));
+            }
+        }
+
+        let entry = |name: &str| {
+            entries
+                .get(name)
+                .ok_or_else(|| archive_error(format!("{} is missing", name)))
+        };
+        let dna: Dna = serde_json::from_slice(entry(DNA_ENTRY)?)?;
+        if dna.address().to_string() != manifest.dna_hash {
+            return Err(archive_error("DNA does not match the manifest".to_string()));
+        }
+        let archive = InstanceArchive {
+            dna,
+            agent: serde_json::from_slice(entry(AGENT_ENTRY)?)?,
+            keystore: entries.get(KEYSTORE_ENTRY).cloned(),
+            cas: serde_json::from_slice(entry(CAS_ENTRY)?)?,
+            eavis: serde_json::from_slice(entry(EAV_ENTRY)?)?,
+            manifest,
+        };
+        if archive.cas.len() != archive.manifest.cas_items
+            || archive.eavis.len() != archive.manifest.eavis
+        {
+            return Err(archive_error(
+                "item counts don't match the manifest".to_string(),
+            ));
+        }
+        Ok(archive)
+    }
+
+    /// Writes the archived CAS items and EAVIs into the given storage, which has to be empty
+    pub fn restore_storage(&self, storage: &StorageConfiguration) -> Result<(), HolochainError> {
+        {
+            let target = InstanceStorage::open(storage)?;
+            if !target.is_empty()? {
+                return Err(archive_error("target storage is not empty".to_string()));
+            }
+            let mut target_cas = target.cas.write().unwrap();
+            for (address, content) in self.cas.iter() {
+                target_cas.add(&StoredContent {
+                    address: address.clone(),
+                    content: JsonString::from_json(content),
+                })?;
+            }
+            let mut target_eav = target.eav.write().unwrap();
+            for eavi in self.eavis.iter() {
+                target_eav.add_eavi(eavi)?;
+            }
+        }
+
+        let target = InstanceStorage::open(storage)?;
+        if target.cas_addresses()?.len() != self.cas.len()
+            || target.all_eavis()?.len() != self.eavis.len()
+        {
+            return Err(archive_error(
+                "restored storage does not hold all archived items".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core::agent::state::AGENT_SNAPSHOT_ADDRESS;
+    use holochain_json_api::json::RawString;
+    use holochain_persistence_api::cas::content::Content;
+    use tempfile::tempdir;
+
+    fn test_agent() -> AgentConfiguration {
+        AgentConfiguration {
+            id: "test-agent".to_string(),
+            name: "Test Agent".to_string(),
+            public_address: "HcScIkRaAaaaaaaaaaAaaaAAAAaaaaaaaaAaaaaAaaaaaaaaAaaaaaaaaaaaaaa"
+                .to_string(),
+            keystore_file: String::new(),
+            holo_remote_key: None,
+            test_agent: Some(true),
+        }
+    }
+
+    fn test_export(dir: &Path) -> (StorageConfiguration, ArchiveManifest) {
+        let storage = StorageConfiguration::File {
+            path: dir.join("storage").to_string_lossy().to_string(),
+        };
+        {
+            let source = InstanceStorage::open(&storage).unwrap();
+            let entry = Content::from(RawString::from("some entry"));
+            source.cas.write().unwrap().add(&entry).unwrap();
+            source
+                .cas
+                .write()
+                .unwrap()
+                .add(&StoredContent {
+                    address: Address::from(AGENT_SNAPSHOT_ADDRESS),
+                    content: Content::from(RawString::from("agent snapshot")),
+                })
+                .unwrap();
+            source
+                .eav
+                .write()
+                .unwrap()
+                .add_eavi(
+                    &EntityAttributeValueIndex::new(
+                        &entry.address(),
+                        &Attribute::EntryHeader,
+                        &entry.address(),
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+        }
+        let manifest = export_instance(
+            "test-instance",
+            &Dna::new(),
+            &test_agent(),
+            &storage,
+            &dir.join("archive.tar.gz"),
+        )
+        .unwrap();
+        (storage, manifest)
+    }
+
+    #[test]
+    fn exported_archive_restores_into_other_storage() {
+        let dir = tempdir().unwrap();
+        let (_, manifest) = test_export(dir.path());
+        assert_eq!(manifest.cas_items, 2);
+        assert_eq!(manifest.eavis, 1);
+        assert_eq!(manifest.snapshots, vec![AGENT_SNAPSHOT_ADDRESS.to_string()]);
+
+        let archive = InstanceArchive::read(&dir.path().join("archive.tar.gz")).unwrap();
+        assert_eq!(archive.manifest, manifest);
+        assert_eq!(archive.agent, test_agent());
+        assert_eq!(archive.keystore, None);
+
+        let target = StorageConfiguration::Sqlite {
+            path: dir.path().join("restored").to_string_lossy().to_string(),
+        };
+        archive.restore_storage(&target).unwrap();
+        assert_eq!(
+            InstanceStorage::open(&target)
+                .unwrap()
+                .cas
+                .read()
+                .unwrap()
+                .fetch(&Address::from(AGENT_SNAPSHOT_ADDRESS))
+                .unwrap(),
+            Some(Content::from(RawString::from("agent snapshot")))
+        );
+        // restoring twice would mix two chains
+        assert!(archive.restore_storage(&target).is_err());
+    }
+
+    #[test]
+    fn corrupted_archive_is_rejected() {
+        let dir = tempdir().unwrap();
+        let (_, manifest) = test_export(dir.path());
+        let archive = InstanceArchive::read(&dir.path().join("archive.tar.gz")).unwrap();
+
+        let corrupted_cas = serde_json::to_vec(&vec![(
+            Address::from(AGENT_SNAPSHOT_ADDRESS),
+            "tampered".to_string(),
+        )])
+        .unwrap();
+        write_archive(
+            &dir.path().join("corrupted.tar.gz"),
+            &[
+                (MANIFEST_ENTRY, serde_json::to_vec(&manifest).unwrap()),
+                (DNA_ENTRY, serde_json::to_vec_pretty(&archive.dna).unwrap()),
+                (
+                    AGENT_ENTRY,
+                    serde_json::to_vec_pretty(&archive.agent).unwrap(),
+                ),
+                (CAS_ENTRY, corrupted_cas),
+                (EAV_ENTRY, serde_json::to_vec(&archive.eavis).unwrap()),
+            ],
+        )
+        .unwrap();
+        assert!(InstanceArchive::read(&dir.path().join("corrupted.tar.gz")).is_err());
+    }
+}
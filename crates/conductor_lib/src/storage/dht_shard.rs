@@ -0,0 +1,368 @@
+//! Inspects and repairs the DHT shard of an instance that is not running.
+//!
+//! The shard gets read through a context on the instance's storage that holds the state
+//! SimplePersister saved last. That way the aspects of an entry get built by the same
+//! fetch_aspects_for_entry() a running conductor answers fetch requests with.
+
+use crate::{
+    config::StorageConfiguration, context_builder::ContextBuilder, storage::InstanceStorage,
+};
+use holochain_core::{
+    context::Context,
+    dht::{
+        dht_store::{DhtStoreSnapshot, DHT_STORE_SNAPSHOT_ADDRESS},
+        pending_validations::{PendingValidationWithTimeout, ValidatingWorkflow},
+    },
+    entry::CanPublish,
+    network::handler::fetch::fetch_aspects_for_entry,
+    nucleus::actions::get_entry::get_entry_with_meta,
+    persister::{migrate_snapshot, Persister, SimplePersister},
+    state::StateWrapper,
+};
+use holochain_core_types::{
+    crud_status::CrudStatus, error::HolochainError, network::entry_aspect::EntryAspect,
+};
+use holochain_locksmith::RwLock;
+use holochain_net::aspect_map::{AspectMap, AspectSet};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use lib3h_protocol::types::{AspectHash, EntryHash};
+use std::{collections::BTreeSet, sync::Arc};
+
+/// An entry of the holding map together with its CRUD status
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HeldEntry {
+    pub address: Address,
+    pub aspects: BTreeSet<AspectHash>,
+    /// None if the entry is missing from the CAS or has no CRUD status in the EAV
+    pub crud_status: Option<CrudStatus>,
+    /// The entry that updated or deleted this one
+    pub crud_link: Option<Address>,
+}
+
+/// A holding workflow that waits in one of the queues of the DHT store
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HoldingWorkflow {
+    pub workflow: ValidatingWorkflow,
+    pub entry_address: Address,
+    pub header_address: Address,
+    pub dependencies: Vec<Address>,
+    pub uuid: String,
+}
+
+impl From<&PendingValidationWithTimeout> for HoldingWorkflow {
+    fn from(item: &PendingValidationWithTimeout) -> Self {
+        let pending = &item.pending;
+        HoldingWorkflow {
+            workflow: pending.workflow.clone(),
+            entry_address: pending.entry_with_header.entry.address(),
+            header_address: pending.entry_with_header.header.address(),
+            dependencies: pending.dependencies.clone(),
+            uuid: pending.uuid.to_string(),
+        }
+    }
+}
+
+/// An entry for which the holding map and the aspects that can be built
+/// from the CAS and EAV disagree
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HoldingMismatch {
+    pub entry_address: Address,
+    /// Aspects the CAS and EAV have that the holding map lacks.
+    /// The node does not tell the network it holds them.
+    pub missing_in_holding_map: BTreeSet<AspectHash>,
+    /// Aspects in the holding map that can't be built from the CAS and EAV.
+    /// The node claims to hold them but can't serve them.
+    pub missing_in_cas: BTreeSet<AspectHash>,
+}
+
+/// What `hc dht` prints about a shard
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DhtShardReport {
+    pub held_entries: Vec<HeldEntry>,
+    pub queued_holding_workflows: Vec<HoldingWorkflow>,
+    pub in_process_holding_workflows: Vec<HoldingWorkflow>,
+    pub mismatches: Vec<HoldingMismatch>,
+}
+
+impl DhtShardReport {
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// The DHT shard in the storage of an instance, with the DHT store snapshot
+/// that got saved last
+pub struct DhtShard {
+    storage: InstanceStorage,
+    context: Arc<Context>,
+    snapshot: DhtStoreSnapshot,
+}
+
+fn no_saved_state() -> HolochainError {
+    HolochainError::ErrorGeneric(
+        "Storage holds no saved state, was the instance ever started?".to_string(),
+    )
+}
+
+impl DhtShard {
+    /// Opens the storage the given configuration points to and loads the saved state.
+    /// Fails if there is no saved state or its source chain has no DNA, which is needed
+    /// to tell which entries get published.
+    pub fn open(config: &StorageConfiguration) -> Result<Self, HolochainError> {
+        let storage = InstanceStorage::open(config)?;
+        let snapshot = storage
+            .cas
+            .read()
+            .unwrap()
+            .fetch(&Address::from(DHT_STORE_SNAPSHOT_ADDRESS))?
+            .ok_or_else(no_saved_state)?;
+        let snapshot = DhtStoreSnapshot::try_from_content(&migrate_snapshot(&snapshot)?)?;
+
+        let mut context = ContextBuilder::new()
+            .with_instance_storage(&storage)
+            .with_instance_name("dht-shard")
+            .spawn();
+        let state = SimplePersister::new(context.dht_storage.clone())
+            .load(Arc::new(context.clone()))?
+            .ok_or_else(no_saved_state)?;
+        if state.nucleus().dna().is_none() {
+            return Err(HolochainError::ErrorGeneric(
+                "Source chain of the saved state has no DNA".to_string(),
+            ));
+        }
+        context.set_state(Arc::new(RwLock::new(StateWrapper::from(state))));
+
+        Ok(DhtShard {
+            storage,
+            context: Arc::new(context),
+            snapshot,
+        })
+    }
+
+    /// Lists the held entries and holding workflows of the saved DHT store
+    /// and compares its holding map with the CAS and EAV.
+    pub fn report(&self) -> Result<DhtShardReport, HolochainError> {
+        let holding_map = AspectMap::from(self.snapshot.holding_map.clone());
+        let mut held_entries: Vec<HeldEntry> = holding_map
+            .bare()
+            .iter()
+            .map(|(entry_hash, aspects)| self.held_entry(entry_hash, aspects))
+            .collect();
+        held_entries.sort_by(|a, b| a.address.cmp(&b.address));
+
+        Ok(DhtShardReport {
+            held_entries,
+            queued_holding_workflows: self
+                .snapshot
+                .queued_holding_workflows()
+                .iter()
+                .map(HoldingWorkflow::from)
+                .collect(),
+            in_process_holding_workflows: self
+                .snapshot
+                .in_process_holding_workflows()
+                .iter()
+                .map(HoldingWorkflow::from)
+                .collect(),
+            mismatches: holding_mismatches(&holding_map, &self.expected_holding_map()?),
+        })
+    }
+
+    /// Replaces the holding map of the saved DHT store with the one built from the CAS
+    /// and EAV, so that the instance announces what it can actually serve once it starts
+    /// again and gets missing aspects gossiped to it. Returns the mismatches that got fixed.
+    pub fn repair(&mut self) -> Result<Vec<HoldingMismatch>, HolochainError> {
+        let expected = self.expected_holding_map()?;
+        let mismatches = holding_mismatches(
+            &AspectMap::from(self.snapshot.holding_map.clone()),
+            &expected,
+        );
+        if !mismatches.is_empty() {
+            self.snapshot.holding_map = expected.bare().clone();
+            self.storage.cas.write().unwrap().add(&self.snapshot)?;
+        }
+        Ok(mismatches)
+    }
+
+    fn held_entry(&self, entry_hash: &EntryHash, aspects: &AspectSet) -> HeldEntry {
+        let address = Address::from(entry_hash.clone());
+        let entry_with_meta =
+            get_entry_with_meta(&self.context, address.clone()).unwrap_or_default();
+        HeldEntry {
+            aspects: aspects.iter().cloned().collect(),
+            crud_status: entry_with_meta
+                .as_ref()
+                .map(|entry_with_meta| entry_with_meta.crud_status),
+            crud_link: entry_with_meta
+                .and_then(|entry_with_meta| entry_with_meta.maybe_link_update_delete),
+            address,
+        }
+    }
+
+    /// The holding map the CAS and EAV make up for: all aspects of every entry
+    /// in the CAS that has a header of a type that gets published
+    fn expected_holding_map(&self) -> Result<AspectMap, HolochainError> {
+        let mut holding_map = AspectMap::new();
+        for address in self.storage.cas_addresses()? {
+            let aspects = fetch_aspects_for_entry(&EntryHash::from(address), self.context.clone());
+            let published = aspects.iter().any(|aspect| match aspect {
+                EntryAspect::Content(_, header) => header.entry_type().can_publish(&self.context),
+                _ => false,
+            });
+            if published {
+                for aspect in aspects.iter() {
+                    holding_map.add(aspect);
+                }
+            }
+        }
+        Ok(holding_map)
+    }
+}
+
+fn holding_mismatches(held: &AspectMap, expected: &AspectMap) -> Vec<HoldingMismatch> {
+    let no_aspects = AspectSet::new();
+    let entries: BTreeSet<&EntryHash> = held
+        .entry_addresses()
+        .chain(expected.entry_addresses())
+        .collect();
+    entries
+        .into_iter()
+        .filter_map(|entry_hash| {
+            let held_aspects = held.per_entry(entry_hash).unwrap_or(&no_aspects);
+            let expected_aspects = expected.per_entry(entry_hash).unwrap_or(&no_aspects);
+            if held_aspects == expected_aspects {
+                return None;
+            }
+            Some(HoldingMismatch {
+                entry_address: Address::from(entry_hash.clone()),
+                missing_in_holding_map: expected_aspects
+                    .iter()
+                    .filter(|aspect| !held_aspects.contains(aspect))
+                    .cloned()
+                    .collect(),
+                missing_in_cas: held_aspects
+                    .iter()
+                    .filter(|aspect| !expected_aspects.contains(aspect))
+                    .cloned()
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core::{
+        agent::state::AgentStateSnapshot,
+        nucleus::state::{NucleusStateSnapshot, NucleusStatus},
+        persister::SNAPSHOT_FORMAT_VERSION,
+    };
+    use holochain_core_types::{
+        chain_header::{test_provenances, ChainHeader},
+        crud_status::create_crud_status_eav,
+        eav::{Attribute, EntityAttributeValueIndex},
+        entry::{test_sys_entry, test_unpublishable_entry, Entry},
+        time::Iso8601,
+    };
+    use holochain_persistence_api::cas::content::Content;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn header_for(entry: &Entry) -> ChainHeader {
+        ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &test_provenances("sig"),
+            &None,
+            &None,
+            &None,
+            &Iso8601::from(0),
+        )
+    }
+
+    /// A chain with only the DNA, and an agent entry that is held for the DHT
+    /// but has a wrong aspect in the holding map
+    fn test_shard(config: &StorageConfiguration) -> EntryAspect {
+        let storage = InstanceStorage::open(config).unwrap();
+        let dna = test_unpublishable_entry();
+        let dna_header = header_for(&dna);
+        let held = test_sys_entry();
+        let held_header = header_for(&held);
+
+        let mut cas = storage.cas.write().unwrap();
+        cas.add(&dna).unwrap();
+        cas.add(&dna_header).unwrap();
+        cas.add(&held).unwrap();
+        cas.add(&held_header).unwrap();
+        cas.add(&AgentStateSnapshot::new(Some(dna_header))).unwrap();
+        cas.add(&NucleusStateSnapshot {
+            status: NucleusStatus::New,
+            cap_grant_uses: HashMap::new(),
+            format_version: SNAPSHOT_FORMAT_VERSION,
+        })
+        .unwrap();
+        let dht_snapshot = json!({
+            "holding_map": {String::from(held.address()): ["QmStaleAspect"]},
+            "queued_holding_workflows": [],
+            "format_version": SNAPSHOT_FORMAT_VERSION,
+        });
+        cas.add(
+            &DhtStoreSnapshot::try_from_content(&Content::from_json(&dht_snapshot.to_string()))
+                .unwrap(),
+        )
+        .unwrap();
+
+        let mut eav = storage.eav.write().unwrap();
+        eav.add_eavi(
+            &EntityAttributeValueIndex::new(
+                &held.address(),
+                &Attribute::EntryHeader,
+                &held_header.address(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        eav.add_eavi(&create_crud_status_eav(&held.address(), CrudStatus::Live).unwrap())
+            .unwrap();
+
+        EntryAspect::Content(held, held_header)
+    }
+
+    #[test]
+    fn reports_and_repairs_holding_map() {
+        let dir = tempdir().unwrap();
+        let config = StorageConfiguration::File {
+            path: dir.path().to_string_lossy().to_string(),
+        };
+        let aspect = test_shard(&config);
+        let entry_address = aspect.entry_address().unwrap();
+
+        let mut shard = DhtShard::open(&config).unwrap();
+        let report = shard.report().unwrap();
+        assert_eq!(report.held_entries.len(), 1);
+        assert_eq!(report.held_entries[0].address, entry_address);
+        assert_eq!(report.held_entries[0].crud_status, Some(CrudStatus::Live));
+        assert!(report.queued_holding_workflows.is_empty());
+        let expected_mismatch = HoldingMismatch {
+            entry_address,
+            missing_in_holding_map: vec![AspectHash::from(aspect.address())]
+                .into_iter()
+                .collect(),
+            missing_in_cas: vec![AspectHash::from("QmStaleAspect")]
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(report.mismatches, vec![expected_mismatch.clone()]);
+
+        assert_eq!(shard.repair().unwrap(), vec![expected_mismatch]);
+        let report = DhtShard::open(&config).unwrap().report().unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(
+            report.held_entries[0].aspects,
+            vec![AspectHash::from(aspect.address())]
+                .into_iter()
+                .collect()
+        );
+    }
+}
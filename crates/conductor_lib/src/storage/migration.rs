@@ -0,0 +1,334 @@
+//! Copies the CAS and EAV data of an instance from one storage backend to another.
+//!
+//! This works on the storage directly, so the instance must not be running while it migrates.
+//! The state snapshots SimplePersister writes live in the CAS under fixed addresses
+//! and get copied like any other item.
+
+use crate::{
+    config::StorageConfiguration,
+    storage::{snapshots_in, InstanceStorage, StoredContent},
+};
+use holochain_core_types::{eav::Attribute, error::HolochainError};
+use holochain_persistence_api::{
+    cas::content::{Address, AddressableContent},
+    eav::EntityAttributeValueIndex,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// What got copied by migrate_storage()
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub cas_items: usize,
+    pub eavis: usize,
+    /// Addresses of the state snapshots that were found and copied
+    pub snapshots: Vec<String>,
+}
+
+fn migration_error(message: String) -> HolochainError {
+    HolochainError::ErrorGeneric(format!("Storage migration failed: {}", message))
+}
+
+/// Entity, attribute and value of an EAVI.
+/// Backends assign a new index to an EAVI whose index is already taken,
+/// so only these are expected to survive the migration unchanged.
+fn eav_triple(eavi: &EntityAttributeValueIndex<Attribute>) -> (Address, Attribute, Address) {
+    (eavi.entity(), eavi.attribute(), eavi.value())
+}
+
+/// Copies every CAS item and EAVI from the storage `from` points to into the storage `to`
+/// points to, whose directory must not exist yet or be empty.
+///
+/// The data gets copied into a temporary directory next to the target, which then gets
+/// opened again to verify that it holds the same number of items, with the same content,
+/// as the source. Only then it gets moved into place, so an interrupted migration never
+/// leaves a partial target behind and can simply be retried. Temporary directories left
+/// behind by interrupted migrations get removed first.
+pub fn migrate_storage(
+    from: &StorageConfiguration,
+    to: &StorageConfiguration,
+) -> Result<MigrationReport, HolochainError> {
+    if from == to {
+        return Err(migration_error(
+            "source and target are the same storage".to_string(),
+        ));
+    }
+    let target_path = PathBuf::from(
+        to.path()
+            .ok_or_else(|| migration_error("can't migrate into memory storage".to_string()))?,
+    );
+    // The target doesn't get opened since LMDB environments stay cached by their path
+    // and would outlive the directory getting replaced.
+    if target_path.exists() && fs::read_dir(&target_path)?.next().is_some() {
+        return Err(migration_error("target storage is not empty".to_string()));
+    }
+    remove_temporary_dirs(&target_path)?;
+
+    let temporary_path = temporary_dir(&target_path)?;
+    let temporary = to.with_path(temporary_path.to_string_lossy().to_string());
+    let report = copy_and_verify(from, &temporary).and_then(|report| {
+        if target_path.exists() {
+            fs::remove_dir(&target_path)?;
+        }
+        fs::rename(&temporary_path, &target_path)?;
+        Ok(report)
+    });
+    if report.is_err() && temporary_path.exists() {
+        let _ = fs::remove_dir_all(&temporary_path);
+    }
+    report
+}
+
+/// Prefix of the temporary directories migrations into the given target use
+fn temporary_dir_prefix(target_path: &Path) -> Result<String, HolochainError> {
+    let name = target_path
+        .file_name()
+        .ok_or_else(|| {
+            migration_error(format!(
+                "target path {} has no directory name",
+                target_path.display()
+            ))
+        })?
+        .to_string_lossy();
+    Ok(format!(".{}.migrating.", name))
+}
+
+/// A fresh directory name next to the target, so the final rename stays on one file system.
+/// Every migration gets a new one since LMDB environments stay cached by their path.
+fn temporary_dir(target_path: &Path) -> Result<PathBuf, HolochainError> {
+    static MIGRATIONS: AtomicUsize = AtomicUsize::new(0);
+    Ok(target_path.with_file_name(format!(
+        "{}{}.{}",
+        temporary_dir_prefix(target_path)?,
+        process::id(),
+        MIGRATIONS.fetch_add(1, Ordering::SeqCst)
+    )))
+}
+
+/// Removes what interrupted migrations into the given target left behind
+fn remove_temporary_dirs(target_path: &Path) -> Result<(), HolochainError> {
+    let prefix = temporary_dir_prefix(target_path)?;
+    let parent = match target_path.parent() {
+        Some(parent) if parent.exists() => parent,
+        _ => return Ok(()),
+    };
+    for dir_entry in fs::read_dir(parent)? {
+        let dir_entry = dir_entry?;
+        if dir_entry.file_name().to_string_lossy().starts_with(&prefix) {
+            fs::remove_dir_all(dir_entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies everything from `from` into the empty storage `to` and checks the result
+fn copy_and_verify(
+    from: &StorageConfiguration,
+    to: &StorageConfiguration,
+) -> Result<MigrationReport, HolochainError> {
+    let source = InstanceStorage::open(from)?;
+    let addresses = source.cas_addresses()?;
+    let eavis = source.all_eavis()?;
+
+    {
+        let target = InstanceStorage::open(to)?;
+        let source_cas = source.cas.read().unwrap();
+        let mut target_cas = target.cas.write().unwrap();
+        for address in addresses.iter() {
+            let content = source_cas.fetch(address)?.ok_or_else(|| {
+                migration_error(format!("could not read {} from source", address))
+            })?;
+            target_cas.add(&StoredContent {
+                address: address.clone(),
+                content,
+            })?;
+        }
+
+        // BTreeSet iterates in index order, so EAVIs that get a new index in the
+        // target still end up in the same order
+        let mut target_eav = target.eav.write().unwrap();
+        for eavi in eavis.iter() {
+            target_eav.add_eavi(eavi)?;
+        }
+        // Dropping the target here flushes backends that write to disk lazily
+    }
+
+    let target = InstanceStorage::open(to)?;
+    let target_addresses = target.cas_addresses()?;
+    if target_addresses.len() != addresses.len() {
+        return Err(migration_error(format!(
+            "source has {} CAS items but target has {}",
+            addresses.len(),
+            target_addresses.len()
+        )));
+    }
+    {
+        let source_cas = source.cas.read().unwrap();
+        let target_cas = target.cas.read().unwrap();
+        for address in addresses.iter() {
+            let source_hash = source_cas.fetch(address)?.map(|content| content.address());
+            let target_hash = target_cas.fetch(address)?.map(|content| content.address());
+            if source_hash != target_hash {
+                return Err(migration_error(format!(
+                    "content of {} differs between source and target",
+                    address
+                )));
+            }
+        }
+    }
+
+    let mut source_triples: Vec<_> = eavis.iter().map(eav_triple).collect();
+    let mut target_triples: Vec<_> = target.all_eavis()?.iter().map(eav_triple).collect();
+    source_triples.sort();
+    target_triples.sort();
+    if source_triples != target_triples {
+        return Err(migration_error(format!(
+            "source has {} EAVIs but target has {} that don't match",
+            source_triples.len(),
+            target_triples.len()
+        )));
+    }
+
+    Ok(MigrationReport {
+        cas_items: addresses.len(),
+        eavis: eavis.len(),
+        snapshots: snapshots_in(&addresses),
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core::agent::state::AGENT_SNAPSHOT_ADDRESS;
+    use holochain_json_api::json::RawString;
+    use holochain_persistence_api::cas::content::Content;
+    use tempfile::tempdir;
+
+    fn test_storage_content(storage: &InstanceStorage) {
+        let entry = Content::from(RawString::from("some entry"));
+        storage.cas.write().unwrap().add(&entry).unwrap();
+        storage
+            .cas
+            .write()
+            .unwrap()
+            .add(&StoredContent {
+                address: Address::from(AGENT_SNAPSHOT_ADDRESS),
+                content: Content::from(RawString::from("agent snapshot")),
+            })
+            .unwrap();
+        let link = Content::from(RawString::from("link target"));
+        storage.cas.write().unwrap().add(&link).unwrap();
+        for attribute in vec![Attribute::EntryHeader, Attribute::Link] {
+            storage
+                .eav
+                .write()
+                .unwrap()
+                .add_eavi(
+                    &EntityAttributeValueIndex::new(&entry.address(), &attribute, &link.address())
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+    }
+
+    fn test_migration(to: StorageConfiguration) {
+        let dir = tempdir().unwrap();
+        let from = StorageConfiguration::File {
+            path: dir.path().join("file").to_string_lossy().to_string(),
+        };
+        test_storage_content(&InstanceStorage::open(&from).unwrap());
+
+        let report = migrate_storage(&from, &to).unwrap();
+        assert_eq!(
+            report,
+            MigrationReport {
+                cas_items: 3,
+                eavis: 2,
+                snapshots: vec![AGENT_SNAPSHOT_ADDRESS.to_string()],
+            }
+        );
+
+        let target = InstanceStorage::open(&to).unwrap();
+        assert_eq!(
+            target
+                .cas
+                .read()
+                .unwrap()
+                .fetch(&Address::from(AGENT_SNAPSHOT_ADDRESS))
+                .unwrap(),
+            Some(Content::from(RawString::from("agent snapshot")))
+        );
+        assert_eq!(target.all_eavis().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn migrates_file_storage_to_sqlite() {
+        let dir = tempdir().unwrap();
+        test_migration(StorageConfiguration::Sqlite {
+            path: dir.path().to_string_lossy().to_string(),
+        });
+    }
+
+    #[test]
+    fn migrates_file_storage_to_lmdb() {
+        let dir = tempdir().unwrap();
+        test_migration(StorageConfiguration::Lmdb {
+            path: dir.path().to_string_lossy().to_string(),
+            initial_mmap_bytes: None,
+        });
+    }
+
+    #[test]
+    fn refuses_to_migrate_into_storage_with_data() {
+        let dir = tempdir().unwrap();
+        let from = StorageConfiguration::File {
+            path: dir.path().join("from").to_string_lossy().to_string(),
+        };
+        let to = StorageConfiguration::Pickle {
+            path: dir.path().join("to").to_string_lossy().to_string(),
+        };
+        test_storage_content(&InstanceStorage::open(&from).unwrap());
+        test_storage_content(&InstanceStorage::open(&to).unwrap());
+
+        assert!(migrate_storage(&from, &to).is_err());
+        assert!(migrate_storage(&from, &from).is_err());
+    }
+
+    #[test]
+    fn interrupted_migration_can_be_retried() {
+        let dir = tempdir().unwrap();
+        let from = StorageConfiguration::File {
+            path: dir.path().join("from").to_string_lossy().to_string(),
+        };
+        let to = StorageConfiguration::Pickle {
+            path: dir.path().join("to").to_string_lossy().to_string(),
+        };
+        test_storage_content(&InstanceStorage::open(&from).unwrap());
+        // what a migration that got killed half way through leaves behind
+        let leftover = dir.path().join(".to.migrating.1.0");
+        test_storage_content(
+            &InstanceStorage::open(&to.with_path(leftover.to_string_lossy().to_string())).unwrap(),
+        );
+        fs::create_dir(dir.path().join("to")).unwrap();
+
+        assert_eq!(migrate_storage(&from, &to).unwrap().cas_items, 3);
+        let mut dir_names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|dir_entry| dir_entry.unwrap().file_name())
+            .collect();
+        dir_names.sort();
+        assert_eq!(dir_names, vec!["from", "to"]);
+        assert_eq!(
+            InstanceStorage::open(&to)
+                .unwrap()
+                .cas_addresses()
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Storage implementations that live in the conductor rather than in one of the
+//! holochain_persistence_* crates, and tools that work on the storage of an instance
+//! while that instance is not running.
+
+pub mod archive;
+pub mod dht_shard;
+pub mod migration;
+pub mod sqlite;
+
+use crate::{
+    config::StorageConfiguration,
+    storage::sqlite::{EavSqliteStorage, SqliteStorage},
+};
+use holochain_core::{
+    agent::state::AGENT_SNAPSHOT_ADDRESS, dht::dht_store::DHT_STORE_SNAPSHOT_ADDRESS,
+    nucleus::state::NUCLEUS_SNAPSHOT_ADDRESS,
+};
+use holochain_core_types::{eav::Attribute, error::HolochainError};
+use holochain_json_api::error::JsonError;
+use holochain_locksmith::RwLock;
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent, Content},
+        storage::ContentAddressableStorage,
+    },
+    eav::{EaviQuery, EntityAttributeValueIndex, EntityAttributeValueStorage, IndexFilter},
+};
+use holochain_persistence_file::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
+use holochain_persistence_lmdb::{cas::lmdb::LmdbStorage, eav::lmdb::EavLmdbStorage};
+use holochain_persistence_pickle::{cas::pickle::PickleStorage, eav::pickle::EavPickleStorage};
+use pickledb::{PickleDb, SerializationMethod};
+use rkv::{Manager, Rkv, StoreOptions};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The CAS and EAV storage of an instance as described by its StorageConfiguration,
+/// opened without the rest of the instance.
+pub struct InstanceStorage {
+    pub cas: Arc<RwLock<dyn ContentAddressableStorage>>,
+    pub eav: Arc<RwLock<dyn EntityAttributeValueStorage<Attribute>>>,
+    config: StorageConfiguration,
+}
+
+impl InstanceStorage {
+    /// Opens the storage the given configuration points to, with the same layout
+    /// the ContextBuilder uses. Storage that does not exist yet gets created.
+    /// Memory storage can't be opened since it only exists inside a running instance.
+    pub fn open(config: &StorageConfiguration) -> Result<Self, HolochainError> {
+        let (cas, eav): (
+            Arc<RwLock<dyn ContentAddressableStorage>>,
+            Arc<RwLock<dyn EntityAttributeValueStorage<Attribute>>>,
+        ) = match config {
+            StorageConfiguration::Memory => {
+                return Err(HolochainError::ConfigError(
+                    "Memory storage only exists inside a running instance".to_string(),
+                ))
+            }
+            StorageConfiguration::File { path } => {
+                let (cas_path, eav_path) = create_cas_and_eav_dirs(path)?;
+                (
+                    Arc::new(RwLock::new(FilesystemStorage::new(&cas_path)?)),
+                    Arc::new(RwLock::new(EavFileStorage::new(eav_path)?)),
+                )
+            }
+            StorageConfiguration::Pickle { path } => {
+                let (cas_path, eav_path) = create_cas_and_eav_dirs(path)?;
+                (
+                    Arc::new(RwLock::new(PickleStorage::new(&cas_path))),
+                    Arc::new(RwLock::new(EavPickleStorage::new(eav_path))),
+                )
+            }
+            StorageConfiguration::Lmdb {
+                path,
+                initial_mmap_bytes,
+            } => {
+                let (cas_path, eav_path) = create_cas_and_eav_dirs(path)?;
+                (
+                    Arc::new(RwLock::new(LmdbStorage::new(
+                        &cas_path,
+                        *initial_mmap_bytes,
+                    ))),
+                    Arc::new(RwLock::new(EavLmdbStorage::new(
+                        eav_path,
+                        *initial_mmap_bytes,
+                    ))),
+                )
+            }
+            StorageConfiguration::Sqlite { path } => {
+                fs::create_dir_all(path)?;
+                (
+                    Arc::new(RwLock::new(SqliteStorage::new(path)?)),
+                    Arc::new(RwLock::new(EavSqliteStorage::new(path)?)),
+                )
+            }
+        };
+        Ok(InstanceStorage {
+            cas,
+            eav,
+            config: config.clone(),
+        })
+    }
+
+    /// Addresses of all items in the CAS.
+    /// The CAS trait has no way of listing its content, so this reads the
+    /// underlying files or database of the backend directly.
+    pub fn cas_addresses(&self) -> Result<Vec<Address>, HolochainError> {
+        match &self.config {
+            StorageConfiguration::Memory => unreachable!(),
+            StorageConfiguration::File { path } => {
+                let mut addresses = Vec::new();
+                for dir_entry in fs::read_dir(Path::new(path).join("cas"))? {
+                    let file_path = dir_entry?.path();
+                    if file_path
+                        .extension()
+                        .map(|ext| ext == "txt")
+                        .unwrap_or(false)
+                    {
+                        if let Some(stem) = file_path.file_stem().and_then(|stem| stem.to_str()) {
+                            addresses.push(Address::from(stem));
+                        }
+                    }
+                }
+                Ok(addresses)
+            }
+            StorageConfiguration::Pickle { path } => {
+                let db_path = Path::new(path).join("cas").join("cas.db");
+                if !db_path.exists() {
+                    return Ok(Vec::new());
+                }
+                let db = PickleDb::load_read_only(&db_path, SerializationMethod::Cbor)
+                    .map_err(|e| HolochainError::IoError(e.to_string()))?;
+                Ok(db.get_all().into_iter().map(Address::from).collect())
+            }
+            StorageConfiguration::Lmdb { path, .. } => {
+                lmdb_keys(&Path::new(path).join("cas").join("cas.db"), "cas")
+            }
+            StorageConfiguration::Sqlite { path } => Ok(SqliteStorage::new(path)?.addresses()?),
+        }
+    }
+
+    /// True if there are neither CAS items nor EAVIs
+    pub fn is_empty(&self) -> Result<bool, HolochainError> {
+        Ok(self.cas_addresses()?.is_empty() && self.all_eavis()?.is_empty())
+    }
+
+    /// All EAVIs, including the ones that got superseded by later ones
+    pub fn all_eavis(
+        &self,
+    ) -> Result<BTreeSet<EntityAttributeValueIndex<Attribute>>, HolochainError> {
+        let query = EaviQuery::new(
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            IndexFilter::Range(None, None),
+            None,
+        );
+        Ok(self.eav.read().unwrap().fetch_eavi(&query)?)
+    }
+}
+
+/// Which of the state snapshots SimplePersister writes are among the given CAS addresses
+pub(crate) fn snapshots_in(addresses: &[Address]) -> Vec<String> {
+    [
+        AGENT_SNAPSHOT_ADDRESS,
+        NUCLEUS_SNAPSHOT_ADDRESS,
+        DHT_STORE_SNAPSHOT_ADDRESS,
+    ]
+    .iter()
+    .filter(|snapshot| addresses.contains(&Address::from(**snapshot)))
+    .map(|snapshot| snapshot.to_string())
+    .collect()
+}
+
+/// Content as it was read from a CAS, to be stored again under its original address.
+/// Some content is not stored at the hash of its content (agent IDs, state snapshots),
+/// so the address can't be recomputed from the content.
+pub(crate) struct StoredContent {
+    pub address: Address,
+    pub content: Content,
+}
+
+impl AddressableContent for StoredContent {
+    fn address(&self) -> Address {
+        self.address.clone()
+    }
+
+    fn content(&self) -> Content {
+        self.content.clone()
+    }
+
+    fn try_from_content(_content: &Content) -> Result<Self, JsonError> {
+        Err(JsonError::ErrorGeneric(
+            "StoredContent needs its address".to_string(),
+        ))
+    }
+}
+
+fn create_cas_and_eav_dirs(path: &str) -> Result<(PathBuf, PathBuf), HolochainError> {
+    let cas_path = Path::new(path).join("cas");
+    let eav_path = Path::new(path).join("eav");
+    fs::create_dir_all(&cas_path)?;
+    fs::create_dir_all(&eav_path)?;
+    Ok((cas_path, eav_path))
+}
+
+/// Keys of the given store in the LMDB environment at the given path.
+/// The environment is shared with the LmdbStorage through the rkv manager,
+/// so this has to run after InstanceStorage::open() opened it.
+fn lmdb_keys(env_path: &Path, store_name: &str) -> Result<Vec<Address>, HolochainError> {
+    let lmdb_error = |e: rkv::StoreError| HolochainError::IoError(format!("LMDB error: {}", e));
+    let env = Manager::singleton()
+        .write()
+        .unwrap()
+        .get_or_create(env_path, |path: &Path| {
+            let mut env_builder = Rkv::environment_builder();
+            env_builder.set_max_dbs(1);
+            Rkv::from_env(path, env_builder)
+        })
+        .map_err(lmdb_error)?;
+    let env = env.read().unwrap();
+    let store = env
+        .open_single(store_name, StoreOptions::create())
+        .map_err(lmdb_error)?;
+    let reader = env.read().map_err(lmdb_error)?;
+    let mut keys = Vec::new();
+    for item in store.iter_start(&reader).map_err(lmdb_error)? {
+        let (key, _) = item.map_err(lmdb_error)?;
+        keys.push(Address::from(String::from_utf8_lossy(key).to_string()));
+    }
+    Ok(keys)
+}
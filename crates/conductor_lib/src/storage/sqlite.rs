@@ -0,0 +1,432 @@
+//! CAS and EAV storage in a single SQLite database.
+//!
+//! Both tables live in the file `storage.sqlite` inside the storage directory of an instance,
+//! so its data can be inspected with the `sqlite3` shell and backed up with standard tools.
+//!
+//! EAV queries get narrowed down in SQL by their exact entity, attribute and value filters
+//! and by index ranges, all of which are backed by indexes. Predicate filters and the
+//! LatestByAttribute reduction then run on the rows SQL returned, like in the other backends.
+
+use holochain_json_api::json::JsonString;
+use holochain_locksmith::Mutex;
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent, Content},
+        storage::ContentAddressableStorage,
+    },
+    eav::{
+        Attribute, EavFilter, EaviQuery, EntityAttributeValueIndex, EntityAttributeValueStorage,
+        IndexFilter,
+    },
+    error::{PersistenceError, PersistenceResult},
+    reporting::{ReportStorage, StorageReport},
+};
+use rusqlite::{params, types::ToSql, Connection, OptionalExtension, NO_PARAMS};
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    fmt::{Debug, Error, Formatter},
+    marker::PhantomData,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use uuid::Uuid;
+
+/// Name of the database file inside the storage directory
+pub const SQLITE_FILE_NAME: &str = "storage.sqlite";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS cas (
+        address TEXT PRIMARY KEY NOT NULL,
+        content TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS eav (
+        entity TEXT NOT NULL,
+        attribute TEXT NOT NULL,
+        value TEXT NOT NULL,
+        idx INTEGER NOT NULL,
+        eavi TEXT NOT NULL,
+        PRIMARY KEY (entity, idx)
+    );
+    CREATE INDEX IF NOT EXISTS eav_entity_attribute ON eav (entity, attribute);
+    CREATE INDEX IF NOT EXISTS eav_value ON eav (value);
+    CREATE INDEX IF NOT EXISTS eav_idx ON eav (idx);
+";
+
+fn sqlite_error(e: rusqlite::Error) -> PersistenceError {
+    PersistenceError::ErrorGeneric(format!("SQLite error: {}", e))
+}
+
+fn serialization_error(e: serde_json::Error) -> PersistenceError {
+    PersistenceError::SerializationError(e.to_string())
+}
+
+/// Opens the database in the given directory, creating file and tables if needed.
+fn open_connection<P: AsRef<Path>>(dir: P) -> PersistenceResult<Arc<Mutex<Connection>>> {
+    let connection = Connection::open(dir.as_ref().join(SQLITE_FILE_NAME)).map_err(sqlite_error)?;
+    // The CAS and the EAV storage of an instance hold separate connections to the same file,
+    // so writers have to wait for each other instead of failing right away.
+    connection
+        .busy_timeout(Duration::from_secs(5))
+        .map_err(sqlite_error)?;
+    connection
+        .query_row("PRAGMA journal_mode = WAL", NO_PARAMS, |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(sqlite_error)?;
+    connection.execute_batch(SCHEMA).map_err(sqlite_error)?;
+    Ok(Arc::new(Mutex::new(connection)))
+}
+
+#[derive(Clone)]
+pub struct SqliteStorage {
+    id: Uuid,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl Debug for SqliteStorage {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_struct("SqliteStorage")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl SqliteStorage {
+    pub fn new<P: AsRef<Path>>(db_dir: P) -> PersistenceResult<SqliteStorage> {
+        Ok(SqliteStorage {
+            id: Uuid::new_v4(),
+            connection: open_connection(db_dir)?,
+        })
+    }
+
+    /// Addresses of all stored items
+    pub fn addresses(&self) -> PersistenceResult<Vec<Address>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare("SELECT address FROM cas")
+            .map_err(sqlite_error)?;
+        let rows = statement
+            .query_map(NO_PARAMS, |row| row.get::<_, String>(0))
+            .map_err(sqlite_error)?;
+        rows.map(|address| address.map(Address::from).map_err(sqlite_error))
+            .collect()
+    }
+}
+
+impl ContentAddressableStorage for SqliteStorage {
+    fn add(&mut self, content: &dyn AddressableContent) -> PersistenceResult<()> {
+        // Content is immutable, except that the DHT overwrites the content of evicted entries
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT OR REPLACE INTO cas (address, content) VALUES (?1, ?2)",
+                params![content.address().to_string(), content.content().to_string()],
+            )
+            .map(|_| ())
+            .map_err(sqlite_error)
+    }
+
+    fn contains(&self, address: &Address) -> PersistenceResult<bool> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT 1 FROM cas WHERE address = ?1",
+                params![address.to_string()],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|result| result.is_some())
+            .map_err(sqlite_error)
+    }
+
+    fn fetch(&self, address: &Address) -> PersistenceResult<Option<Content>> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT content FROM cas WHERE address = ?1",
+                params![address.to_string()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map(|result| result.map(|json| JsonString::from_json(&json)))
+            .map_err(sqlite_error)
+    }
+
+    fn get_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl ReportStorage for SqliteStorage {
+    fn get_storage_report(&self) -> PersistenceResult<StorageReport> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT IFNULL(SUM(LENGTH(address) + LENGTH(content)), 0) FROM cas",
+                NO_PARAMS,
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|bytes| StorageReport::new(bytes as usize))
+            .map_err(sqlite_error)
+    }
+}
+
+#[derive(Clone)]
+pub struct EavSqliteStorage<A: Attribute> {
+    id: Uuid,
+    connection: Arc<Mutex<Connection>>,
+    attribute: PhantomData<A>,
+}
+
+impl<A: Attribute> Debug for EavSqliteStorage<A> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_struct("EavSqliteStorage")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl<A: Attribute> EavSqliteStorage<A> {
+    pub fn new<P: AsRef<Path>>(db_dir: P) -> PersistenceResult<EavSqliteStorage<A>> {
+        Ok(EavSqliteStorage {
+            id: Uuid::new_v4(),
+            connection: open_connection(db_dir)?,
+            attribute: PhantomData,
+        })
+    }
+}
+
+/// Translates the exact filters and the index range of the given query into
+/// the WHERE clause of a SELECT on the eav table.
+pub(crate) fn eavi_query_sql<A: Attribute>(
+    query: &EaviQuery<A>,
+) -> PersistenceResult<(String, Vec<Box<dyn ToSql>>)> {
+    let mut conditions = Vec::new();
+    let mut parameters: Vec<Box<dyn ToSql>> = Vec::new();
+
+    if let EavFilter::Exact(entity) = query.entity() {
+        conditions.push("entity = ?");
+        parameters.push(Box::new(entity.to_string()));
+    }
+    if let EavFilter::Exact(attribute) = query.attribute() {
+        conditions.push("attribute = ?");
+        parameters.push(Box::new(
+            serde_json::to_string(attribute).map_err(serialization_error)?,
+        ));
+    }
+    if let EavFilter::Exact(value) = query.value() {
+        conditions.push("value = ?");
+        parameters.push(Box::new(value.to_string()));
+    }
+    if let IndexFilter::Range(start, end) = query.index() {
+        if let Some(start) = start {
+            conditions.push("idx >= ?");
+            parameters.push(Box::new(*start));
+        }
+        if let Some(end) = end {
+            conditions.push("idx <= ?");
+            parameters.push(Box::new(*end));
+        }
+    }
+
+    let sql = if conditions.is_empty() {
+        "SELECT eavi FROM eav".to_string()
+    } else {
+        format!("SELECT eavi FROM eav WHERE {}", conditions.join(" AND "))
+    };
+    Ok((sql, parameters))
+}
+
+impl<A: Attribute> EntityAttributeValueStorage<A> for EavSqliteStorage<A>
+where
+    A: Sync + Send + serde::de::DeserializeOwned,
+{
+    fn add_eavi(
+        &mut self,
+        eav: &EntityAttributeValueIndex<A>,
+    ) -> PersistenceResult<Option<EntityAttributeValueIndex<A>>> {
+        let attribute = serde_json::to_string(&eav.attribute()).map_err(serialization_error)?;
+        let connection = self.connection.lock().unwrap();
+        let mut new_eav = eav.clone();
+        // (entity, index) is the primary key, so move the index up until it is unique
+        loop {
+            let inserted = connection
+                .execute(
+                    "INSERT OR IGNORE INTO eav (entity, attribute, value, idx, eavi)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        new_eav.entity().to_string(),
+                        attribute,
+                        new_eav.value().to_string(),
+                        new_eav.index(),
+                        new_eav.content().to_string(),
+                    ],
+                )
+                .map_err(sqlite_error)?;
+            if inserted > 0 {
+                return Ok(Some(new_eav));
+            }
+            new_eav.set_index(new_eav.index() + 1);
+        }
+    }
+
+    fn fetch_eavi(
+        &self,
+        query: &EaviQuery<A>,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex<A>>> {
+        let (sql, parameters) = eavi_query_sql(query)?;
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(&sql).map_err(sqlite_error)?;
+        let entries = statement
+            .query_map(parameters.iter().map(|p| &**p), |row| {
+                row.get::<_, String>(0)
+            })
+            .map_err(sqlite_error)?
+            .map(|row| {
+                let json = row.map_err(sqlite_error)?;
+                EntityAttributeValueIndex::try_from(JsonString::from_json(&json))
+                    .map_err(PersistenceError::from)
+            })
+            .collect::<PersistenceResult<BTreeSet<EntityAttributeValueIndex<A>>>>()?;
+
+        Ok(query.run(entries.iter().cloned()))
+    }
+}
+
+impl<A: Attribute> ReportStorage for EavSqliteStorage<A> {
+    fn get_storage_report(&self) -> PersistenceResult<StorageReport> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT IFNULL(SUM(LENGTH(eavi)), 0) FROM eav",
+                NO_PARAMS,
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|bytes| StorageReport::new(bytes as usize))
+            .map_err(sqlite_error)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core::dht::dht_store::create_get_links_eavi_query;
+    use holochain_json_api::json::RawString;
+    use holochain_persistence_api::{
+        cas::{
+            content::{ExampleAddressableContent, OtherExampleAddressableContent},
+            storage::{EavTestSuite, StorageTestSuite},
+        },
+        eav::ExampleAttribute,
+    };
+    use tempfile::tempdir;
+
+    #[test]
+    fn sqlite_content_round_trip_test() {
+        let dir = tempdir().unwrap();
+        let test_suite = StorageTestSuite::new(SqliteStorage::new(dir.path()).unwrap());
+        test_suite.round_trip_test::<ExampleAddressableContent, OtherExampleAddressableContent>(
+            RawString::from("foo").into(),
+            RawString::from("bar").into(),
+        );
+    }
+
+    #[test]
+    fn sqlite_content_survives_reopening() {
+        let dir = tempdir().unwrap();
+        let content = Content::from(RawString::from("persistent"));
+        SqliteStorage::new(dir.path())
+            .unwrap()
+            .add(&content)
+            .unwrap();
+
+        let cas = SqliteStorage::new(dir.path()).unwrap();
+        assert_eq!(
+            cas.fetch(&content.address()).unwrap(),
+            Some(content.clone())
+        );
+        assert!(dir.path().join(SQLITE_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn sqlite_eav_one_to_many() {
+        let dir = tempdir().unwrap();
+        EavTestSuite::test_one_to_many::<
+            ExampleAddressableContent,
+            ExampleAttribute,
+            EavSqliteStorage<ExampleAttribute>,
+        >(
+            EavSqliteStorage::new(dir.path()).unwrap(),
+            &ExampleAttribute::default(),
+        );
+    }
+
+    #[test]
+    fn sqlite_eav_many_to_one() {
+        let dir = tempdir().unwrap();
+        EavTestSuite::test_many_to_one::<
+            ExampleAddressableContent,
+            ExampleAttribute,
+            EavSqliteStorage<ExampleAttribute>,
+        >(
+            EavSqliteStorage::new(dir.path()).unwrap(),
+            &ExampleAttribute::default(),
+        );
+    }
+
+    #[test]
+    fn sqlite_eav_range() {
+        let dir = tempdir().unwrap();
+        EavTestSuite::test_range::<
+            ExampleAddressableContent,
+            ExampleAttribute,
+            EavSqliteStorage<ExampleAttribute>,
+        >(
+            EavSqliteStorage::new(dir.path()).unwrap(),
+            &ExampleAttribute::default(),
+        );
+    }
+
+    #[test]
+    fn sqlite_eav_tombstone() {
+        let dir = tempdir().unwrap();
+        EavTestSuite::test_tombstone::<ExampleAddressableContent, _>(
+            EavSqliteStorage::new(dir.path()).unwrap(),
+        );
+    }
+
+    #[test]
+    fn get_links_queries_use_an_index() {
+        let dir = tempdir().unwrap();
+        let storage = EavSqliteStorage::<ExampleAttribute>::new(dir.path()).unwrap();
+        let query = create_get_links_eavi_query(
+            Address::from("QmEntity"),
+            Some("link_type".to_string()),
+            None,
+        )
+        .unwrap();
+        let (sql, parameters) = eavi_query_sql(&query).unwrap();
+
+        let connection = storage.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+            .unwrap();
+        let plan = statement
+            .query_map(parameters.iter().map(|p| &**p), |row| {
+                row.get::<_, String>(3)
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .join("\n");
+        assert!(plan.contains("SEARCH"), "query plan: {}", plan);
+        assert!(!plan.contains("SCAN"), "query plan: {}", plan);
+    }
+}
@@ -1,5 +1,9 @@
 use crate::{
-    agent::state::AgentState,
+    agent::{
+        bundle::{Bundle, BundledEntry},
+        countersigning::CountersigningSession,
+        state::AgentState,
+    },
     dht::{
         actions::remove_queued_holding_workflow::HoldingWorkflowQueueing,
         dht_store::HoldAspectAttemptId, pending_validations::PendingValidation,
@@ -10,6 +14,7 @@ use crate::{
         entry_with_header::EntryWithHeader,
         query::{GetLinksNetworkQuery, NetworkQueryResult},
         state::NetworkState,
+        validation_receipt::ValidationReceipt,
     },
     nucleus::{
         actions::{call_zome_function::ExecuteZomeFnResponse, initialize::Initialization},
@@ -20,8 +25,8 @@ use crate::{
 };
 
 use holochain_core_types::{
-    chain_header::ChainHeader, crud_status::CrudStatus, dna::Dna, entry::Entry,
-    signature::Provenance, validation::ValidationPackage,
+    chain_fork::ChainFork, chain_header::ChainHeader, crud_status::CrudStatus, dna::Dna,
+    entry::Entry, network::warrant::Warrant, signature::Provenance, validation::ValidationPackage,
 };
 use holochain_net::{connection::net_connection::NetHandler, p2p_config::P2pConfig};
 use holochain_persistence_api::cas::content::Address;
@@ -55,6 +60,11 @@ impl ActionWrapper {
         }
     }
 
+    /// constructor for an action that got dispatched before, e.g. when replaying the journal
+    pub(crate) fn with_id(a: Action, id: String) -> Self {
+        ActionWrapper { action: a, id }
+    }
+
     /// read only access to action
     pub fn action(&self) -> &Action {
         &self.action
@@ -110,6 +120,35 @@ pub enum Action {
     /// Does not validate, assumes entry is valid.
     Commit((Entry, Option<Address>, Vec<Provenance>)),
 
+    /// Puts an entry and its header that got committed before the instance went down back on
+    /// top of the source chain, as recorded in the journal.
+    /// Does nothing unless the header links to the current top, e.g. if it is there already.
+    ReplayCommit(EntryWithHeader),
+
+    /// Opens a bundle for the zome function call given by its ID.
+    /// Fails if another call has a bundle open that hasn't timed out yet.
+    StartBundle(Bundle),
+
+    /// Adds an entry to the open bundle of the zome function call given by its ID
+    /// instead of writing it to the source chain.
+    StageBundleEntry((String, BundledEntry)),
+
+    /// Removes the open bundle of the zome function call given by its ID from the state
+    /// and hands it over in the action response.
+    CloseBundle(String),
+
+    /// Writes the entries of a bundle onto the source chain in the given order, all at once.
+    /// Either all of them get committed or, if one fails, none.
+    /// Does not validate, assumes entries are valid.
+    CommitBundle(Vec<BundledEntry>),
+
+    /// Remembers a countersigned entry we signed so we commit it once all parties signed.
+    StartCountersigning(CountersigningSession),
+
+    /// Forgets the countersigned entry with the given address, after we committed it or its
+    /// proposer aborted.
+    EndCountersigning(Address),
+
     // -------------
     // DHT actions:
     // -------------
@@ -125,6 +164,20 @@ pub enum Action {
     /// Does not validate, assumes referenced entry is valid.
     HoldAspect((EntryAspect, HoldAspectAttemptId)),
 
+    /// Stops holding all aspects of the given entries to get back within the storage quota
+    /// and tells the network that we no longer hold them.
+    EvictHeldEntries(Vec<Address>),
+
+    /// Stores a validation receipt that a holder sent us for one of our entries.
+    AddValidationReceipt(ValidationReceipt),
+
+    /// Indexes a header that got published to its author's address by its predecessor.
+    /// Does not validate, assumes the header's signatures got checked.
+    HoldAuthoredHeader(ChainHeader),
+
+    /// Flags a fork in another agent's source chain that we noticed in the headers we hold.
+    FlagChainFork(ChainFork),
+
     //action for updating crudstatus
     CrudStatus((EntryWithHeader, CrudStatus)),
 
@@ -147,6 +200,10 @@ pub enum Action {
     /// Note that the given address is that of the entry NOT the address of the header itself
     PublishHeaderEntry(Address),
 
+    /// Publish a warrant against the author of an invalid entry to the
+    /// neighborhood of the author's agent address.
+    PublishWarrant(Warrant),
+
     /// Performs a Network Query Action based on the key and payload, used for links and Entries.
     /// Includes the timeout information: system time of dispatch and duration until it timeouts.
     Query((QueryKey, QueryPayload, Option<(SystemTime, Duration)>)),
@@ -238,6 +295,11 @@ pub enum Action {
     /// Remove all traces of the given call from state (mainly the result)
     ClearZomeFunctionCall(ZomeFnCall),
 
+    /// Counts a zome call that got granted through the capability grant with the given token,
+    /// unless the grant got used the given maximum number of times already.
+    /// The response tells the call whether it may go ahead.
+    UseCapGrant((Address, u32)),
+
     /// No-op, used to check if an action channel is still open
     Ping,
 }
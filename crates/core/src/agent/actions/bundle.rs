@@ -0,0 +1,110 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    agent::{
+        bundle::{Bundle, BundledEntry},
+        state::AgentActionResponse,
+    },
+    context::Context,
+    instance::dispatch_action,
+};
+use futures::{future::Future, task::Poll};
+use holochain_core_types::error::HolochainError;
+use snowflake::ProcessUniqueId;
+use std::{pin::Pin, sync::Arc};
+
+/// StartBundle Action Creator
+/// Opens the given bundle in the agent state.
+///
+/// Returns a future that resolves to the opened bundle, or an error if another
+/// zome function call has a bundle open.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn start_bundle(
+    bundle: Bundle,
+    context: &Arc<Context>,
+) -> Result<Bundle, HolochainError> {
+    dispatch_and_wait(Action::StartBundle(bundle), context).await
+}
+
+/// StageBundleEntry Action Creator
+/// Adds the entry to the bundle of the given zome function call.
+///
+/// Returns a future that resolves to the bundle including the new entry.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn stage_bundle_entry(
+    zome_call_id: String,
+    bundled_entry: BundledEntry,
+    context: &Arc<Context>,
+) -> Result<Bundle, HolochainError> {
+    dispatch_and_wait(
+        Action::StageBundleEntry((zome_call_id, bundled_entry)),
+        context,
+    )
+    .await
+}
+
+/// CloseBundle Action Creator
+/// Removes the bundle of the given zome function call from the agent state.
+///
+/// Returns a future that resolves to the removed bundle so its entries can be authored.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn close_bundle(
+    zome_call_id: String,
+    context: &Arc<Context>,
+) -> Result<Bundle, HolochainError> {
+    dispatch_and_wait(Action::CloseBundle(zome_call_id), context).await
+}
+
+async fn dispatch_and_wait(
+    action: Action,
+    context: &Arc<Context>,
+) -> Result<Bundle, HolochainError> {
+    let action_wrapper = ActionWrapper::new(action);
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+    BundleFuture {
+        context: context.clone(),
+        action: action_wrapper,
+        id: ProcessUniqueId::new(),
+    }
+    .await
+}
+
+/// BundleFuture resolves to the bundle the reducer put into the action response
+/// Tracks the state for a response to its ActionWrapper
+pub struct BundleFuture {
+    context: Arc<Context>,
+    action: ActionWrapper,
+    id: ProcessUniqueId,
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+impl Future for BundleFuture {
+    type Output = Result<Bundle, HolochainError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context) -> Poll<Self::Output> {
+        if let Some(err) = self.context.action_channel_error("BundleFuture") {
+            return Poll::Ready(Err(err));
+        }
+        self.context
+            .register_waker(self.id.clone(), cx.waker().clone());
+        if let Some(state) = self.context.try_state() {
+            match state.agent().actions().get(&self.action) {
+                Some(r) => match r.response() {
+                    AgentActionResponse::Bundle(result) => {
+                        dispatch_action(
+                            self.context.action_channel(),
+                            ActionWrapper::new(Action::ClearActionResponse(
+                                self.action.id().to_string(),
+                            )),
+                        );
+                        self.context.unregister_waker(self.id.clone());
+                        Poll::Ready(result.clone())
+                    }
+                    _ => unreachable!(),
+                },
+                None => Poll::Pending,
+            }
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
     action::{Action, ActionWrapper},
-    agent::state::AgentActionResponse,
+    agent::{bundle::BundledEntry, state::AgentActionResponse},
     context::Context,
     instance::dispatch_action,
 };
 use futures::{future::Future, task::Poll};
-use holochain_core_types::{entry::Entry, error::HolochainError};
+use holochain_core_types::{entry::Entry, error::HolochainError, signature::Provenance};
 use holochain_persistence_api::cas::content::Address;
 use snowflake::ProcessUniqueId;
 use std::{pin::Pin, sync::Arc};
@@ -20,11 +20,23 @@ pub async fn commit_entry(
     entry: Entry,
     maybe_link_update_delete: Option<Address>,
     context: &Arc<Context>,
+) -> Result<Address, HolochainError> {
+    commit_entry_with_provenances(entry, maybe_link_update_delete, Vec::new(), context).await
+}
+
+/// Like commit_entry() but the header gets the given provenances.
+/// Ours gets added unless it is among them already, as it is for countersigned entries.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn commit_entry_with_provenances(
+    entry: Entry,
+    maybe_link_update_delete: Option<Address>,
+    provenances: Vec<Provenance>,
+    context: &Arc<Context>,
 ) -> Result<Address, HolochainError> {
     let action_wrapper = ActionWrapper::new(Action::Commit((
         entry.clone(),
         maybe_link_update_delete,
-        vec![],
+        provenances,
     )));
     dispatch_action(context.action_channel(), action_wrapper.clone());
     let id = ProcessUniqueId::new();
@@ -36,6 +48,25 @@ pub async fn commit_entry(
     .await
 }
 
+/// CommitBundle Action Creator
+/// Commits the entries of a bundle in the given order, either all of them or none.
+///
+/// Returns a future that resolves to the addresses of the committed entries.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn commit_bundle(
+    bundled_entries: Vec<BundledEntry>,
+    context: &Arc<Context>,
+) -> Result<Vec<Address>, HolochainError> {
+    let action_wrapper = ActionWrapper::new(Action::CommitBundle(bundled_entries));
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+    CommitBundleFuture {
+        context: context.clone(),
+        action: action_wrapper,
+        id: ProcessUniqueId::new(),
+    }
+    .await
+}
+
 /// CommitFuture resolves to ActionResponse
 /// Tracks the state for a response to its ActionWrapper
 pub struct CommitFuture {
@@ -76,3 +107,44 @@ impl Future for CommitFuture {
         }
     }
 }
+
+/// CommitBundleFuture resolves to the addresses of the committed bundle entries
+/// Tracks the state for a response to its ActionWrapper
+pub struct CommitBundleFuture {
+    context: Arc<Context>,
+    action: ActionWrapper,
+    id: ProcessUniqueId,
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+impl Future for CommitBundleFuture {
+    type Output = Result<Vec<Address>, HolochainError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context) -> Poll<Self::Output> {
+        if let Some(err) = self.context.action_channel_error("CommitBundleFuture") {
+            return Poll::Ready(Err(err));
+        }
+        self.context
+            .register_waker(self.id.clone(), cx.waker().clone());
+        if let Some(state) = self.context.try_state() {
+            match state.agent().actions().get(&self.action) {
+                Some(r) => match r.response() {
+                    AgentActionResponse::CommitBundle(result) => {
+                        dispatch_action(
+                            self.context.action_channel(),
+                            ActionWrapper::new(Action::ClearActionResponse(
+                                self.action.id().to_string(),
+                            )),
+                        );
+                        self.context.unregister_waker(self.id.clone());
+                        Poll::Ready(result.clone())
+                    }
+                    _ => unreachable!(),
+                },
+                None => Poll::Pending,
+            }
+        } else {
+            Poll::Pending
+        }
+    }
+}
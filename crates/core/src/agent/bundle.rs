@@ -0,0 +1,114 @@
+use holochain_core_types::{entry::Entry, signature::Provenance, time::Timeout};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::time::{Duration, SystemTime};
+
+/// An entry that got committed while a bundle was open.
+/// Holds everything that is needed to author it when the bundle gets closed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BundledEntry {
+    pub entry: Entry,
+    pub maybe_link_update_delete: Option<Address>,
+    pub provenances: Vec<Provenance>,
+}
+
+/// The staging area of a bundle opened by hdk::start_bundle().
+/// Entries collected here only get written to the source chain, all at once,
+/// when the zome function call that owns the bundle closes it with `BundleOnClose::Commit`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+    /// ID of the zome function call that opened the bundle
+    zome_call_id: String,
+    started_at: SystemTime,
+    timeout: Timeout,
+    user_param: JsonString,
+    entries: Vec<BundledEntry>,
+}
+
+impl Bundle {
+    pub fn new(zome_call_id: String, timeout: Timeout, user_param: JsonString) -> Self {
+        Bundle {
+            zome_call_id,
+            started_at: SystemTime::now(),
+            timeout,
+            user_param,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn zome_call_id(&self) -> &String {
+        &self.zome_call_id
+    }
+
+    pub fn user_param(&self) -> &JsonString {
+        &self.user_param
+    }
+
+    pub fn entries(&self) -> &Vec<BundledEntry> {
+        &self.entries
+    }
+
+    pub fn add_entry(&mut self, entry: BundledEntry) {
+        self.entries.push(entry);
+    }
+
+    /// True if the bundle was open for longer than its timeout
+    pub fn is_expired(&self) -> bool {
+        self.started_at
+            .elapsed()
+            .map(|elapsed| elapsed > Duration::from(&self.timeout))
+            .unwrap_or(false)
+    }
+
+    /// The staged entry with the given address, if there is one
+    pub fn get_entry(&self, address: &Address) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .map(|bundled| &bundled.entry)
+            .find(|entry| &entry.address() == address)
+    }
+
+    /// True if an entry with the given address is staged in this bundle
+    pub fn contains(&self, address: &Address) -> bool {
+        self.get_entry(address).is_some()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::entry::test_entry;
+
+    pub fn test_bundle(timeout_ms: usize) -> Bundle {
+        Bundle::new(
+            "test-zome-call".to_string(),
+            Timeout::new(timeout_ms),
+            JsonString::null(),
+        )
+    }
+
+    #[test]
+    fn test_bundle_contains_added_entries() {
+        let mut bundle = test_bundle(1000);
+        assert!(!bundle.contains(&test_entry().address()));
+        bundle.add_entry(BundledEntry {
+            entry: test_entry(),
+            maybe_link_update_delete: None,
+            provenances: Vec::new(),
+        });
+        assert!(bundle.contains(&test_entry().address()));
+        assert_eq!(
+            bundle.get_entry(&test_entry().address()),
+            Some(&test_entry())
+        );
+        assert_eq!(bundle.entries().len(), 1);
+    }
+
+    #[test]
+    fn test_bundle_expires() {
+        assert!(!test_bundle(60000).is_expired());
+        let bundle = test_bundle(0);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(bundle.is_expired());
+    }
+}
@@ -0,0 +1,562 @@
+//! Checks the integrity of a stored source chain end to end, without trusting
+//! anything but the content of the chain's CAS.
+//!
+//! The verifier walks the chain from its top header following `link` and reports every
+//! break it finds instead of stopping at the first one, so a report can be used to see
+//! how much of a damaged chain is still intact.
+
+use crate::{
+    agent::chain_store::ChainStore, content_store::GetContent,
+    nucleus::validation::validate_header_signatures,
+};
+use holochain_core_types::{
+    chain_header::ChainHeader,
+    entry::{entry_type::EntryType, Entry},
+    error::HolochainError,
+    time::Iso8601,
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::collections::HashSet;
+
+/// Which of the links of a header a break was found in
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderLink {
+    Link,
+    LinkSameType,
+}
+
+/// A single integrity violation found in a source chain.
+/// Serialized with a `break` field naming the kind of violation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "break", rename_all = "snake_case")]
+pub enum ChainBreak {
+    /// A link of the header points to an address that holds nothing
+    MissingHeader {
+        header: Address,
+        link: HeaderLink,
+        address: Address,
+    },
+    /// A link of the header points to content that is not a chain header
+    MalformedHeader {
+        header: Address,
+        link: HeaderLink,
+        address: Address,
+        error: String,
+    },
+    /// The header stored at the address a link points to has a different address.
+    /// The walk down the chain stops there, as that header's links can't be trusted.
+    HeaderAddressMismatch {
+        header: Address,
+        link: HeaderLink,
+        address: Address,
+        actual_address: Address,
+    },
+    /// `link` points back to a header that was already walked, which stops the walk
+    LinkCycle { header: Address, address: Address },
+    /// The header stored at the address `link_same_type` points to has another entry type
+    LinkSameTypeMismatch {
+        header: Address,
+        address: Address,
+        entry_type: EntryType,
+        linked_entry_type: EntryType,
+    },
+    /// The entry of the header is not in the CAS or could not be read
+    MissingEntry {
+        header: Address,
+        entry_address: Address,
+        error: Option<String>,
+    },
+    /// The entry stored under the header's entry address hashes to another address
+    EntryAddressMismatch {
+        header: Address,
+        entry_address: Address,
+        actual_address: Address,
+    },
+    /// The provenance signatures of the header don't verify against the keys of their
+    /// sources, as checked by system validation
+    InvalidSignature { header: Address, error: String },
+    /// None of the provenances is from an agent whose AgentId entry is on the chain
+    MissingAgentSignature { header: Address },
+    /// The chain has no AgentId entry, so signatures can't be matched to its agent
+    MissingAgentEntry,
+    /// The header is older than the header before it
+    TimestampNotMonotonic {
+        header: Address,
+        timestamp: Iso8601,
+        previous_header: Address,
+        previous_timestamp: Iso8601,
+    },
+}
+
+/// Result of verifying a source chain, meant to be serialized as a machine-readable report
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainVerificationReport {
+    /// Address of the header the walk started at, None for an empty chain
+    pub top_header: Option<Address>,
+    /// Number of headers that could be reached by following `link` from the top
+    pub headers_checked: usize,
+    pub breaks: Vec<ChainBreak>,
+}
+
+impl ChainVerificationReport {
+    pub fn is_intact(&self) -> bool {
+        self.breaks.is_empty()
+    }
+}
+
+/// Walks the chain from `top_header` down to its first header and checks that
+///  * `link` and `link_same_type` of every header point to existing headers,
+///  * the entry of every header is stored under its `entry_address`,
+///  * every provenance signature verifies and one of them is from the chain's agent,
+///  * timestamps never decrease from one header to the next.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn verify_chain(
+    chain_store: &ChainStore,
+    top_header: Option<ChainHeader>,
+) -> ChainVerificationReport {
+    let mut breaks = Vec::new();
+    let headers = walk_chain(chain_store, top_header.clone(), &mut breaks);
+
+    for (header, previous) in headers.iter().zip(headers.iter().skip(1)) {
+        if header.timestamp() < previous.timestamp() {
+            breaks.push(ChainBreak::TimestampNotMonotonic {
+                header: header.address(),
+                timestamp: header.timestamp().clone(),
+                previous_header: previous.address(),
+                previous_timestamp: previous.timestamp().clone(),
+            });
+        }
+    }
+
+    let mut agent_keys = HashSet::new();
+    for header in headers.iter() {
+        if let Some(Entry::AgentId(agent_id)) = verify_entry(chain_store, header, &mut breaks) {
+            agent_keys.insert(agent_id.address());
+        }
+        verify_same_type_link(chain_store, header, &mut breaks);
+    }
+
+    if agent_keys.is_empty() && !headers.is_empty() {
+        breaks.push(ChainBreak::MissingAgentEntry);
+    }
+    for header in headers.iter() {
+        verify_provenances(header, &agent_keys, &mut breaks);
+    }
+
+    ChainVerificationReport {
+        top_header: top_header.map(|header| header.address()),
+        headers_checked: headers.len(),
+        breaks,
+    }
+}
+
+/// All headers reachable from the top by following `link`, latest first.
+/// Stops at a header that was reached before, so a damaged chain can't make it loop.
+fn walk_chain(
+    chain_store: &ChainStore,
+    top_header: Option<ChainHeader>,
+    breaks: &mut Vec<ChainBreak>,
+) -> Vec<ChainHeader> {
+    let mut headers = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = top_header;
+    while let Some(header) = current {
+        visited.insert(header.address());
+        current = header.link().and_then(|address| {
+            if visited.contains(&address) {
+                breaks.push(ChainBreak::LinkCycle {
+                    header: header.address(),
+                    address,
+                });
+                return None;
+            }
+            fetch_linked_header(chain_store, &header, HeaderLink::Link, &address, breaks)
+        });
+        headers.push(header);
+    }
+    headers
+}
+
+fn fetch_linked_header(
+    chain_store: &ChainStore,
+    header: &ChainHeader,
+    link: HeaderLink,
+    address: &Address,
+    breaks: &mut Vec<ChainBreak>,
+) -> Option<ChainHeader> {
+    let content = match chain_store.get_raw(address) {
+        Ok(Some(content)) => content,
+        Ok(None) => {
+            breaks.push(ChainBreak::MissingHeader {
+                header: header.address(),
+                link,
+                address: address.clone(),
+            });
+            return None;
+        }
+        Err(error) => {
+            breaks.push(ChainBreak::MalformedHeader {
+                header: header.address(),
+                link,
+                address: address.clone(),
+                error: error.to_string(),
+            });
+            return None;
+        }
+    };
+    match ChainHeader::try_from_content(&content) {
+        Ok(linked_header) => {
+            if linked_header.address() != *address {
+                breaks.push(ChainBreak::HeaderAddressMismatch {
+                    header: header.address(),
+                    link,
+                    address: address.clone(),
+                    actual_address: linked_header.address(),
+                });
+                return None;
+            }
+            Some(linked_header)
+        }
+        Err(error) => {
+            breaks.push(ChainBreak::MalformedHeader {
+                header: header.address(),
+                link,
+                address: address.clone(),
+                error: error.to_string(),
+            });
+            None
+        }
+    }
+}
+
+fn verify_same_type_link(
+    chain_store: &ChainStore,
+    header: &ChainHeader,
+    breaks: &mut Vec<ChainBreak>,
+) {
+    if let Some(address) = header.link_same_type() {
+        let linked_header = fetch_linked_header(
+            chain_store,
+            header,
+            HeaderLink::LinkSameType,
+            &address,
+            breaks,
+        );
+        if let Some(linked_header) = linked_header {
+            if linked_header.entry_type() != header.entry_type() {
+                breaks.push(ChainBreak::LinkSameTypeMismatch {
+                    header: header.address(),
+                    address,
+                    entry_type: header.entry_type().clone(),
+                    linked_entry_type: linked_header.entry_type().clone(),
+                });
+            }
+        }
+    }
+}
+
+fn verify_entry(
+    chain_store: &ChainStore,
+    header: &ChainHeader,
+    breaks: &mut Vec<ChainBreak>,
+) -> Option<Entry> {
+    let missing_entry = |error: Option<String>| ChainBreak::MissingEntry {
+        header: header.address(),
+        entry_address: header.entry_address().clone(),
+        error,
+    };
+    match chain_store.get(header.entry_address()) {
+        Ok(Some(entry)) => {
+            if entry.address() != *header.entry_address() {
+                breaks.push(ChainBreak::EntryAddressMismatch {
+                    header: header.address(),
+                    entry_address: header.entry_address().clone(),
+                    actual_address: entry.address(),
+                });
+            }
+            Some(entry)
+        }
+        Ok(None) => {
+            breaks.push(missing_entry(None));
+            None
+        }
+        Err(error) => {
+            breaks.push(missing_entry(Some(error.to_string())));
+            None
+        }
+    }
+}
+
+fn verify_provenances(
+    header: &ChainHeader,
+    agent_keys: &HashSet<Address>,
+    breaks: &mut Vec<ChainBreak>,
+) {
+    if let Err(error) = validate_header_signatures(header) {
+        breaks.push(ChainBreak::InvalidSignature {
+            header: header.address(),
+            error: HolochainError::from(error).to_string(),
+        });
+    }
+    if !agent_keys.is_empty()
+        && !header
+            .provenances()
+            .iter()
+            .any(|provenance| agent_keys.contains(&provenance.source()))
+    {
+        breaks.push(ChainBreak::MissingAgentSignature {
+            header: header.address(),
+        });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{agent::chain_store::tests::test_chain_store, content_store::AddContent};
+    use holochain_core_types::{
+        agent::AgentId,
+        countersigning::countersigning_payload,
+        entry::{test_entry, test_entry_b, test_entry_c},
+        signature::{Provenance, Signature},
+    };
+    use holochain_dpki::keypair::{generate_random_sign_keypair, SigningKeyPair};
+    use holochain_json_api::error::JsonError;
+    use holochain_persistence_api::cas::content::Content;
+    use lib3h_sodium::secbuf::SecBuf;
+
+    /// Content stored under an address it doesn't hash to
+    struct Misplaced {
+        address: Address,
+        content: Content,
+    }
+
+    impl AddressableContent for Misplaced {
+        fn address(&self) -> Address {
+            self.address.clone()
+        }
+
+        fn content(&self) -> Content {
+            self.content.clone()
+        }
+
+        fn try_from_content(_content: &Content) -> Result<Self, JsonError> {
+            Err(JsonError::ErrorGeneric(
+                "Misplaced needs its address".to_string(),
+            ))
+        }
+    }
+
+    fn sign(keys: &mut SigningKeyPair, data: String) -> Signature {
+        let mut data_buf = SecBuf::with_insecure_from_string(data);
+        let mut signature_buf = keys.sign(&mut data_buf).unwrap();
+        let buf = signature_buf.read_lock();
+        Signature::from(base64::encode(&**buf))
+    }
+
+    /// An AgentId entry and two app entries with headers signed by that agent,
+    /// one second apart, starting at the given number of seconds since the epoch
+    fn signed_chain(start: i64) -> (Vec<Entry>, Vec<ChainHeader>) {
+        signed_chain_of(&mut generate_random_sign_keypair().unwrap(), start)
+    }
+
+    fn signed_chain_of(keys: &mut SigningKeyPair, start: i64) -> (Vec<Entry>, Vec<ChainHeader>) {
+        let agent = Entry::AgentId(AgentId::new("verifier", keys.public.clone()));
+        let entries = vec![agent, test_entry(), test_entry_b()];
+        let mut headers: Vec<ChainHeader> = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let signature = sign(keys, entry.address().to_string());
+            headers.push(ChainHeader::new(
+                &entry.entry_type(),
+                &entry.address(),
+                &[Provenance::new(
+                    Address::from(keys.public.clone()),
+                    signature,
+                )],
+                &headers.last().map(|header| header.address()),
+                &None,
+                &None,
+                &Iso8601::from(start + i as i64),
+            ));
+        }
+        (entries, headers)
+    }
+
+    fn store_chain(entries: &[Entry], headers: &[ChainHeader]) -> ChainStore {
+        let mut chain_store = test_chain_store();
+        for entry in entries {
+            chain_store.add(entry).unwrap();
+        }
+        for header in headers {
+            chain_store.add(header).unwrap();
+        }
+        chain_store
+    }
+
+    #[test]
+    fn verifies_empty_chain() {
+        let report = verify_chain(&test_chain_store(), None);
+        assert!(report.is_intact());
+        assert_eq!(report.headers_checked, 0);
+    }
+
+    #[test]
+    fn verifies_intact_chain() {
+        let (entries, headers) = signed_chain(1_000);
+        let chain_store = store_chain(&entries, &headers);
+        let report = verify_chain(&chain_store, headers.last().cloned());
+        assert_eq!(report.breaks, Vec::new());
+        assert_eq!(report.headers_checked, 3);
+        assert_eq!(report.top_header, Some(headers[2].address()));
+    }
+
+    #[test]
+    fn verifies_countersigned_headers() {
+        let mut alice = generate_random_sign_keypair().unwrap();
+        let mut bob = generate_random_sign_keypair().unwrap();
+        let (mut entries, mut headers) = signed_chain_of(&mut alice, 1_000);
+        let entry = test_entry_c();
+        let parties = vec![
+            Address::from(alice.public.clone()),
+            Address::from(bob.public.clone()),
+        ];
+        let payload = countersigning_payload(&entry.address(), &parties);
+        let header = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &[
+                Provenance::new(parties[0].clone(), sign(&mut alice, payload.clone())),
+                Provenance::new(parties[1].clone(), sign(&mut bob, payload)),
+            ],
+            &headers.last().map(|header| header.address()),
+            &None,
+            &None,
+            &Iso8601::from(1_003),
+        );
+        entries.push(entry);
+        headers.push(header.clone());
+
+        let report = verify_chain(&store_chain(&entries, &headers), Some(header));
+        assert_eq!(report.breaks, Vec::new());
+        assert_eq!(report.headers_checked, 4);
+    }
+
+    #[test]
+    fn reports_missing_header_and_entry() {
+        let (entries, headers) = signed_chain(1_000);
+        let mut chain_store = store_chain(&entries, &headers);
+        let entry = test_entry_c();
+        let header = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            headers[2].provenances(),
+            &Some(Address::from("QmMissingHeader")),
+            &Some(headers[0].address()),
+            &None,
+            &Iso8601::from(2_000),
+        );
+        chain_store.add(&header).unwrap();
+
+        let report = verify_chain(&chain_store, Some(header.clone()));
+        assert_eq!(report.headers_checked, 1);
+        assert!(report.breaks.contains(&ChainBreak::MissingHeader {
+            header: header.address(),
+            link: HeaderLink::Link,
+            address: Address::from("QmMissingHeader"),
+        }));
+        assert!(report.breaks.contains(&ChainBreak::MissingEntry {
+            header: header.address(),
+            entry_address: entry.address(),
+            error: None,
+        }));
+        assert!(report.breaks.contains(&ChainBreak::LinkSameTypeMismatch {
+            header: header.address(),
+            address: headers[0].address(),
+            entry_type: entry.entry_type(),
+            linked_entry_type: EntryType::AgentId,
+        }));
+        assert!(report.breaks.contains(&ChainBreak::MissingAgentEntry));
+    }
+
+    #[test]
+    fn reports_invalid_signature_and_timestamp_going_back() {
+        let (entries, mut headers) = signed_chain(1_000);
+        let last = headers.pop().unwrap();
+        let forged = ChainHeader::new(
+            last.entry_type(),
+            last.entry_address(),
+            &[Provenance::new(
+                last.provenances()[0].source(),
+                headers[1].provenances()[0].signature(),
+            )],
+            &last.link(),
+            &None,
+            &None,
+            &Iso8601::from(10),
+        );
+        headers.push(forged.clone());
+        let chain_store = store_chain(&entries, &headers);
+
+        let report = verify_chain(&chain_store, Some(forged.clone()));
+        assert_eq!(report.headers_checked, 3);
+        assert_eq!(
+            report.breaks,
+            vec![
+                ChainBreak::TimestampNotMonotonic {
+                    header: forged.address(),
+                    timestamp: Iso8601::from(10),
+                    previous_header: headers[1].address(),
+                    previous_timestamp: headers[1].timestamp().clone(),
+                },
+                ChainBreak::InvalidSignature {
+                    header: forged.address(),
+                    error: HolochainError::from(validate_header_signatures(&forged).unwrap_err())
+                        .to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_header_stored_under_another_address() {
+        let (entries, headers) = signed_chain(1_000);
+        let mut chain_store = store_chain(&entries, &headers);
+        let loop_address = Address::from("QmLoop");
+        // A header that links to the address it is stored under would be walked forever
+        let looping = ChainHeader::new(
+            headers[1].entry_type(),
+            headers[1].entry_address(),
+            headers[1].provenances(),
+            &Some(loop_address.clone()),
+            &None,
+            &None,
+            headers[1].timestamp(),
+        );
+        chain_store
+            .add(&Misplaced {
+                address: loop_address.clone(),
+                content: looping.content(),
+            })
+            .unwrap();
+        let top = ChainHeader::new(
+            headers[2].entry_type(),
+            headers[2].entry_address(),
+            headers[2].provenances(),
+            &Some(loop_address.clone()),
+            &None,
+            &None,
+            headers[2].timestamp(),
+        );
+        chain_store.add(&top).unwrap();
+
+        let report = verify_chain(&chain_store, Some(top.clone()));
+        assert_eq!(report.headers_checked, 1);
+        assert!(report.breaks.contains(&ChainBreak::HeaderAddressMismatch {
+            header: top.address(),
+            link: HeaderLink::Link,
+            address: loop_address,
+            actual_address: looping.address(),
+        }));
+    }
+}
@@ -0,0 +1,71 @@
+use holochain_core_types::{countersigning::CountersigningRequest, time::Timeout};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::time::{Duration, SystemTime};
+
+/// A countersigned entry that we signed as one of its parties and that we commit once its
+/// proposer sends us the signatures of all parties, unless it aborts or the session expires.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CountersigningSession {
+    entry_address: Address,
+    parties: Vec<Address>,
+    started_at: SystemTime,
+    timeout: Timeout,
+}
+
+impl CountersigningSession {
+    pub fn new(request: &CountersigningRequest) -> Self {
+        CountersigningSession {
+            entry_address: request.entry.address(),
+            parties: request.parties.clone(),
+            started_at: SystemTime::now(),
+            timeout: request.timeout.clone(),
+        }
+    }
+
+    pub fn entry_address(&self) -> &Address {
+        &self.entry_address
+    }
+
+    pub fn parties(&self) -> &Vec<Address> {
+        &self.parties
+    }
+
+    /// The agent that proposed the entry and collects the signatures
+    pub fn proposer(&self) -> Option<&Address> {
+        self.parties.first()
+    }
+
+    /// True if the entry did not get committed within twice the timeout:
+    /// one for the proposer to collect the signatures and one to send us the commit.
+    pub fn is_expired(&self) -> bool {
+        self.started_at
+            .elapsed()
+            .map(|elapsed| elapsed > Duration::from(&self.timeout) * 2)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::entry::test_entry;
+
+    pub fn test_countersigning_session(timeout_ms: usize) -> CountersigningSession {
+        CountersigningSession::new(&CountersigningRequest {
+            entry: test_entry(),
+            parties: vec![Address::from("alice"), Address::from("bob")],
+            timeout: Timeout::new(timeout_ms),
+        })
+    }
+
+    #[test]
+    fn test_countersigning_session_expires() {
+        let session = test_countersigning_session(60000);
+        assert_eq!(session.entry_address(), &test_entry().address());
+        assert_eq!(session.proposer(), Some(&Address::from("alice")));
+        assert!(!session.is_expired());
+        let session = test_countersigning_session(0);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(session.is_expired());
+    }
+}
@@ -1,7 +1,12 @@
 use crate::{
     action::{Action, ActionWrapper, AgentReduceFn},
-    agent::chain_store::{ChainStore, ChainStoreIterator},
+    agent::{
+        bundle::Bundle,
+        chain_store::{ChainStore, ChainStoreIterator},
+        countersigning::CountersigningSession,
+    },
     network::entry_with_header::EntryWithHeader,
+    persister::SNAPSHOT_FORMAT_VERSION,
     state::State,
 };
 use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
@@ -24,7 +29,7 @@ use holochain_json_api::{
     json::JsonString,
 };
 use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
-use im::HashMap;
+use im::{HashMap, HashSet};
 use serde_json;
 use std::{convert::TryFrom, ops::Deref, sync::Arc, time::SystemTime};
 
@@ -37,6 +42,14 @@ pub struct AgentState {
     chain_store: ChainStore,
     top_chain_header: Option<ChainHeader>,
     initial_agent_address: Address,
+    /// entries of an atomic commit that is being prepared by a zome function call
+    bundle: Option<Bundle>,
+    /// countersigned entries we signed and wait to commit, by entry address
+    countersigning_sessions: HashMap<Address, CountersigningSession>,
+    /// capability grants that got updated or deleted on the chain
+    revoked_grants: HashSet<Address>,
+    /// true once a ChainMigrate entry closed the chain
+    chain_closed: bool,
 }
 
 #[autotrace]
@@ -49,6 +62,10 @@ impl AgentState {
             chain_store,
             top_chain_header: None,
             initial_agent_address,
+            bundle: None,
+            countersigning_sessions: HashMap::new(),
+            revoked_grants: HashSet::new(),
+            chain_closed: false,
         }
     }
 
@@ -57,12 +74,21 @@ impl AgentState {
         chain_header: Option<ChainHeader>,
         initial_agent_address: Address,
     ) -> AgentState {
-        AgentState {
+        let mut agent_state = AgentState {
             actions: HashMap::new(),
             chain_store,
             top_chain_header: chain_header,
             initial_agent_address,
+            bundle: None,
+            countersigning_sessions: HashMap::new(),
+            revoked_grants: HashSet::new(),
+            chain_closed: false,
+        };
+        let headers: Vec<ChainHeader> = agent_state.iter_chain().collect();
+        for chain_header in headers.iter() {
+            agent_state.index_header(chain_header);
         }
+        agent_state
     }
 
     /// getter for a copy of self.actions
@@ -79,6 +105,55 @@ impl AgentState {
         self.top_chain_header.clone()
     }
 
+    pub fn bundle(&self) -> Option<Bundle> {
+        self.bundle.clone()
+    }
+
+    /// The bundle opened by the zome function call with the given ID, if there is one
+    pub fn bundle_of_zome_call(&self, zome_call_id: &str) -> Option<&Bundle> {
+        self.bundle
+            .as_ref()
+            .filter(|bundle| bundle.zome_call_id() == zome_call_id)
+    }
+
+    /// The session of a countersigned entry we signed, if it hasn't timed out
+    pub fn countersigning_session(
+        &self,
+        entry_address: &Address,
+    ) -> Option<&CountersigningSession> {
+        self.countersigning_sessions
+            .get(entry_address)
+            .filter(|session| !session.is_expired())
+    }
+
+    /// True if the capability grant with the given address got updated or deleted on the chain
+    pub fn is_grant_revoked(&self, address: &Address) -> bool {
+        self.revoked_grants.contains(address)
+    }
+
+    /// True if a ChainMigrate entry closed the chain, so nothing can be committed anymore
+    pub fn is_chain_closed(&self) -> bool {
+        self.chain_closed
+    }
+
+    /// Makes the given header the top of the chain and keeps the indexes of the chain
+    /// up to date with it.
+    fn set_top_chain_header(&mut self, chain_header: ChainHeader) {
+        self.index_header(&chain_header);
+        self.top_chain_header = Some(chain_header);
+    }
+
+    fn index_header(&mut self, chain_header: &ChainHeader) {
+        if *chain_header.entry_type() == EntryType::ChainMigrate {
+            self.chain_closed = true;
+        }
+        if let Some(target) = chain_header.link_update_delete() {
+            if let Ok(Some(Entry::CapTokenGrant(_))) = self.chain_store.get(target) {
+                self.revoked_grants.insert(target.clone());
+            }
+        }
+    }
+
     pub fn iter_chain(&self) -> ChainStoreIterator {
         self.chain_store.iter(&self.top_chain_header)
     }
@@ -115,12 +190,15 @@ impl AgentState {
 #[derive(Clone, Debug, Deserialize, Serialize, DefaultJson)]
 pub struct AgentStateSnapshot {
     top_chain_header: Option<ChainHeader>,
+    #[serde(default)]
+    format_version: u32,
 }
 
 impl AgentStateSnapshot {
     pub fn new(chain_header: Option<ChainHeader>) -> AgentStateSnapshot {
         AgentStateSnapshot {
             top_chain_header: chain_header,
+            format_version: SNAPSHOT_FORMAT_VERSION,
         }
     }
     pub fn from_json_str(header_str: &str) -> serde_json::Result<Self> {
@@ -164,6 +242,8 @@ pub enum AgentActionResponse {
     FetchEntry(Option<Entry>),
     GetLinks(Result<Vec<Address>, HolochainError>),
     LinkEntries(Result<Entry, HolochainError>),
+    Bundle(Result<Bundle, HolochainError>),
+    CommitBundle(Result<Vec<Address>, HolochainError>),
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DefaultJson)]
@@ -192,21 +272,26 @@ pub fn create_new_chain_header(
     provenances: &Vec<Provenance>,
 ) -> Result<ChainHeader, HolochainError> {
     let agent_address = agent_state.get_agent_address()?;
-    let signature = Signature::from(
-        root_state
-            .conductor_api()
-            .execute(entry.address().to_string(), CryptoMethod::Sign)?,
-        // Temporarily replaced by error handling for Holo hack signing.
-        // TODO: pull in the expect below after removing the Holo signing hack again
-        //.expect("Must be able to create signatures!"),
-    );
+    let mut provenances: Vec<Provenance> = provenances.to_vec();
+    // Countersigned entries come with our signature among the others already
+    if !provenances
+        .iter()
+        .any(|provenance| provenance.source() == agent_address)
+    {
+        let signature = Signature::from(
+            root_state
+                .conductor_api()
+                .execute(entry.address().to_string(), CryptoMethod::Sign)?,
+            // Temporarily replaced by error handling for Holo hack signing.
+            // TODO: pull in the expect below after removing the Holo signing hack again
+            //.expect("Must be able to create signatures!"),
+        );
+        provenances.push(Provenance::new(agent_address, signature));
+    }
     let duration_since_epoch = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .expect("System time must not be before UNIX EPOCH");
 
-    let mut provenances: Vec<Provenance> = provenances.to_vec();
-    provenances.push(Provenance::new(agent_address, signature));
-
     Ok(ChainHeader::new(
         &entry.entry_type(),
         &entry.address(),
@@ -288,7 +373,7 @@ fn reduce_commit_entry(
         Ok((chain_header, entry.address()))
     })
     .and_then(|(chain_header, address)| {
-        agent_state.top_chain_header = Some(chain_header);
+        agent_state.set_top_chain_header(chain_header);
         Ok(address)
     });
 
@@ -298,6 +383,201 @@ fn reduce_commit_entry(
     );
 }
 
+/// Commits all entries of a bundle on top of each other and only moves the top of the chain
+/// if all of them made it, so a failing entry leaves no partial bundle on the chain.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_commit_bundle(
+    agent_state: &mut AgentState,
+    root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let bundled_entries = unwrap_to!(action => Action::CommitBundle);
+
+    let root_state = StateWrapper::from(root_state.clone());
+    let original_top = agent_state.top_chain_header.clone();
+    let original_revoked_grants = agent_state.revoked_grants.clone();
+    let original_chain_closed = agent_state.chain_closed;
+    let result = bundled_entries
+        .iter()
+        .map(|bundled| {
+            let chain_header = create_new_chain_header(
+                &bundled.entry,
+                agent_state,
+                &root_state,
+                &bundled.maybe_link_update_delete,
+                &bundled.provenances,
+            )?;
+            agent_state.chain_store.add(&bundled.entry)?;
+            agent_state.chain_store.add(&chain_header)?;
+            agent_state.set_top_chain_header(chain_header);
+            Ok(bundled.entry.address())
+        })
+        .collect::<Result<Vec<Address>, HolochainError>>();
+    if result.is_err() {
+        agent_state.top_chain_header = original_top;
+        agent_state.revoked_grants = original_revoked_grants;
+        agent_state.chain_closed = original_chain_closed;
+    }
+
+    agent_state.actions.insert(
+        action_wrapper.clone(),
+        Response::from(AgentActionResponse::CommitBundle(result)),
+    );
+}
+
+/// Restores a commit from the journal with the exact header it got the first time,
+/// since committing the entry again would give it a new timestamp and signature.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_replay_commit(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let EntryWithHeader { entry, header } = unwrap_to!(action => Action::ReplayCommit);
+
+    let top_address = agent_state
+        .top_chain_header
+        .as_ref()
+        .map(|chain_header| chain_header.address());
+    if header.link() != top_address {
+        return;
+    }
+    let stored = agent_state
+        .chain_store
+        .add(entry)
+        .and_then(|_| agent_state.chain_store.add(header));
+    if stored.is_ok() {
+        agent_state.set_top_chain_header(header.clone());
+    }
+}
+
+fn no_open_bundle_error(zome_call_id: &str) -> HolochainError {
+    HolochainError::ErrorGeneric(format!("No open bundle for zome call {}", zome_call_id))
+}
+
+/// Opens the given bundle unless another zome function call still has one open.
+/// Bundles that have timed out don't block new ones.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_start_bundle(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let bundle = unwrap_to!(action => Action::StartBundle);
+
+    let result = match agent_state.bundle {
+        Some(ref open) if !open.is_expired() => Err(HolochainError::ErrorGeneric(format!(
+            "Can't start bundle: zome call {} already has a bundle open",
+            open.zome_call_id()
+        ))),
+        _ => {
+            agent_state.bundle = Some(bundle.clone());
+            Ok(bundle.clone())
+        }
+    };
+
+    agent_state.actions.insert(
+        action_wrapper.clone(),
+        Response::from(AgentActionResponse::Bundle(result)),
+    );
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_stage_bundle_entry(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (zome_call_id, bundled_entry) = unwrap_to!(action => Action::StageBundleEntry);
+
+    let result = match agent_state.bundle {
+        Some(ref mut bundle) if bundle.zome_call_id() == zome_call_id => {
+            bundle.add_entry(bundled_entry.clone());
+            Ok(bundle.clone())
+        }
+        _ => Err(no_open_bundle_error(zome_call_id)),
+    };
+
+    agent_state.actions.insert(
+        action_wrapper.clone(),
+        Response::from(AgentActionResponse::Bundle(result)),
+    );
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_close_bundle(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let zome_call_id = unwrap_to!(action => Action::CloseBundle);
+
+    let result = match agent_state.bundle.take() {
+        Some(bundle) if bundle.zome_call_id() == zome_call_id => Ok(bundle),
+        other => {
+            agent_state.bundle = other;
+            Err(no_open_bundle_error(zome_call_id))
+        }
+    };
+
+    agent_state.actions.insert(
+        action_wrapper.clone(),
+        Response::from(AgentActionResponse::Bundle(result)),
+    );
+}
+
+/// Remembers a countersigned entry we signed and drops the sessions that timed out.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_start_countersigning(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let session = unwrap_to!(action => Action::StartCountersigning);
+
+    agent_state
+        .countersigning_sessions
+        .retain(|_, session| !session.is_expired());
+    agent_state
+        .countersigning_sessions
+        .insert(session.entry_address().clone(), session.clone());
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_end_countersigning(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let entry_address = unwrap_to!(action => Action::EndCountersigning);
+
+    agent_state.countersigning_sessions.remove(entry_address);
+}
+
+/// A bundle that is still open when its zome function call returns can't ever be closed,
+/// so it gets discarded.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_return_zome_function_result(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let response = unwrap_to!(action => Action::ReturnZomeFunctionResult);
+    let zome_call_id = response.call().id().to_string();
+
+    if agent_state.bundle_of_zome_call(&zome_call_id).is_some() {
+        agent_state.bundle = None;
+    }
+}
+
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 fn reduce_prune(agent_state: &mut AgentState, _root_state: &State, action_wrapper: &ActionWrapper) {
     assert_eq!(action_wrapper.action(), &Action::Prune);
@@ -343,8 +623,16 @@ fn reduce_clear_action_response(
 fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<AgentReduceFn> {
     match action_wrapper.action() {
         Action::ClearActionResponse(_) => Some(reduce_clear_action_response),
+        Action::CloseBundle(_) => Some(reduce_close_bundle),
         Action::Commit(_) => Some(reduce_commit_entry),
+        Action::CommitBundle(_) => Some(reduce_commit_bundle),
+        Action::EndCountersigning(_) => Some(reduce_end_countersigning),
         Action::Prune => Some(reduce_prune),
+        Action::ReplayCommit(_) => Some(reduce_replay_commit),
+        Action::ReturnZomeFunctionResult(_) => Some(reduce_return_zome_function_result),
+        Action::StageBundleEntry(_) => Some(reduce_stage_bundle_entry),
+        Action::StartBundle(_) => Some(reduce_start_bundle),
+        Action::StartCountersigning(_) => Some(reduce_start_countersigning),
         _ => None,
     }
 }
@@ -371,14 +659,20 @@ pub fn reduce(
 pub mod tests {
     use super::*;
     use crate::{
-        action::tests::test_action_wrapper_commit, agent::chain_store::tests::test_chain_store,
-        instance::tests::test_context, state::State,
+        action::tests::test_action_wrapper_commit,
+        agent::{
+            bundle::{tests::test_bundle, BundledEntry},
+            chain_store::tests::test_chain_store,
+        },
+        instance::tests::test_context,
+        state::State,
     };
     use holochain_core_types::{
         chain_header::{test_chain_header, ChainHeader},
-        entry::{expected_entry_address, test_entry, Entry},
+        entry::{expected_entry_address, test_entry, test_entry_a, test_entry_b, Entry},
         error::HolochainError,
         signature::Signature,
+        time::Timeout,
     };
     use holochain_json_api::json::JsonString;
     use holochain_persistence_api::cas::content::AddressableContent;
@@ -428,6 +722,124 @@ pub mod tests {
         assert_eq!(response.response(), &test_action_response_commit(),);
     }
 
+    #[test]
+    /// test for committing all entries of a bundle on top of each other
+    fn test_reduce_commit_bundle() {
+        let netname = Some("test_reduce_commit_bundle");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id.address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+        let entries = vec![test_entry_a(), test_entry_b()];
+        let bundled_entries = entries
+            .iter()
+            .map(|entry| BundledEntry {
+                entry: entry.clone(),
+                maybe_link_update_delete: None,
+                provenances: Vec::new(),
+            })
+            .collect();
+        let action_wrapper = ActionWrapper::new(Action::CommitBundle(bundled_entries));
+
+        reduce_commit_bundle(&mut agent_state, &state, &action_wrapper);
+
+        let response = agent_state.actions().get(&action_wrapper).unwrap().clone();
+        assert_eq!(
+            response.response(),
+            &AgentActionResponse::CommitBundle(Ok(entries
+                .iter()
+                .map(|entry| entry.address())
+                .collect()))
+        );
+        let committed: Vec<Address> = agent_state
+            .iter_chain()
+            .map(|header| header.entry_address().clone())
+            .collect();
+        assert_eq!(committed, vec![entries[1].address(), entries[0].address()]);
+    }
+
+    #[test]
+    fn test_reduce_commit_bundle_keeps_provenances() {
+        let netname = Some("test_reduce_commit_bundle_keeps_provenances");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id.address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+        let caller = Provenance::new(Address::from("caller"), Signature::from("caller signature"));
+        let bundled_entry = BundledEntry {
+            entry: test_entry(),
+            maybe_link_update_delete: None,
+            provenances: vec![caller.clone()],
+        };
+        let action_wrapper = ActionWrapper::new(Action::CommitBundle(vec![bundled_entry]));
+
+        reduce_commit_bundle(&mut agent_state, &state, &action_wrapper);
+
+        let header = agent_state.top_chain_header().unwrap();
+        assert_eq!(header.provenances().len(), 2);
+        assert_eq!(header.provenances()[0], caller);
+    }
+
+    fn bundle_response(
+        agent_state: &AgentState,
+        action_wrapper: &ActionWrapper,
+    ) -> Result<Bundle, HolochainError> {
+        let response = agent_state.actions().get(action_wrapper).unwrap().clone();
+        match response.response() {
+            AgentActionResponse::Bundle(result) => result.clone(),
+            other => panic!("Expected bundle response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    /// test for opening, filling and closing a bundle
+    fn test_reduce_bundle() {
+        let netname = Some("test_reduce_bundle");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id.address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+        let bundle = test_bundle(60000);
+        let zome_call_id = bundle.zome_call_id().clone();
+
+        let start = ActionWrapper::new(Action::StartBundle(bundle.clone()));
+        reduce_start_bundle(&mut agent_state, &state, &start);
+        assert_eq!(bundle_response(&agent_state, &start), Ok(bundle.clone()));
+        assert_eq!(agent_state.bundle(), Some(bundle));
+
+        // only one bundle can be open at a time
+        let other_bundle = Bundle::new("other".to_string(), Timeout::new(1000), JsonString::null());
+        let start_other = ActionWrapper::new(Action::StartBundle(other_bundle));
+        reduce_start_bundle(&mut agent_state, &state, &start_other);
+        assert!(bundle_response(&agent_state, &start_other).is_err());
+
+        let bundled_entry = BundledEntry {
+            entry: test_entry(),
+            maybe_link_update_delete: None,
+            provenances: Vec::new(),
+        };
+        let stage = ActionWrapper::new(Action::StageBundleEntry((
+            zome_call_id.clone(),
+            bundled_entry,
+        )));
+        reduce_stage_bundle_entry(&mut agent_state, &state, &stage);
+        assert!(agent_state
+            .bundle_of_zome_call(&zome_call_id)
+            .unwrap()
+            .contains(&test_entry().address()));
+        // staged entries don't touch the chain
+        assert_eq!(agent_state.top_chain_header(), None);
+
+        // only the zome call that opened the bundle can close it
+        let close_other = ActionWrapper::new(Action::CloseBundle("other".to_string()));
+        reduce_close_bundle(&mut agent_state, &state, &close_other);
+        assert!(bundle_response(&agent_state, &close_other).is_err());
+        assert!(agent_state.bundle().is_some());
+
+        let close = ActionWrapper::new(Action::CloseBundle(zome_call_id));
+        reduce_close_bundle(&mut agent_state, &state, &close);
+        let closed = bundle_response(&agent_state, &close).unwrap();
+        assert_eq!(closed.entries().len(), 1);
+        assert_eq!(agent_state.bundle(), None);
+    }
+
     #[test]
     /// test response to json
     fn test_commit_response_to_json() {
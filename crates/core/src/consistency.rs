@@ -1,10 +1,12 @@
 use crate::{action::Action, context::Context, entry::CanPublish, nucleus::ZomeFnCall};
 use holochain_core_types::{
-    entry::Entry, link::link_data::LinkData, network::entry_aspect::EntryAspect,
+    chain_fork::ChainFork, entry::Entry, link::link_data::LinkData,
+    network::entry_aspect::EntryAspect,
 };
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
 use serde::Serialize;
 use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
 
 #[derive(Clone, Debug, Serialize)]
 pub struct ConsistencySignal<E: Serialize> {
@@ -56,18 +58,21 @@ type ConsistencySignalE = ConsistencySignal<ConsistencyEvent>;
 #[allow(clippy::large_enum_variant)]
 pub enum ConsistencyEvent {
     // CAUSES
-    Publish(Address),                                           // -> Hold
-    InitializeNetwork, // -> Hold (the AgentId if initialize chain happend)
-    InitializeChain,   // -> prepare to hold AgentId
-    SignalZomeFunctionCall(String, snowflake::ProcessUniqueId), // -> ReturnZomeFunctionResult
+    Publish(Address),                     // -> Hold
+    InitializeNetwork,                    // -> Hold (the AgentId if initialize chain happend)
+    InitializeChain,                      // -> prepare to hold AgentId
+    SignalZomeFunctionCall(String, Uuid), // -> ReturnZomeFunctionResult
 
     // EFFECTS
-    Hold(Address),                                                // <- Publish
-    UpdateEntry(Address, Address),                                // <- Publish, entry_type=Update
-    RemoveEntry(Address, Address),                                // <- Publish, entry_type=Deletion
-    AddLink(LinkData),                                            // <- Publish, entry_type=LinkAdd
-    RemoveLink(Address), // <- Publish, entry_type=LinkRemove
-    ReturnZomeFunctionResult(String, snowflake::ProcessUniqueId), // <- SignalZomeFunctionCall
+    Hold(Address),                          // <- Publish
+    UpdateEntry(Address, Address),          // <- Publish, entry_type=Update
+    RemoveEntry(Address, Address),          // <- Publish, entry_type=Deletion
+    AddLink(LinkData),                      // <- Publish, entry_type=LinkAdd
+    RemoveLink(Address),                    // <- Publish, entry_type=LinkRemove
+    ReturnZomeFunctionResult(String, Uuid), // <- SignalZomeFunctionCall
+
+    // OBSERVATIONS
+    ChainFork(ChainFork), // two held headers of an agent share their predecessor
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -124,7 +129,7 @@ impl ConsistencyModel {
                     let address = entry.address();
                     let hold = Hold(address.clone());
                     let meta = match entry {
-                        Entry::App(_, _) => crud_link
+                        Entry::App(_, _) | Entry::Encrypted(_) => crud_link
                             .clone()
                             .map(|crud| UpdateEntry(crud, address.clone())),
                         Entry::Deletion(_) => crud_link
@@ -194,6 +199,7 @@ impl ConsistencyModel {
                     error!("Got EntryAspect::Header type, unexpectedly");
                     None
                 }
+                EntryAspect::Warrant(_) => None,
             }
 
             Action::QueueZomeFunctionCall(call) => Some(ConsistencySignal::new_pending(
@@ -225,6 +231,9 @@ impl ConsistencyModel {
                 self.chain_initialized = true;
                 None
             }
+            Action::FlagChainFork(fork) => Some(ConsistencySignal::new_terminal(
+                ConsistencyEvent::ChainFork(fork.clone()),
+            )),
             _ => None,
         }
     }
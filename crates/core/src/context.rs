@@ -2,10 +2,15 @@ use crate::{
     action::{Action, ActionWrapper},
     content_store::GetContent,
     instance::Observer,
+    journal::Journal,
     network::state::NetworkState,
     persister::Persister,
     signal::{Signal, SignalSender},
     state::StateWrapper,
+    wasm_engine::{
+        engine::{WasmEngine, WasmModule, WasmiEngine},
+        metering::WasmLimits,
+    },
 };
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use futures::{
@@ -39,8 +44,9 @@ use holochain_persistence_api::{
 use holochain_tracing as ht;
 use jsonrpc_core::{self, IoHandler};
 use std::{
+    collections::HashMap,
     sync::{
-        atomic::{AtomicBool, Ordering::Relaxed},
+        atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed},
         Arc,
     },
     thread::sleep,
@@ -82,6 +88,11 @@ pub struct InstanceStats {
     pub number_pending_validations: usize,
     pub number_delayed_validations: usize,
     pub number_running_zome_calls: usize,
+    pub number_wasm_limit_breaches: usize,
+    /// Bytes of DHT data held for the network
+    pub storage_usage: usize,
+    pub storage_quota: Option<usize>,
+    pub number_evicted_entries: usize,
     pub offline: bool,
 }
 
@@ -94,6 +105,8 @@ pub struct Context {
     pub(crate) instance_name: String,
     pub agent_id: AgentId,
     pub persister: Arc<RwLock<dyn Persister>>,
+    /// Journal of all dispatched actions, if it is switched on for this instance
+    pub journal: Option<Arc<Mutex<Journal>>>,
     state: Option<Arc<RwLock<StateWrapper>>>,
     pub action_channel: Option<ActionSender>,
     pub observer_channel: Option<Sender<Observer>>,
@@ -110,6 +123,16 @@ pub struct Context {
     pub redux_wants_write: Arc<AtomicBool>,
     pub metric_publisher: Arc<RwLock<dyn MetricPublisher>>,
     pub tracer: Arc<ht::Tracer>,
+    pub wasm_limits: WasmLimits,
+    pub(crate) wasm_limit_breaches: Arc<AtomicUsize>,
+    /// Bytes of DHT data this instance holds for others before it starts evicting
+    pub storage_quota: Option<usize>,
+    pub(crate) evicted_entries: Arc<AtomicUsize>,
+    /// Proof that the agent may join the network, committed with its agent entry at genesis
+    pub membrane_proof: Option<String>,
+    pub wasm_engine: Arc<dyn WasmEngine>,
+    /// Compiled zome modules, keyed by zome name
+    pub(crate) wasm_modules: Arc<RwLock<HashMap<String, Arc<dyn WasmModule>>>>,
 }
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
@@ -159,6 +182,7 @@ impl Context {
             instance_name: instance_name.to_owned(),
             agent_id: agent_id.clone(),
             persister,
+            journal: None,
             state: None,
             action_channel: None,
             signal_tx,
@@ -178,6 +202,13 @@ impl Context {
             redux_wants_write: Arc::new(AtomicBool::new(false)),
             metric_publisher,
             tracer,
+            wasm_limits: WasmLimits::default(),
+            wasm_limit_breaches: Arc::new(AtomicUsize::new(0)),
+            storage_quota: None,
+            evicted_entries: Arc::new(AtomicUsize::new(0)),
+            membrane_proof: None,
+            wasm_engine: Arc::new(WasmiEngine),
+            wasm_modules: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -200,6 +231,7 @@ impl Context {
             instance_name: instance_name.to_owned(),
             agent_id: agent_id.clone(),
             persister,
+            journal: None,
             state: None,
             action_channel,
             signal_tx,
@@ -216,6 +248,13 @@ impl Context {
             redux_wants_write: Arc::new(AtomicBool::new(false)),
             metric_publisher,
             tracer,
+            wasm_limits: WasmLimits::default(),
+            wasm_limit_breaches: Arc::new(AtomicUsize::new(0)),
+            storage_quota: None,
+            evicted_entries: Arc::new(AtomicUsize::new(0)),
+            membrane_proof: None,
+            wasm_engine: Arc::new(WasmiEngine),
+            wasm_modules: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
@@ -435,6 +474,16 @@ impl Context {
         ))
     }
 
+    /// Counts a zome or callback call that got aborted for breaching the WasmLimits
+    pub(crate) fn record_wasm_limit_breach(&self) {
+        self.wasm_limit_breaches.fetch_add(1, Relaxed);
+    }
+
+    /// Counts entries that got evicted from the DHT shard to stay within the storage quota
+    pub(crate) fn record_evictions(&self, count: usize) {
+        self.evicted_entries.fetch_add(count, Relaxed);
+    }
+
     pub fn get_stats(&self) -> HcResult<InstanceStats> {
         let state = self
             .state()
@@ -453,6 +502,10 @@ impl Context {
                 .filter(|p| p.timeout.is_some())
                 .count(),
             number_running_zome_calls: state.nucleus().running_zome_calls.len(),
+            number_wasm_limit_breaches: self.wasm_limit_breaches.load(Relaxed),
+            storage_usage: dht_store.storage_usage(),
+            storage_quota: self.storage_quota,
+            number_evicted_entries: self.evicted_entries.load(Relaxed),
             offline: false,
         })
     }
@@ -11,6 +11,7 @@ use holochain_persistence_api::cas::content::AddressableContent;
 use lib3h_protocol::data_types::EntryListData;
 use snowflake::ProcessUniqueId;
 use std::{pin::Pin, sync::Arc};
+use uuid::Uuid;
 
 pub fn ack_single(context: Arc<Context>, aspect: EntryAspect) {
     log_debug!(context, "sending back fat ack");
@@ -31,17 +32,18 @@ pub fn ack_single(context: Arc<Context>, aspect: EntryAspect) {
 }
 
 pub async fn hold_aspect_no_ack(
-    pending_id: &ProcessUniqueId,
+    pending_id: &Uuid,
     aspect: EntryAspect,
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
-    let id = (*pending_id, ProcessUniqueId::new());
+    let id = (*pending_id, Uuid::new_v4());
     let action_wrapper = ActionWrapper::new(Action::HoldAspect((aspect.clone(), id)));
     dispatch_action(context.action_channel(), action_wrapper.clone());
     let r = HoldAspectFuture {
         context: context.clone(),
         //        aspect,
         id,
+        waker_id: ProcessUniqueId::new(),
     }
     .await;
     if r.is_err() {
@@ -51,17 +53,18 @@ pub async fn hold_aspect_no_ack(
 }
 
 pub async fn hold_aspect(
-    pending_id: &ProcessUniqueId,
+    pending_id: &Uuid,
     aspect: EntryAspect,
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
-    let id = (*pending_id, ProcessUniqueId::new());
+    let id = (*pending_id, Uuid::new_v4());
     let action_wrapper = ActionWrapper::new(Action::HoldAspect((aspect.clone(), id)));
     dispatch_action(context.action_channel(), action_wrapper.clone());
     let r = HoldAspectFuture {
         context: context.clone(),
         //        aspect,
         id,
+        waker_id: ProcessUniqueId::new(),
     }
     .await;
     if r.is_err() {
@@ -77,6 +80,7 @@ pub struct HoldAspectFuture {
     context: Arc<Context>,
     //    aspect: EntryAspect,
     id: HoldAspectAttemptId,
+    waker_id: ProcessUniqueId,
 }
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
@@ -88,11 +92,11 @@ impl Future for HoldAspectFuture {
             return Poll::Ready(Err(err));
         }
         self.context
-            .register_waker(self.id.1.clone(), cx.waker().clone());
+            .register_waker(self.waker_id, cx.waker().clone());
         if let Some(state) = self.context.try_state() {
             // wait for the request to complete
             if let Some(result) = state.dht().hold_aspec_request_complete(&self.id) {
-                self.context.unregister_waker(self.id.1.clone());
+                self.context.unregister_waker(self.waker_id);
                 Poll::Ready(result.clone())
             } else {
                 Poll::Pending
@@ -0,0 +1,65 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    instance::dispatch_action,
+};
+use futures::{future::Future, task::Poll};
+use holochain_core_types::{chain_header::ChainHeader, error::HolochainError};
+use holochain_persistence_api::cas::content::Address;
+use snowflake::ProcessUniqueId;
+use std::{pin::Pin, sync::Arc};
+
+/// Indexes a header published to the address of its author and waits until we hold it,
+/// so the author's other headers can be compared against it right after.
+pub async fn hold_authored_header(
+    author: Address,
+    header: ChainHeader,
+    context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    let action_wrapper = ActionWrapper::new(Action::HoldAuthoredHeader(header.clone()));
+    dispatch_action(context.action_channel(), action_wrapper);
+    HoldAuthoredHeaderFuture {
+        context,
+        author,
+        header,
+        waker_id: ProcessUniqueId::new(),
+    }
+    .await
+}
+
+pub struct HoldAuthoredHeaderFuture {
+    context: Arc<Context>,
+    author: Address,
+    header: ChainHeader,
+    waker_id: ProcessUniqueId,
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+impl Future for HoldAuthoredHeaderFuture {
+    type Output = Result<(), HolochainError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context) -> Poll<Self::Output> {
+        if let Some(err) = self
+            .context
+            .action_channel_error("HoldAuthoredHeaderFuture")
+        {
+            return Poll::Ready(Err(err));
+        }
+        self.context
+            .register_waker(self.waker_id, cx.waker().clone());
+        if let Some(state) = self.context.try_state() {
+            match state
+                .dht()
+                .holds_authored_header(&self.author, &self.header)
+            {
+                Ok(false) => Poll::Pending,
+                result => {
+                    self.context.unregister_waker(self.waker_id);
+                    Poll::Ready(result.map(|_| ()))
+                }
+            }
+        } else {
+            Poll::Pending
+        }
+    }
+}
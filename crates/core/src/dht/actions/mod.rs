@@ -1,3 +1,4 @@
 pub mod hold_aspect;
+pub mod hold_header;
 pub mod queue_holding_workflow;
 pub mod remove_queued_holding_workflow;
@@ -49,6 +49,10 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<DhtReducer> {
         Action::HoldAspect(_) => Some(reduce_hold_aspect),
         Action::QueueHoldingWorkflow(_) => Some(reduce_queue_holding_workflow),
         Action::RemoveQueuedHoldingWorkflow(_) => Some(reduce_remove_queued_holding_workflow),
+        Action::EvictHeldEntries(_) => Some(reduce_evict_held_entries),
+        Action::AddValidationReceipt(_) => Some(reduce_add_validation_receipt),
+        Action::HoldAuthoredHeader(_) => Some(reduce_hold_authored_header),
+        Action::FlagChainFork(_) => Some(reduce_flag_chain_fork),
         Action::Prune => Some(reduce_prune),
         _ => None,
     }
@@ -173,6 +177,14 @@ pub(crate) fn reduce_hold_aspect(
                 None
             }
         }
+        EntryAspect::Warrant(warrant) => match new_store.add_warrant(warrant) {
+            Ok(()) => Some(new_store),
+            Err(e) => {
+                let err = format!("EntryAspect::Warrant hold error: {}", e);
+                hold_result = Err(HolochainError::ErrorGeneric(err));
+                None
+            }
+        },
         EntryAspect::Header(_) => {
             let err = "Got EntryAspect::Header which is not implemented.";
             hold_result = Err(HolochainError::ErrorGeneric(err.to_string()));
@@ -283,6 +295,67 @@ pub fn reduce_prune(old_store: &DhtStore, _action_wrapper: &ActionWrapper) -> Op
     }
 }
 
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub(crate) fn reduce_evict_held_entries(
+    old_store: &DhtStore,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore> {
+    let entry_addresses = unwrap_to!(action_wrapper.action() => Action::EvictHeldEntries);
+    let mut new_store = (*old_store).clone();
+    for entry_address in entry_addresses {
+        new_store.evict_entry(entry_address);
+    }
+    Some(new_store)
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub(crate) fn reduce_add_validation_receipt(
+    old_store: &DhtStore,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore> {
+    let receipt = unwrap_to!(action_wrapper.action() => Action::AddValidationReceipt);
+    let mut new_store = (*old_store).clone();
+    match new_store.add_validation_receipt(receipt) {
+        Ok(()) => Some(new_store),
+        Err(e) => {
+            error!("Could not store validation receipt: {}", e);
+            None
+        }
+    }
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub(crate) fn reduce_hold_authored_header(
+    old_store: &DhtStore,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore> {
+    let header = unwrap_to!(action_wrapper.action() => Action::HoldAuthoredHeader);
+    let mut new_store = (*old_store).clone();
+    match new_store.add_authored_header(header) {
+        Ok(()) => Some(new_store),
+        Err(e) => {
+            error!("Could not hold authored header: {}", e);
+            None
+        }
+    }
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub(crate) fn reduce_flag_chain_fork(
+    old_store: &DhtStore,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore> {
+    let fork = unwrap_to!(action_wrapper.action() => Action::FlagChainFork);
+    let mut new_store = (*old_store).clone();
+    match new_store.add_chain_fork(fork) {
+        Ok(()) => Some(new_store),
+        Err(e) => {
+            error!("Could not flag chain fork: {}", e);
+            None
+        }
+    }
+}
+
 #[allow(unknown_lints)]
 #[allow(clippy::needless_pass_by_value)]
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
@@ -326,8 +399,8 @@ pub mod tests {
         network::entry_aspect::EntryAspect,
     };
     use holochain_persistence_api::cas::content::{Address, AddressableContent};
-    use snowflake::ProcessUniqueId;
     use std::{sync::Arc, time::SystemTime};
+    use uuid::Uuid;
 
     // TODO do this for all crate tests somehow
     #[allow(dead_code)]
@@ -354,7 +427,7 @@ pub mod tests {
             &store.dht(),
             &ActionWrapper::new(Action::HoldAspect((
                 EntryAspect::Content(sys_entry.clone(), test_chain_header()),
-                (ProcessUniqueId::new(), ProcessUniqueId::new()),
+                (Uuid::new_v4(), Uuid::new_v4()),
             ))),
         )
         .expect("there should be a new store for committing a sys entry");
@@ -396,7 +469,7 @@ pub mod tests {
         );
         let action = ActionWrapper::new(Action::HoldAspect((
             EntryAspect::LinkAdd(link_data.clone(), test_chain_header()),
-            (ProcessUniqueId::new(), ProcessUniqueId::new()),
+            (Uuid::new_v4(), Uuid::new_v4()),
         )));
         let link_entry = Entry::LinkAdd(link_data.clone());
 
@@ -444,7 +517,7 @@ pub mod tests {
         let entry_link_add = Entry::LinkAdd(link_data.clone());
         let action_link_add = ActionWrapper::new(Action::HoldAspect((
             EntryAspect::LinkAdd(link_data.clone(), test_chain_header()),
-            (ProcessUniqueId::new(), ProcessUniqueId::new()),
+            (Uuid::new_v4(), Uuid::new_v4()),
         )));
 
         let new_dht_store = reduce(store.dht(), &action_link_add);
@@ -467,7 +540,7 @@ pub mod tests {
                 ),
                 test_chain_header(),
             ),
-            (ProcessUniqueId::new(), ProcessUniqueId::new()),
+            (Uuid::new_v4(), Uuid::new_v4()),
         )));
         let new_dht_store = reduce(new_dht_store, &action_link_remove);
 
@@ -500,7 +573,7 @@ pub mod tests {
         //add new link with same chain header
         let action_link_add = ActionWrapper::new(Action::HoldAspect((
             EntryAspect::LinkAdd(link_data.clone(), test_chain_header()),
-            (ProcessUniqueId::new(), ProcessUniqueId::new()),
+            (Uuid::new_v4(), Uuid::new_v4()),
         )));
         let new_dht_store = reduce(store.dht(), &action_link_add);
 
@@ -540,7 +613,7 @@ pub mod tests {
         let entry_link_add = Entry::LinkAdd(link_data.clone());
         let action_link_add = ActionWrapper::new(Action::HoldAspect((
             EntryAspect::LinkAdd(link_data.clone(), test_chain_header()),
-            (ProcessUniqueId::new(), ProcessUniqueId::new()),
+            (Uuid::new_v4(), Uuid::new_v4()),
         )));
         let new_dht_store_2 = reduce(store.dht(), &action_link_add);
 
@@ -586,7 +659,7 @@ pub mod tests {
         );
         let action = ActionWrapper::new(Action::HoldAspect((
             EntryAspect::LinkAdd(link_data.clone(), test_chain_header()),
-            (ProcessUniqueId::new(), ProcessUniqueId::new()),
+            (Uuid::new_v4(), Uuid::new_v4()),
         )));
 
         let new_dht_store = reduce(store.dht(), &action);
@@ -611,7 +684,7 @@ pub mod tests {
         let entry = test_entry();
         let action_wrapper = ActionWrapper::new(Action::HoldAspect((
             EntryAspect::Content(entry.clone(), test_chain_header()),
-            (ProcessUniqueId::new(), ProcessUniqueId::new()),
+            (Uuid::new_v4(), Uuid::new_v4()),
         )));
 
         store.reduce(action_wrapper);
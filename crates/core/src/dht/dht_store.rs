@@ -5,8 +5,10 @@ use crate::{
         pending_validations::{PendingValidationWithTimeout, ValidationTimeout},
     },
     instance::RETRY_VALIDATION_DURATION_MIN,
+    network::validation_receipt::ValidationReceipt,
 };
 use holochain_core_types::{
+    chain_fork::ChainFork,
     chain_header::ChainHeader,
     crud_status::CrudStatus,
     eav::{Attribute, EaviQuery, EntityAttributeValueIndex},
@@ -15,11 +17,12 @@ use holochain_core_types::{
     network::{
         entry_aspect::EntryAspect,
         query::{GetLinksQueryConfiguration, Pagination, SortOrder},
+        warrant::Warrant,
     },
 };
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_locksmith::RwLock;
-use holochain_net::aspect_map::{AspectMap, AspectMapBare};
+use holochain_net::aspect_map::{AspectMap, AspectMapBare, AspectSet};
 use holochain_persistence_api::{
     cas::{
         content::{Address, AddressableContent, Content},
@@ -28,20 +31,25 @@ use holochain_persistence_api::{
     eav::{EavFilter, EntityAttributeValueStorage, IndexFilter},
 };
 
-use crate::{dht::pending_validations::PendingValidation, state::StateWrapper};
+use crate::{
+    dht::pending_validations::PendingValidation, persister::SNAPSHOT_FORMAT_VERSION,
+    state::StateWrapper,
+};
 use chrono::{offset::FixedOffset, DateTime};
 use holochain_json_api::error::JsonResult;
 use holochain_persistence_api::error::PersistenceResult;
-use snowflake::ProcessUniqueId;
+use itertools::Itertools;
+use lib3h_protocol::types::{AspectHash, EntryHash};
 use std::{
-    collections::{BTreeSet, HashMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     convert::TryFrom,
     sync::Arc,
     time::{Duration, SystemTime},
 };
+use uuid::Uuid;
 
 /// A type for identifying holding attempts uniquely and by parent pending validation id
-pub type HoldAspectAttemptId = (ProcessUniqueId, ProcessUniqueId);
+pub type HoldAspectAttemptId = (Uuid, Uuid);
 
 /// The state-slice for the DHT.
 /// Holds the CAS and EAVi that's used for the agent's local shard
@@ -55,6 +63,16 @@ pub struct DhtStore {
     /// All the entry aspects that the network has told us to hold
     holding_map: AspectMap,
 
+    /// Size in bytes of each held aspect, which is what counts towards the storage quota
+    held_aspect_sizes: HashMap<AspectHash, usize>,
+
+    /// Entries we stopped holding to stay within the storage quota
+    evicted_entries: HashSet<Address>,
+
+    /// Agents we hold a confirmed warrant against, so checking for them doesn't need to
+    /// query the EAV store for every message and aspect we get
+    blocked_agents: HashSet<Address>,
+
     /// Hold aspect attempts that come from pending validations
     holding_attempt_results: HashMap<HoldAspectAttemptId, Result<(), HolochainError>>,
 
@@ -81,6 +99,12 @@ pub struct DhtStoreSnapshot {
     queued_holding_workflows: VecDeque<PendingValidationWithTimeout>,
     #[serde(default)]
     in_process_holding_workflows: VecDeque<PendingValidationWithTimeout>,
+    #[serde(default)]
+    held_aspect_sizes: HashMap<AspectHash, usize>,
+    #[serde(default)]
+    evicted_entries: HashSet<Address>,
+    #[serde(default)]
+    format_version: u32,
 }
 
 impl From<&StateWrapper> for DhtStoreSnapshot {
@@ -89,10 +113,43 @@ impl From<&StateWrapper> for DhtStoreSnapshot {
             holding_map: state.dht().get_holding_map().bare().clone(),
             queued_holding_workflows: state.dht().queued_holding_workflows.clone(),
             in_process_holding_workflows: state.dht().in_process_holding_workflows.clone(),
+            held_aspect_sizes: state.dht().held_aspect_sizes.clone(),
+            evicted_entries: state.dht().evicted_entries.clone(),
+            format_version: SNAPSHOT_FORMAT_VERSION,
         }
     }
 }
 
+impl DhtStoreSnapshot {
+    pub fn queued_holding_workflows(&self) -> &VecDeque<PendingValidationWithTimeout> {
+        &self.queued_holding_workflows
+    }
+
+    pub fn in_process_holding_workflows(&self) -> &VecDeque<PendingValidationWithTimeout> {
+        &self.in_process_holding_workflows
+    }
+}
+
+/// What the content of an evicted entry gets overwritten with,
+/// since the CAS has no way to remove content
+struct EvictedContent(Address);
+
+impl AddressableContent for EvictedContent {
+    fn content(&self) -> Content {
+        JsonString::null()
+    }
+
+    fn try_from_content(_content: &Content) -> JsonResult<Self> {
+        Err(JsonError::ErrorGeneric(String::from(
+            "Evicted content can't be restored",
+        )))
+    }
+
+    fn address(&self) -> Address {
+        self.0.clone()
+    }
+}
+
 pub static DHT_STORE_SNAPSHOT_ADDRESS: &str = "DhtStore";
 impl AddressableContent for DhtStoreSnapshot {
     fn content(&self) -> Content {
@@ -155,6 +212,9 @@ impl DhtStore {
             content_storage,
             meta_storage,
             holding_map: AspectMap::new(),
+            held_aspect_sizes: HashMap::new(),
+            evicted_entries: HashSet::new(),
+            blocked_agents: HashSet::new(),
             queued_holding_workflows: VecDeque::new(),
             in_process_holding_workflows: VecDeque::new(),
             holding_attempt_results: HashMap::new(),
@@ -168,6 +228,14 @@ impl DhtStore {
     ) -> Self {
         let mut new_dht_store = Self::new(content_storage, meta_storage);
         new_dht_store.holding_map = snapshot.holding_map.into();
+        new_dht_store.held_aspect_sizes = snapshot.held_aspect_sizes;
+        new_dht_store.evicted_entries = snapshot.evicted_entries;
+        new_dht_store.blocked_agents = new_dht_store
+            .fetch_warrants(None)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|warrant| warrant.accused())
+            .collect();
 
         // the in_process queue is no longer in-process when being restored so
         // items are put in the waiting queue
@@ -316,8 +384,271 @@ impl DhtStore {
         Ok(())
     }
 
+    /// Add a header that got published to its author's address to the CAS and index it
+    /// by its predecessor, so forks in the author's chain can be found
+    pub fn add_authored_header(&mut self, header: &ChainHeader) -> Result<(), HolochainError> {
+        self.add(header)?;
+        if let Some(predecessor) = header.link() {
+            let eavi = EntityAttributeValueIndex::new(
+                &predecessor,
+                &Attribute::NextHeader,
+                &header.address(),
+            )?;
+            self.add_eavi(&eavi)?;
+        }
+        Ok(())
+    }
+
+    /// Addresses of the held headers by the given author that follow the given header
+    fn next_headers(
+        &self,
+        predecessor: &Address,
+        author: &Address,
+    ) -> Result<Vec<Address>, HolochainError> {
+        let query = EaviQuery::new(
+            Some(predecessor.to_owned()).into(),
+            Some(Attribute::NextHeader).into(),
+            None.into(),
+            IndexFilter::Range(Some(0), Some(std::i64::MAX)),
+            None,
+        );
+        let mut headers = Vec::new();
+        for eavi in self.fetch_eavi(&query)? {
+            if headers.contains(&eavi.value()) {
+                continue;
+            }
+            let by_author = match self.get_raw(&eavi.value())? {
+                Some(content) => ChainHeader::try_from_content(&content)?
+                    .provenances()
+                    .iter()
+                    .any(|provenance| &provenance.source() == author),
+                None => false,
+            };
+            if by_author {
+                headers.push(eavi.value());
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Whether we hold the given header as one of the headers in the chain of the given author
+    pub fn holds_authored_header(
+        &self,
+        author: &Address,
+        header: &ChainHeader,
+    ) -> Result<bool, HolochainError> {
+        Ok(match header.link() {
+            Some(predecessor) => self
+                .next_headers(&predecessor, author)?
+                .contains(&header.address()),
+            None => false,
+        })
+    }
+
+    /// The fork the given header is part of, if its author published another header
+    /// with the same predecessor that we hold
+    pub fn fork_of(
+        &self,
+        header: &ChainHeader,
+        author: &Address,
+    ) -> Result<Option<ChainFork>, HolochainError> {
+        let predecessor = match header.link() {
+            Some(predecessor) => predecessor,
+            None => return Ok(None),
+        };
+        let headers = self.next_headers(&predecessor, author)?;
+        Ok(if headers.len() > 1 {
+            Some(ChainFork {
+                author: author.clone(),
+                predecessor,
+                headers,
+            })
+        } else {
+            None
+        })
+    }
+
+    /// Remember a fork in the chain of its author
+    pub fn add_chain_fork(&mut self, fork: &ChainFork) -> Result<(), HolochainError> {
+        let eavi =
+            EntityAttributeValueIndex::new(&fork.author, &Attribute::ChainFork, &fork.predecessor)?;
+        self.add_eavi(&eavi)?;
+        Ok(())
+    }
+
+    /// The flagged forks in the chain of the given agent
+    pub fn get_chain_forks(&self, author: &Address) -> Result<Vec<ChainFork>, HolochainError> {
+        let query = EaviQuery::new(
+            Some(author.to_owned()).into(),
+            Some(Attribute::ChainFork).into(),
+            None.into(),
+            IndexFilter::Range(Some(0), Some(std::i64::MAX)),
+            None,
+        );
+        self.fetch_eavi(&query)?
+            .into_iter()
+            .map(|eavi| eavi.value())
+            .unique()
+            .map(|predecessor| {
+                Ok(ChainFork {
+                    author: author.clone(),
+                    headers: self.next_headers(&predecessor, author)?,
+                    predecessor,
+                })
+            })
+            .collect()
+    }
+
+    /// Add a validation receipt for one of our entries to the CAS and EAV
+    pub fn add_validation_receipt(
+        &mut self,
+        receipt: &ValidationReceipt,
+    ) -> Result<(), HolochainError> {
+        let eavi = EntityAttributeValueIndex::new(
+            &receipt.entry_address,
+            &Attribute::ValidationReceipt,
+            &receipt.address(),
+        )?;
+        self.add(receipt)?;
+        self.add_eavi(&eavi)?;
+        Ok(())
+    }
+
+    /// The validation receipts stored for the given entry, one per validator
+    pub fn get_validation_receipts(
+        &self,
+        entry_address: &Address,
+    ) -> Result<Vec<ValidationReceipt>, HolochainError> {
+        let query = EaviQuery::new(
+            Some(entry_address.to_owned()).into(),
+            Some(Attribute::ValidationReceipt).into(),
+            None.into(),
+            IndexFilter::Range(Some(0), Some(std::i64::MAX)),
+            None,
+        );
+        let mut receipts: Vec<ValidationReceipt> = Vec::new();
+        for eavi in self.fetch_eavi(&query)? {
+            let content = self.get_raw(&eavi.value())?.ok_or_else(|| {
+                HolochainError::ErrorGeneric(format!(
+                    "Validation receipt {} missing in CAS",
+                    eavi.value()
+                ))
+            })?;
+            let receipt = ValidationReceipt::try_from_content(&content)?;
+            if !receipts
+                .iter()
+                .any(|known| known.validator.source() == receipt.validator.source())
+            {
+                receipts.push(receipt);
+            }
+        }
+        Ok(receipts)
+    }
+
+    /// Add a confirmed warrant to the CAS and link it from the accused agent's address
+    pub fn add_warrant(&mut self, warrant: &Warrant) -> Result<(), HolochainError> {
+        let accused = warrant.accused().ok_or_else(|| {
+            HolochainError::ErrorGeneric("Warrant without accused agent".to_string())
+        })?;
+        let eavi =
+            EntityAttributeValueIndex::new(&accused, &Attribute::Warrant, &warrant.address())?;
+        self.add(warrant)?;
+        self.add_eavi(&eavi)?;
+        self.blocked_agents.insert(accused);
+        Ok(())
+    }
+
+    fn fetch_warrants(&self, agent: Option<Address>) -> Result<Vec<Warrant>, HolochainError> {
+        let query = EaviQuery::new(
+            agent.into(),
+            Some(Attribute::Warrant).into(),
+            None.into(),
+            IndexFilter::Range(Some(0), Some(std::i64::MAX)),
+            None,
+        );
+        self.fetch_eavi(&query)?
+            .into_iter()
+            .map(|eavi| {
+                let content = self.get_raw(&eavi.value())?.ok_or_else(|| {
+                    HolochainError::ErrorGeneric(format!("Warrant {} missing in CAS", eavi.value()))
+                })?;
+                Ok(Warrant::try_from_content(&content)?)
+            })
+            .collect()
+    }
+
+    /// The warrants we confirmed against the given agent
+    pub fn get_warrants(&self, agent: &Address) -> Result<Vec<Warrant>, HolochainError> {
+        self.fetch_warrants(Some(agent.clone()))
+    }
+
+    /// Agents with a confirmed warrant don't get their data held or accepted
+    pub fn is_blocked(&self, agent: &Address) -> bool {
+        self.blocked_agents.contains(agent)
+    }
+
+    /// All blocked agents with the warrants against them
+    pub fn blocked_agents(&self) -> Result<BTreeMap<Address, Vec<Warrant>>, HolochainError> {
+        let mut blocked: BTreeMap<Address, Vec<Warrant>> = BTreeMap::new();
+        for warrant in self.fetch_warrants(None)? {
+            if let Some(accused) = warrant.accused() {
+                blocked.entry(accused).or_default().push(warrant);
+            }
+        }
+        Ok(blocked)
+    }
+
     pub fn mark_aspect_as_held(&mut self, aspect: &EntryAspect) {
         self.holding_map.add(aspect);
+        self.held_aspect_sizes.insert(
+            AspectHash::from(aspect.address()),
+            String::from(aspect.content()).len(),
+        );
+        if let Ok(entry_address) = aspect.entry_address() {
+            self.evicted_entries.remove(&entry_address);
+        }
+    }
+
+    /// Stops holding all aspects of the given entry and erases its content from the CAS.
+    /// Its headers and meta data stay in the CAS and EAV, but no longer count towards
+    /// the storage usage.
+    pub fn evict_entry(&mut self, entry_address: &Address) -> Option<AspectSet> {
+        let aspects = self
+            .holding_map
+            .remove(&EntryHash::from(entry_address.clone()))?;
+        for aspect in aspects.iter() {
+            self.held_aspect_sizes.remove(aspect);
+        }
+        if let Err(e) = self.add(&EvictedContent(entry_address.clone())) {
+            error!("Could not erase evicted entry {}: {}", entry_address, e);
+        }
+        self.evicted_entries.insert(entry_address.clone());
+        Some(aspects)
+    }
+
+    pub fn is_evicted(&self, entry_address: &Address) -> bool {
+        self.evicted_entries.contains(entry_address)
+    }
+
+    /// Bytes of all the aspects held for the given entry
+    pub fn entry_storage_usage(&self, entry_address: &EntryHash) -> usize {
+        self.holding_map
+            .per_entry(entry_address)
+            .map(|aspects| {
+                aspects
+                    .iter()
+                    .filter_map(|aspect| self.held_aspect_sizes.get(aspect))
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Bytes of all held aspects. Aspects held before their sizes got recorded don't count.
+    pub fn storage_usage(&self) -> usize {
+        self.holding_map
+            .entry_addresses()
+            .map(|entry_address| self.entry_storage_usage(entry_address))
+            .sum()
     }
 
     pub fn mark_hold_aspect_complete(
@@ -531,7 +862,9 @@ where
 
 impl GetContent for DhtStore {
     fn get_raw(&self, address: &Address) -> HcResult<Option<Content>> {
-        Ok((*self.content_storage.read().unwrap()).fetch(address)?)
+        Ok((*self.content_storage.read().unwrap())
+            .fetch(address)?
+            .filter(|content| !content.is_null()))
     }
 }
 
@@ -551,8 +884,10 @@ pub mod tests {
         network::entry_with_header::EntryWithHeader,
     };
     use holochain_core_types::{
-        chain_header::test_chain_header_with_sig,
+        chain_header::{test_chain_header_with_sig, test_provenances},
         entry::{test_entry, test_entry_a, test_entry_b, test_entry_c},
+        signature::{Provenance, Signature},
+        time::test_iso_8601,
     };
 
     use holochain_persistence_api::{
@@ -576,6 +911,185 @@ pub mod tests {
         assert_eq!(headers, vec![header1, header2]);
     }
 
+    #[test]
+    fn validation_receipts_are_counted_per_validator() {
+        let mut store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        let entry = test_entry();
+        let receipt = |header: &ChainHeader, validator: &str| ValidationReceipt {
+            entry_address: entry.address(),
+            header_address: header.address(),
+            validator: Provenance::new(validator.into(), Signature::fake()),
+        };
+        let header1 = test_chain_header_with_sig("sig1", None);
+        let header2 = test_chain_header_with_sig("sig2", None);
+        assert!(store
+            .get_validation_receipts(&entry.address())
+            .unwrap()
+            .is_empty());
+
+        store
+            .add_validation_receipt(&receipt(&header1, "alice"))
+            .unwrap();
+        store
+            .add_validation_receipt(&receipt(&header2, "alice"))
+            .unwrap();
+        store
+            .add_validation_receipt(&receipt(&header1, "bob"))
+            .unwrap();
+        let validators: Vec<Address> = store
+            .get_validation_receipts(&entry.address())
+            .unwrap()
+            .iter()
+            .map(|receipt| receipt.validator.source())
+            .collect();
+        assert_eq!(validators.len(), 2);
+        assert!(validators.contains(&Address::from("alice")));
+        assert!(validators.contains(&Address::from("bob")));
+        assert!(store
+            .get_validation_receipts(&test_entry_b().address())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn warrants_block_the_accused_agent() {
+        let mut store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        let header = test_chain_header_with_sig("sig1", None);
+        let accused = header.provenances()[0].source();
+        let warrant = |issuer: &str| Warrant {
+            header: header.clone(),
+            entry: test_entry(),
+            workflow: String::from("HoldEntry"),
+            reason: String::from("invalid"),
+            issuer: Provenance::new(issuer.into(), Signature::fake()),
+        };
+        assert!(!store.is_blocked(&accused));
+        assert!(store.blocked_agents().unwrap().is_empty());
+
+        store.add_warrant(&warrant("alice")).unwrap();
+        store.add_warrant(&warrant("bob")).unwrap();
+        assert!(store.is_blocked(&accused));
+        assert!(!store.is_blocked(&Address::from("alice")));
+        assert_eq!(store.get_warrants(&accused).unwrap().len(), 2);
+        let blocked = store.blocked_agents().unwrap();
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[&accused].len(), 2);
+
+        // The warrants are in the storage, so the agent stays blocked after a restart
+        let restored = DhtStore::new_from_snapshot(
+            store.content_storage.clone(),
+            store.meta_storage.clone(),
+            DhtStoreSnapshot {
+                holding_map: AspectMapBare::new(),
+                queued_holding_workflows: VecDeque::new(),
+                in_process_holding_workflows: VecDeque::new(),
+                held_aspect_sizes: HashMap::new(),
+                evicted_entries: HashSet::new(),
+                format_version: SNAPSHOT_FORMAT_VERSION,
+            },
+        );
+        assert!(restored.is_blocked(&accused));
+    }
+
+    #[test]
+    fn evicted_entries_stay_evicted_after_restart() {
+        let mut store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        let entry = test_entry();
+        let header = test_chain_header_with_sig("sig1", None);
+        store.add(&entry).unwrap();
+        store.mark_aspect_as_held(&EntryAspect::Content(entry.clone(), header.clone()));
+        store.evict_entry(&entry.address()).unwrap();
+        assert_eq!(store.get(&entry.address()).unwrap(), None);
+
+        let snapshot = DhtStoreSnapshot::try_from(JsonString::from(DhtStoreSnapshot {
+            holding_map: store.get_holding_map().bare().clone(),
+            queued_holding_workflows: VecDeque::new(),
+            in_process_holding_workflows: VecDeque::new(),
+            held_aspect_sizes: store.held_aspect_sizes.clone(),
+            evicted_entries: store.evicted_entries.clone(),
+            format_version: SNAPSHOT_FORMAT_VERSION,
+        }))
+        .unwrap();
+        let mut restored = DhtStore::new_from_snapshot(
+            store.content_storage.clone(),
+            store.meta_storage.clone(),
+            snapshot,
+        );
+        assert!(restored.is_evicted(&entry.address()));
+
+        // Holding the entry again brings its content back
+        restored.add(&entry).unwrap();
+        restored.mark_aspect_as_held(&EntryAspect::Content(entry.clone(), header));
+        assert!(!restored.is_evicted(&entry.address()));
+        assert_eq!(restored.get(&entry.address()).unwrap(), Some(entry));
+    }
+
+    #[test]
+    fn detects_forks_by_predecessor() {
+        let mut store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        let predecessor = Address::from("QmPredecessor");
+        let header = |entry: &Entry, sig: &'static str| {
+            ChainHeader::new(
+                &entry.entry_type(),
+                &entry.address(),
+                &test_provenances(sig),
+                &Some(predecessor.clone()),
+                &None,
+                &None,
+                &test_iso_8601(),
+            )
+        };
+        let header1 = header(&test_entry(), "sig1");
+        let header2 = header(&test_entry_b(), "sig2");
+        let author = header1.provenances()[0].source();
+
+        store.add_header_for_entry(&test_entry(), &header1).unwrap();
+        assert!(!store.holds_authored_header(&author, &header1).unwrap());
+        store.add_authored_header(&header1).unwrap();
+        assert!(store.holds_authored_header(&author, &header1).unwrap());
+        assert_eq!(store.fork_of(&header1, &author).unwrap(), None);
+        store.add_authored_header(&header2).unwrap();
+        assert_eq!(
+            store.fork_of(&header2, &Address::from("alice")).unwrap(),
+            None
+        );
+        let fork = store.fork_of(&header2, &author).unwrap().unwrap();
+        assert_eq!(fork.author, author);
+        assert_eq!(fork.predecessor, predecessor);
+        assert_eq!(fork.headers.len(), 2);
+        assert!(fork.headers.contains(&header1.address()));
+        assert!(fork.headers.contains(&header2.address()));
+
+        assert!(store.get_chain_forks(&author).unwrap().is_empty());
+        store.add_chain_fork(&fork).unwrap();
+        store.add_chain_fork(&fork).unwrap();
+        assert_eq!(store.get_chain_forks(&author).unwrap(), vec![fork]);
+        assert!(store
+            .get_chain_forks(&Address::from("alice"))
+            .unwrap()
+            .is_empty());
+    }
+
     fn pending_validation_for_entry(
         entry: Entry,
         dependencies: Vec<Address>,
@@ -10,7 +10,6 @@ use holochain_core_types::{
 };
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::cas::content::Address;
-use snowflake::ProcessUniqueId;
 use std::{
     convert::TryFrom,
     fmt,
@@ -18,6 +17,7 @@ use std::{
     sync::Arc,
     time::{Duration, SystemTime},
 };
+use uuid::Uuid;
 
 pub type PendingValidation = Arc<PendingValidationStruct>;
 
@@ -75,7 +75,8 @@ pub struct PendingValidationStruct {
     pub entry_with_header: EntryWithHeader,
     pub dependencies: Vec<Address>,
     pub workflow: ValidatingWorkflow,
-    pub uuid: ProcessUniqueId,
+    /// Gets saved with the state, so it has to be unique across restarts
+    pub uuid: Uuid,
 }
 
 impl PendingValidationStruct {
@@ -85,13 +86,13 @@ impl PendingValidationStruct {
             entry_with_header,
             dependencies,
             workflow,
-            uuid: ProcessUniqueId::new(),
+            uuid: Uuid::new_v4(),
         }
     }
 
     pub fn same(&self) -> Self {
         let mut clone = self.clone();
-        clone.uuid = ProcessUniqueId::new();
+        clone.uuid = Uuid::new_v4();
         clone
     }
 }
@@ -107,6 +108,9 @@ impl TryFrom<EntryAspect> for PendingValidationStruct {
             EntryAspect::Header(_header) => Err(HolochainError::NotImplemented(String::from(
                 "EntryAspect::Header",
             ))),
+            EntryAspect::Warrant(_) => Err(HolochainError::ErrorGeneric(String::from(
+                "EntryAspect::Warrant is not held through a holding workflow",
+            ))),
             EntryAspect::LinkAdd(link_data, header) => {
                 let entry = Entry::LinkAdd(link_data);
                 Ok(PendingValidationStruct::new(
@@ -0,0 +1,209 @@
+//! Encryption of app entries whose entry type is defined with `Sharing::Encrypted`.
+//!
+//! Such entries get encrypted on commit for the author, the recipients listed in the
+//! entry type definition and the recipients given in the commit options.
+//! Only the resulting `Entry::Encrypted` gets stored and published.
+//! Reading agents that are among the recipients get the plain app entry back from `get_entry`.
+
+use crate::{
+    context::Context, nucleus::validation::validate_enc_key,
+    workflows::get_entry_result::get_entry_with_meta_workflow,
+};
+use holochain_core_types::{
+    agent::Base32,
+    dna::entry_types::Sharing,
+    entry::{
+        encrypted_entry::EncryptedEntry,
+        entry_type::{AppEntryType, EntryType},
+        Entry,
+    },
+    error::HolochainError,
+    time::Timeout,
+};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::get_entry::{
+    GetEntryResult, GetEntryResultItem, GetEntryResultType,
+};
+use std::sync::Arc;
+
+/// True if the DNA defines the given entry type with `Sharing::Encrypted`
+pub fn is_encrypted(entry_type: &EntryType, context: &Arc<Context>) -> bool {
+    if !entry_type.is_app() {
+        return false;
+    }
+    context
+        .get_dna()
+        .and_then(|dna| {
+            dna.get_entry_type_def(&entry_type.to_string())
+                .map(|def| def.sharing == Sharing::Encrypted)
+        })
+        .unwrap_or(false)
+}
+
+/// Agent addresses the entry definition of the given type lists as recipients
+fn recipients_from_definition(entry_type: &AppEntryType, context: &Arc<Context>) -> Vec<Address> {
+    context
+        .get_dna()
+        .and_then(|dna| {
+            dna.get_entry_type_def(&String::from(entry_type.clone()))
+                .map(|def| def.recipients.clone())
+        })
+        .unwrap_or_default()
+}
+
+/// Looks up the AgentId entry of the given agent to read its public encrypting key.
+/// The key only gets used if the agent signed it, since whoever we got the entry from
+/// could have swapped it.
+async fn encrypting_key_of(
+    agent: &Address,
+    context: &Arc<Context>,
+) -> Result<Base32, HolochainError> {
+    if *agent == context.agent_id.address() {
+        return context.agent_id.pub_enc_key.clone().ok_or_else(|| {
+            HolochainError::ErrorGeneric("Agent has no encrypting key".to_string())
+        });
+    }
+    let maybe_agent_entry = get_entry_with_meta_workflow(context, agent, &Timeout::default())
+        .await?
+        .map(|entry_with_meta_and_header| entry_with_meta_and_header.entry_with_meta.entry);
+    match maybe_agent_entry {
+        Some(Entry::AgentId(agent_id)) => {
+            validate_enc_key(&agent_id).map_err(|error| {
+                HolochainError::ErrorGeneric(format!(
+                    "Won't encrypt for agent {}: {:?}",
+                    agent, error
+                ))
+            })?;
+            agent_id.pub_enc_key.ok_or_else(|| {
+                HolochainError::ErrorGeneric(format!("Agent {} has no encrypting key", agent))
+            })
+        }
+        _ => Err(HolochainError::ErrorGeneric(format!(
+            "Could not find agent {} to encrypt for",
+            agent
+        ))),
+    }
+}
+
+/// Encrypts an app entry for its author, the recipients of its entry definition
+/// and the given additional recipients.
+/// Returns the resulting `Entry::Encrypted`.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn encrypt_entry(
+    entry: &Entry,
+    recipients: &[Address],
+    context: &Arc<Context>,
+) -> Result<Entry, HolochainError> {
+    let (entry_type, value) = match entry {
+        Entry::App(entry_type, value) => (entry_type, value),
+        _ => {
+            return Err(HolochainError::ErrorGeneric(
+                "Only app entries can be encrypted".to_string(),
+            ))
+        }
+    };
+
+    let mut agents = vec![context.agent_id.address()];
+    agents.extend(recipients_from_definition(entry_type, context));
+    agents.extend(recipients.iter().cloned());
+    agents.sort();
+    agents.dedup();
+
+    let mut keys = Vec::new();
+    for agent in agents.iter() {
+        keys.push(encrypting_key_of(agent, context).await?);
+    }
+
+    let sealed = context
+        .conductor_api
+        .encrypt_for(String::from(value.clone()), keys)?;
+    Ok(Entry::Encrypted(EncryptedEntry::new(
+        entry_type.clone(),
+        sealed,
+    )))
+}
+
+/// Decrypts an `Entry::Encrypted` back to the app entry if this agent is among its recipients.
+/// All other entries get returned unchanged.
+pub fn decrypt_entry(entry: Entry, context: &Arc<Context>) -> Result<Entry, HolochainError> {
+    let encrypted = match entry {
+        Entry::Encrypted(ref encrypted) => encrypted,
+        _ => return Ok(entry),
+    };
+    let is_recipient = context
+        .agent_id
+        .pub_enc_key
+        .as_ref()
+        .map(|key| encrypted.sealed().is_recipient(key))
+        .unwrap_or(false);
+    if !is_recipient {
+        return Ok(entry);
+    }
+    let plaintext = context.conductor_api.decrypt_sealed(encrypted.sealed())?;
+    Ok(Entry::App(
+        encrypted.entry_type().clone(),
+        JsonString::from_json(&plaintext),
+    ))
+}
+
+fn decrypt_result_item(
+    item: &mut GetEntryResultItem,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
+    if let Some(entry) = item.entry.take() {
+        item.entry = Some(decrypt_entry(entry, context)?);
+    }
+    Ok(())
+}
+
+/// Decrypts the entries of a get result in place.
+/// The meta data keeps the address of the encrypted entry since that is where it is stored.
+pub fn decrypt_get_entry_result(
+    result: &mut GetEntryResult,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
+    match result.result {
+        GetEntryResultType::Single(ref mut item) => decrypt_result_item(item, context),
+        GetEntryResultType::All(ref mut history) => history
+            .items
+            .iter_mut()
+            .map(|item| decrypt_result_item(item, context))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::instance::tests::test_context;
+    use holochain_core_types::entry::test_entry;
+
+    #[test]
+    fn encrypted_entries_decrypt_for_the_author() {
+        let context = test_context("alice", None);
+        let entry = test_entry();
+        let encrypted = context
+            .block_on(encrypt_entry(&entry, &[], &context))
+            .expect("Could not encrypt entry");
+
+        assert!(match encrypted {
+            Entry::Encrypted(_) => true,
+            _ => false,
+        });
+        assert_eq!(encrypted.entry_type(), entry.entry_type());
+        assert_ne!(encrypted.address(), entry.address());
+        assert_eq!(decrypt_entry(encrypted, &context).unwrap(), entry);
+    }
+
+    #[test]
+    fn encrypted_entries_stay_encrypted_for_others() {
+        let alice = test_context("alice", None);
+        let bob = test_context("bob", None);
+        let encrypted = alice
+            .block_on(encrypt_entry(&test_entry(), &[], &alice))
+            .expect("Could not encrypt entry");
+
+        assert_eq!(decrypt_entry(encrypted.clone(), &bob).unwrap(), encrypted);
+    }
+}
@@ -10,7 +10,7 @@ pub trait ValidationDependencies {
 impl ValidationDependencies for EntryWithHeader {
     fn get_validation_dependencies(&self) -> Vec<Address> {
         match &self.entry {
-            Entry::App(_, _) => {
+            Entry::App(_, _) | Entry::Encrypted(_) => {
                 // In the future an entry should be dependent its previous header but
                 // for now it can require nothing by default.
                 // There is also potential to add a WASM function for determining dependencies as a function
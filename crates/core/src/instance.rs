@@ -96,6 +96,11 @@ impl Instance {
             .run(scheduled_jobs::create_state_pruning_callback(
                 context.clone(),
             ));
+        scheduler
+            .every(30.seconds())
+            .run(scheduled_jobs::create_storage_quota_callback(
+                context.clone(),
+            ));
         self.scheduler_handle = Some(Arc::new(scheduler.watch_thread(Duration::from_millis(10))));
 
         self.persister = Some(context.persister.clone());
@@ -310,8 +315,32 @@ impl Instance {
                     HolochainError::Timeout(format!("timeout src: {}:{}", file!(), line!()))
                 })?;
 
+            if let Some(journal) = &context.journal {
+                if let Err(e) = journal.lock().unwrap().append_action(&action_wrapper.data) {
+                    log_error!(
+                        context,
+                        "instance/process_action: could not write action to journal: {:?}",
+                        e
+                    );
+                }
+            }
+
             new_state = state.reduce(action_wrapper.data.clone());
 
+            if let Some(journal) = &context.journal {
+                if let Err(e) = journal.lock().unwrap().append_commits(
+                    &action_wrapper.data,
+                    &state.agent(),
+                    &new_state.agent(),
+                ) {
+                    log_error!(
+                        context,
+                        "instance/process_action: could not write commits to journal: {:?}",
+                        e
+                    );
+                }
+            }
+
             // Change the state
             *state = new_state;
 
@@ -327,6 +356,15 @@ impl Instance {
                     "reduce/process_actions: reducing {:?}",
                     action_wrapper
                 );
+                if let Some(journal) = &context.journal {
+                    if let Err(e) = journal.lock().unwrap().mark_snapshot() {
+                        log_error!(
+                            context,
+                            "instance/process_action: could not write snapshot marker to journal: {:?}",
+                            e
+                        );
+                    }
+                }
             }
         }
 
@@ -0,0 +1,445 @@
+//! An optional, append-only journal of every action that gets dispatched to an instance.
+//!
+//! The persister only saves full snapshots of the state, so everything that got reduced
+//! after the last successful save is lost when the process dies. With the journal switched
+//! on, `Instance::process_action()` writes each action to the journal before reducing it
+//! and writes a snapshot marker once the persister saved the resulting state.
+//! Loading an instance replays the actions after the last marker on top of the loaded
+//! snapshots (see `replay()`).
+//!
+//! Commits don't get replayed as actions since committing an entry again would give it a
+//! header with a new timestamp and signature. Instead, the headers an action added to the
+//! source chain get written after it got reduced and are put back exactly as they were.
+//!
+//! The journal is a JSON lines file with one `JournalRecord` per line. It gets cut down to
+//! the snapshot marker every time the state gets saved, so it only ever holds the records
+//! since the last save.
+//! Writes are not synced to disk, so the journal survives the process crashing but not
+//! necessarily the machine losing power. A crash between saving the state and writing the
+//! marker makes the last action get replayed a second time, which restored headers
+//! ignore. Storage backends that write lazily (pickle) can lose a save the marker was
+//! already written for.
+//!
+//! Only actions that change the state that gets persisted are replayed,
+//! everything else (network, zome call tracking, ..) gets written for debugging only.
+
+use crate::{
+    action::{Action, ActionWrapper},
+    agent::{
+        bundle::{Bundle, BundledEntry},
+        state::AgentState,
+    },
+    content_store::GetContent,
+    dht::{
+        actions::remove_queued_holding_workflow::HoldingWorkflowQueueing,
+        dht_store::HoldAspectAttemptId, pending_validations::PendingValidation,
+    },
+    network::{entry_aspect::EntryAspect, entry_with_header::EntryWithHeader},
+    nucleus::actions::initialize::Initialization,
+    state::State,
+};
+use holochain_core_types::{
+    dna::Dna,
+    error::{HcResult, HolochainError},
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use serde_json::Value;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Name of the journal file inside the storage directory of an instance
+pub const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+
+/// One line of the journal.
+/// Every record gets the next number of a sequence that starts at 0 for a new journal.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "lowercase")]
+pub enum JournalRecord {
+    /// An action as it got dispatched, written before it got reduced
+    Action { seq: u64, id: String, action: Value },
+    /// An entry and header the action with the given ID added to the source chain,
+    /// written after the action got reduced
+    Committed {
+        seq: u64,
+        id: String,
+        commit: EntryWithHeader,
+    },
+    /// The state that includes every action before this record got saved
+    Snapshot { seq: u64 },
+}
+
+impl JournalRecord {
+    pub fn seq(&self) -> u64 {
+        match self {
+            JournalRecord::Action { seq, .. }
+            | JournalRecord::Committed { seq, .. }
+            | JournalRecord::Snapshot { seq } => *seq,
+        }
+    }
+
+    /// The `action_type` of the action, None for snapshot markers
+    pub fn action_type(&self) -> Option<String> {
+        match self {
+            JournalRecord::Action { action, .. } => action
+                .get("action_type")
+                .and_then(|action_type| action_type.as_str())
+                .map(String::from),
+            JournalRecord::Committed { .. } => Some("ReplayCommit".to_string()),
+            JournalRecord::Snapshot { .. } => None,
+        }
+    }
+
+    /// The action of this record as it can be reduced again.
+    /// None for snapshot markers and actions that don't get replayed.
+    pub fn replayable_action(&self) -> HcResult<Option<ActionWrapper>> {
+        match (self, self.action_type()) {
+            (JournalRecord::Action { seq, id, action }, Some(action_type))
+                if REPLAYED_ACTION_TYPES.contains(&action_type.as_str()) =>
+            {
+                let replayable: ReplayableAction =
+                    serde_json::from_value(action.clone()).map_err(|e| {
+                        HolochainError::SerializationError(format!(
+                            "Could not read {} action #{} from journal: {}",
+                            action_type, seq, e
+                        ))
+                    })?;
+                Ok(Some(ActionWrapper::with_id(
+                    Action::from(replayable),
+                    id.clone(),
+                )))
+            }
+            (JournalRecord::Committed { commit, .. }, _) => Ok(Some(ActionWrapper::new(
+                Action::ReplayCommit(commit.clone()),
+            ))),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Appends records to the journal file of an instance
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    next_seq: u64,
+}
+
+impl Journal {
+    /// Opens the journal in the given storage directory, creating it if it does not exist.
+    /// A last line that did not get written completely gets cut off.
+    pub fn open<P: AsRef<Path>>(dir: P) -> HcResult<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        let path = dir.as_ref().join(JOURNAL_FILE_NAME);
+        let content = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+        let complete_length = content.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        file.set_len(complete_length as u64)?;
+        let next_seq = parse_records(&content[..complete_length])?
+            .last()
+            .map(|record| record.seq() + 1)
+            .unwrap_or(0);
+        Ok(Journal {
+            path,
+            file,
+            next_seq,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the given action to the journal
+    pub fn append_action(&mut self, action_wrapper: &ActionWrapper) -> HcResult<()> {
+        let record = JournalRecord::Action {
+            seq: self.next_seq,
+            id: action_wrapper.id().clone(),
+            action: serde_json::to_value(action_wrapper.action())?,
+        };
+        self.append(record)
+    }
+
+    /// Writes the entries and headers the given action added to the source chain,
+    /// i.e. the ones between the top headers of the agent states before and after it.
+    pub fn append_commits(
+        &mut self,
+        action_wrapper: &ActionWrapper,
+        before: &AgentState,
+        after: &AgentState,
+    ) -> HcResult<()> {
+        let old_top = before.top_chain_header().map(|header| header.address());
+        if after.top_chain_header().map(|header| header.address()) == old_top {
+            return Ok(());
+        }
+        let mut commits = after
+            .iter_chain()
+            .take_while(|header| Some(header.address()) != old_top)
+            .map(|header| {
+                let entry = after
+                    .chain_store()
+                    .get(header.entry_address())?
+                    .ok_or_else(|| {
+                        HolochainError::ErrorGeneric(format!(
+                            "Entry {} of committed header not found",
+                            header.entry_address()
+                        ))
+                    })?;
+                Ok(EntryWithHeader::new(entry, header))
+            })
+            .collect::<HcResult<Vec<EntryWithHeader>>>()?;
+        commits.reverse();
+        for commit in commits {
+            let record = JournalRecord::Committed {
+                seq: self.next_seq,
+                id: action_wrapper.id().clone(),
+                commit,
+            };
+            self.append(record)?;
+        }
+        Ok(())
+    }
+
+    /// Records that the persister saved the state with every action written so far.
+    /// Everything before the marker is not needed anymore, so the journal gets emptied first.
+    pub fn mark_snapshot(&mut self) -> HcResult<()> {
+        self.file.set_len(0)?;
+        let record = JournalRecord::Snapshot { seq: self.next_seq };
+        self.append(record)
+    }
+
+    /// The records that were written after the last snapshot marker
+    pub fn unsaved_records(&self) -> HcResult<Vec<JournalRecord>> {
+        let mut records = read_journal(&self.path)?;
+        let last_snapshot = records
+            .iter()
+            .rposition(|record| match record {
+                JournalRecord::Snapshot { .. } => true,
+                _ => false,
+            })
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        Ok(records.split_off(last_snapshot))
+    }
+
+    fn append(&mut self, record: JournalRecord) -> HcResult<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        // One write per record so that a crash can only leave the last line incomplete
+        self.file.write_all(line.as_bytes())?;
+        self.next_seq += 1;
+        Ok(())
+    }
+}
+
+/// Reads all records of the journal file at the given path.
+/// An incomplete last line is ignored.
+pub fn read_journal<P: AsRef<Path>>(path: P) -> HcResult<Vec<JournalRecord>> {
+    let content = fs::read_to_string(path.as_ref())?;
+    let complete_length = content.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    parse_records(&content[..complete_length])
+}
+
+fn parse_records(content: &str) -> HcResult<Vec<JournalRecord>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|e| {
+                HolochainError::SerializationError(format!("Invalid journal record: {}", e))
+            })
+        })
+        .collect()
+}
+
+/// Reduces the replayable actions of the given records into the given state.
+/// Returns the resulting state and the number of actions that got reduced.
+pub fn replay(state: State, records: &[JournalRecord]) -> HcResult<(State, usize)> {
+    let mut state = state;
+    let mut replayed = 0;
+    for record in records {
+        if let Some(action_wrapper) = record.replayable_action()? {
+            state = state.reduce(action_wrapper);
+            replayed += 1;
+        }
+    }
+    Ok((state, replayed))
+}
+
+const REPLAYED_ACTION_TYPES: &[&str] = &[
+    "Prune",
+    "ClearActionResponse",
+    "StartBundle",
+    "StageBundleEntry",
+    "CloseBundle",
+    "QueueHoldingWorkflow",
+    "RemoveQueuedHoldingWorkflow",
+    "HoldAspect",
+    "EvictHeldEntries",
+    "InitializeChain",
+    "ReturnInitializationResult",
+    "UseCapGrant",
+];
+
+/// The actions from REPLAYED_ACTION_TYPES with the same serialization as in Action.
+/// Action itself can't be deserialized since some of the other actions carry
+/// things like network handlers.
+#[derive(Deserialize)]
+#[serde(tag = "action_type", content = "data")]
+#[allow(clippy::large_enum_variant)]
+enum ReplayableAction {
+    Prune,
+    ClearActionResponse(String),
+    StartBundle(Bundle),
+    StageBundleEntry((String, BundledEntry)),
+    CloseBundle(String),
+    QueueHoldingWorkflow((PendingValidation, Option<(SystemTime, Duration)>)),
+    RemoveQueuedHoldingWorkflow((HoldingWorkflowQueueing, PendingValidation)),
+    HoldAspect((EntryAspect, HoldAspectAttemptId)),
+    EvictHeldEntries(Vec<Address>),
+    InitializeChain(Dna),
+    ReturnInitializationResult(Result<Initialization, String>),
+    UseCapGrant((Address, u32)),
+}
+
+impl From<ReplayableAction> for Action {
+    fn from(action: ReplayableAction) -> Action {
+        match action {
+            ReplayableAction::Prune => Action::Prune,
+            ReplayableAction::ClearActionResponse(id) => Action::ClearActionResponse(id),
+            ReplayableAction::StartBundle(bundle) => Action::StartBundle(bundle),
+            ReplayableAction::StageBundleEntry(data) => Action::StageBundleEntry(data),
+            ReplayableAction::CloseBundle(id) => Action::CloseBundle(id),
+            ReplayableAction::QueueHoldingWorkflow(data) => Action::QueueHoldingWorkflow(data),
+            ReplayableAction::RemoveQueuedHoldingWorkflow(data) => {
+                Action::RemoveQueuedHoldingWorkflow(data)
+            }
+            ReplayableAction::HoldAspect(data) => Action::HoldAspect(data),
+            ReplayableAction::EvictHeldEntries(entries) => Action::EvictHeldEntries(entries),
+            ReplayableAction::InitializeChain(dna) => Action::InitializeChain(dna),
+            ReplayableAction::ReturnInitializationResult(result) => {
+                Action::ReturnInitializationResult(result)
+            }
+            ReplayableAction::UseCapGrant(data) => Action::UseCapGrant(data),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        agent::state::AgentStateSnapshot, instance::tests::test_context_with_agent_state,
+        state::StateWrapper,
+    };
+    use holochain_core_types::{chain_header::test_chain_header, entry::test_entry};
+    use std::fs::OpenOptions;
+    use tempfile::tempdir;
+
+    #[test]
+    fn journal_round_trip() {
+        let dir = tempdir().unwrap();
+        let commit = ActionWrapper::new(Action::Commit((test_entry(), None, Vec::new())));
+        let committed = EntryWithHeader::new(test_entry(), test_chain_header());
+        {
+            let mut journal = Journal::open(dir.path()).unwrap();
+            journal
+                .append_action(&ActionWrapper::new(Action::Ping))
+                .unwrap();
+            journal.mark_snapshot().unwrap();
+            journal.append_action(&commit).unwrap();
+            let record = JournalRecord::Committed {
+                seq: journal.next_seq,
+                id: commit.id().clone(),
+                commit: committed.clone(),
+            };
+            journal.append(record).unwrap();
+        }
+
+        let journal = Journal::open(dir.path()).unwrap();
+        assert_eq!(journal.next_seq, 4);
+        let unsaved = journal.unsaved_records().unwrap();
+        assert_eq!(unsaved.len(), 2);
+        assert_eq!(unsaved[0].seq(), 2);
+        assert_eq!(unsaved[0].action_type(), Some("Commit".to_string()));
+
+        // Commits get replayed with the header they got, not by committing again
+        assert_eq!(unsaved[0].replayable_action().unwrap(), None);
+        let replayed = unsaved[1].replayable_action().unwrap().unwrap();
+        assert_eq!(replayed.action(), &Action::ReplayCommit(committed));
+
+        // Everything before the last snapshot marker got dropped
+        let records = read_journal(journal.path()).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], JournalRecord::Snapshot { seq: 1 });
+    }
+
+    #[test]
+    fn cuts_off_incomplete_record() {
+        let dir = tempdir().unwrap();
+        {
+            let mut journal = Journal::open(dir.path()).unwrap();
+            journal.mark_snapshot().unwrap();
+        }
+        OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(JOURNAL_FILE_NAME))
+            .unwrap()
+            .write_all(b"{\"record\":\"act")
+            .unwrap();
+        assert_eq!(
+            read_journal(dir.path().join(JOURNAL_FILE_NAME)).unwrap(),
+            vec![JournalRecord::Snapshot { seq: 0 }]
+        );
+
+        let mut journal = Journal::open(dir.path()).unwrap();
+        journal
+            .append_action(&ActionWrapper::new(Action::Ping))
+            .unwrap();
+        assert_eq!(read_journal(journal.path()).unwrap().len(), 2);
+        journal.mark_snapshot().unwrap();
+        assert_eq!(
+            read_journal(journal.path()).unwrap(),
+            vec![JournalRecord::Snapshot { seq: 2 }]
+        );
+    }
+
+    #[test]
+    fn replays_commits_with_the_header_they_got() {
+        let dir = tempdir().unwrap();
+        let context = test_context_with_agent_state(None);
+        let mut journal = Journal::open(dir.path()).unwrap();
+        let commit = ActionWrapper::new(Action::Commit((test_entry(), None, Vec::new())));
+        let before = State::new(context.clone());
+        let after = before.reduce(commit.clone());
+        journal.append_action(&commit).unwrap();
+        journal
+            .append_commits(&commit, &before.agent(), &after.agent())
+            .unwrap();
+        let committed_header = after.agent().top_chain_header().unwrap();
+
+        let top_header = |state: State| {
+            AgentStateSnapshot::from(&StateWrapper::from(state))
+                .top_chain_header()
+                .cloned()
+                .unwrap()
+        };
+        let records = journal.unsaved_records().unwrap();
+        let (replayed_state, replayed) = replay(before, &records).unwrap();
+        assert_eq!(replayed, 1);
+        assert_eq!(top_header(replayed_state.clone()), committed_header);
+
+        // Replaying again, e.g. after a crash before the snapshot marker got written,
+        // doesn't add the header a second time
+        let (replayed_twice, _) = replay(replayed_state, &records).unwrap();
+        let top = top_header(replayed_twice);
+        assert_eq!(top, committed_header);
+        assert_eq!(top.link(), None);
+    }
+}
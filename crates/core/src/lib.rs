@@ -57,6 +57,8 @@ pub mod entry;
 #[autotrace]
 #[allow(clippy::suspicious_else_formatting, clippy::redundant_closure)]
 pub mod instance;
+#[allow(clippy::suspicious_else_formatting, clippy::redundant_closure)]
+pub mod journal;
 #[cfg(test)]
 #[allow(clippy::suspicious_else_formatting, clippy::redundant_closure)]
 pub mod link_tests;
@@ -20,9 +20,26 @@ pub async fn custom_send(
     custom_direct_message: CustomDirectMessage,
     timeout: Timeout,
     context: Arc<Context>,
+) -> Result<String, HolochainError> {
+    send_and_wait(
+        to_agent,
+        DirectMessage::Custom(custom_direct_message),
+        timeout,
+        context,
+    )
+    .await
+}
+
+/// Sends the given message and waits for the response that the receiving node put
+/// into NetworkState::custom_direct_message_replys, or for the timeout.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub(crate) async fn send_and_wait(
+    to_agent: Address,
+    direct_message: DirectMessage,
+    timeout: Timeout,
+    context: Arc<Context>,
 ) -> Result<String, HolochainError> {
     let id = nanoid::simple();
-    let direct_message = DirectMessage::Custom(custom_direct_message);
     let direct_message_data = DirectMessageData {
         address: to_agent,
         message: direct_message,
@@ -2,7 +2,12 @@ use crate::action::ValidationKey;
 
 use holochain_json_api::{error::JsonError, json::JsonString};
 
-use holochain_core_types::validation::ValidationPackage;
+use crate::network::validation_receipt::ValidationReceipt;
+use holochain_core_types::{
+    countersigning::CountersigningRequest, entry::Entry, signature::Provenance,
+    validation::ValidationPackage,
+};
+use holochain_persistence_api::cas::content::Address;
 
 /// This is direct message that got created by the zome code through hdk::send().
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
@@ -35,4 +40,24 @@ pub enum DirectMessage {
     /// Option<> since there has to be a way to respond saying
     /// "I can't"
     ValidationPackage(Option<ValidationPackage>),
+
+    /// A holder sends this to the author of an entry after validating and holding it.
+    ValidationReceipt(ValidationReceipt),
+
+    /// With this message an author confirms that it got a ValidationReceipt.
+    ValidationReceiptReceived,
+
+    /// The proposer of a countersigned entry asks one of its parties to sign it.
+    CountersignRequest(CountersigningRequest),
+
+    /// The proposer sends the countersigned entry with the provenances of all parties,
+    /// for every party to commit it.
+    CountersignCommit((Entry, Vec<Provenance>)),
+
+    /// The proposer gives up on the countersigned entry with the given address.
+    CountersignAbort(Address),
+
+    /// A party's response to the countersigning messages above: its provenance or the
+    /// address of the committed entry as JSON, or why it refused.
+    CountersignResponse(Result<String, String>),
 }
@@ -7,6 +7,7 @@ use crate::{
     },
 };
 use holochain_core_types::network::entry_aspect::EntryAspect;
+use holochain_persistence_api::cas::content::Address;
 use lib3h_protocol::{data_types::FetchEntryData, types::EntryHash};
 use std::{collections::HashSet, sync::Arc};
 
@@ -60,5 +61,20 @@ pub fn fetch_aspects_for_entry(address: &EntryHash, context: Arc<Context>) -> Ha
         }
     }
 
+    // Warrants are held at the address of the agent they are against
+    if let Some(state) = context.state() {
+        match state.dht().get_warrants(&Address::from(address.clone())) {
+            Ok(warrants) => warrants.into_iter().for_each(|warrant| {
+                aspects.insert(EntryAspect::Warrant(warrant));
+            }),
+            Err(error) => log_error!(
+                context,
+                "net/handle_fetch_entry: Error getting warrants for {:?}: {:?}",
+                address,
+                error,
+            ),
+        }
+    }
+
     aspects
 }
@@ -391,9 +391,9 @@ fn entry_to_meta_aspect(
     header: ChainHeader,
 ) -> Result<Option<(Address, EntryAspect)>, HolochainError> {
     let maybe_aspect = match entry {
-        Entry::App(app_type, app_value) => header
+        entry @ Entry::App(_, _) | entry @ Entry::Encrypted(_) => header
             .link_update_delete()
-            .map(|_| EntryAspect::Update(Entry::App(app_type, app_value), header)),
+            .map(|_| EntryAspect::Update(entry, header)),
         Entry::LinkAdd(link_data) => Some(EntryAspect::LinkAdd(link_data, header)),
         Entry::LinkRemove((link_data, addresses)) => {
             Some(EntryAspect::LinkRemove((link_data, addresses), header))
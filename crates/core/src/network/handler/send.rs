@@ -4,13 +4,18 @@ use crate::{
     instance::dispatch_action,
     network::direct_message::DirectMessage,
     workflows::{
+        countersign::{
+            handle_countersign_abort, handle_countersign_commit, handle_countersign_request,
+        },
         handle_custom_direct_message::handle_custom_direct_message,
         respond_validation_package_request::respond_validation_package_request,
+        validation_receipt::handle_validation_receipt,
     },
 };
 use std::sync::Arc;
 
 use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::Address;
 use lib3h_protocol::data_types::DirectMessageData;
 use std::convert::TryFrom;
 
@@ -40,6 +45,16 @@ pub fn handle_send_message(message_data: DirectMessageData, context: Arc<Context
         }
     };
 
+    let from_agent_id: Address = message_data.from_agent_id.clone().into();
+    if context.state().unwrap().dht().is_blocked(&from_agent_id) {
+        log_debug!(
+            context,
+            "net/handle_send_message: Ignoring message from blocked agent {}",
+            from_agent_id
+        );
+        return;
+    }
+
     match message {
         DirectMessage::Custom(custom_direct_message) => {
             let c = context.clone();
@@ -80,6 +95,67 @@ pub fn handle_send_message(message_data: DirectMessageData, context: Arc<Context
             "net: Got DirectMessage::ValidationPackage as initial message. This should not happen.",
         )
         }
+        DirectMessage::ValidationReceipt(receipt) => {
+            context.spawn_task({
+                let context = context.clone();
+                async move || {
+                    handle_validation_receipt(
+                        message_data.from_agent_id.into(),
+                        message_data.request_id,
+                        receipt,
+                        context,
+                    );
+                }
+            }());
+        }
+        DirectMessage::ValidationReceiptReceived => log_error!(context,
+            "net: Got DirectMessage::ValidationReceiptReceived as initial message. This should not happen.",
+        ),
+        DirectMessage::CountersignRequest(request) => {
+            context.spawn_task({
+                let context = context.clone();
+                async move || {
+                    handle_countersign_request(
+                        message_data.from_agent_id.into(),
+                        message_data.request_id,
+                        request,
+                        context,
+                    )
+                    .await;
+                }
+            }());
+        }
+        DirectMessage::CountersignCommit((entry, provenances)) => {
+            context.spawn_task({
+                let context = context.clone();
+                async move || {
+                    handle_countersign_commit(
+                        message_data.from_agent_id.into(),
+                        message_data.request_id,
+                        entry,
+                        provenances,
+                        context,
+                    )
+                    .await;
+                }
+            }());
+        }
+        DirectMessage::CountersignAbort(entry_address) => {
+            context.spawn_task({
+                let context = context.clone();
+                async move || {
+                    handle_countersign_abort(
+                        message_data.from_agent_id.into(),
+                        message_data.request_id,
+                        entry_address,
+                        context,
+                    );
+                }
+            }());
+        }
+        DirectMessage::CountersignResponse(_) => log_error!(context,
+            "net: Got DirectMessage::CountersignResponse as initial message. This should not happen.",
+        ),
     };
 }
 
@@ -143,6 +219,46 @@ pub fn handle_send_message_result(message_data: DirectMessageData, context: Arc<
             )));
             dispatch_action(context.action_channel(), action_wrapper);
 
+            let action_wrapper =
+                ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
+            dispatch_action(context.action_channel(), action_wrapper);
+        }
+        DirectMessage::ValidationReceipt(_) => log_error!(context,
+            "net: Got DirectMessage::ValidationReceipt as a response. This should not happen.",
+        ),
+        DirectMessage::ValidationReceiptReceived => {
+            if initial_message.is_none() {
+                log_error!(context, "net: Received a validation receipt ack but could not find message ID {} in history. Not able to process.", message_data.request_id);
+                return;
+            }
+
+            let action_wrapper =
+                ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
+            dispatch_action(context.action_channel(), action_wrapper);
+        }
+        DirectMessage::CountersignRequest(_) => log_error!(context,
+            "net: Got DirectMessage::CountersignRequest as a response. This should not happen.",
+        ),
+        DirectMessage::CountersignCommit(_) => log_error!(context,
+            "net: Got DirectMessage::CountersignCommit as a response. This should not happen.",
+        ),
+        DirectMessage::CountersignAbort(_) => log_error!(context,
+            "net: Got DirectMessage::CountersignAbort as a response. This should not happen.",
+        ),
+        DirectMessage::CountersignResponse(result) => {
+            if initial_message.is_none() {
+                log_error!(context, "net: Received a countersigning response but could not find message ID {} in history. Not able to process.", message_data.request_id);
+                return;
+            }
+
+            // Countersigning parties respond the same way as custom messages,
+            // so the proposer waits for them with the same future.
+            let action_wrapper = ActionWrapper::new(Action::HandleCustomSendResponse((
+                message_data.request_id.clone(),
+                result,
+            )));
+            dispatch_action(context.action_channel(), action_wrapper);
+
             let action_wrapper =
                 ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
             dispatch_action(context.action_channel(), action_wrapper);
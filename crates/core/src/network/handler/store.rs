@@ -6,9 +6,11 @@ use crate::{
         },
         pending_validations::PendingValidationStruct,
     },
+    workflows::{hold_header::hold_header_workflow, warrant::hold_warrant_workflow},
 };
 use holochain_core_types::network::entry_aspect::EntryAspect;
 use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::Address;
 use lib3h_protocol::data_types::StoreEntryAspectData;
 use std::{
     convert::{TryFrom, TryInto},
@@ -23,6 +25,19 @@ pub fn handle_store(dht_data: StoreEntryAspectData, context: Arc<Context>) {
         JsonString::from_json(std::str::from_utf8(&*dht_data.entry_aspect.aspect).unwrap());
     let maybe_aspect: Result<EntryAspect, _> = aspect_json.clone().try_into();
     if let Ok(aspect) = maybe_aspect {
+        // Don't take back what we evicted to stay within the storage quota
+        if context.storage_quota.is_some() {
+            if let Ok(entry_address) = aspect.entry_address() {
+                if context.state().unwrap().dht().is_evicted(&entry_address) {
+                    log_debug!(
+                        context,
+                        "net/handle_store: Not holding aspect of evicted entry {}",
+                        entry_address
+                    );
+                    return;
+                }
+            }
+        }
         if context
             .state()
             .unwrap()
@@ -38,6 +53,46 @@ pub fn handle_store(dht_data: StoreEntryAspectData, context: Arc<Context>) {
             ack_single(context, aspect);
             return;
         }
+        if let EntryAspect::Warrant(warrant) = aspect {
+            context.spawn_task({
+                let context = context.clone();
+                async move || {
+                    if let Err(error) = hold_warrant_workflow(warrant, context.clone()).await {
+                        log_warn!(context, "net/handle_store: Not holding warrant: {}", error);
+                    }
+                }
+            }());
+            return;
+        }
+        // Don't hold anything from agents we have a warrant against
+        if let Some(author) = aspect
+            .header()
+            .provenances()
+            .iter()
+            .map(|provenance| provenance.source())
+            .find(|source| context.state().unwrap().dht().is_blocked(source))
+        {
+            log_debug!(
+                context,
+                "net/handle_store: Not holding aspect of blocked agent {}",
+                author
+            );
+            return;
+        }
+        // Headers get published to the address of the agent whose chain they are part of
+        if let EntryAspect::Header(header) = aspect {
+            let author: Address = dht_data.entry_address.into();
+            context.spawn_task({
+                let context = context.clone();
+                async move || {
+                    if let Err(error) = hold_header_workflow(author, header, context.clone()).await
+                    {
+                        log_warn!(context, "net/handle_store: Not holding header: {}", error);
+                    }
+                }
+            }());
+            return;
+        }
         match PendingValidationStruct::try_from(aspect.clone()) {
             Err(e) => log_error!(
                 context,
@@ -8,6 +8,7 @@ pub mod reducers;
 pub mod state;
 #[cfg(test)]
 pub mod test_utils;
+pub mod validation_receipt;
 
 pub use holochain_core_types::network::{entry_aspect, query};
 
@@ -0,0 +1,43 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    network::{reducers::send, state::NetworkState},
+    state::State,
+};
+use lib3h_protocol::{
+    data_types::EntryListData, protocol_client::Lib3hClientProtocol, types::EntryHash,
+};
+use snowflake::ProcessUniqueId;
+
+/// Tells the network that we no longer hold the evicted entries by listing them
+/// without any aspects.
+/// Needs a request id so the message doesn't get merged into the batched holding list acks.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn reduce_evict_held_entries(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let entry_addresses = unwrap_to!(action => Action::EvictHeldEntries);
+    if network_state.initialized().is_err() {
+        return;
+    }
+    let entry_list_data = EntryListData {
+        space_address: network_state.dna_address.clone().unwrap().into(),
+        provider_agent_id: network_state.agent_id.clone().unwrap().into(),
+        request_id: ProcessUniqueId::new().to_string(),
+        address_map: entry_addresses
+            .iter()
+            .map(|address| (EntryHash::from(address.clone()), Vec::new()))
+            .collect(),
+    };
+    if let Err(err) = send(
+        network_state,
+        Lib3hClientProtocol::HandleGetGossipingEntryListResult(entry_list_data),
+    ) {
+        println!(
+            "Error sending Lib3hClientProtocol::HandleGetGossipEntryListResult: {:?}",
+            err
+        )
+    }
+}
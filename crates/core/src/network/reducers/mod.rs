@@ -1,5 +1,6 @@
 pub mod clear;
 pub mod clear_action_response;
+pub mod evict_held_entries;
 pub mod get_validation_package;
 pub mod handle_custom_send_response;
 pub mod handle_get_result;
@@ -8,6 +9,7 @@ pub mod init;
 pub mod prune;
 pub mod publish;
 pub mod publish_header_entry;
+pub mod publish_warrant;
 pub mod query;
 pub mod resolve_direct_connection;
 pub mod respond_authoring_list;
@@ -23,6 +25,7 @@ use crate::{
         direct_message::DirectMessage,
         reducers::{
             clear_action_response::reduce_clear_action_response,
+            evict_held_entries::reduce_evict_held_entries,
             get_validation_package::{
                 reduce_get_validation_package, reduce_get_validation_package_timeout,
             },
@@ -33,6 +36,7 @@ use crate::{
             prune::reduce_prune,
             publish::reduce_publish,
             publish_header_entry::reduce_publish_header_entry,
+            publish_warrant::reduce_publish_warrant,
             query::{reduce_query, reduce_query_timeout},
             resolve_direct_connection::reduce_resolve_direct_connection,
             respond_authoring_list::reduce_respond_authoring_list,
@@ -73,6 +77,7 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NetworkReduceFn> {
         Action::ClearCustomSendResponse(_) => Some(reduce_clear_custom_send_response),
         Action::Query(_) => Some(reduce_query),
         Action::QueryTimeout(_) => Some(reduce_query_timeout),
+        Action::EvictHeldEntries(_) => Some(reduce_evict_held_entries),
         Action::GetValidationPackage(_) => Some(reduce_get_validation_package),
         Action::GetValidationPackageTimeout(_) => Some(reduce_get_validation_package_timeout),
         Action::HandleCustomSendResponse(_) => Some(reduce_handle_custom_send_response),
@@ -82,6 +87,7 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NetworkReduceFn> {
         Action::Prune => Some(reduce_prune),
         Action::Publish(_) => Some(reduce_publish),
         Action::PublishHeaderEntry(_) => Some(reduce_publish_header_entry),
+        Action::PublishWarrant(_) => Some(reduce_publish_warrant),
         Action::ResolveDirectConnection(_) => Some(reduce_resolve_direct_connection),
         Action::RespondAuthoringList(_) => Some(reduce_respond_authoring_list),
         Action::RespondGossipList(_) => Some(reduce_respond_gossip_list),
@@ -150,6 +150,7 @@ fn reduce_publish_inner(
 
     match entry_with_header.entry.entry_type() {
         EntryType::AgentId => publish_entry(network_state, &entry_with_header),
+        EntryType::ChainMigrate => publish_entry(network_state, &entry_with_header),
         EntryType::App(_) => publish_entry(network_state, &entry_with_header).and_then(|_| {
             match entry_with_header.header.link_update_delete() {
                 Some(modified_entry) => publish_update_delete_meta(
@@ -21,14 +21,18 @@ use holochain_persistence_api::cas::content::{Address, AddressableContent};
 
 /// Send to network a request to publish a header entry alone
 /// This is similar to publishing a regular entry but it has its own special dummy header.
+/// The header also gets published to our agent address, so the agents holding it see every
+/// header of our chain and can tell if it forks.
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 fn publish_header(
     network_state: &mut NetworkState,
     root_state: &State,
     chain_header: ChainHeader,
 ) -> Result<(), HolochainError> {
-    let EntryWithHeader { entry, header } =
-        create_entry_with_header_for_header(&StateWrapper::from(root_state.clone()), chain_header)?;
+    let EntryWithHeader { entry, header } = create_entry_with_header_for_header(
+        &StateWrapper::from(root_state.clone()),
+        chain_header.clone(),
+    )?;
     send(
         network_state,
         Lib3hClientProtocol::PublishEntry(ProvidedEntryData {
@@ -41,6 +45,20 @@ fn publish_header(
                 ))],
             },
         }),
+    )?;
+    let author = Address::from(network_state.agent_id.clone().unwrap());
+    send(
+        network_state,
+        Lib3hClientProtocol::PublishEntry(ProvidedEntryData {
+            space_address: network_state.dna_address.clone().unwrap().into(),
+            provider_agent_id: network_state.agent_id.clone().unwrap().into(),
+            entry: EntryData {
+                entry_address: author.into(),
+                aspect_list: vec![entry_data_to_entry_aspect_data(&EntryAspect::Header(
+                    chain_header,
+                ))],
+            },
+        }),
     )
 }
 
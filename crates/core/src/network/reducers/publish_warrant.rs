@@ -0,0 +1,49 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    network::{
+        entry_aspect::EntryAspect,
+        reducers::{publish::entry_data_to_entry_aspect_data, send},
+        state::NetworkState,
+    },
+    state::State,
+};
+use holochain_core_types::{error::HolochainError, network::warrant::Warrant};
+use lib3h_protocol::{
+    data_types::{EntryData, ProvidedEntryData},
+    protocol_client::Lib3hClientProtocol,
+};
+
+/// Send to network a request to publish a warrant at the address of the accused agent,
+/// so it reaches the nodes that hold the agent's data.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn reduce_publish_warrant_inner(
+    network_state: &mut NetworkState,
+    warrant: &Warrant,
+) -> Result<(), HolochainError> {
+    network_state.initialized()?;
+    let aspect = EntryAspect::Warrant(warrant.clone());
+    send(
+        network_state,
+        Lib3hClientProtocol::PublishEntry(ProvidedEntryData {
+            space_address: network_state.dna_address.clone().unwrap().into(),
+            provider_agent_id: network_state.agent_id.clone().unwrap().into(),
+            entry: EntryData {
+                entry_address: aspect.entry_address()?.into(),
+                aspect_list: vec![entry_data_to_entry_aspect_data(&aspect)],
+            },
+        }),
+    )
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn reduce_publish_warrant(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let warrant = unwrap_to!(action => Action::PublishWarrant);
+    if let Err(error) = reduce_publish_warrant_inner(network_state, warrant) {
+        error!("err/net: Error publishing warrant: {:?}", error);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::context::Context;
+use holochain_core_types::{
+    chain_header::ChainHeader,
+    error::HolochainError,
+    signature::{Provenance, Signature},
+};
+use holochain_dpki::utils::Verify;
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
+use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
+use std::convert::TryFrom;
+
+/// A holder's signed statement that it validated an entry and holds it now.
+/// Holders send these to the author of the entry, who keeps them in its DHT store.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DefaultJson)]
+pub struct ValidationReceipt {
+    pub entry_address: Address,
+    pub header_address: Address,
+    /// The holder and its signature of the entry and header addresses
+    pub validator: Provenance,
+}
+
+impl ValidationReceipt {
+    /// Creates a receipt for the entry of the given header, signed by the agent of the context
+    pub fn sign(header: &ChainHeader, context: &Context) -> Result<Self, HolochainError> {
+        let signature = context.conductor_api.execute(
+            signed_data(header.entry_address(), &header.address()),
+            CryptoMethod::Sign,
+        )?;
+        Ok(ValidationReceipt {
+            entry_address: header.entry_address().clone(),
+            header_address: header.address(),
+            validator: Provenance::new(context.agent_id.address(), Signature::from(signature)),
+        })
+    }
+
+    /// Checks that the validator signed this receipt
+    pub fn verify(&self) -> bool {
+        self.validator
+            .verify(signed_data(&self.entry_address, &self.header_address))
+            .unwrap_or(false)
+    }
+}
+
+fn signed_data(entry_address: &Address, header_address: &Address) -> String {
+    format!("validation_receipt:{}:{}", entry_address, header_address)
+}
+
+impl AddressableContent for ValidationReceipt {
+    fn content(&self) -> Content {
+        self.to_owned().into()
+    }
+
+    fn try_from_content(content: &Content) -> Result<Self, JsonError> {
+        Self::try_from(content.to_owned())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::instance::tests::test_context;
+    use holochain_core_types::{chain_header::test_chain_header, signature::Signature};
+
+    #[test]
+    fn signs_and_verifies_receipts() {
+        let context = test_context("bob", None);
+        let header = test_chain_header();
+        let receipt = ValidationReceipt::sign(&header, &context).unwrap();
+        assert_eq!(&receipt.entry_address, header.entry_address());
+        assert_eq!(receipt.header_address, header.address());
+        assert_eq!(receipt.validator.source(), context.agent_id.address());
+        assert!(receipt.verify());
+
+        let other_header = ValidationReceipt {
+            header_address: Address::from("QmOtherHeader"),
+            ..receipt.clone()
+        };
+        assert!(!other_header.verify());
+
+        let forged = ValidationReceipt {
+            validator: Provenance::new(context.agent_id.address(), Signature::fake()),
+            ..receipt
+        };
+        assert!(!forged.verify());
+    }
+}
@@ -61,6 +61,10 @@ pub fn build_validation_package<'a>(
         EntryType::AgentId => {
             // FIXME
         }
+
+        EntryType::ChainMigrate => {
+            // ChainMigrate is validated natively, see nucleus::validation::chain_migrate
+        }
         _ => {
             return Err(HolochainError::ValidationFailed(format!(
                 "Attempted to validate system entry type {:?}",
@@ -146,6 +150,16 @@ pub fn build_validation_package<'a>(
                 }
             })
         })
+        .map(|mut package| {
+            // Holders need the headers to check that the chain was not closed before the entry
+            if package.source_chain_headers.is_none() {
+                package.source_chain_headers = Some(all_chain_headers_before_header(
+                    &context,
+                    &package.chain_header,
+                ));
+            }
+            package
+        })
 }
 
 // given a slice of headers return the entries for those marked public
@@ -219,9 +233,9 @@ mod tests {
         assert!(maybe_validation_package.is_ok());
 
         let expected = ValidationPackage {
-            chain_header,
+            chain_header: chain_header.clone(),
             source_chain_entries: None,
-            source_chain_headers: None,
+            source_chain_headers: Some(all_chain_headers_before_header(&context, &chain_header)),
             custom: None,
         };
 
@@ -253,7 +267,7 @@ mod tests {
                 &context,
                 &all_chain_headers_before_header(&context, &chain_header),
             )),
-            source_chain_headers: None,
+            source_chain_headers: Some(all_chain_headers_before_header(&context, &chain_header)),
             custom: None,
         };
 
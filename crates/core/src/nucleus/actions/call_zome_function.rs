@@ -5,13 +5,17 @@ use crate::{
     wasm_engine::{self, WasmCallData},
 };
 use holochain_core_types::{
-    dna::{capabilities::CapabilityRequest, wasm::DnaWasm},
+    dna::{
+        capabilities::{encode_call_data_for_signing, CapabilityRequest},
+        wasm::DnaWasm,
+    },
     entry::{
         cap_entries::{CapTokenGrant, CapabilityType},
         Entry,
     },
     error::HolochainError,
     signature::{Provenance, Signature},
+    time::Iso8601,
 };
 
 use holochain_json_api::json::JsonString;
@@ -21,7 +25,6 @@ use holochain_tracing::channel::lax_send_wrapped;
 use holochain_dpki::utils::Verify;
 
 use crate::instance::dispatch_action;
-use base64;
 use futures::{future::Future, task::Poll};
 use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
 use snowflake::ProcessUniqueId;
@@ -155,26 +158,68 @@ fn is_token_the_agent(context: Arc<Context>, request: &CapabilityRequest) -> boo
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 fn get_grant(context: &Arc<Context>, address: &Address) -> Option<CapTokenGrant> {
+    if is_grant_revoked(context, address) {
+        return None;
+    }
     match get_entry_from_agent_chain(context, address).ok()?? {
         Entry::CapTokenGrant(grant) => Some(grant),
         _ => None,
     }
 }
 
+/// A grant is revoked once it got updated or deleted on the local chain
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+fn is_grant_revoked(context: &Arc<Context>, address: &Address) -> bool {
+    context
+        .state()
+        .map(|state| state.agent().is_grant_revoked(address))
+        .unwrap_or(false)
+}
+
 /// checks to see if a given function call is allowable according to the capabilities
 /// that have been registered to callers by looking for grants in the chain.
+/// Counts the call as a use of the grant if the grant has a use limit, and only allows it
+/// if the grant still had a use left when it got counted.
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 pub fn check_capability(context: Arc<Context>, fn_call: &ZomeFnCall) -> bool {
     let maybe_grant = get_grant(&context.clone(), &fn_call.cap_token());
     match maybe_grant {
         None => false,
-        Some(grant) => verify_grant(context, &grant, fn_call),
+        Some(grant) => {
+            if !verify_grant(context.clone(), &grant, fn_call) {
+                return false;
+            }
+            match grant.limits().max_uses {
+                Some(max_uses) => {
+                    let granted =
+                        context.block_on(use_cap_grant(grant.token(), max_uses, context.clone()));
+                    if !granted {
+                        log_debug!(
+                            context,
+                            "actions/check_capability: grant used up by concurrent calls"
+                        );
+                    }
+                    granted
+                }
+                None => true,
+            }
+        }
     }
 }
 
-#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
-pub fn encode_call_data_for_signing<J: Into<JsonString>>(function: &str, parameters: J) -> String {
-    base64::encode(&format!("{}:{}", function, parameters.into()))
+/// UseCapGrant Action Creator
+/// Counts a use of the grant with the given token unless it got used max_uses times already.
+///
+/// Returns a future that resolves to true if the use got counted.
+pub async fn use_cap_grant(token: Address, max_uses: u32, context: Arc<Context>) -> bool {
+    let action_wrapper = ActionWrapper::new(Action::UseCapGrant((token, max_uses)));
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+    UseCapGrantFuture {
+        context,
+        action: action_wrapper,
+        id: ProcessUniqueId::new(),
+    }
+    .await
 }
 
 // temporary function to create a mock signature of for a zome call cap request
@@ -209,7 +254,14 @@ pub fn verify_call_sig<J: Into<JsonString>>(
     parameters: J,
 ) -> bool {
     let what_was_signed = encode_call_data_for_signing(function, parameters);
-    provenance.verify(what_was_signed).unwrap()
+    provenance.verify(what_was_signed).unwrap_or_else(|err| {
+        warn!(
+            "could not verify zome call signature of {}: {}",
+            provenance.source(),
+            err
+        );
+        false
+    })
 }
 
 /// creates a capability request for a zome call by signing the function name and parameters
@@ -261,6 +313,10 @@ pub fn verify_grant(context: Arc<Context>, grant: &CapTokenGrant, fn_call: &Zome
         return false;
     }
 
+    if !verify_grant_limits(context.clone(), grant) {
+        return false;
+    }
+
     if !verify_call_sig(
         &fn_call.cap.provenance,
         &fn_call.fn_name,
@@ -295,6 +351,36 @@ pub fn verify_grant(context: Arc<Context>, grant: &CapTokenGrant, fn_call: &Zome
     }
 }
 
+/// verifies that the grant is within its validity period and has uses left
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn verify_grant_limits(context: Arc<Context>, grant: &CapTokenGrant) -> bool {
+    let limits = grant.limits();
+    let now = Iso8601::from(chrono::Utc::now().timestamp());
+    if !limits.is_active_at(&now) {
+        log_debug!(
+            context,
+            "actions/verify_grant: grant not valid at {}: {:?}",
+            now,
+            limits
+        );
+        return false;
+    }
+
+    let uses = context
+        .state()
+        .map(|state| state.nucleus().cap_grant_uses(&grant.token()))
+        .unwrap_or(0);
+    if !limits.allows_use_after(uses) {
+        log_debug!(
+            context,
+            "actions/verify_grant: grant used up after {} uses",
+            uses
+        );
+        return false;
+    }
+    true
+}
+
 use std::time::Instant;
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 pub fn spawn_zome_function(context: Arc<Context>, zome_call: ZomeFnCall) {
@@ -376,10 +462,53 @@ impl Future for CallResultFuture {
     }
 }
 
+/// UseCapGrantFuture resolves to whether the use of the grant got counted.
+/// Tracks the state for a response to its ActionWrapper
+pub struct UseCapGrantFuture {
+    context: Arc<Context>,
+    action: ActionWrapper,
+    id: ProcessUniqueId,
+}
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+impl Future for UseCapGrantFuture {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context) -> Poll<Self::Output> {
+        if self
+            .context
+            .action_channel_error("UseCapGrantFuture")
+            .is_some()
+        {
+            return Poll::Ready(false);
+        }
+        self.context
+            .register_waker(self.id.clone(), cx.waker().clone());
+        if let Some(state) = self.context.try_state() {
+            match state.nucleus().cap_grant_use_responses.get(&self.action) {
+                Some(granted) => {
+                    dispatch_action(
+                        self.context.action_channel(),
+                        ActionWrapper::new(Action::ClearActionResponse(
+                            self.action.id().to_string(),
+                        )),
+                    );
+                    self.context.unregister_waker(self.id.clone());
+                    Poll::Ready(*granted)
+                }
+                None => Poll::Pending,
+            }
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
     use crate::{
+        agent::actions::commit::commit_entry,
         context::Context,
         instance::tests::*,
         nucleus::{actions::tests::test_dna, tests::*},
@@ -388,7 +517,8 @@ pub mod tests {
     use holochain_core_types::{
         dna::capabilities::CapabilityRequest,
         entry::{
-            cap_entries::{CapFunctions, CapTokenGrant, CapabilityType},
+            cap_entries::{CapFunctions, CapGrantLimits, CapTokenGrant, CapabilityType},
+            deletion_entry::DeletionEntry,
             Entry,
         },
         signature::Signature,
@@ -429,6 +559,14 @@ pub mod tests {
         assert!(!verify_call_sig(&bad_provenance, "func", "{}"));
     }
 
+    #[test]
+    fn test_call_signatures_from_malformed_sources() {
+        let context = test_context("alice", None);
+        let call_sig = make_call_sig(context, "func", "{}");
+        let provenance = Provenance::new(Address::from("not an agent key"), call_sig);
+        assert!(!verify_call_sig(&provenance, "func", "{}"));
+    }
+
     #[test]
     fn test_make_cap_request_for_call() {
         let context = test_context("alice", None);
@@ -592,4 +730,61 @@ pub mod tests {
             &zome_call_valid(context2.clone(), &token)
         ));
     }
+
+    #[test]
+    fn test_get_grant_after_revocation() {
+        let dna = test_dna();
+        let (_instance, context) =
+            test_instance_and_context(dna, None).expect("Could not initialize test instance");
+
+        let grant = CapTokenGrant::create(
+            "foo",
+            CapabilityType::Transferable,
+            None,
+            CapFunctions::new(),
+        )
+        .unwrap();
+        let grant_addr = context
+            .block_on(author_entry(
+                &Entry::CapTokenGrant(grant.clone()),
+                None,
+                &context,
+                &vec![],
+            ))
+            .unwrap()
+            .address();
+        assert_eq!(get_grant(&context, &grant_addr), Some(grant));
+
+        let deletion = Entry::Deletion(DeletionEntry::new(grant_addr.clone()));
+        context
+            .block_on(commit_entry(deletion, Some(grant_addr.clone()), &context))
+            .unwrap();
+        assert_eq!(get_grant(&context, &grant_addr), None);
+    }
+
+    #[test]
+    fn test_verify_grant_limits() {
+        let context = test_context("alice", None);
+        let grant = CapTokenGrant::create(
+            "foo",
+            CapabilityType::Transferable,
+            None,
+            CapFunctions::new(),
+        )
+        .unwrap();
+        assert!(verify_grant_limits(context.clone(), &grant));
+
+        let expired = grant
+            .clone()
+            .with_limits(CapGrantLimits::new().with_not_after(Iso8601::from(0)));
+        assert!(!verify_grant_limits(context.clone(), &expired));
+
+        let not_yet_valid = grant
+            .clone()
+            .with_limits(CapGrantLimits::new().with_not_before(Iso8601::from(4_102_444_800i64)));
+        assert!(!verify_grant_limits(context.clone(), &not_yet_valid));
+
+        let used_up = grant.with_limits(CapGrantLimits::new().with_max_uses(0));
+        assert!(!verify_grant_limits(context, &used_up));
+    }
 }
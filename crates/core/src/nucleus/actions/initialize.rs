@@ -17,12 +17,15 @@ use holochain_core_types::{
         Entry,
     },
     error::HolochainError,
+    signature::Signature,
 };
 use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
 
 use crate::instance::dispatch_action;
 use snowflake::ProcessUniqueId;
 use std::{pin::Pin, sync::Arc, time::*};
+use uuid::Uuid;
 
 /// Initialization is the value returned by successful initialization of a DNA instance
 /// this consists of any public tokens that were granted for use by the container to
@@ -107,10 +110,32 @@ pub async fn initialize_chain(
 
     let ewh = create_entry_with_header_for_header(&context.state().unwrap(), dna_header)?;
     let entry_aspect = EntryAspect::Content(ewh.entry, ewh.header);
-    hold_aspect_no_ack(&ProcessUniqueId::new(), entry_aspect, context.clone()).await?;
-
-    // Commit AgentId to chain
-    let agent_id_entry = Entry::AgentId(context_clone.agent_id.clone());
+    hold_aspect_no_ack(&Uuid::new_v4(), entry_aspect, context.clone()).await?;
+
+    // Commit AgentId to chain, together with the signed proof that we may join the network
+    let agent_id = match context_clone.membrane_proof {
+        Some(ref membrane_proof) => {
+            let signature = match context_clone
+                .conductor_api
+                .execute(membrane_proof.clone(), CryptoMethod::Sign)
+            {
+                Ok(signature) => Signature::from(signature),
+                Err(error) => {
+                    dispatch_error_result(&context_clone, error.clone());
+                    return Err(HolochainError::InitializationFailed(format!(
+                        "Error signing membrane proof: {:?}",
+                        error
+                    )));
+                }
+            };
+            context_clone
+                .agent_id
+                .clone()
+                .with_membrane_proof(membrane_proof.clone(), signature)
+        }
+        None => context_clone.agent_id.clone(),
+    };
+    let agent_id_entry = Entry::AgentId(agent_id);
     let agent_id_commit = commit_entry(agent_id_entry.clone(), None, &context_clone).await;
 
     // Let initialization fail if AgentId could not be committed.
@@ -128,11 +153,11 @@ pub async fn initialize_chain(
 
         // mark the entry and it's header as held in the dht store because we always hold ourselves.
         let entry_aspect = EntryAspect::Content(agent_id_entry, agent_id_header.clone());
-        hold_aspect_no_ack(&ProcessUniqueId::new(), entry_aspect, context.clone()).await?;
+        hold_aspect_no_ack(&Uuid::new_v4(), entry_aspect, context.clone()).await?;
 
         let ewh = create_entry_with_header_for_header(&context.state().unwrap(), agent_id_header)?;
         let entry_aspect = EntryAspect::Content(ewh.entry, ewh.header);
-        hold_aspect_no_ack(&ProcessUniqueId::new(), entry_aspect, context.clone()).await?;
+        hold_aspect_no_ack(&Uuid::new_v4(), entry_aspect, context.clone()).await?;
     }
 
     let mut cap_functions = CapFunctions::new();
@@ -189,7 +214,7 @@ pub async fn initialize_chain(
                 let ewh =
                     create_entry_with_header_for_header(&context.state().unwrap(), grant_header)?;
                 let entry_aspect = EntryAspect::Content(ewh.entry, ewh.header);
-                hold_aspect_no_ack(&ProcessUniqueId::new(), entry_aspect, context.clone()).await?;
+                hold_aspect_no_ack(&Uuid::new_v4(), entry_aspect, context.clone()).await?;
 
                 log_debug!(context, "initialize: created public token: {:?}", addr);
                 Some(addr)
@@ -307,3 +332,31 @@ impl Future for InitializationFuture {
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        instance::{tests::test_context_with_memory_network, Instance},
+        nucleus::{actions::tests::test_dna, validation::validate_membrane_proof},
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn genesis_commits_the_membrane_proof_with_the_agent_id() {
+        let mut context = (*test_context_with_memory_network("jane", None)).clone();
+        context.membrane_proof = Some(String::from("let me in"));
+        let context = Arc::new(context);
+        let mut instance = Instance::new(context.clone());
+        instance
+            .initialize(Some(test_dna()), context)
+            .expect("Could not initialize instance");
+
+        let agent_id = instance
+            .state()
+            .agent()
+            .get_agent()
+            .expect("No agent entry on the chain");
+        assert_eq!(agent_id.membrane_proof, Some(String::from("let me in")));
+        assert_eq!(validate_membrane_proof(&agent_id), Ok(()));
+    }
+}
@@ -58,6 +58,14 @@ pub async fn run_validation_callback(
                         panic!(error_string)
                     }
                 }
+                // The callback could not finish within the limits of this instance.
+                // The entry doesn't get committed or held, but it isn't known to be invalid
+                // either since the limits are local and wall-clock time depends on the load.
+                Err(HolochainError::WasmLimitExceeded(reason)) => {
+                    Err(ValidationError::Error(HolochainError::WasmLimitExceeded(
+                        format!("Validation callback exceeded WASM limits: {}", reason),
+                    )))
+                }
                 Err(error) => panic!(error.to_string()), // same here
             }
         },
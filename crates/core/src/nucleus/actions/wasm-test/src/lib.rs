@@ -17,7 +17,7 @@ use hdk::holochain_core_types::{
     validation::EntryValidationData
 };
 
-use hdk::holochain_json_api::{error::JsonError, json::JsonString};
+use hdk::holochain_json_api::{error::JsonError, json::{JsonString, RawString}};
 
 #[derive(Serialize, Deserialize, DefaultJson, Debug,Clone)]
 struct TestEntryType {
@@ -158,7 +158,14 @@ define_zome! {
     }
 
     validate_agent: |validation_data : EntryValidationData::<AgentId>| {
-        Ok(())
+        // Tests of membrane proofs put the proof they expect into the DNA properties
+        match (validation_data, hdk::property("membrane_proof")) {
+            (EntryValidationData::Create{entry, ..}, Ok(expected_proof)) => {
+                (entry.membrane_proof.map(|proof| JsonString::from(RawString::from(proof))) == Some(expected_proof))
+                .ok_or_else(|| "Invalid membrane proof".to_string())
+            }
+            _ => Ok(())
+        }
     }
 
     functions: [
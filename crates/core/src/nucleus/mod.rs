@@ -20,6 +20,7 @@ use holochain_json_api::json::JsonString;
 use holochain_persistence_api::cas::content::Address;
 use snowflake;
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// Struct holding data for tracing the call of an HDK function from a zome function
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
@@ -33,7 +34,9 @@ pub type HdkFnCallResult = Result<JsonString, String>;
 /// Struct holding data for requesting the execution of a Zome function (QueueZomeFunctionCall Action)
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
 pub struct ZomeFnCall {
-    id: snowflake::ProcessUniqueId,
+    /// Bundles opened by the call are tied to this ID and can outlive the process
+    /// through the journal, so it has to be unique across restarts
+    id: Uuid,
     pub zome_name: String,
     pub cap: CapabilityRequest,
     pub fn_name: String,
@@ -50,7 +53,7 @@ impl ZomeFnCall {
         ZomeFnCall {
             // @TODO can we defer to the ActionWrapper id?
             // @see https://github.com/holochain/holochain-rust/issues/198
-            id: snowflake::ProcessUniqueId::new(),
+            id: Uuid::new_v4(),
             zome_name: zome.to_string(),
             cap,
             fn_name: function.to_string(),
@@ -84,7 +87,7 @@ impl ZomeFnCall {
         self.cap.cap_token.clone()
     }
 
-    pub fn id(&self) -> snowflake::ProcessUniqueId {
+    pub fn id(&self) -> Uuid {
         self.id
     }
 }
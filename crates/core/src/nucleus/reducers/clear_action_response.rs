@@ -0,0 +1,22 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    nucleus::state::NucleusState,
+    state::State,
+};
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn reduce_clear_action_response(
+    nucleus_state: &mut NucleusState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let id = unwrap_to!(action => Action::ClearActionResponse);
+
+    nucleus_state.cap_grant_use_responses = nucleus_state
+        .cap_grant_use_responses
+        .iter()
+        .filter(|(action, _)| action.id() != id)
+        .cloned()
+        .collect();
+}
@@ -1,3 +1,4 @@
+pub mod clear_action_response;
 pub mod clear_zome_function_call;
 pub mod init_application;
 pub mod queue_zome_function_call;
@@ -5,11 +6,13 @@ pub mod return_initialization_result;
 pub mod return_zome_function_result;
 pub mod trace_invoke_hdk_function;
 pub mod trace_return_hdk_function;
+pub mod use_cap_grant;
 
 use crate::{
     action::{Action, ActionWrapper, NucleusReduceFn},
     nucleus::{
         reducers::{
+            clear_action_response::reduce_clear_action_response,
             clear_zome_function_call::reduce_clear_zome_function_call,
             init_application::reduce_initialize_chain,
             queue_zome_function_call::reduce_queue_zome_function_call,
@@ -17,6 +20,7 @@ use crate::{
             return_zome_function_result::reduce_return_zome_function_result,
             trace_invoke_hdk_function::reduce_trace_invoke_hdk_function,
             trace_return_hdk_function::reduce_trace_return_hdk_function,
+            use_cap_grant::reduce_use_cap_grant,
         },
         state::NucleusState,
     },
@@ -28,6 +32,7 @@ use std::sync::Arc;
 /// Maps incoming action to the correct reducer
 fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NucleusReduceFn> {
     match action_wrapper.action() {
+        Action::ClearActionResponse(_) => Some(reduce_clear_action_response),
         Action::ClearZomeFunctionCall(_) => Some(reduce_clear_zome_function_call),
         Action::ReturnInitializationResult(_) => Some(reduce_return_initialization_result),
         Action::InitializeChain(_) => Some(reduce_initialize_chain),
@@ -35,6 +40,7 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NucleusReduceFn> {
         Action::QueueZomeFunctionCall(_) => Some(reduce_queue_zome_function_call),
         Action::TraceInvokeHdkFunction(_) => Some(reduce_trace_invoke_hdk_function),
         Action::TraceReturnHdkFunction(_) => Some(reduce_trace_return_hdk_function),
+        Action::UseCapGrant(_) => Some(reduce_use_cap_grant),
         _ => None,
     }
 }
@@ -0,0 +1,59 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    nucleus::state::NucleusState,
+    state::State,
+};
+
+/// Reduce UseCapGrant Action.
+/// Increments the number of uses of the given capability grant if it has uses left.
+/// Checking and counting happen here in one go, so concurrent calls can't overuse a grant.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn reduce_use_cap_grant(
+    nucleus_state: &mut NucleusState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (token, max_uses) = unwrap_to!(action => Action::UseCapGrant);
+    let uses = nucleus_state
+        .cap_grant_uses
+        .entry(token.clone())
+        .or_insert(0);
+    let granted = *uses < *max_uses;
+    if granted {
+        *uses += 1;
+    }
+    nucleus_state
+        .cap_grant_use_responses
+        .insert(action_wrapper.clone(), granted);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::test_context, nucleus::state::tests::test_nucleus_state, state::test_store,
+    };
+    use holochain_persistence_api::cas::content::Address;
+
+    #[test]
+    fn test_reduce_use_cap_grant() {
+        let context = test_context("jimmy", None);
+        let store = test_store(context);
+        let mut state = test_nucleus_state();
+        let token = Address::from("token");
+        assert_eq!(state.cap_grant_uses(&token), 0);
+
+        let uses: Vec<bool> = (0..3)
+            .map(|_| {
+                let action_wrapper = ActionWrapper::new(Action::UseCapGrant((token.clone(), 2)));
+                reduce_use_cap_grant(&mut state, &store, &action_wrapper);
+                state.cap_grant_use_responses[&action_wrapper]
+            })
+            .collect();
+
+        assert_eq!(uses, vec![true, true, false]);
+        assert_eq!(state.cap_grant_uses(&token), 2);
+        assert_eq!(state.cap_grant_uses(&Address::from("other")), 0);
+    }
+}
@@ -1,10 +1,13 @@
 use crate::{
+    action::ActionWrapper,
     dht::pending_validations::ValidatingWorkflow,
     nucleus::{actions::initialize::Initialization, HdkFnCall, HdkFnCallResult, ZomeFnCall},
 };
 use holochain_core_types::{dna::Dna, error::HolochainError};
 
-use crate::{state::StateWrapper, wasm_engine::api::ZomeApiFunction};
+use crate::{
+    persister::SNAPSHOT_FORMAT_VERSION, state::StateWrapper, wasm_engine::api::ZomeApiFunction,
+};
 use holochain_json_api::{
     error::{JsonError, JsonResult},
     json::JsonString,
@@ -97,6 +100,8 @@ impl PendingValidationKey {
 pub struct NucleusState {
     // Persisted fields:
     pub status: NucleusStatus,
+    /// Number of zome calls each capability grant (by token) with a use limit was used for
+    pub cap_grant_uses: HashMap<Address, u32>,
 
     // Transient fields:
     pub dna: Option<Dna>, //DNA is transient here because it is stored in the chain and gets
@@ -105,6 +110,8 @@ pub struct NucleusState {
     pub running_zome_calls: HashSet<ZomeFnCall>,
     pub hdk_function_calls: HashMap<ZomeFnCall, ZomeFnCallState>,
     pub zome_call_results: HashMap<ZomeFnCall, Result<JsonString, HolochainError>>,
+    /// Whether a UseCapGrant action got the use it asked for, until its response got cleared
+    pub cap_grant_use_responses: HashMap<ActionWrapper, bool>,
 }
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
@@ -113,10 +120,12 @@ impl NucleusState {
         NucleusState {
             dna: None,
             status: NucleusStatus::New,
+            cap_grant_uses: HashMap::new(),
             queued_zome_calls: VecDeque::new(),
             running_zome_calls: HashSet::new(),
             zome_call_results: HashMap::new(),
             hdk_function_calls: HashMap::new(),
+            cap_grant_use_responses: HashMap::new(),
         }
     }
 
@@ -155,17 +164,31 @@ impl NucleusState {
     pub fn status(&self) -> NucleusStatus {
         self.status.clone()
     }
+    pub fn cap_grant_uses(&self, token: &Address) -> u32 {
+        self.cap_grant_uses.get(token).cloned().unwrap_or(0)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, DefaultJson)]
 pub struct NucleusStateSnapshot {
     pub status: NucleusStatus,
+    #[serde(default)]
+    pub cap_grant_uses: std::collections::HashMap<Address, u32>,
+    #[serde(default)]
+    pub format_version: u32,
 }
 
 impl From<&StateWrapper> for NucleusStateSnapshot {
     fn from(state: &StateWrapper) -> Self {
         NucleusStateSnapshot {
             status: state.nucleus().status(),
+            cap_grant_uses: state
+                .nucleus()
+                .cap_grant_uses
+                .iter()
+                .map(|(token, uses)| (token.clone(), *uses))
+                .collect(),
+            format_version: SNAPSHOT_FORMAT_VERSION,
         }
     }
 }
@@ -175,10 +198,12 @@ impl From<NucleusStateSnapshot> for NucleusState {
         NucleusState {
             dna: None,
             status: snapshot.status,
+            cap_grant_uses: snapshot.cap_grant_uses.into_iter().collect(),
             queued_zome_calls: VecDeque::new(),
             running_zome_calls: HashSet::new(),
             zome_call_results: HashMap::new(),
             hdk_function_calls: HashMap::new(),
+            cap_grant_use_responses: HashMap::new(),
         }
     }
 }
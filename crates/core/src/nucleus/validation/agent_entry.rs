@@ -9,8 +9,10 @@ use crate::{
 use holochain_core_types::{
     agent::AgentId,
     entry::Entry,
+    signature::{Provenance, Signature},
     validation::{EntryValidationData, ValidationData},
 };
+use holochain_dpki::utils::Verify;
 use holochain_persistence_api::cas::content::AddressableContent;
 use holochain_wasm_utils::api_serialization::validation::AgentIdValidationArgs;
 
@@ -26,6 +28,8 @@ pub async fn validate_agent_entry(
     let dna = context.get_dna().expect("Callback called without DNA set!");
 
     let agent_id = unwrap_to!(entry => Entry::AgentId);
+    validate_enc_key(agent_id)?;
+    validate_membrane_proof(agent_id)?;
 
     let params = AgentIdValidationArgs {
         validation_data: EntryValidationData::<AgentId>::Create {
@@ -61,3 +65,114 @@ pub async fn validate_agent_entry(
         ))
     }
 }
+
+/// Checks that the agent signed its encrypting key, if it has one.
+/// Otherwise anybody serving the AgentId could swap in their own key and have
+/// the entries meant for this agent encrypted to them.
+pub fn validate_enc_key(agent_id: &AgentId) -> ValidationResult {
+    let enc_key = match agent_id.pub_enc_key {
+        Some(ref enc_key) => enc_key,
+        None => return Ok(()),
+    };
+    let signature = agent_id.enc_key_signature.clone().ok_or_else(|| {
+        ValidationError::Fail("Encrypting key of agent is not signed".to_string())
+    })?;
+    if signed_by(agent_id, enc_key, signature) {
+        Ok(())
+    } else {
+        Err(ValidationError::Fail(format!(
+            "Encrypting key of agent {} is not signed by its signing key",
+            agent_id.address()
+        )))
+    }
+}
+
+/// Checks that the agent signed its membrane proof, if it has one, before the
+/// DNA gets to see it. Otherwise a node relaying the AgentId could replace the
+/// proof with one of its own.
+pub fn validate_membrane_proof(agent_id: &AgentId) -> ValidationResult {
+    let membrane_proof = match agent_id.membrane_proof {
+        Some(ref membrane_proof) => membrane_proof,
+        None => return Ok(()),
+    };
+    let signature = agent_id.membrane_proof_signature.clone().ok_or_else(|| {
+        ValidationError::Fail("Membrane proof of agent is not signed".to_string())
+    })?;
+    if signed_by(agent_id, membrane_proof, signature) {
+        Ok(())
+    } else {
+        Err(ValidationError::Fail(format!(
+            "Membrane proof of agent {} is not signed by its signing key",
+            agent_id.address()
+        )))
+    }
+}
+
+fn signed_by(agent_id: &AgentId, data: &str, signature: Signature) -> bool {
+    Provenance::new(agent_id.address(), signature)
+        .verify(data.to_string())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nucleus::actions::tests::{instance_by_name, test_dna};
+    use serde_json::json;
+    use test_utils::mock_signing::{mock_signer, registered_test_agent};
+
+    #[test]
+    fn encrypting_keys_need_the_signature_of_their_agent() {
+        let agent_id = registered_test_agent("alice");
+        assert_eq!(validate_enc_key(&agent_id), Ok(()));
+
+        let mut swapped = agent_id.clone();
+        swapped.pub_enc_key = registered_test_agent("mallory").pub_enc_key;
+        assert!(validate_enc_key(&swapped).is_err());
+
+        let mut unsigned = agent_id;
+        unsigned.enc_key_signature = None;
+        assert!(validate_enc_key(&unsigned).is_err());
+    }
+
+    #[test]
+    fn agents_need_the_membrane_proof_the_dna_asks_for() {
+        let mut dna = test_dna();
+        // makes the test zome's agent validation require this proof
+        dna.properties = json!({ "membrane_proof": "let me in" });
+        let (_instance, context) = instance_by_name("jill", dna, None);
+
+        let jane = registered_test_agent("jane");
+        let validate = |agent_id: AgentId| {
+            context.block_on(validate_agent_entry(
+                Entry::AgentId(agent_id),
+                ValidationData::default(),
+                &context,
+            ))
+        };
+        let with_proof = |proof: &str| {
+            let signature = Signature::from(mock_signer(proof.to_string(), &jane));
+            jane.clone()
+                .with_membrane_proof(proof.to_string(), signature)
+        };
+        assert!(validate(with_proof("let me in")).is_ok());
+        assert!(validate(with_proof("let me in, please")).is_err());
+        assert!(validate(jane.clone()).is_err());
+    }
+
+    #[test]
+    fn membrane_proofs_need_the_signature_of_their_agent() {
+        let jane = registered_test_agent("jane");
+        let signature = Signature::from(mock_signer("let me in".to_string(), &jane));
+        let agent_id = jane.with_membrane_proof("let me in".to_string(), signature);
+        assert_eq!(validate_membrane_proof(&agent_id), Ok(()));
+
+        let mut replaced = agent_id.clone();
+        replaced.membrane_proof = Some("let mallory in".to_string());
+        assert!(validate_membrane_proof(&replaced).is_err());
+
+        let mut unsigned = agent_id;
+        unsigned.membrane_proof_signature = None;
+        assert!(validate_membrane_proof(&unsigned).is_err());
+    }
+}
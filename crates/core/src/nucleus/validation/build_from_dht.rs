@@ -104,6 +104,8 @@ pub(crate) async fn try_make_validation_package_dht(
     log_debug!(context, "Chain headers obtained successfully");
 
     let mut package = ValidationPackage::only_header(entry_header.clone());
+    // Holders need the headers to check that the chain was not closed before the entry
+    package.source_chain_headers = Some(chain_headers.clone());
 
     match validation_package_definition {
         ValidationPackageDefinition::Entry => {
@@ -114,10 +116,9 @@ pub(crate) async fn try_make_validation_package_dht(
                 Some(public_chain_entries_from_headers_dht(context.clone(), &chain_headers).await?);
         }
         ValidationPackageDefinition::ChainHeaders => {
-            package.source_chain_headers = Some(chain_headers)
+            // the headers are already part of every package
         }
         ValidationPackageDefinition::ChainFull => {
-            package.source_chain_headers = Some(chain_headers.clone());
             package.source_chain_entries =
                 Some(public_chain_entries_from_headers_dht(context.clone(), &chain_headers).await?);
         }
@@ -0,0 +1,94 @@
+use crate::{
+    context::Context,
+    nucleus::validation::{ValidationContext, ValidationError, ValidationResult},
+};
+use holochain_core_types::{
+    chain_header::ChainHeader,
+    entry::{entry_type::EntryType, Entry},
+    validation::ValidationData,
+};
+use holochain_persistence_api::cas::content::AddressableContent;
+use std::sync::Arc;
+
+fn is_chain_migrate(header: &ChainHeader) -> bool {
+    *header.entry_type() == EntryType::ChainMigrate
+}
+
+/// Rejects every entry that comes after a ChainMigrate entry on its source chain.
+///
+/// When authoring, the agent state knows if the local chain got closed. When holding,
+/// the source chain headers of the validation package get checked. Packages of entries
+/// that only need their header don't carry any, so holders can't tell and let them pass.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn validate_chain_not_closed(
+    validation_data: &ValidationData,
+    context: &Arc<Context>,
+    validation_context: &ValidationContext,
+) -> ValidationResult {
+    let closed = match validation_context {
+        ValidationContext::Authoring => context
+            .state()
+            .ok_or_else(|| ValidationError::Error("No state to check source chain".into()))?
+            .agent()
+            .is_chain_closed(),
+        ValidationContext::Holding => validation_data
+            .package
+            .source_chain_headers
+            .as_ref()
+            .map(|headers| headers.iter().any(is_chain_migrate))
+            .unwrap_or(false),
+    };
+
+    if closed {
+        Err(ValidationError::Fail(
+            "Source chain was closed by a ChainMigrate entry, no further commits allowed"
+                .to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// A ChainMigrate entry has to close a chain of this DNA, has to be authored
+/// by the agent it names and has to point to a different DNA.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn validate_chain_migrate_entry(
+    entry: Entry,
+    validation_data: ValidationData,
+    context: &Arc<Context>,
+) -> ValidationResult {
+    let migrate = unwrap_to!(entry => Entry::ChainMigrate);
+    let dna_address = context
+        .get_dna()
+        .ok_or_else(|| ValidationError::Error("No DNA to validate against".into()))?
+        .address();
+
+    if *migrate.old_dna_address() != dna_address {
+        return Err(ValidationError::Fail(format!(
+            "ChainMigrate closes chain of DNA {} but this is DNA {}",
+            migrate.old_dna_address(),
+            dna_address
+        )));
+    }
+
+    if migrate.new_dna_address() == migrate.old_dna_address() {
+        return Err(ValidationError::Fail(
+            "ChainMigrate must point to a different DNA".to_string(),
+        ));
+    }
+
+    let signed_by_agent = validation_data
+        .package
+        .chain_header
+        .provenances()
+        .iter()
+        .any(|provenance| provenance.source() == *migrate.agent());
+    if !signed_by_agent {
+        return Err(ValidationError::Fail(format!(
+            "ChainMigrate was not signed by its agent {}",
+            migrate.agent()
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::{
+    context::Context,
+    entry::encryption::{decrypt_entry, is_encrypted},
+    nucleus::validation::{app_entry, ValidationError, ValidationResult},
+};
+use holochain_core_types::{
+    entry::{entry_type::AppEntryType, Entry},
+    validation::ValidationData,
+};
+use holochain_persistence_api::cas::content::Address;
+use std::sync::Arc;
+
+/// Entries of types with `Sharing::Encrypted` have to be stored and published encrypted.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn validate_encryption(entry: &Entry, context: &Arc<Context>) -> ValidationResult {
+    let should_be_encrypted = is_encrypted(&entry.entry_type(), context);
+    match entry {
+        Entry::Encrypted(_) if !should_be_encrypted => Err(ValidationError::Fail(
+            "Entry is encrypted but its entry type is not".to_string(),
+        )),
+        Entry::App(_, _) if should_be_encrypted => Err(ValidationError::Fail(
+            "Entry of an encrypted entry type was not encrypted".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Runs the app validation callback on the plaintext of an encrypted entry.
+///
+/// Only recipients can do that, which always includes the author.
+/// Holding nodes that can't decrypt the entry accept it based on what the ciphertext
+/// reveals: its header, provenances and entry type, which got checked before.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn validate_encrypted_entry(
+    entry: Entry,
+    app_entry_type: AppEntryType,
+    context: &Arc<Context>,
+    link: Option<Address>,
+    validation_data: ValidationData,
+) -> ValidationResult {
+    match decrypt_entry(entry, context).map_err(ValidationError::Error)? {
+        plain @ Entry::App(_, _) => {
+            app_entry::validate_app_entry(plain, app_entry_type, context, link, validation_data)
+                .await
+        }
+        _ => Ok(()),
+    }
+}
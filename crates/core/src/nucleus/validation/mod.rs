@@ -1,10 +1,13 @@
-use crate::{context::Context, workflows::get_entry_result::get_entry_with_meta_workflow};
+use crate::{
+    context::Context, entry::encryption::decrypt_entry,
+    workflows::get_entry_result::get_entry_with_meta_workflow,
+};
 use holochain_core_types::{
     chain_header::ChainHeader,
     entry::{entry_type::EntryType, Entry, EntryWithMeta},
     error::HolochainError,
     time::Timeout,
-    validation::{EntryValidationData, ValidationData},
+    validation::{EntryValidationData, ValidationData, ValidationPackage},
 };
 use holochain_persistence_api::cas::content::Address;
 
@@ -13,11 +16,15 @@ use std::sync::Arc;
 mod agent_entry;
 mod app_entry;
 pub mod build_from_dht;
+mod chain_migrate;
+mod encrypted_entry;
 mod header_address;
 mod link_entry;
 mod provenances;
 mod remove_entry;
 
+pub use agent_entry::{validate_enc_key, validate_membrane_proof};
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 /// A failed validation.
 pub enum ValidationError {
@@ -75,7 +82,9 @@ pub enum ValidationContext {
 ///    the validation package.
 /// 2. Validates provenances given in the header by verifying the cryptographic signatures
 ///    against the source agent addresses.
-/// 3. Finally spawns a thread to run the type specific validation callback in a Ribosome.
+/// 3. Rejects the entry if its source chain was already closed by a ChainMigrate entry.
+/// 4. Checks that entries of encrypted entry types are encrypted, and only those.
+/// 5. Finally spawns a thread to run the type specific validation callback in a Ribosome.
 ///
 /// All of this actually happens in the functions of the sub modules. This function is the
 /// main validation entry point and, like a workflow, stays high-level.
@@ -96,24 +105,48 @@ pub async fn validate_entry(
         headers.retain(|header| header.timestamp() < t);
     }
 
+    // let app validation know about forks we've seen in the sources' chains
+    if let Some(state) = context.state() {
+        validation_data.chain_forks = validation_data
+            .sources()
+            .iter()
+            .filter_map(|source| state.dht().get_chain_forks(source).ok())
+            .flatten()
+            .collect();
+    }
+
     header_address::validate_header_address(&entry, &validation_data.package.chain_header)?;
     provenances::validate_provenances(&validation_data)?;
+    chain_migrate::validate_chain_not_closed(&validation_data, context, &validation_context)?;
+    encrypted_entry::validate_encryption(&entry, context)?;
 
     match entry.entry_type() {
         // DNA entries are not validated currently and always valid
         // TODO: Specify when DNA can be commited as an update and how to implement validation of DNA entries then.
         EntryType::Dna => Ok(()),
 
-        EntryType::App(app_entry_type) => {
-            app_entry::validate_app_entry(
-                entry.clone(),
-                app_entry_type.clone(),
-                context,
-                link,
-                validation_data,
-            )
-            .await
-        }
+        EntryType::App(app_entry_type) => match entry {
+            Entry::Encrypted(_) => {
+                encrypted_entry::validate_encrypted_entry(
+                    entry.clone(),
+                    app_entry_type.clone(),
+                    context,
+                    link,
+                    validation_data,
+                )
+                .await
+            }
+            _ => {
+                app_entry::validate_app_entry(
+                    entry.clone(),
+                    app_entry_type.clone(),
+                    context,
+                    link,
+                    validation_data,
+                )
+                .await
+            }
+        },
 
         EntryType::LinkAdd => {
             link_entry::validate_link_entry(
@@ -151,10 +184,31 @@ pub async fn validate_entry(
         // chain headers always pass for now. In future this should check that the entry is valid
         EntryType::ChainHeader => Ok(()),
 
+        EntryType::ChainMigrate => {
+            chain_migrate::validate_chain_migrate_entry(entry.clone(), validation_data, context)
+        }
+
         _ => Err(ValidationError::NotImplemented),
     }
 }
 
+/// Checks that the entry belongs to the header and that every provenance of the header
+/// signed it, i.e. that the entry provably comes from the agents the header lists.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn validate_signatures(entry: &Entry, header: &ChainHeader) -> ValidationResult {
+    header_address::validate_header_address(entry, header)?;
+    validate_header_signatures(header)
+}
+
+/// Checks that every provenance of the header signed the entry it points to.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn validate_header_signatures(header: &ChainHeader) -> ValidationResult {
+    provenances::validate_provenances(&ValidationData {
+        package: ValidationPackage::only_header(header.clone()),
+        ..Default::default()
+    })
+}
+
 /// interprets the validation error from validate_entry. for use by the various workflows
 pub fn process_validation_err(
     src: &str,
@@ -181,6 +235,16 @@ pub fn process_validation_err(
             );
             HolochainError::from(err)
         }
+        ValidationError::Error(HolochainError::WasmLimitExceeded(ref reason)) => {
+            log_warn!(
+                context,
+                "workflow/{}: Entry {} could not be validated within the WASM limits of this instance: {}",
+                src,
+                addr,
+                reason,
+            );
+            HolochainError::from(err)
+        }
         ValidationError::Error(HolochainError::Timeout(e)) => {
             log_warn!(
                 context,
@@ -217,7 +281,8 @@ pub fn entry_to_validation_data(
                 get_entry_with_header(context.clone(), &link_update)
                     .map(|entry_with_header| {
                         Ok(EntryValidationData::Modify {
-                            old_entry: entry_with_header.0.entry.clone(),
+                            // the app validation only ever sees the plaintext of encrypted entries
+                            old_entry: decrypt_entry(entry_with_header.0.entry, &context)?,
                             new_entry: entry.clone(),
                             old_entry_header: entry_with_header.1,
                             validation_data: validation_data.clone(),
@@ -239,10 +304,10 @@ pub fn entry_to_validation_data(
             }),
         Entry::Deletion(deletion_entry) => {
             let deletion_address = deletion_entry.deleted_entry_address().clone();
-            get_entry_with_header(context, &deletion_address)
+            get_entry_with_header(context.clone(), &deletion_address)
                 .map(|entry_with_header| {
                     Ok(EntryValidationData::Delete {
-                        old_entry: entry_with_header.0.entry.clone(),
+                        old_entry: decrypt_entry(entry_with_header.0.entry, &context)?,
                         old_entry_header: entry_with_header.1,
                         validation_data: validation_data.clone(),
                     })
@@ -1,16 +1,39 @@
 use crate::nucleus::validation::{ValidationError, ValidationResult};
 use boolinator::Boolinator;
-use holochain_core_types::validation::ValidationData;
+use holochain_core_types::{
+    countersigning::countersigning_payload, signature::Provenance, validation::ValidationData,
+};
 use holochain_dpki::utils::Verify;
+use holochain_persistence_api::cas::content::Address;
 
+/// Every provenance has to sign the entry address.
+/// Parties of a countersigned entry sign it together with the list of all parties instead,
+/// so those signatures only verify if the header lists exactly the parties that signed.
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 pub fn validate_provenances(validation_data: &ValidationData) -> ValidationResult {
     let header = &validation_data.package.chain_header;
+    let parties: Vec<Address> = header
+        .provenances()
+        .iter()
+        .map(|provenance| provenance.source())
+        .collect();
+    let countersigned = if parties.len() > 1 {
+        Some(countersigning_payload(header.entry_address(), &parties))
+    } else {
+        None
+    };
+    let verify = |provenance: &Provenance| {
+        let has_authored = provenance.verify(header.entry_address().to_string())?;
+        match countersigned {
+            Some(ref payload) if !has_authored => provenance.verify(payload.clone()),
+            _ => Ok(has_authored),
+        }
+    };
     header
         .provenances()
         .iter()
         .map(|provenance| {
-            let maybe_has_authored = provenance.verify(header.entry_address().to_string());
+            let maybe_has_authored = verify(provenance);
             match maybe_has_authored {
                 Err(_) => {
                     Err(ValidationError::Fail(format!(
@@ -31,3 +54,82 @@ pub fn validate_provenances(validation_data: &ValidationData) -> ValidationResul
         .collect::<Result<Vec<()>, ValidationError>>()?;
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::{
+        chain_header::ChainHeader, entry::test_entry, signature::Signature, time::test_iso_8601,
+        validation::ValidationPackage,
+    };
+    use holochain_dpki::keypair::{generate_random_sign_keypair, SigningKeyPair};
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use lib3h_sodium::secbuf::SecBuf;
+
+    fn sign(keys: &mut SigningKeyPair, data: String) -> Provenance {
+        let mut data_buf = SecBuf::with_insecure_from_string(data);
+        let mut signature_buf = keys.sign(&mut data_buf).unwrap();
+        let buf = signature_buf.read_lock();
+        Provenance::new(
+            Address::from(keys.public.clone()),
+            Signature::from(base64::encode(&**buf)),
+        )
+    }
+
+    fn validation_data(provenances: &[Provenance]) -> ValidationData {
+        let entry = test_entry();
+        let header = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            provenances,
+            &None,
+            &None,
+            &None,
+            &test_iso_8601(),
+        );
+        ValidationData {
+            package: ValidationPackage::only_header(header),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn countersigned_entries_need_the_signatures_of_all_listed_parties() {
+        let entry_address = test_entry().address();
+        let mut alice = generate_random_sign_keypair().unwrap();
+        let mut bob = generate_random_sign_keypair().unwrap();
+        let mut carol = generate_random_sign_keypair().unwrap();
+        let parties = vec![
+            Address::from(alice.public.clone()),
+            Address::from(bob.public.clone()),
+        ];
+        let payload = countersigning_payload(&entry_address, &parties);
+        let alice_countersigned = sign(&mut alice, payload.clone());
+        let bob_countersigned = sign(&mut bob, payload);
+
+        assert_eq!(
+            validate_provenances(&validation_data(&[sign(
+                &mut alice,
+                entry_address.to_string()
+            )])),
+            Ok(())
+        );
+        assert_eq!(
+            validate_provenances(&validation_data(&[
+                bob_countersigned.clone(),
+                alice_countersigned.clone()
+            ])),
+            Ok(())
+        );
+        // dropping a party invalidates the signatures of the others
+        assert!(validate_provenances(&validation_data(&[alice_countersigned.clone()])).is_err());
+        // and so does adding one, even with a valid signature
+        let carol_signed = sign(&mut carol, entry_address.to_string());
+        assert!(validate_provenances(&validation_data(&[
+            alice_countersigned,
+            bob_countersigned,
+            carol_signed
+        ]))
+        .is_err());
+    }
+}
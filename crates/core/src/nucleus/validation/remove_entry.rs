@@ -29,6 +29,7 @@ pub async fn validate_remove_entry(
         })?;
     let app_entry_type = match entry_to_delete.clone() {
         Entry::App(app_entry_type, _) => Ok(app_entry_type),
+        Entry::Encrypted(encrypted) => Ok(encrypted.entry_type().clone()),
         _ => Err(ValidationError::Fail(
             "Entry type should be App Type".to_string(),
         )),
@@ -16,14 +16,12 @@ use crate::{
     dht::dht_store::{DhtStoreSnapshot, DHT_STORE_SNAPSHOT_ADDRESS},
     state::StateWrapper,
 };
+use serde_json::Value;
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// trait that defines the persistence functionality that holochain_core requires
 pub trait Persister: Send + Sync {
-    // @TODO how does save/load work with snowflake IDs?
-    // snowflake is only unique across a single process, not a reboot save/load round trip
-    // we'd need real UUIDs for persistant uniqueness
-    // @see https://github.com/holochain/holochain-rust/issues/203
     fn save(&mut self, state: &StateWrapper) -> Result<(), HolochainError>;
     fn load(&self, context: Arc<Context>) -> Result<Option<State>, HolochainError>;
 }
@@ -58,6 +56,8 @@ impl Persister for SimplePersister {
 
         let agent_snapshot: Option<AgentStateSnapshot> = store
             .fetch(&Address::from(AGENT_SNAPSHOT_ADDRESS))?
+            .map(|s: Content| migrate_snapshot(&s))
+            .transpose()?
             .map(|s: Content| {
                 AgentStateSnapshot::try_from_content(&s)
                     .expect("could not load AgentStateSnapshot from content")
@@ -65,6 +65,8 @@ impl Persister for SimplePersister {
 
         let nucleus_snapshot: Option<NucleusStateSnapshot> = store
             .fetch(&Address::from(NUCLEUS_SNAPSHOT_ADDRESS))?
+            .map(|s: Content| migrate_snapshot(&s))
+            .transpose()?
             .map(|s: Content| {
                 NucleusStateSnapshot::try_from_content(&s)
                     .expect("could not load NucleusStateSnapshot from content")
@@ -72,6 +74,8 @@ impl Persister for SimplePersister {
 
         let dht_store_snapshot: Option<DhtStoreSnapshot> = store
             .fetch(&Address::from(DHT_STORE_SNAPSHOT_ADDRESS))?
+            .map(|s: Content| migrate_snapshot(&s))
+            .transpose()?
             .map(|s: Content| {
                 DhtStoreSnapshot::try_from_content(&s)
                     .expect("could not load DhtStoreSnapshot from content")
@@ -91,6 +95,51 @@ impl Persister for SimplePersister {
     }
 }
 
+/// Version of the format the state snapshots get saved in.
+/// Snapshots that were saved before there was a version count as version 0.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Brings a snapshot that was saved in an older format up to SNAPSHOT_FORMAT_VERSION,
+/// one version at a time. Fails for snapshots of a newer version than this one.
+pub fn migrate_snapshot(content: &Content) -> Result<Content, HolochainError> {
+    let mut json: Value = serde_json::from_str(&String::from(content.clone()))?;
+    let version = json
+        .get("format_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version > u64::from(SNAPSHOT_FORMAT_VERSION) {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "State snapshot has format version {} but this version of Holochain only reads up to {}",
+            version, SNAPSHOT_FORMAT_VERSION
+        )));
+    }
+    if version == u64::from(SNAPSHOT_FORMAT_VERSION) {
+        return Ok(content.clone());
+    }
+    for from_version in version..u64::from(SNAPSHOT_FORMAT_VERSION) {
+        match from_version {
+            0 => migrate_snapshot_v0(&mut json),
+            _ => unreachable!(),
+        }
+    }
+    json["format_version"] = Value::from(SNAPSHOT_FORMAT_VERSION);
+    Ok(Content::from_json(&json.to_string()))
+}
+
+/// Version 0 identified pending validations with snowflake IDs, which are only unique
+/// within one process. They get replaced by new UUIDs.
+fn migrate_snapshot_v0(json: &mut Value) {
+    for queue in &["queued_holding_workflows", "in_process_holding_workflows"] {
+        if let Some(Value::Array(items)) = json.get_mut(*queue) {
+            for item in items.iter_mut() {
+                if let Some(pending) = item.get_mut("pending") {
+                    pending["uuid"] = Value::from(Uuid::new_v4().to_string());
+                }
+            }
+        }
+    }
+}
+
 impl SimplePersister {
     pub fn new(storage: Arc<RwLock<dyn ContentAddressableStorage>>) -> Self {
         SimplePersister { storage }
@@ -102,11 +151,21 @@ mod tests {
 
     use self::tempfile::tempdir;
     use crate::{
+        action::{Action, ActionWrapper},
+        dht::{
+            dht_store::DhtStoreSnapshot,
+            pending_validations::{PendingValidationStruct, ValidatingWorkflow},
+        },
         instance::tests::test_context_with_agent_state,
-        persister::{Persister, SimplePersister},
+        network::entry_with_header::EntryWithHeader,
+        persister::{migrate_snapshot, Persister, SimplePersister, SNAPSHOT_FORMAT_VERSION},
     };
-    use std::fs::File;
+    use holochain_core_types::{chain_header::test_chain_header, entry::test_entry};
+    use holochain_persistence_api::cas::content::{AddressableContent, Content};
+    use serde_json::{json, Value};
+    use std::{fs::File, sync::Arc};
     use tempfile;
+    use uuid::Uuid;
 
     #[test]
     fn persistence_round_trip() {
@@ -127,4 +186,44 @@ mod tests {
         // need to fix this so `persitance.load()` takes a networks or something
         assert_ne!(state.network(), state_from_file.network());
     }
+
+    #[test]
+    fn migrates_v0_snapshot_with_snowflake_ids() {
+        let context = test_context_with_agent_state(None);
+        let pending = Arc::new(PendingValidationStruct::new(
+            EntryWithHeader {
+                entry: test_entry(),
+                header: test_chain_header(),
+            },
+            ValidatingWorkflow::HoldEntry,
+        ));
+        let action_wrapper = ActionWrapper::new(Action::QueueHoldingWorkflow((pending, None)));
+        let state = context.state().unwrap().reduce(action_wrapper);
+
+        // turn the snapshot into one that was saved before format versions existed
+        let mut json: Value =
+            serde_json::from_str(&String::from(DhtStoreSnapshot::from(&state).content())).unwrap();
+        json.as_object_mut().unwrap().remove("format_version");
+        json["queued_holding_workflows"][0]["pending"]["uuid"] = json!({"prefix": 0, "offset": 1});
+        assert!(
+            DhtStoreSnapshot::try_from_content(&Content::from_json(&json.to_string())).is_err()
+        );
+
+        let migrated = migrate_snapshot(&Content::from_json(&json.to_string())).unwrap();
+        assert!(DhtStoreSnapshot::try_from_content(&migrated).is_ok());
+        let migrated: Value = serde_json::from_str(&String::from(migrated)).unwrap();
+        assert_eq!(migrated["format_version"], json!(SNAPSHOT_FORMAT_VERSION));
+        let uuid = migrated["queued_holding_workflows"][0]["pending"]["uuid"]
+            .as_str()
+            .unwrap();
+        assert!(Uuid::parse_str(uuid).is_ok());
+    }
+
+    #[test]
+    fn rejects_snapshot_from_newer_version() {
+        let content = Content::from_json(
+            &json!({ "format_version": SNAPSHOT_FORMAT_VERSION + 1 }).to_string(),
+        );
+        assert!(migrate_snapshot(&content).is_err());
+    }
 }
@@ -1,4 +1,5 @@
 pub mod state_dump;
+mod storage_quota;
 mod timeouts;
 
 use crate::{
@@ -34,3 +35,11 @@ pub fn create_state_pruning_callback(
         dispatch_action(context.action_channel(), ActionWrapper::new(Action::Prune));
     }
 }
+
+pub fn create_storage_quota_callback(
+    context: Arc<Context>,
+) -> impl 'static + FnMut() + Sync + Send {
+    move || {
+        storage_quota::check_storage_quota(context.clone());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    dht::dht_store::DhtStore,
+    instance::dispatch_action,
+    network::handler::lists::create_authoring_map,
+};
+use holochain_net::{aspect_map::AspectMap, sharding::entry_distance};
+use holochain_persistence_api::cas::content::Address;
+use std::sync::Arc;
+
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn check_storage_quota(context: Arc<Context>) {
+    let quota = match context.storage_quota {
+        Some(quota) => quota,
+        None => return,
+    };
+    let state = match context.state() {
+        Some(state) => state,
+        None => return,
+    };
+    if state.dht().storage_usage() <= quota {
+        return;
+    }
+
+    let authored = create_authoring_map(context.clone());
+    let evicted = entries_to_evict(
+        &state.dht(),
+        &authored,
+        &context.agent_id.pub_sign_key,
+        quota,
+    );
+    if evicted.is_empty() {
+        log_warn!(
+            context,
+            "storage_quota: Quota of {} bytes exceeded but all held entries are our own",
+            quota
+        );
+        return;
+    }
+    log_debug!(
+        context,
+        "storage_quota: Evicting {} entries to get back within {} bytes",
+        evicted.len(),
+        quota
+    );
+    context.record_evictions(evicted.len());
+    dispatch_action(
+        context.action_channel(),
+        ActionWrapper::new(Action::EvictHeldEntries(evicted)),
+    );
+}
+
+/// The held entries to evict so the rest fit within the quota, furthest from the
+/// agent's location first. Entries with any aspect we authored are never picked.
+pub(crate) fn entries_to_evict(
+    dht: &DhtStore,
+    authored: &AspectMap,
+    agent_id: &str,
+    quota: usize,
+) -> Vec<Address> {
+    let mut candidates: Vec<_> = dht
+        .get_holding_map()
+        .entry_addresses()
+        .filter(|entry_address| authored.per_entry(entry_address).is_none())
+        .map(|entry_address| (entry_distance(agent_id, entry_address), entry_address))
+        .collect();
+    candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let mut usage = dht.storage_usage();
+    let mut evicted = Vec::new();
+    for (_, entry_address) in candidates {
+        if usage <= quota {
+            break;
+        }
+        usage = usage.saturating_sub(dht.entry_storage_usage(entry_address));
+        evicted.push(Address::from(entry_address.clone()));
+    }
+    evicted
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        content_store::{AddContent, GetContent},
+        instance::tests::test_context,
+    };
+    use holochain_core_types::{
+        chain_header::{test_provenances, ChainHeader},
+        entry::{test_entry, test_entry_b, test_entry_c, Entry},
+        network::entry_aspect::EntryAspect,
+        time::Iso8601,
+    };
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use lib3h_protocol::types::EntryHash;
+
+    fn content_aspect(entry: Entry) -> EntryAspect {
+        let header = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &test_provenances("sig"),
+            &None,
+            &None,
+            &None,
+            &Iso8601::from(0),
+        );
+        EntryAspect::Content(entry, header)
+    }
+
+    #[test]
+    fn evicts_furthest_entries_that_are_not_authored() {
+        let context = test_context("bob", None);
+        let agent_id = context.agent_id.pub_sign_key.clone();
+        let mut dht = DhtStore::new(context.dht_storage.clone(), context.eav_storage.clone());
+        let own = content_aspect(test_entry());
+        let mut authored = AspectMap::new();
+        authored.add(&own);
+        dht.add(&test_entry()).unwrap();
+        dht.mark_aspect_as_held(&own);
+        let mut others = vec![test_entry_b(), test_entry_c()];
+        for entry in others.iter() {
+            dht.add(entry).unwrap();
+            dht.mark_aspect_as_held(&content_aspect(entry.clone()));
+        }
+        others.sort_by_key(|entry| {
+            std::cmp::Reverse(entry_distance(&agent_id, &EntryHash::from(entry.address())))
+        });
+        let usage = dht.storage_usage();
+
+        assert!(entries_to_evict(&dht, &authored, &agent_id, usage).is_empty());
+        assert_eq!(
+            entries_to_evict(&dht, &authored, &agent_id, usage - 1),
+            vec![others[0].address()]
+        );
+        assert_eq!(
+            entries_to_evict(&dht, &authored, &agent_id, 0),
+            vec![others[0].address(), others[1].address()]
+        );
+
+        for entry_address in entries_to_evict(&dht, &authored, &agent_id, 0) {
+            dht.evict_entry(&entry_address);
+        }
+        assert!(dht.is_evicted(&others[0].address()));
+        assert!(!dht.is_evicted(&test_entry().address()));
+        assert_eq!(dht.get(&others[0].address()).unwrap(), None);
+        assert_eq!(
+            dht.get(&test_entry().address()).unwrap(),
+            Some(test_entry())
+        );
+        assert_eq!(
+            dht.storage_usage(),
+            dht.entry_storage_usage(&EntryHash::from(test_entry().address()))
+        );
+    }
+}
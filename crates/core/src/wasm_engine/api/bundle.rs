@@ -0,0 +1,149 @@
+use crate::{
+    agent::{
+        actions::bundle::{close_bundle, stage_bundle_entry, start_bundle},
+        bundle::{Bundle, BundledEntry},
+    },
+    context::Context,
+    wasm_engine::{api::ZomeApiResult, Runtime},
+    workflows::{author_bundle::author_bundle, author_entry::author_entry},
+};
+use holochain_core_types::{entry::Entry, error::HolochainError, signature::Provenance};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::bundle::{
+    BundleOnClose, CloseBundleArgs, StartBundleArgs,
+};
+use std::{convert::TryFrom, sync::Arc};
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::StartBundle function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: StartBundleArgs
+/// Returns an HcApiReturnCode as I64
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn invoke_start_bundle(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    let zome_call_data = runtime.zome_call_data()?;
+
+    let args_str = runtime.load_json_string_from_args(&args);
+    let start_bundle_args = match StartBundleArgs::try_from(args_str.clone()) {
+        Ok(args) => args,
+        // Exit on error
+        Err(error) => {
+            log_error!(
+                context,
+                "zome: invoke_start_bundle failed to \
+                 deserialize arguments: {:?} with error {:?}",
+                args_str,
+                error
+            );
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let bundle = Bundle::new(
+        zome_call_data.call.id().to_string(),
+        start_bundle_args.timeout,
+        start_bundle_args.user_param,
+    );
+    let result = context.block_on(start_bundle(bundle, &context)).map(|_| ());
+
+    runtime.store_result(result)
+}
+
+/// ZomeApiFunction::CloseBundle function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: CloseBundleArgs
+/// Returns an HcApiReturnCode as I64
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn invoke_close_bundle(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    let zome_call_id = runtime.zome_call_data()?.call.id().to_string();
+
+    let args_str = runtime.load_json_string_from_args(&args);
+    let close_bundle_args = match CloseBundleArgs::try_from(args_str.clone()) {
+        Ok(args) => args,
+        // Exit on error
+        Err(error) => {
+            log_error!(
+                context,
+                "zome: invoke_close_bundle failed to \
+                 deserialize arguments: {:?} with error {:?}",
+                args_str,
+                error
+            );
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let result = match close_bundle_args.action {
+        BundleOnClose::Discard => context
+            .block_on(close_bundle(zome_call_id, &context))
+            .map(|_| ()),
+        BundleOnClose::Commit => commit_bundle(zome_call_id, &context),
+    };
+
+    runtime.store_result(result)
+}
+
+/// Authors the entries of the open bundle and then removes it from the state.
+/// The bundle gets removed in any case, i.e. a failed commit discards it.
+fn commit_bundle(zome_call_id: String, context: &Arc<Context>) -> Result<(), HolochainError> {
+    let bundle = context
+        .state()
+        .and_then(|state| state.agent().bundle_of_zome_call(&zome_call_id).cloned())
+        .ok_or_else(|| HolochainError::ErrorGeneric("No open bundle to commit".to_string()))?;
+
+    let authored = context.block_on(author_bundle(&bundle, context));
+    let closed = context.block_on(close_bundle(zome_call_id, context));
+    if let Err(ref error) = authored {
+        log_warn!(
+            context,
+            "zome: discarding bundle that could not be committed: {:?}",
+            error
+        );
+    }
+    authored.and(closed).map(|_| ())
+}
+
+/// ID of the running zome function call if it has a bundle open
+fn open_bundle_id(runtime: &Runtime, context: &Arc<Context>) -> Option<String> {
+    let zome_call_id = runtime.zome_call_data().ok()?.call.id().to_string();
+    context
+        .state()?
+        .agent()
+        .bundle_of_zome_call(&zome_call_id)
+        .map(|_| zome_call_id)
+}
+
+/// Authors the given entry, or, if the running zome function call has a bundle open,
+/// stages it in that bundle to be authored when the bundle gets closed.
+/// Returns the address of the entry in both cases.
+#[allow(clippy::ptr_arg)]
+pub fn author_or_stage_entry(
+    runtime: &Runtime,
+    context: &Arc<Context>,
+    entry: &Entry,
+    maybe_link_update_delete: Option<Address>,
+    provenances: &Vec<Provenance>,
+) -> Result<Address, HolochainError> {
+    match open_bundle_id(runtime, context) {
+        Some(zome_call_id) => {
+            let bundled_entry = BundledEntry {
+                entry: entry.clone(),
+                maybe_link_update_delete,
+                provenances: provenances.clone(),
+            };
+            context
+                .block_on(stage_bundle_entry(zome_call_id, bundled_entry, context))
+                .map(|_| entry.address())
+        }
+        None => context
+            .block_on(author_entry(
+                entry,
+                maybe_link_update_delete,
+                context,
+                provenances,
+            ))
+            .map(|result| result.address()),
+    }
+}
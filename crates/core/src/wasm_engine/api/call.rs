@@ -6,12 +6,13 @@ use crate::{
     },
     wasm_engine::{api::ZomeApiResult, Runtime},
 };
-use holochain_core_types::error::HolochainError;
+use holochain_core_types::{dna::capabilities::CapabilityRequest, error::HolochainError};
 use holochain_json_api::json::JsonString;
 use holochain_logging::prelude::*;
 
 use holochain_wasm_utils::api_serialization::{ZomeFnCallArgs, THIS_INSTANCE};
 use jsonrpc_lite::JsonRpc;
+use serde_json::json;
 use snowflake::ProcessUniqueId;
 use std::{convert::TryFrom, sync::Arc};
 use wasmi::{RuntimeArgs, RuntimeValue};
@@ -20,14 +21,19 @@ use wasmi::{RuntimeArgs, RuntimeValue};
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 impl ZomeFnCall {
     fn from_args(context: Arc<Context>, args: ZomeFnCallArgs) -> Self {
-        // TODO we are currently signing the call ourself.  This signature
-        // should have happend at the client and be extracted from the args.
-        let cap_call = make_cap_request_for_call(
-            context,
-            args.cap_token,
-            &args.fn_name,
-            JsonString::from_json(&args.fn_args.clone()),
-        );
+        // Calls without a provenance from the caller get signed by our own agent,
+        // which is who is making the call in that case
+        let cap_call = match args.provenance {
+            Some(provenance) => {
+                CapabilityRequest::new(args.cap_token, provenance.source(), provenance.signature())
+            }
+            None => make_cap_request_for_call(
+                context,
+                args.cap_token,
+                &args.fn_name,
+                JsonString::from_json(&args.fn_args.clone()),
+            ),
+        };
         ZomeFnCall::new(
             &args.zome_name,
             cap_call,
@@ -126,10 +132,21 @@ fn bridge_call(runtime: &mut Runtime, input: ZomeFnCallArgs) -> Result<JsonStrin
     })?;
     let conductor_api = context.conductor_api.clone();
 
-    let params = format!(
-        r#"{{"instance_id":"{}", "zome": "{}", "function": "{}", "args": {}}}"#,
-        input.instance_handle, input.zome_name, input.fn_name, input.fn_args
-    );
+    let mut params = json!({
+        "instance_id": input.instance_handle,
+        "zome": input.zome_name,
+        "function": input.fn_name,
+        "args": serde_json::from_str::<serde_json::Value>(&input.fn_args)?,
+    });
+    // Zomes pass their own public token for public functions of the callee,
+    // in which case the conductor falls back to the callee's public token
+    if context.get_public_token().ok() != Some(input.cap_token.clone()) {
+        params["token"] = json!(input.cap_token);
+    }
+    // Let the callee verify the caller's own signature instead of signing the call itself
+    if let Some(provenance) = input.provenance {
+        params["provenance"] = serde_json::to_value(provenance)?;
+    }
 
     let handler = conductor_api.get().write().unwrap();
 
@@ -207,7 +224,7 @@ pub mod tests {
             Dna,
         },
         entry::{
-            cap_entries::{CapFunctions, CapTokenGrant, CapabilityType},
+            cap_entries::{CapFunctions, CapGrantLimits, CapTokenGrant, CapabilityType},
             Entry,
         },
         error::{DnaError, HolochainError},
@@ -229,6 +246,7 @@ pub mod tests {
             cap_token: Address::from("bad cap_token"),
             fn_name: "fn_name".to_string(),
             fn_args: "fn_args".to_string(),
+            provenance: None,
         };
         serde_json::to_string(&args)
             .expect("args should serialize")
@@ -243,6 +261,7 @@ pub mod tests {
             cap_token: Address::from("test_token"),
             fn_name: test_function_name(),
             fn_args: test_parameters().to_string(),
+            provenance: None,
         };
         serde_json::to_string(&args)
             .expect("args should serialize")
@@ -557,4 +576,80 @@ pub mod tests {
         );
         assert!(check_capability(context.clone(), &zome_call));
     }
+
+    #[test]
+    fn test_check_capability_counts_limited_uses() {
+        let dna = setup_dna_for_test(false);
+        let test_setup = setup_test(dna, "test_check_cap_limited_uses");
+        let context = test_setup.context;
+
+        let mut cap_functions = CapFunctions::new();
+        cap_functions.insert("test_zome".to_string(), vec![String::from("test")]);
+        let grant = CapTokenGrant::create("foo", CapabilityType::Transferable, None, cap_functions)
+            .unwrap()
+            .with_limits(CapGrantLimits::new().with_max_uses(1));
+        let grant_entry = Entry::CapTokenGrant(grant);
+        let grant_addr = context
+            .block_on(author_entry(&grant_entry, None, &context, &vec![]))
+            .unwrap()
+            .address();
+
+        let zome_call = ZomeFnCall::new(
+            "test_zome",
+            make_cap_request_for_call(
+                test_context("some_random_agent", None),
+                grant_addr.clone(),
+                "test",
+                "{}",
+            ),
+            "test",
+            "{}",
+        );
+        assert!(check_capability(context.clone(), &zome_call));
+        assert!(!check_capability(context.clone(), &zome_call));
+        assert_eq!(
+            context
+                .state()
+                .unwrap()
+                .nucleus()
+                .cap_grant_uses(&grant_addr),
+            1
+        );
+    }
+
+    #[test]
+    fn test_zome_fn_call_from_args_with_provenance() {
+        let context = test_context("alice", None);
+        let caller_context = test_context("bob", None);
+        let caller_request = make_cap_request_for_call(
+            caller_context.clone(),
+            Address::from("test_token"),
+            "test",
+            "{}",
+        );
+        let args = ZomeFnCallArgs {
+            instance_handle: THIS_INSTANCE.to_string(),
+            zome_name: "test_zome".to_string(),
+            cap_token: Address::from("test_token"),
+            fn_name: "test".to_string(),
+            fn_args: "{}".to_string(),
+            provenance: Some(caller_request.provenance.clone()),
+        };
+
+        // the caller's signature gets used instead of signing the call ourselves
+        let zome_call = ZomeFnCall::from_args(context.clone(), args.clone());
+        assert_eq!(zome_call.cap, caller_request);
+
+        let zome_call = ZomeFnCall::from_args(
+            context.clone(),
+            ZomeFnCallArgs {
+                provenance: None,
+                ..args
+            },
+        );
+        assert_eq!(
+            zome_call.cap.provenance.source(),
+            context.agent_id.address()
+        );
+    }
 }
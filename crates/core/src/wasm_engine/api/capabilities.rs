@@ -1,5 +1,7 @@
 use crate::{
     agent::actions::commit::commit_entry,
+    context::Context,
+    nucleus::actions::get_entry::get_entry_from_agent_chain,
     wasm_engine::{api::ZomeApiResult, Runtime},
 };
 use holochain_core_types::{
@@ -14,7 +16,7 @@ use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::capabilities::{
     CommitCapabilityClaimArgs, CommitCapabilityGrantArgs,
 };
-use std::convert::TryFrom;
+use std::{convert::TryFrom, sync::Arc};
 use wasmi::{RuntimeArgs, RuntimeValue};
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
@@ -30,7 +32,7 @@ pub fn invoke_commit_capability_grant(runtime: &mut Runtime, args: &RuntimeArgs)
     let task_result: Result<Address, HolochainError> =
         match CapTokenGrant::create(&args.id, args.cap_type, args.assignees, args.functions) {
             Ok(grant) => context.block_on(commit_entry(
-                Entry::CapTokenGrant(grant),
+                Entry::CapTokenGrant(grant.with_limits(args.limits)),
                 None,
                 &context.clone(),
             )),
@@ -61,6 +63,31 @@ pub fn invoke_commit_capability_claim(runtime: &mut Runtime, args: &RuntimeArgs)
     runtime.store_result(task_result)
 }
 
+/// True if the given address is the address of a capability grant on the local chain
+pub(crate) fn is_local_cap_grant(context: &Arc<Context>, address: &Address) -> bool {
+    match get_entry_from_agent_chain(context, address) {
+        Ok(Some(Entry::CapTokenGrant(_))) => true,
+        _ => false,
+    }
+}
+
+/// Commits the update or deletion of a capability grant, which revokes the grant.
+/// Grants are private and never get published, so neither does their revocation.
+pub(crate) fn revoke_cap_grant(
+    context: &Arc<Context>,
+    grant_address: &Address,
+    entry: Entry,
+) -> Result<Address, HolochainError> {
+    match entry {
+        Entry::Deletion(_) | Entry::CapTokenGrant(_) => {
+            context.block_on(commit_entry(entry, Some(grant_address.clone()), context))
+        }
+        _ => Err(HolochainError::ErrorGeneric(
+            "A capability grant can only be updated with another grant".to_string(),
+        )),
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::wasm_engine::{
@@ -84,6 +111,7 @@ pub mod tests {
             cap_type: CapabilityType::Assigned,
             assignees: Some(vec![Address::from("fake address")]),
             functions,
+            limits: Default::default(),
         };
 
         JsonString::from(grant_args).to_bytes()
@@ -0,0 +1,56 @@
+use crate::{
+    wasm_engine::{api::ZomeApiResult, Runtime},
+    workflows::author_entry::author_entry,
+};
+use holochain_core_types::{chain_migrate::ChainMigrate, entry::Entry, error::HolochainError};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::chain_migrate::CloseChainArgs;
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::CloseChain function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: CloseChainArgs
+/// Returns an HcApiReturnCode as I64
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn invoke_close_chain(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+
+    let args_str = runtime.load_json_string_from_args(&args);
+    let close_chain_args = match CloseChainArgs::try_from(args_str.clone()) {
+        Ok(args) => args,
+        // Exit on error
+        Err(error) => {
+            log_error!(
+                context,
+                "zome: invoke_close_chain failed to \
+                 deserialize arguments: {:?} with error {:?}",
+                args_str,
+                error
+            );
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let result: Result<Address, HolochainError> = context
+        .get_dna()
+        .ok_or(HolochainError::DnaMissing)
+        .and_then(|dna| {
+            let migrate = ChainMigrate::new(
+                dna.address(),
+                close_chain_args.new_dna_address,
+                context.agent_id.address(),
+                close_chain_args.reason,
+            );
+            // Closing the chain can't be bundled since nothing can be committed after it
+            context.block_on(author_entry(
+                &Entry::ChainMigrate(migrate),
+                None,
+                &context,
+                &vec![],
+            ))
+        })
+        .map(|result| result.address());
+
+    runtime.store_result(result)
+}
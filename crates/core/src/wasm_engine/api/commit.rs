@@ -1,6 +1,9 @@
 use crate::{
-    wasm_engine::{api::ZomeApiResult, Runtime},
-    workflows::author_entry::author_entry,
+    entry::encryption::{encrypt_entry, is_encrypted},
+    wasm_engine::{
+        api::{bundle::author_or_stage_entry, ZomeApiResult},
+        Runtime,
+    },
 };
 use holochain_core_types::error::HolochainError;
 
@@ -45,13 +48,30 @@ pub fn invoke_commit_app_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> Zom
         .into();
     let _spanguard = ht::push_span(span);
 
+    // Entries of encrypted entry types only ever get stored as ciphertext
+    let entry = commit_entry_arg.entry();
+    let maybe_entry = if is_encrypted(&entry.entry_type(), &context) {
+        context.block_on(encrypt_entry(
+            &entry,
+            &commit_entry_arg.options().recipients(),
+            &context,
+        ))
+    } else {
+        Ok(entry)
+    };
+
     // Wait for future to be resolved
-    let task_result: Result<CommitEntryResult, HolochainError> = context.block_on(author_entry(
-        &commit_entry_arg.entry(),
-        None,
-        &context,
-        &commit_entry_arg.options().provenance(),
-    ));
+    let task_result: Result<CommitEntryResult, HolochainError> = maybe_entry
+        .and_then(|entry| {
+            author_or_stage_entry(
+                runtime,
+                &context,
+                &entry,
+                None,
+                &commit_entry_arg.options().provenance(),
+            )
+        })
+        .map(CommitEntryResult::new);
 
     runtime.store_result(task_result)
 }
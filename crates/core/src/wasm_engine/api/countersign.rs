@@ -0,0 +1,40 @@
+use crate::{
+    wasm_engine::{api::ZomeApiResult, Runtime},
+    workflows::countersign::countersign_entry_workflow,
+};
+use holochain_wasm_utils::api_serialization::countersigning::CountersignEntryArgs;
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::CountersignEntry function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: CountersignEntryArgs
+/// Returns an HcApiReturnCode as I64
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn invoke_countersign_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+
+    let args_str = runtime.load_json_string_from_args(&args);
+    let countersign_args = match CountersignEntryArgs::try_from(args_str.clone()) {
+        Ok(args) => args,
+        // Exit on error
+        Err(error) => {
+            log_error!(
+                context,
+                "zome: invoke_countersign_entry failed to deserialize arguments: {:?} with error {:?}",
+                args_str,
+                error
+            );
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let result = context.block_on(countersign_entry_workflow(
+        countersign_args.entry,
+        countersign_args.parties,
+        countersign_args.timeout,
+        context.clone(),
+    ));
+
+    runtime.store_result(result)
+}
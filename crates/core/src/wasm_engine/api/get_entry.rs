@@ -1,4 +1,5 @@
 use crate::{
+    entry::encryption::decrypt_get_entry_result,
     wasm_engine::{api::ZomeApiResult, Runtime},
     workflows::get_entry_result::get_entry_result_workflow,
 };
@@ -28,7 +29,12 @@ pub fn invoke_get_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiRes
         }
     };
     // Create workflow future and block on it
-    let result = context.block_on(get_entry_result_workflow(&context, &input));
+    let result = context
+        .block_on(get_entry_result_workflow(&context, &input))
+        .and_then(|mut result| {
+            // Encrypted entries get returned in the clear to their recipients
+            decrypt_get_entry_result(&mut result, &context).map(|_| result)
+        });
     // Store result in wasm memory
     runtime.store_result(result)
 }
@@ -1,13 +1,13 @@
-use crate::{
-    wasm_engine::{api::ZomeApiResult, runtime::Runtime},
-    workflows::author_entry::author_entry,
+use crate::wasm_engine::{
+    api::{bundle::author_or_stage_entry, ZomeApiResult},
+    runtime::Runtime,
 };
 use holochain_core_types::{
     entry::Entry,
     error::HolochainError,
     link::{link_data::LinkData, LinkActionKind},
 };
-use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_persistence_api::cas::content::Address;
 
 use holochain_wasm_utils::api_serialization::link_entries::LinkEntriesArgs;
 use std::convert::TryFrom;
@@ -62,9 +62,8 @@ pub fn invoke_link_entries(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
 
     // Wait for future to be resolved
     // This is where the link entry actually gets created.
-    let result: Result<Address, HolochainError> = context
-        .block_on(author_entry(&entry, None, &context, &vec![]))
-        .map(|_| entry.address());
+    let result: Result<Address, HolochainError> =
+        author_or_stage_entry(runtime, &context, &entry, None, &vec![]);
 
     runtime.store_result(result)
 }
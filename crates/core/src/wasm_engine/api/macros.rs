@@ -97,6 +97,33 @@ macro_rules! link_zome_api {
                     } , )*
                 }
             }
+
+            /// Host functions of all Zome API functions for instances of the wasmer engine.
+            /// Every function gets its own closure since wasmer tells imported closures
+            /// apart by their type.
+            pub(crate) fn wasmer_namespace() -> wasmer_runtime_core::import::Namespace {
+                use crate::wasm_engine::engine::wasmer_engine::invoke_host_function;
+                use wasmer_runtime::{func, Ctx};
+
+                let mut namespace = wasmer_runtime_core::import::Namespace::new();
+                namespace.insert(
+                    "abort",
+                    func!(|_ctx: &mut Ctx, _message: i64, _file: i64, _line: i64, _col: i64| {}),
+                );
+                $(
+                    namespace.insert(
+                        $internal_name,
+                        func!(|ctx: &mut Ctx, encoded_args: i64| {
+                            invoke_host_function(
+                                ctx,
+                                ZomeApiFunction::$enum_variant as usize,
+                                &[wasmi::RuntimeValue::I64(encoded_args)],
+                            )
+                        }),
+                    );
+                )*
+                namespace
+            }
         }
     };
 }
@@ -1,8 +1,11 @@
 //! Module for ZomeApiFunctions
 //! ZomeApiFunctions are the functions provided by the ribosome that are callable by Zomes.
 
+pub mod bundle;
 pub mod call;
+pub mod chain_migrate;
 pub mod commit;
+pub mod countersign;
 pub mod debug;
 pub mod emit_signal;
 pub mod entry_address;
@@ -24,13 +27,17 @@ pub mod send;
 pub mod sign;
 pub mod sleep;
 pub mod update_entry;
+pub mod validation_receipts;
 pub mod verify_signature;
 
 use crate::wasm_engine::{
     api::{
+        bundle::{invoke_close_bundle, invoke_start_bundle},
         call::invoke_call,
         capabilities::{invoke_commit_capability_claim, invoke_commit_capability_grant},
+        chain_migrate::invoke_close_chain,
         commit::invoke_commit_app_entry,
+        countersign::invoke_countersign_entry,
         crypto::invoke_crypto,
         debug::invoke_debug,
         emit_signal::invoke_emit_signal,
@@ -53,6 +60,7 @@ use crate::wasm_engine::{
         sign::invoke_sign_one_time,
         sleep::invoke_sleep,
         update_entry::invoke_update_entry,
+        validation_receipts::invoke_get_validation_receipt_count,
         verify_signature::invoke_verify_signature,
     },
     runtime::Runtime,
@@ -154,6 +162,26 @@ link_zome_api! {
 
     ///send a meta
     "hc_meta",Meta,invoke_meta;
+
+    /// Open a bundle in which following commits of the zome call are staged
+    /// hc_start_bundle(timeout: Timeout, user_param: JsonString)
+    "hc_start_bundle", StartBundle, invoke_start_bundle;
+
+    /// Commit or discard all entries staged in the open bundle
+    /// hc_close_bundle(action: BundleOnClose)
+    "hc_close_bundle", CloseBundle, invoke_close_bundle;
+
+    /// Close the source chain with a ChainMigrate entry pointing to the successor DNA
+    /// hc_close_chain(new_dna_address: Address, reason: String) -> Address
+    "hc_close_chain", CloseChain, invoke_close_chain;
+
+    /// Get the number of validators that sent a receipt for an entry we authored
+    /// hc_get_validation_receipt_count(entry_address: Address) -> ValidationReceiptCount
+    "hc_get_validation_receipt_count", GetValidationReceiptCount, invoke_get_validation_receipt_count;
+
+    /// Commit an entry together with the other given agents, each onto their own chain
+    /// hc_countersign_entry(entry: Entry, parties: Vec<Address>, timeout: Timeout) -> Address
+    "hc_countersign_entry", CountersignEntry, invoke_countersign_entry;
 }
 
 #[cfg(test)]
@@ -1,6 +1,13 @@
 use crate::{
-    wasm_engine::{api::ZomeApiResult, Runtime},
-    workflows::{author_entry::author_entry, get_entry_result::get_entry_result_workflow},
+    wasm_engine::{
+        api::{
+            bundle::author_or_stage_entry,
+            capabilities::{is_local_cap_grant, revoke_cap_grant},
+            ZomeApiResult,
+        },
+        Runtime,
+    },
+    workflows::get_entry_result::get_entry_result_workflow,
 };
 use holochain_core_types::{
     entry::{deletion_entry::DeletionEntry, Entry},
@@ -36,6 +43,13 @@ pub fn invoke_remove_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
     }
     let deleted_entry_address = try_address.unwrap();
 
+    // Deleting a capability grant revokes it
+    if is_local_cap_grant(&context, &deleted_entry_address) {
+        let deletion_entry = Entry::Deletion(DeletionEntry::new(deleted_entry_address.clone()));
+        let res = revoke_cap_grant(&context, &deleted_entry_address, deletion_entry);
+        return runtime.store_result(res);
+    }
+
     // Get Current entry's latest version
     let get_args = GetEntryArgs {
         address: deleted_entry_address,
@@ -59,14 +73,13 @@ pub fn invoke_remove_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
     // Create deletion entry
     let deletion_entry = Entry::Deletion(DeletionEntry::new(deleted_entry_address.clone()));
 
-    let res: Result<Address, HolochainError> = context
-        .block_on(author_entry(
-            &deletion_entry.clone(),
-            Some(deleted_entry_address),
-            &context.clone(),
-            &vec![],
-        ))
-        .map(|_| deletion_entry.address());
+    let res: Result<Address, HolochainError> = author_or_stage_entry(
+        runtime,
+        &context,
+        &deletion_entry,
+        Some(deleted_entry_address),
+        &vec![],
+    );
 
     runtime.store_result(res)
 }
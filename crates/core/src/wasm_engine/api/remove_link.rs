@@ -6,8 +6,10 @@ use crate::{
             NetworkQueryResult,
         },
     },
-    wasm_engine::{api::ZomeApiResult, Runtime},
-    workflows::author_entry::author_entry,
+    wasm_engine::{
+        api::{bundle::author_or_stage_entry, ZomeApiResult},
+        Runtime,
+    },
 };
 
 use holochain_core_types::{
@@ -104,9 +106,8 @@ pub fn invoke_remove_link(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiR
             let entry = Entry::LinkRemove((link_remove, filtered_links));
 
             // Wait for future to be resolved
-            let result: Result<(), HolochainError> = context
-                .block_on(author_entry(&entry, None, &context, &vec![]))
-                .map(|_| ());
+            let result: Result<(), HolochainError> =
+                author_or_stage_entry(runtime, &context, &entry, None, &vec![]).map(|_| ());
 
             runtime.store_result(result)
         }
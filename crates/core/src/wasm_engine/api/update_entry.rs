@@ -1,6 +1,14 @@
 use crate::{
-    wasm_engine::{api::ZomeApiResult, Runtime},
-    workflows::{author_entry::author_entry, get_entry_result::get_entry_result_workflow},
+    entry::encryption::{encrypt_entry, is_encrypted},
+    wasm_engine::{
+        api::{
+            bundle::author_or_stage_entry,
+            capabilities::{is_local_cap_grant, revoke_cap_grant},
+            ZomeApiResult,
+        },
+        Runtime,
+    },
+    workflows::get_entry_result::get_entry_result_workflow,
 };
 use holochain_core_types::error::HolochainError;
 
@@ -32,6 +40,12 @@ pub fn invoke_update_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
         }
     };
 
+    // Updating a capability grant revokes it in favor of the new grant
+    if is_local_cap_grant(&context, &entry_args.address) {
+        let res = revoke_cap_grant(&context, &entry_args.address, entry_args.new_entry);
+        return runtime.store_result(res);
+    }
+
     // Get Current entry's latest version
     let get_args = GetEntryArgs {
         address: entry_args.address,
@@ -48,17 +62,23 @@ pub fn invoke_update_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
     }
     let latest_entry = entry_result.latest().unwrap();
 
-    // Create Chain Entry
+    // Create Chain Entry, encrypted for the recipients of its definition if its type requires it
     let entry = entry_args.new_entry.clone();
+    let maybe_entry = if is_encrypted(&entry.entry_type(), &context) {
+        context.block_on(encrypt_entry(&entry, &[], &context))
+    } else {
+        Ok(entry)
+    };
 
-    let res: Result<Address, HolochainError> = context
-        .block_on(author_entry(
+    let res: Result<Address, HolochainError> = maybe_entry.and_then(|entry| {
+        author_or_stage_entry(
+            runtime,
+            &context,
             &entry,
             Some(latest_entry.address()),
-            &context.clone(),
             &vec![], // TODO should provenance be a parameter?
-        ))
-        .map(|result| result.address());
+        )
+    });
 
     runtime.store_result(res)
 }
@@ -0,0 +1,49 @@
+use crate::wasm_engine::{api::ZomeApiResult, Runtime};
+use holochain_core_types::error::HolochainError;
+use holochain_wasm_utils::api_serialization::validation_receipts::{
+    ValidationReceiptCount, ValidationReceiptCountArgs,
+};
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::GetValidationReceiptCount function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: ValidationReceiptCountArgs
+/// Returns an HcApiReturnCode as I64
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn invoke_get_validation_receipt_count(
+    runtime: &mut Runtime,
+    args: &RuntimeArgs,
+) -> ZomeApiResult {
+    let context = runtime.context()?;
+
+    let args_str = runtime.load_json_string_from_args(&args);
+    let receipt_count_args = match ValidationReceiptCountArgs::try_from(args_str.clone()) {
+        Ok(args) => args,
+        // Exit on error
+        Err(error) => {
+            log_error!(
+                context,
+                "zome: invoke_get_validation_receipt_count failed to \
+                 deserialize arguments: {:?} with error {:?}",
+                args_str,
+                error
+            );
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let result: Result<ValidationReceiptCount, HolochainError> = context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("State not initialized".to_string()))
+        .and_then(|state| {
+            state
+                .dht()
+                .get_validation_receipts(&receipt_count_args.entry_address)
+        })
+        .map(|receipts| ValidationReceiptCount {
+            count: receipts.len(),
+        });
+
+    runtime.store_result(result)
+}
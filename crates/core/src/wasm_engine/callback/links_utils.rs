@@ -4,45 +4,47 @@ use holochain_core_types::{
     error::HolochainError,
     link::Link,
 };
+use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::{get_entry::*, validation::LinkDirection};
 use std::sync::Arc;
 
 /// Retrieves the base and target entries of the link and returns both.
+/// Entries staged in an open bundle count as well since links between them
+/// get validated before the bundle is committed.
 #[autotrace]
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 pub fn get_link_entries(
     link: &Link,
     context: &Arc<Context>,
 ) -> Result<(Entry, Entry), HolochainError> {
-    let base_address = link.base();
-    let target_address = link.target();
-    let entry_args = &GetEntryArgs {
-        address: base_address.clone(),
-        options: Default::default(),
-    };
-    let base_entry_get_result =
-        context.block_on(get_entry_result_workflow(&context, entry_args))?;
-    if !base_entry_get_result.found() {
-        return Err(HolochainError::ErrorGeneric(format!(
-            "Base for link not found: {:?}",
-            link
-        )));
+    let base_entry = get_linked_entry(link.base(), context)?.ok_or_else(|| {
+        HolochainError::ErrorGeneric(format!("Base for link not found: {:?}", link))
+    })?;
+    let target_entry = get_linked_entry(link.target(), context)?.ok_or_else(|| {
+        HolochainError::ErrorGeneric(format!("Target for link not found: {:?}", link))
+    })?;
+    Ok((base_entry, target_entry))
+}
+
+fn get_linked_entry(
+    address: &Address,
+    context: &Arc<Context>,
+) -> Result<Option<Entry>, HolochainError> {
+    let staged_entry = context.state().and_then(|state| {
+        state
+            .agent()
+            .bundle()
+            .and_then(|bundle| bundle.get_entry(address).cloned())
+    });
+    if staged_entry.is_some() {
+        return Ok(staged_entry);
     }
-    let base_entry = base_entry_get_result.latest().unwrap();
     let entry_args = &GetEntryArgs {
-        address: target_address.clone(),
+        address: address.clone(),
         options: Default::default(),
     };
-    let target_entry_get_result =
-        context.block_on(get_entry_result_workflow(&context, entry_args))?;
-    if !target_entry_get_result.found() {
-        return Err(HolochainError::ErrorGeneric(format!(
-            "Target for link not found: {:?}",
-            link
-        )));
-    }
-
-    Ok((base_entry, target_entry_get_result.latest().unwrap()))
+    let get_result = context.block_on(get_entry_result_workflow(&context, entry_args))?;
+    Ok(get_result.latest())
 }
 
 /// This is a "path" in the DNA tree.
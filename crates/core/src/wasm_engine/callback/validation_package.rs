@@ -113,6 +113,8 @@ pub fn get_validation_package_definition(
         EntryType::CapTokenGrant => JsonString::from(ValidationPackageDefinition::Entry),
         EntryType::AgentId => JsonString::from(ValidationPackageDefinition::Entry),
         EntryType::ChainHeader => JsonString::from(ValidationPackageDefinition::Entry),
+        // holders need the headers to see if the chain was closed before
+        EntryType::ChainMigrate => JsonString::from(ValidationPackageDefinition::ChainHeaders),
         _ => Err(HolochainError::NotImplemented(
             "get_validation_package_definition/3".into(),
         ))?,
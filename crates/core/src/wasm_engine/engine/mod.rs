@@ -0,0 +1,128 @@
+//! The engines that can run zome code.
+//!
+//! Every instance runs its zomes with the engine configured for it, which defaults to
+//! the wasmi interpreter. The wasmer engine compiles zomes to native code instead and
+//! can keep the compiled artifacts in an on-disk cache.
+//!
+//! Modules get compiled once per zome and instance and are cached in the context.
+//! Every call then runs in a fresh instance of the module.
+
+pub mod wasmer_engine;
+pub mod wasmi_engine;
+
+pub use self::{wasmer_engine::WasmerEngine, wasmi_engine::WasmiEngine};
+
+use crate::{
+    context::Context,
+    wasm_engine::{memory::WasmMemory, metering::metered_code, runtime::Runtime},
+};
+use holochain_core_types::{dna::wasm::DnaWasm, error::HolochainError};
+use std::{path::PathBuf, sync::Arc};
+use wasmi::RuntimeValue;
+
+/// Selects the engine of an instance
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum WasmEngineConfig {
+    /// Interprets zome code
+    Wasmi,
+    /// Compiles zome code to native code.
+    /// Compiled artifacts get stored in `cache_dir`, keyed by the hash of the WASM,
+    /// so they don't have to be compiled again after a restart.
+    Wasmer {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_dir: Option<PathBuf>,
+    },
+}
+
+impl Default for WasmEngineConfig {
+    fn default() -> WasmEngineConfig {
+        WasmEngineConfig::Wasmi
+    }
+}
+
+/// Creates the engine the given configuration asks for
+pub fn create_wasm_engine(
+    config: &WasmEngineConfig,
+) -> Result<Arc<dyn WasmEngine>, HolochainError> {
+    match config {
+        WasmEngineConfig::Wasmi => Ok(Arc::new(WasmiEngine)),
+        WasmEngineConfig::Wasmer { cache_dir } => Ok(Arc::new(match cache_dir {
+            Some(path) => WasmerEngine::with_cache(path)?,
+            None => WasmerEngine::new(),
+        })),
+    }
+}
+
+/// Turns WASM binaries into modules that can be run
+pub trait WasmEngine: Send + Sync {
+    fn compile(&self, wasm: &[u8]) -> Result<Arc<dyn WasmModule>, HolochainError>;
+}
+
+/// A compiled zome that gets instantiated for every call
+pub trait WasmModule: Send + Sync {
+    /// Creates a runnable instance with the Zome API functions as imports
+    fn instantiate(&self) -> Result<Box<dyn WasmInstance>, HolochainError>;
+}
+
+/// A running zome
+pub trait WasmInstance {
+    /// The linear memory the instance exports as "memory"
+    fn memory(&self) -> Result<WasmMemory, HolochainError>;
+
+    /// Calls an exported function.
+    /// All calls the zome makes to the Zome API get handled by the given runtime.
+    fn invoke_export(
+        &mut self,
+        fn_name: &str,
+        args: &[RuntimeValue],
+        runtime: &mut Runtime,
+    ) -> Result<Option<RuntimeValue>, String>;
+}
+
+/// Returns the module of the given zome as compiled by the engine of the instance.
+/// If the instance has WasmLimits configured, the zome gets instrumented for metering first.
+pub fn get_zome_module(
+    context: &Arc<Context>,
+    zome_name: &str,
+    wasm: &DnaWasm,
+) -> Result<Arc<dyn WasmModule>, HolochainError> {
+    if let Some(module) = context.wasm_modules.read().unwrap().get(zome_name) {
+        return Ok(module.clone());
+    }
+    let module = if context.wasm_limits.is_unlimited() {
+        context.wasm_engine.compile(&wasm.code)?
+    } else {
+        context.wasm_engine.compile(&metered_code(&wasm.code)?)?
+    };
+    context
+        .wasm_modules
+        .write()
+        .unwrap()
+        .insert(zome_name.to_string(), module.clone());
+    Ok(module)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_engine_config_from_toml() {
+        let config: WasmEngineConfig = toml::from_str(
+            r#"
+type = "wasmer"
+cache_dir = "/tmp/wasm_cache"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            WasmEngineConfig::Wasmer {
+                cache_dir: Some(PathBuf::from("/tmp/wasm_cache"))
+            }
+        );
+        let config: WasmEngineConfig = toml::from_str(r#"type = "wasmi""#).unwrap();
+        assert_eq!(config, WasmEngineConfig::default());
+    }
+}
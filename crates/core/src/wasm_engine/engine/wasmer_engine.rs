@@ -0,0 +1,210 @@
+use crate::wasm_engine::{
+    api::ZomeApiFunction,
+    engine::{WasmEngine, WasmInstance, WasmModule},
+    memory::WasmMemory,
+    metering::{GAS_FUNCTION_INDEX, GAS_FUNCTION_NAME},
+    runtime::Runtime,
+};
+use holochain_core_types::error::HolochainError;
+use holochain_locksmith::Mutex;
+use std::{ffi::c_void, path::Path, ptr, sync::Arc};
+use wasmer_runtime::{
+    cache::{Cache, FileSystemCache, WasmHash},
+    compile, func, Ctx, Export, ImportObject, Instance, Module, Value,
+};
+use wasmi::{Externals, RuntimeArgs, RuntimeValue};
+
+/// Compiles zome code to native code with wasmer.
+/// With a cache, compiled modules get stored on disk keyed by the hash of their WASM.
+pub struct WasmerEngine {
+    cache: Option<Mutex<FileSystemCache>>,
+}
+
+impl WasmerEngine {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        WasmerEngine { cache: None }
+    }
+
+    /// Uses the given directory as cache of compiled modules, creating it if needed.
+    /// Cached modules are native code that gets loaded without further checks,
+    /// so nothing else should be able to write to this directory.
+    pub fn with_cache<P: AsRef<Path>>(path: P) -> Result<Self, HolochainError> {
+        let cache = unsafe { FileSystemCache::new(path.as_ref()) }.map_err(|e| {
+            HolochainError::IoError(format!(
+                "Could not open WASM cache at {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Ok(WasmerEngine {
+            cache: Some(Mutex::new(cache)),
+        })
+    }
+}
+
+impl WasmEngine for WasmerEngine {
+    fn compile(&self, wasm: &[u8]) -> Result<Arc<dyn WasmModule>, HolochainError> {
+        let hash = WasmHash::generate(wasm);
+        if let Some(ref cache) = self.cache {
+            if let Ok(module) = cache.lock().unwrap().load(hash) {
+                return Ok(Arc::new(WasmerModule(module)));
+            }
+        }
+
+        let module = compile(wasm).map_err(|e| {
+            HolochainError::ErrorGeneric(format!("Could not compile WASM: {:?}", e))
+        })?;
+
+        if let Some(ref cache) = self.cache {
+            // Not being able to cache only costs time on the next start
+            if let Err(e) = cache.lock().unwrap().store(hash, module.clone()) {
+                warn!("wasm_engine: Could not cache compiled module: {:?}", e);
+            }
+        }
+        Ok(Arc::new(WasmerModule(module)))
+    }
+}
+
+pub struct WasmerModule(Module);
+
+impl WasmModule for WasmerModule {
+    fn instantiate(&self) -> Result<Box<dyn WasmInstance>, HolochainError> {
+        let mut namespace = ZomeApiFunction::wasmer_namespace();
+        // Imported by modules that got instrumented to enforce WasmLimits
+        namespace.insert(
+            GAS_FUNCTION_NAME,
+            func!(|ctx: &mut Ctx, instructions: i32| -> Result<(), String> {
+                invoke_host_function(ctx, GAS_FUNCTION_INDEX, &[RuntimeValue::I32(instructions)])
+                    .map(|_| ())
+            }),
+        );
+        let mut imports = ImportObject::new();
+        imports.register("env", namespace);
+
+        let instance = self.0.instantiate(&imports).map_err(|e| {
+            HolochainError::RibosomeFailed(format!("Failed to instantiate module: {}", e))
+        })?;
+        Ok(Box::new(WasmerInstance(instance)))
+    }
+}
+
+/// Hands a call the zome makes to a host function over to the runtime of the running call.
+/// The runtime gets passed through the data pointer of the instance's context
+/// for the duration of invoke_export().
+pub(crate) fn invoke_host_function(
+    ctx: &mut Ctx,
+    index: usize,
+    args: &[RuntimeValue],
+) -> Result<i64, String> {
+    if ctx.data.is_null() {
+        return Err("Host function called outside of a zome call".to_string());
+    }
+    let runtime = unsafe { &mut *(ctx.data as *mut Runtime) };
+    match runtime.invoke_index(index, RuntimeArgs::from(args)) {
+        Ok(Some(RuntimeValue::I64(value))) => Ok(value),
+        Ok(_) => Ok(0),
+        Err(trap) => Err(trap.to_string()),
+    }
+}
+
+pub struct WasmerInstance(Instance);
+
+impl WasmInstance for WasmerInstance {
+    fn memory(&self) -> Result<WasmMemory, HolochainError> {
+        self.0
+            .exports()
+            .find_map(|(name, export)| match export {
+                Export::Memory(memory) if name == "memory" => Some(WasmMemory::Wasmer(memory)),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                HolochainError::RibosomeFailed("Module has no memory export".to_string())
+            })
+    }
+
+    fn invoke_export(
+        &mut self,
+        fn_name: &str,
+        args: &[RuntimeValue],
+        runtime: &mut Runtime,
+    ) -> Result<Option<RuntimeValue>, String> {
+        let params = args
+            .iter()
+            .map(|arg| match arg {
+                RuntimeValue::I32(value) => Ok(Value::I32(*value)),
+                RuntimeValue::I64(value) => Ok(Value::I64(*value)),
+                _ => Err(format!("Unsupported argument type: {:?}", arg)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.0.context_mut().data = runtime as *mut Runtime as *mut c_void;
+        let result = self.0.call(fn_name, &params);
+        self.0.context_mut().data = ptr::null_mut();
+
+        match result.map_err(|err| err.to_string())?.first() {
+            None => Ok(None),
+            Some(Value::I32(value)) => Ok(Some(RuntimeValue::I32(*value))),
+            Some(Value::I64(value)) => Ok(Some(RuntimeValue::I64(*value))),
+            Some(value) => Err(format!("Unsupported return type: {:?}", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::wasm_engine::{memory::WasmPageManager, runtime::WasmCallData};
+    use tempfile::tempdir;
+    use wabt::Wat2Wasm;
+
+    fn test_wasm() -> Vec<u8> {
+        Wat2Wasm::new()
+            .canonicalize_lebs(false)
+            .write_debug_names(true)
+            .convert(
+                r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $p0 i64) (result i64)
+        get_local $p0
+        i64.const 1
+        i64.add)
+)
+"#,
+            )
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    #[test]
+    fn wasmer_engine_runs_and_caches_modules() {
+        let dir = tempdir().unwrap();
+        let wasm = test_wasm();
+        let engine = WasmerEngine::with_cache(dir.path()).unwrap();
+        let module = engine.compile(&wasm).unwrap();
+        // compiled artifacts end up in the cache directory
+        assert!(std::fs::read_dir(dir.path()).unwrap().count() > 0);
+
+        let mut instance = module.instantiate().unwrap();
+        let mut runtime = Runtime {
+            memory_manager: WasmPageManager::new(instance.memory().unwrap()),
+            data: WasmCallData::DirectCall("main".to_string(), Arc::new(wasm.clone())),
+            meter: None,
+        };
+        assert_eq!(
+            instance.invoke_export("main", &[RuntimeValue::I64(41)], &mut runtime),
+            Ok(Some(RuntimeValue::I64(42)))
+        );
+
+        // a second engine loads the module from the cache
+        let engine = WasmerEngine::with_cache(dir.path()).unwrap();
+        let mut instance = engine.compile(&wasm).unwrap().instantiate().unwrap();
+        assert_eq!(
+            instance.invoke_export("main", &[RuntimeValue::I64(1)], &mut runtime),
+            Ok(Some(RuntimeValue::I64(2)))
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::wasm_engine::{
+    api::ZomeApiFunction,
+    engine::{WasmEngine, WasmInstance, WasmModule},
+    memory::WasmMemory,
+    metering::{GAS_FUNCTION_INDEX, GAS_FUNCTION_NAME},
+    runtime::Runtime,
+};
+use holochain_core_types::error::HolochainError;
+use std::{str::FromStr, sync::Arc};
+use wasmi::{
+    self, Error as InterpreterError, FuncInstance, FuncRef, ImportsBuilder, Module,
+    ModuleImportResolver, ModuleInstance, ModuleRef, NopExternals, RuntimeValue, Signature,
+    ValueType,
+};
+
+/// The wasmi interpreter
+pub struct WasmiEngine;
+
+impl WasmEngine for WasmiEngine {
+    fn compile(&self, wasm: &[u8]) -> Result<Arc<dyn WasmModule>, HolochainError> {
+        let module =
+            Module::from_buffer(wasm).map_err(|e| HolochainError::ErrorGeneric(e.into()))?;
+        Ok(Arc::new(WasmiModule(module)))
+    }
+}
+
+pub struct WasmiModule(Module);
+
+impl WasmModule for WasmiModule {
+    fn instantiate(&self) -> Result<Box<dyn WasmInstance>, HolochainError> {
+        // invoke_index and resolve_func work together to enable callable host functions
+        // within WASM modules, which is how the core API functions
+        // read about the Externals trait for more detail
+
+        // Correlate the names of the core ZomeApiFunction's with their indexes
+        // and declare its function signature (which is always the same)
+        struct RuntimeModuleImportResolver;
+        impl ModuleImportResolver for RuntimeModuleImportResolver {
+            fn resolve_func(
+                &self,
+                field_name: &str,
+                _signature: &Signature,
+            ) -> Result<FuncRef, InterpreterError> {
+                // Imported by modules that got instrumented to enforce WasmLimits
+                if field_name == GAS_FUNCTION_NAME {
+                    return Ok(FuncInstance::alloc_host(
+                        Signature::new(&[ValueType::I32][..], None),
+                        GAS_FUNCTION_INDEX,
+                    ));
+                }
+                let api_fn = match ZomeApiFunction::from_str(&field_name) {
+                    Ok(api_fn) => api_fn,
+                    Err(_) => {
+                        return Err(InterpreterError::Function(format!(
+                            "host module doesn't export function with name {}",
+                            field_name
+                        )));
+                    }
+                };
+
+                match api_fn {
+                    // Abort is a way to receive useful debug info from
+                    // assemblyscript memory allocators, see enum definition for function signature
+                    ZomeApiFunction::Abort => Ok(FuncInstance::alloc_host(
+                        Signature::new(
+                            &[
+                                ValueType::I64,
+                                ValueType::I64,
+                                ValueType::I64,
+                                ValueType::I64,
+                            ][..],
+                            None,
+                        ),
+                        api_fn as usize,
+                    )),
+                    // All of our Zome API Functions have the same signature
+                    _ => Ok(FuncInstance::alloc_host(
+                        Signature::new(&[ValueType::I64][..], Some(ValueType::I64)),
+                        api_fn as usize,
+                    )),
+                }
+            }
+        }
+
+        // Create Imports with previously described Resolver
+        let mut imports = ImportsBuilder::new();
+        imports.push_resolver("env", &RuntimeModuleImportResolver);
+
+        // Create module instance from wasm module, and start it if start is defined
+        let instance = ModuleInstance::new(&self.0, &imports)
+            .map_err(|e| {
+                HolochainError::RibosomeFailed(format!("Failed to instantiate module: {}", e))
+            })?
+            .run_start(&mut NopExternals)
+            .map_err(|_| HolochainError::RibosomeFailed("Module failed to start".to_string()))?;
+        Ok(Box::new(WasmiInstance(instance)))
+    }
+}
+
+pub struct WasmiInstance(ModuleRef);
+
+impl WasmInstance for WasmiInstance {
+    fn memory(&self) -> Result<WasmMemory, HolochainError> {
+        self.0
+            .export_by_name("memory")
+            .and_then(|export| export.as_memory().cloned())
+            .map(WasmMemory::Wasmi)
+            .ok_or_else(|| {
+                HolochainError::RibosomeFailed("Module has no memory export".to_string())
+            })
+    }
+
+    fn invoke_export(
+        &mut self,
+        fn_name: &str,
+        args: &[RuntimeValue],
+        runtime: &mut Runtime,
+    ) -> Result<Option<RuntimeValue>, String> {
+        self.0
+            .invoke_export(fn_name, args, runtime)
+            .map_err(|err| err.to_string())
+    }
+}
@@ -3,7 +3,60 @@ use holochain_wasm_utils::memory::{
     stack::WasmStack,
     MemoryBits, MemoryInt,
 };
-use wasmi::{MemoryRef, ModuleRef};
+use std::cell::Cell;
+use wasmi::MemoryRef;
+
+//--------------------------------------------------------------------------------------------------
+// WASM Memory
+//--------------------------------------------------------------------------------------------------
+
+/// The linear memory of a running instance of one of the WASM engines
+#[derive(Clone, Debug)]
+pub enum WasmMemory {
+    Wasmi(MemoryRef),
+    Wasmer(wasmer_runtime::Memory),
+}
+
+impl WasmMemory {
+    fn set(&self, offset: MemoryInt, data: &[u8]) -> Result<(), String> {
+        match self {
+            WasmMemory::Wasmi(memory) => memory.set(offset, data).map_err(|e| e.to_string()),
+            WasmMemory::Wasmer(memory) => {
+                let view = memory.view::<u8>();
+                let start = offset as usize;
+                let cells = view
+                    .get(start..start + data.len())
+                    .ok_or_else(|| "Out of bounds memory write".to_string())?;
+                cells
+                    .iter()
+                    .zip(data.iter())
+                    .for_each(|(cell, byte)| cell.set(*byte));
+                Ok(())
+            }
+        }
+    }
+
+    fn get(&self, offset: MemoryInt, length: usize) -> Result<Vec<u8>, String> {
+        match self {
+            WasmMemory::Wasmi(memory) => memory.get(offset, length).map_err(|e| e.to_string()),
+            WasmMemory::Wasmer(memory) => {
+                let view = memory.view::<u8>();
+                let start = offset as usize;
+                view.get(start..start + length)
+                    .map(|cells| cells.iter().map(Cell::get).collect())
+                    .ok_or_else(|| "Out of bounds memory read".to_string())
+            }
+        }
+    }
+
+    /// Current size in 64KiB pages
+    pub fn current_pages(&self) -> usize {
+        match self {
+            WasmMemory::Wasmi(memory) => memory.current_size().0,
+            WasmMemory::Wasmer(memory) => memory.size().0 as usize,
+        }
+    }
+}
 
 //--------------------------------------------------------------------------------------------------
 // WASM Memory Manager
@@ -13,7 +66,7 @@ use wasmi::{MemoryRef, ModuleRef};
 /// Struct for managing a WASM Memory Instance as a single page memory stack
 pub struct WasmPageManager {
     stack: WasmStack,
-    wasm_memory: MemoryRef,
+    wasm_memory: WasmMemory,
 }
 
 /// A Memory Manager limited to one wasm memory page that works like a stack.
@@ -33,21 +86,18 @@ pub struct WasmPageManager {
 /// and handle multiple memory Pages.
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 impl WasmPageManager {
-    pub fn new(wasm_instance: &ModuleRef) -> Self {
-        // get wasm memory reference from module
-        let wasm_memory = wasm_instance
-            .export_by_name("memory")
-            .expect("all modules compiled with rustc should have an export named 'memory'; qed")
-            .as_memory()
-            .expect("in module generated by rustc export named 'memory' should be a memory; qed")
-            .clone();
-
+    pub fn new(wasm_memory: WasmMemory) -> Self {
         WasmPageManager {
             stack: WasmStack::default(),
             wasm_memory,
         }
     }
 
+    /// Current size of the linear memory in 64KiB pages
+    pub fn current_pages(&self) -> usize {
+        self.wasm_memory.current_pages()
+    }
+
     /// Allocate on stack without writing in it
     pub fn allocate(&mut self, length: Length) -> AllocationResult {
         let allocation = self.stack.next_allocation(length)?;
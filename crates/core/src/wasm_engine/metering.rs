@@ -0,0 +1,475 @@
+//! Limits on the resources a single WASM call can use.
+//!
+//! If an instance has any [WasmLimits](struct.WasmLimits.html) configured, its zome code gets
+//! instrumented before instantiation: every straight-line run of instructions starts with
+//! a call to the host function `__hc_gas`, passing the number of instructions in that run.
+//! The [Meter](struct.Meter.html) of the running call adds these up and checks the linear
+//! memory and the running time of the call on every charge.
+//! Every `memory.grow` ends a run, so the memory limit gets checked right after growing.
+//! The call traps as soon as one of the limits is breached.
+
+use holochain_core_types::error::HolochainError;
+use parity_wasm::elements::{
+    External, FunctionType, ImportEntry, ImportSection, Instruction, Internal, Module, Section,
+    Type, TypeSection, ValueType,
+};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+use wasmi::HostError;
+
+/// Name of the host function instrumented code calls to get charged
+pub const GAS_FUNCTION_NAME: &str = "__hc_gas";
+
+/// Host function index of the gas function, outside the range of the Zome API functions
+pub const GAS_FUNCTION_INDEX: usize = usize::max_value();
+
+/// The clock is only read on every n-th charge since charges happen very often
+const CLOCK_CHECK_INTERVAL: u64 = 128;
+
+/// Upper bounds for a single zome function or callback call.
+/// Limits that are not set don't get enforced.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct WasmLimits {
+    /// Number of WASM instructions a call may execute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_instructions: Option<u64>,
+    /// Number of 64KiB pages the linear memory of a call may grow to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_pages: Option<u32>,
+    /// Wall-clock time a call may take, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<u64>,
+}
+
+impl WasmLimits {
+    pub fn with_max_instructions(mut self, max_instructions: u64) -> Self {
+        self.max_instructions = Some(max_instructions);
+        self
+    }
+
+    pub fn with_max_memory_pages(mut self, max_memory_pages: u32) -> Self {
+        self.max_memory_pages = Some(max_memory_pages);
+        self
+    }
+
+    pub fn with_max_duration_ms(mut self, max_duration_ms: u64) -> Self {
+        self.max_duration_ms = Some(max_duration_ms);
+        self
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_instructions.is_none()
+            && self.max_memory_pages.is_none()
+            && self.max_duration_ms.is_none()
+    }
+}
+
+/// Trap reason of a call that breached one of its limits
+#[derive(Debug)]
+pub struct WasmLimitError(pub String);
+
+impl fmt::Display for WasmLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WASM limit exceeded: {}", self.0)
+    }
+}
+
+impl HostError for WasmLimitError {}
+
+/// Keeps track of the resources used by one running call
+#[derive(Clone, Debug)]
+pub struct Meter {
+    limits: WasmLimits,
+    instructions: u64,
+    charges: u64,
+    deadline: Option<Instant>,
+    exceeded: Option<String>,
+}
+
+impl Meter {
+    /// Starts metering a call, which also starts its clock
+    pub fn new(limits: WasmLimits) -> Self {
+        let deadline = limits
+            .max_duration_ms
+            .map(|ms| Instant::now() + Duration::from_millis(ms));
+        Meter {
+            limits,
+            instructions: 0,
+            charges: 0,
+            deadline,
+            exceeded: None,
+        }
+    }
+
+    /// Accounts for the given number of executed instructions and checks all limits
+    /// against the current size of the linear memory.
+    /// Returns the description of the breached limit if there is one.
+    pub fn charge(&mut self, instructions: u64, memory_pages: usize) -> Result<(), String> {
+        if let Some(ref reason) = self.exceeded {
+            return Err(reason.clone());
+        }
+        self.instructions = self.instructions.saturating_add(instructions);
+        self.charges += 1;
+
+        let breach = match self.limits {
+            WasmLimits {
+                max_instructions: Some(max),
+                ..
+            } if self.instructions > max => {
+                Some(format!("executed more than {} instructions", max))
+            }
+            WasmLimits {
+                max_memory_pages: Some(max),
+                ..
+            } if memory_pages > max as usize => Some(format!(
+                "memory grew to {} pages, only {} allowed",
+                memory_pages, max
+            )),
+            _ => None,
+        };
+        let breach = breach.or_else(|| {
+            self.deadline
+                .filter(|_| self.charges % CLOCK_CHECK_INTERVAL == 0)
+                .filter(|deadline| Instant::now() > *deadline)
+                .map(|_| {
+                    format!(
+                        "call took longer than {} ms",
+                        self.limits.max_duration_ms.unwrap_or_default()
+                    )
+                })
+        });
+
+        match breach {
+            Some(reason) => {
+                self.exceeded = Some(reason.clone());
+                Err(reason)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Description of the limit this call breached, if any
+    pub fn exceeded(&self) -> Option<&String> {
+        self.exceeded.as_ref()
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+}
+
+/// Instruments a WASM module with calls to the gas function.
+/// The gas function gets imported after all other imported functions, so all functions
+/// defined in the module move up by one index.
+pub fn inject_gas_metering(mut module: Module) -> Module {
+    let gas_type = gas_function_type(&mut module);
+    let gas_function = module
+        .import_section()
+        .map(|imports| imports.functions())
+        .unwrap_or(0) as u32;
+
+    if module.import_section().is_none() {
+        let position = module
+            .sections()
+            .iter()
+            .position(|section| match section {
+                Section::Type(_) => true,
+                _ => false,
+            })
+            .map(|type_position| type_position + 1)
+            .unwrap_or(0);
+        module.sections_mut().insert(
+            position,
+            Section::Import(ImportSection::with_entries(vec![])),
+        );
+    }
+    // Only function imports count for the function indices, so the gas function
+    // gets the index after the last imported function wherever it ends up.
+    module
+        .import_section_mut()
+        .expect("Import section got added above")
+        .entries_mut()
+        .push(ImportEntry::new(
+            "env".to_string(),
+            GAS_FUNCTION_NAME.to_string(),
+            External::Function(gas_type),
+        ));
+
+    let shift = |index: &mut u32| {
+        if *index >= gas_function {
+            *index += 1
+        }
+    };
+    for section in module.sections_mut() {
+        match section {
+            Section::Export(exports) => {
+                for export in exports.entries_mut() {
+                    if let Internal::Function(index) = export.internal_mut() {
+                        shift(index)
+                    }
+                }
+            }
+            Section::Element(elements) => {
+                for segment in elements.entries_mut() {
+                    segment.members_mut().iter_mut().for_each(shift)
+                }
+            }
+            Section::Start(index) => shift(index),
+            Section::Code(code) => {
+                for body in code.bodies_mut() {
+                    let instructions = body.code_mut().elements_mut();
+                    for instruction in instructions.iter_mut() {
+                        if let Instruction::Call(index) = instruction {
+                            shift(index)
+                        }
+                    }
+                    meter_instructions(instructions, gas_function);
+                }
+            }
+            _ => {}
+        }
+    }
+    module
+}
+
+/// Returns the index of the function type `(i32) -> ()`, adding it if the module doesn't have it
+fn gas_function_type(module: &mut Module) -> u32 {
+    let gas_type = Type::Function(FunctionType::new(vec![ValueType::I32], None));
+    if module.type_section().is_none() {
+        module
+            .sections_mut()
+            .insert(0, Section::Type(TypeSection::with_types(vec![])));
+    }
+    let types = module
+        .type_section_mut()
+        .expect("Type section got added above")
+        .types_mut();
+    match types.iter().position(|t| *t == gas_type) {
+        Some(index) => index as u32,
+        None => {
+            types.push(gas_type);
+            (types.len() - 1) as u32
+        }
+    }
+}
+
+/// Instructions after which execution can continue somewhere else.
+/// Everything that can be a branch target follows one of them.
+/// Growing the memory also ends a run, so the next charge checks the new size.
+fn ends_run(instruction: &Instruction) -> bool {
+    match instruction {
+        Instruction::GrowMemory(_) => true,
+        Instruction::Block(_)
+        | Instruction::Loop(_)
+        | Instruction::If(_)
+        | Instruction::Else
+        | Instruction::End
+        | Instruction::Br(_)
+        | Instruction::BrIf(_)
+        | Instruction::BrTable(_, _)
+        | Instruction::Return => true,
+        _ => false,
+    }
+}
+
+/// Puts a charge for the length of every straight-line run in front of that run
+fn meter_instructions(instructions: &mut Vec<Instruction>, gas_function: u32) {
+    let mut metered = Vec::with_capacity(instructions.len() * 2);
+    let mut run = Vec::new();
+    for instruction in instructions.drain(..) {
+        let is_last_of_run = ends_run(&instruction);
+        run.push(instruction);
+        if is_last_of_run {
+            charge_run(&mut metered, &mut run, gas_function);
+        }
+    }
+    charge_run(&mut metered, &mut run, gas_function);
+    *instructions = metered;
+}
+
+fn charge_run(metered: &mut Vec<Instruction>, run: &mut Vec<Instruction>, gas_function: u32) {
+    if run.is_empty() {
+        return;
+    }
+    metered.push(Instruction::I32Const(run.len() as i32));
+    metered.push(Instruction::Call(gas_function));
+    metered.append(run);
+}
+
+/// Parses and instruments the given WASM binary
+pub fn metered_code(wasm: &[u8]) -> Result<Vec<u8>, HolochainError> {
+    let module = parity_wasm::deserialize_buffer::<Module>(wasm)
+        .map_err(|e| HolochainError::ErrorGeneric(format!("Could not parse WASM: {}", e)))?;
+    parity_wasm::serialize(inject_gas_metering(module))
+        .map_err(|e| HolochainError::ErrorGeneric(format!("Could not serialize WASM: {}", e)))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::wasm_engine::{
+        engine::{WasmEngine, WasmiEngine},
+        memory::WasmPageManager,
+        runtime::WasmCallData,
+        Runtime,
+    };
+    use std::sync::Arc;
+    use wabt::Wat2Wasm;
+    use wasmi::RuntimeValue;
+
+    fn test_wasm(wat: &str) -> Arc<Vec<u8>> {
+        Arc::new(
+            Wat2Wasm::new()
+                .canonicalize_lebs(false)
+                .write_debug_names(true)
+                .convert(wat)
+                .unwrap()
+                .as_ref()
+                .to_vec(),
+        )
+    }
+
+    #[test]
+    fn limits_smoke_test() {
+        assert!(WasmLimits::default().is_unlimited());
+        let limits = WasmLimits::default().with_max_instructions(10);
+        assert!(!limits.is_unlimited());
+        assert_eq!(limits.max_instructions, Some(10));
+        assert_eq!(
+            WasmLimits::default()
+                .with_max_memory_pages(2)
+                .with_max_duration_ms(100),
+            WasmLimits {
+                max_instructions: None,
+                max_memory_pages: Some(2),
+                max_duration_ms: Some(100),
+            }
+        );
+    }
+
+    #[test]
+    fn meter_enforces_instruction_and_memory_limits() {
+        let mut meter = Meter::new(WasmLimits::default().with_max_instructions(10));
+        assert_eq!(meter.charge(6, 1), Ok(()));
+        assert_eq!(meter.charge(4, 1), Ok(()));
+        assert!(meter.charge(1, 1).is_err());
+        assert!(meter.exceeded().is_some());
+        // stays exceeded
+        assert!(meter.charge(0, 1).is_err());
+
+        let mut meter = Meter::new(WasmLimits::default().with_max_memory_pages(2));
+        assert_eq!(meter.charge(1000, 2), Ok(()));
+        assert!(meter.charge(1, 3).is_err());
+    }
+
+    #[test]
+    fn instrumented_module_charges_every_run() {
+        let wasm = test_wasm(
+            r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+    (func $double (param $x i32) (result i32)
+        get_local $x
+        i32.const 2
+        i32.mul)
+    (func (export "main") (param $p0 i64) (result i64)
+        i32.const 21
+        call $double
+        drop
+        i64.const 0)
+)
+"#,
+        );
+        let module = parity_wasm::deserialize_buffer::<Module>(&wasm).unwrap();
+        let metered = inject_gas_metering(module);
+
+        let imports = metered.import_section().unwrap().entries();
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].field(), GAS_FUNCTION_NAME);
+
+        let bodies = metered.code_section().unwrap().bodies();
+        assert_eq!(
+            bodies[1].code().elements(),
+            &[
+                Instruction::I32Const(5),
+                Instruction::Call(0),
+                Instruction::I32Const(21),
+                // $double moved from index 0 to 1
+                Instruction::Call(1),
+                Instruction::Drop,
+                Instruction::I64Const(0),
+                Instruction::End,
+            ][..]
+        );
+        assert!(wasmi::Module::from_parity_wasm_module(metered).is_ok());
+    }
+
+    #[test]
+    fn endless_loop_gets_aborted() {
+        let wasm = test_wasm(
+            r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $p0 i64) (result i64)
+        (loop $forever
+            br $forever)
+        i64.const 0)
+)
+"#,
+        );
+        let module = WasmiEngine.compile(&metered_code(&wasm).unwrap()).unwrap();
+        let mut wasm_instance = module
+            .instantiate()
+            .expect("could not instantiate metered module");
+        let mut runtime = Runtime {
+            memory_manager: WasmPageManager::new(wasm_instance.memory().unwrap()),
+            data: WasmCallData::DirectCall("main".to_string(), wasm),
+            meter: Some(Meter::new(
+                WasmLimits::default().with_max_instructions(1000),
+            )),
+        };
+        assert!(wasm_instance
+            .invoke_export("main", &[RuntimeValue::I64(0)], &mut runtime)
+            .is_err());
+        let meter = runtime.meter.unwrap();
+        assert!(meter.exceeded().is_some());
+        assert!(meter.instructions() > 1000);
+    }
+
+    #[test]
+    fn memory_limit_gets_checked_right_after_growing() {
+        let wasm = test_wasm(
+            r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $p0 i64) (result i64)
+        i32.const 10
+        grow_memory
+        drop
+        i64.const 0)
+)
+"#,
+        );
+        let module = WasmiEngine.compile(&metered_code(&wasm).unwrap()).unwrap();
+        let mut wasm_instance = module
+            .instantiate()
+            .expect("could not instantiate metered module");
+        let mut runtime = Runtime {
+            memory_manager: WasmPageManager::new(wasm_instance.memory().unwrap()),
+            data: WasmCallData::DirectCall("main".to_string(), wasm),
+            meter: Some(Meter::new(WasmLimits::default().with_max_memory_pages(2))),
+        };
+        assert!(wasm_instance
+            .invoke_export("main", &[RuntimeValue::I64(0)], &mut runtime)
+            .is_err());
+        assert_eq!(
+            runtime.meter.unwrap().exceeded(),
+            Some(&String::from("memory grew to 11 pages, only 2 allowed"))
+        );
+    }
+}
@@ -2,8 +2,10 @@
 
 pub mod api;
 pub mod callback;
+pub mod engine;
 pub mod factories;
 pub mod memory;
+pub mod metering;
 #[autotrace]
 mod run_dna;
 pub mod runtime;
@@ -1,34 +1,34 @@
 use crate::{
     nucleus::ZomeFnResult,
     wasm_engine::{
+        engine::{get_zome_module, WasmModule, WasmiEngine},
         factories::{wasm_instance_factory, wasm_module_factory},
         memory::WasmPageManager,
+        metering::Meter,
         runtime::{Runtime, WasmCallData},
     },
 };
-use holochain_core_types::{
-    dna::wasm::ModuleArc,
-    error::{
-        HcResult, HolochainError, RibosomeEncodedValue, RibosomeEncodingBits, RibosomeRuntimeBits,
-    },
+use holochain_core_types::error::{
+    HcResult, HolochainError, RibosomeEncodedValue, RibosomeEncodingBits, RibosomeRuntimeBits,
 };
 use holochain_json_api::json::JsonString;
 
 use holochain_wasm_utils::memory::allocation::{AllocationError, WasmAllocation};
-use std::convert::TryFrom;
+use std::{convert::TryFrom, sync::Arc};
 use wasmi::RuntimeValue;
 
 /// Returns the WASM module, i.e. the WASM binary program code to run
 /// for the given WasmCallData.
 ///
-/// In case of a direct call, the module gets created from the WASM binary
+/// In case of a direct call, the module gets created by the wasmi engine from the WASM binary
 /// inside the DirectCall specialisation for WasmCallData.
 ///
-/// For ZomeCalls and CallbackCalls it gets the according module from the DNA.
+/// For ZomeCalls and CallbackCalls it gets the according module from the DNA,
+/// compiled by the engine of the instance.
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
-fn get_module(data: WasmCallData) -> Result<ModuleArc, HolochainError> {
+fn get_module(data: WasmCallData) -> Result<Arc<dyn WasmModule>, HolochainError> {
     let (context, zome_name) = if let WasmCallData::DirectCall(_, wasm) = data {
-        let transient_module = ModuleArc::new(wasm_module_factory(wasm)?);
+        let transient_module = wasm_module_factory(&WasmiEngine, &wasm)?;
         return Ok(transient_module);
     } else {
         match data {
@@ -39,18 +39,17 @@ fn get_module(data: WasmCallData) -> Result<ModuleArc, HolochainError> {
     };
 
     let state_lock = context.state()?;
-    let module = state_lock
-        .nucleus()
+    let nucleus = state_lock.nucleus();
+    let wasm = &nucleus
         .dna
         .as_ref()
         .unwrap()
         .zomes
         .get(&zome_name)
         .ok_or_else(|| HolochainError::new(&format!("No Ribosome found for Zome '{}'", zome_name)))?
-        .code
-        .get_wasm_module()?;
+        .code;
 
-    Ok(module)
+    get_zome_module(&context, &zome_name, wasm)
 }
 
 /// Executes an exposed zome function in a wasm binary.
@@ -60,16 +59,22 @@ fn get_module(data: WasmCallData) -> Result<ModuleArc, HolochainError> {
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 pub fn run_dna(parameters: Option<Vec<u8>>, data: WasmCallData) -> ZomeFnResult {
     let wasm_module = get_module(data.clone())?;
-    let wasm_instance = wasm_instance_factory(&wasm_module)?;
+    let mut wasm_instance = wasm_instance_factory(&*wasm_module)?;
     // write input arguments for module call in memory Buffer
     let input_parameters: Vec<_> = parameters.unwrap_or_default();
 
     let fn_name = data.fn_name();
     // instantiate runtime struct for passing external state data over wasm but not to wasm
     let mut runtime = Runtime {
-        memory_manager: WasmPageManager::new(&wasm_instance),
+        memory_manager: WasmPageManager::new(wasm_instance.memory()?),
         data,
+        meter: None,
     };
+    runtime.meter = runtime
+        .context()
+        .ok()
+        .filter(|context| !context.wasm_limits.is_unlimited())
+        .map(|context| Meter::new(context.wasm_limits.clone()));
 
     // Write input arguments in wasm memory
     // scope for mutable borrow of runtime
@@ -113,10 +118,12 @@ pub fn run_dna(parameters: Option<Vec<u8>>, data: WasmCallData) -> ZomeFnResult
                 mut_runtime,
             )
             .map_err(|err| {
-                HolochainError::RibosomeFailed(format!(
-                    "WASM invocation failed: {}. data = {:?}",
-                    err, runtime.data
-                ))
+                runtime.wasm_limit_error().unwrap_or_else(|| {
+                    HolochainError::RibosomeFailed(format!(
+                        "WASM invocation failed: {}. data = {:?}",
+                        err, runtime.data
+                    ))
+                })
             })?
             .unwrap()
             .try_into() // Option<_>
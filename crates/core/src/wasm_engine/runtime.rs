@@ -4,6 +4,7 @@ use crate::{
     wasm_engine::{
         api::{ZomeApiFunction, ZomeApiResult},
         memory::WasmPageManager,
+        metering::{Meter, WasmLimitError, GAS_FUNCTION_INDEX},
         Defn,
     },
 };
@@ -103,6 +104,9 @@ pub struct Runtime {
 
     /// data to be made available to the function at runtime
     pub data: WasmCallData,
+
+    /// Resource usage of the call if it runs with limits
+    pub meter: Option<Meter>,
 }
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
@@ -211,6 +215,35 @@ impl Runtime {
         }
     }
 
+    /// Charges the call for the instructions instrumented code reports through the gas function.
+    /// Traps with a WasmLimitError as soon as the call breaches one of its limits.
+    fn charge_gas(&mut self, args: &RuntimeArgs) -> ZomeApiResult {
+        let instructions: i32 = args.nth(0);
+        let memory_pages = self.memory_manager.current_pages();
+        let charged = match self.meter {
+            Some(ref mut meter) => meter.charge(instructions as u64, memory_pages),
+            None => return Ok(None),
+        };
+        let reason = match charged {
+            Ok(()) => return Ok(None),
+            Err(reason) => reason,
+        };
+        // The trap ends the call, so this is the only time the breach gets reported
+        if let Ok(context) = self.context() {
+            context.record_wasm_limit_breach();
+            log_warn!(context, "wasm_engine: {} aborted: {}", self.data, reason);
+        }
+        Err(Trap::new(TrapKind::Host(Box::new(WasmLimitError(reason)))))
+    }
+
+    /// The error to report if the call got aborted because it breached one of its limits
+    pub fn wasm_limit_error(&self) -> Option<HolochainError> {
+        self.meter
+            .as_ref()
+            .and_then(|meter| meter.exceeded())
+            .map(|reason| HolochainError::WasmLimitExceeded(reason.clone()))
+    }
+
     pub fn store_result<J: Into<JsonString>>(
         &mut self,
         result: Result<J, HolochainError>,
@@ -226,6 +259,9 @@ impl Runtime {
 // by implementing the Externals trait from Wasmi.
 impl Externals for Runtime {
     fn invoke_index(&mut self, index: usize, args: RuntimeArgs) -> ZomeApiResult {
+        if index == GAS_FUNCTION_INDEX {
+            return self.charge_gas(&args);
+        }
         let zf = ZomeApiFunction::from_index(index);
         match zf {
             ZomeApiFunction::MissingNo => panic!("unknown function index"),
@@ -0,0 +1,61 @@
+use crate::{
+    agent::{actions::commit::commit_bundle, bundle::Bundle},
+    context::Context,
+    workflows::author_entry::{publish_authored_entry, validate_authored_entry},
+};
+use holochain_core_types::error::HolochainError;
+use holochain_persistence_api::cas::content::Address;
+use std::sync::Arc;
+
+/// Authors all entries of a bundle as one unit.
+/// Every entry gets validated before any is committed and all of them get committed in a
+/// single action, so an invalid entry, a failing commit or a timed out bundle leaves the
+/// source chain untouched.
+/// The bundle has to still be open in the agent state while this runs, so that links between
+/// staged entries can be validated.
+///
+/// Returns the addresses of the committed entries in the order they were staged.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn author_bundle(
+    bundle: &Bundle,
+    context: &Arc<Context>,
+) -> Result<Vec<Address>, HolochainError> {
+    log_debug!(
+        context,
+        "workflow/author_bundle: {} entries of zome call {}",
+        bundle.entries().len(),
+        bundle.zome_call_id()
+    );
+
+    if bundle.is_expired() {
+        return Err(HolochainError::Timeout(format!(
+            "bundle of zome call {} timed out",
+            bundle.zome_call_id()
+        )));
+    }
+
+    // 1. Validate all entries before committing any of them
+    for bundled in bundle.entries() {
+        validate_authored_entry(
+            &bundled.entry,
+            bundled.maybe_link_update_delete.clone(),
+            context,
+            &bundled.provenances,
+        )
+        .await?;
+    }
+    log_debug!(context, "workflow/author_bundle: all entries are valid");
+
+    // 2. Commit them in the order they were staged, in one action so they either all
+    // end up on the chain or none does
+    let addresses = commit_bundle(bundle.entries().clone(), context).await?;
+    log_debug!(context, "workflow/author_bundle: committed");
+
+    // 3. Publish entries and headers
+    for bundled in bundle.entries() {
+        publish_authored_entry(&bundled.entry, context).await?;
+    }
+    log_debug!(context, "workflow/author_bundle: published");
+
+    Ok(addresses)
+}
@@ -39,6 +39,40 @@ pub async fn author_entry<'a>(
         entry
     );
 
+    validate_authored_entry(
+        entry,
+        maybe_link_update_delete.clone(),
+        context,
+        provenances,
+    )
+    .await?;
+
+    // 3. Commit the entry
+    log_debug!(
+        context,
+        "workflow/authoring_entry/{}: committing...",
+        address
+    );
+    let addr = commit_entry(entry.clone(), maybe_link_update_delete, &context).await?;
+    log_debug!(context, "workflow/authoring_entry/{}: committed", address);
+
+    publish_authored_entry(entry, context).await?;
+
+    Ok(CommitEntryResult::new(addr))
+}
+
+/// Steps 0. to 2. of authoring an entry, i.e. everything that has to succeed
+/// before the entry can be committed.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+#[allow(clippy::ptr_arg)]
+pub async fn validate_authored_entry<'a>(
+    entry: &'a Entry,
+    maybe_link_update_delete: Option<Address>,
+    context: &'a Arc<Context>,
+    provenances: &'a Vec<Provenance>,
+) -> Result<(), HolochainError> {
+    let address = entry.address();
+
     // 0. If we are trying to author a link or link removal, make sure the linked entries exist:
     if let Entry::LinkAdd(link_data) = entry {
         get_link_entries(&link_data.link, context)?;
@@ -52,6 +86,7 @@ pub async fn author_entry<'a>(
     let validation_data = ValidationData {
         package: validation_package,
         lifecycle: EntryLifecycle::Chain,
+        chain_forks: Vec::new(),
     };
 
     // 2. Validate the entry
@@ -62,22 +97,23 @@ pub async fn author_entry<'a>(
     );
     validate_entry(
         entry.clone(),
-        maybe_link_update_delete.clone(),
+        maybe_link_update_delete,
         validation_data,
         &context,
         ValidationContext::Authoring,
     )
     .await?;
     log_debug!(context, "worflow/authoring_entry {}: is valid!", address);
+    Ok(())
+}
 
-    // 3. Commit the entry
-    log_debug!(
-        context,
-        "workflow/authoring_entry/{}: committing...",
-        address
-    );
-    let addr = commit_entry(entry.clone(), maybe_link_update_delete, &context).await?;
-    log_debug!(context, "workflow/authoring_entry/{}: committed", address);
+/// Steps 4. and 5. of authoring an entry, i.e. what happens after it got committed.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn publish_authored_entry<'a>(
+    entry: &'a Entry,
+    context: &'a Arc<Context>,
+) -> Result<(), HolochainError> {
+    let address = entry.address();
 
     // 4. Publish the valid entry to DHT. This will call Hold to itself
     if entry.entry_type().can_publish(context) {
@@ -108,8 +144,7 @@ pub async fn author_entry<'a>(
         "debug/workflow/authoring_entry/{}: header published!",
         address
     );
-
-    Ok(CommitEntryResult::new(addr))
+    Ok(())
 }
 // TODO: Bring the old in-memory network up to speed and turn on this test again!
 
@@ -117,6 +152,7 @@ pub async fn author_entry<'a>(
 pub mod tests {
 
     use crate::{
+        agent::state::AgentState,
         holochain_wasm_utils::holochain_persistence_api::cas::content::AddressableContent,
         nucleus::actions::{
             get_entry::get_entry_from_dht,
@@ -126,8 +162,10 @@ pub mod tests {
     };
     use holochain_core_types::{
         chain_header::ChainHeader,
+        chain_migrate::ChainMigrate,
         entry::{test_entry_with_value, Entry},
     };
+    use holochain_persistence_api::cas::content::Address;
     use std::{thread, time};
 
     // TODO do this for all crate tests somehow
@@ -309,4 +347,44 @@ pub mod tests {
 
         assert_eq!(jack_headers, jill_headers,);
     }
+
+    #[test]
+    /// test that nothing can be committed to a chain after it got closed by a ChainMigrate entry
+    fn test_no_commits_after_chain_migrate() {
+        let mut dna = test_dna();
+        dna.uuid = "test_no_commits_after_chain_migrate".to_string();
+        let (_instance, context) = instance_by_name("jill", dna.clone(), None);
+
+        let migrate = ChainMigrate::new(
+            dna.address(),
+            Address::from("new-dna"),
+            context.agent_id.address(),
+            "testing".to_string(),
+        );
+        context
+            .block_on(author_entry(
+                &Entry::ChainMigrate(migrate),
+                None,
+                &context,
+                &vec![],
+            ))
+            .expect("Could not close chain");
+        let agent = context.state().unwrap().agent();
+        assert!(agent.is_chain_closed());
+        // the flag gets rebuilt from the chain when the state gets restored
+        let restored = AgentState::new_with_top_chain_header(
+            agent.chain_store(),
+            agent.top_chain_header(),
+            context.agent_id.address(),
+        );
+        assert!(restored.is_chain_closed());
+
+        let result = context.block_on(author_entry(
+            &test_entry_with_value("{\"stuff\":\"after close\"}"),
+            None,
+            &context,
+            &vec![],
+        ));
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,494 @@
+use crate::{
+    action::{Action, ActionWrapper, DirectMessageData},
+    agent::{
+        actions::commit::commit_entry_with_provenances, countersigning::CountersigningSession,
+    },
+    context::Context,
+    instance::dispatch_action,
+    network::{actions::custom_send::send_and_wait, direct_message::DirectMessage},
+    workflows::author_entry::{publish_authored_entry, validate_authored_entry},
+};
+
+use futures::future::join_all;
+use holochain_core_types::{
+    countersigning::{countersigning_payload, CountersigningRequest},
+    entry::Entry,
+    error::HolochainError,
+    signature::{Provenance, Signature},
+    time::Timeout,
+};
+use holochain_dpki::utils::Verify;
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
+use std::{
+    convert::TryFrom,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Proposes the given entry to the given parties and, once all of them signed it within
+/// the timeout, has every party commit it with all their signatures before committing it
+/// ourselves. If a party refuses or doesn't answer in time, the parties that signed are
+/// told to abort and nothing gets committed.
+///
+/// Parties keep their session open for another timeout after signing, during which
+/// commits that time out are sent again. If a party still didn't commit by then, we
+/// don't commit either and return which parties did.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn countersign_entry_workflow(
+    entry: Entry,
+    parties: Vec<Address>,
+    timeout: Timeout,
+    context: Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let address = entry.address();
+    let mut all_parties = vec![context.agent_id.address()];
+    for party in parties {
+        if !all_parties.contains(&party) {
+            all_parties.push(party);
+        }
+    }
+    let others = all_parties[1..].to_vec();
+    if others.is_empty() {
+        return Err(HolochainError::ErrorGeneric(
+            "Countersigning needs at least one other party".to_string(),
+        ));
+    }
+
+    // Parties start their session after we do, so they are still open by then.
+    let commit_deadline = Instant::now() + Duration::from(&timeout) * 2;
+    validate_authored_entry(&entry, None, &context, &Vec::new()).await?;
+    let payload = countersigning_payload(&address, &all_parties);
+    let request = CountersigningRequest {
+        entry: entry.clone(),
+        parties: all_parties,
+        timeout: timeout.clone(),
+    };
+
+    log_debug!(
+        context,
+        "workflow/countersign: Collecting signatures of {} parties for {}",
+        others.len(),
+        address
+    );
+    let responses = join_all(others.iter().map(|party| {
+        send_and_wait(
+            party.clone(),
+            DirectMessage::CountersignRequest(request.clone()),
+            timeout.clone(),
+            context.clone(),
+        )
+    }))
+    .await;
+    let mut provenances = vec![sign(payload.clone(), &context)?];
+    let mut refusals = Vec::new();
+    for (party, response) in others.iter().zip(responses) {
+        match response.and_then(|json| check_countersignature(party, &payload, json)) {
+            Ok(provenance) => provenances.push(provenance),
+            Err(error) => refusals.push(format!("{}: {}", party, error)),
+        }
+    }
+
+    if !refusals.is_empty() {
+        // Only the parties that signed hold a session to abort,
+        // the others drop theirs once the timeout runs out.
+        let signed: Vec<Address> = provenances[1..]
+            .iter()
+            .map(|provenance| provenance.source())
+            .collect();
+        abort_countersigning(signed, &address, &timeout, &context).await;
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Countersigning of {} aborted, not all parties signed: {}",
+            address,
+            refusals.join(", ")
+        )));
+    }
+
+    let (committed, failures) = collect_commits(
+        &others,
+        &entry,
+        &provenances,
+        commit_deadline,
+        &timeout,
+        &context,
+    )
+    .await;
+    if !failures.is_empty() {
+        let failed = others
+            .iter()
+            .filter(|party| !committed.contains(party))
+            .cloned()
+            .collect();
+        abort_countersigning(failed, &address, &timeout, &context).await;
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Not committing countersigned entry {}, not all parties did: {}. Committed by: [{}]",
+            address,
+            failures.join(", "),
+            committed
+                .iter()
+                .map(|party| party.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )));
+    }
+
+    author_countersigned_entry(&entry, provenances, &context).await
+}
+
+/// Sends the signed entry to all parties to commit until each of them did, refused, or the
+/// deadline passed. Commits that time out get sent again, as parties answer a commit of an
+/// entry that is already on their chain with its address.
+/// Returns the parties that committed and why the others didn't.
+async fn collect_commits(
+    parties: &[Address],
+    entry: &Entry,
+    provenances: &[Provenance],
+    deadline: Instant,
+    timeout: &Timeout,
+    context: &Arc<Context>,
+) -> (Vec<Address>, Vec<String>) {
+    let mut committed = Vec::new();
+    let mut failures = Vec::new();
+    let mut pending = parties.to_vec();
+    while !pending.is_empty() {
+        let now = Instant::now();
+        if now >= deadline {
+            failures.extend(
+                pending
+                    .iter()
+                    .map(|party| format!("{}: Timed out committing", party)),
+            );
+            break;
+        }
+        let attempt_timeout = Timeout::from(
+            std::cmp::min(Duration::from(timeout), deadline - now).as_millis() as usize,
+        );
+        let responses = join_all(pending.iter().map(|party| {
+            send_and_wait(
+                party.clone(),
+                DirectMessage::CountersignCommit((entry.clone(), provenances.to_vec())),
+                attempt_timeout.clone(),
+                context.clone(),
+            )
+        }))
+        .await;
+        let mut retries = Vec::new();
+        for (party, response) in pending.into_iter().zip(responses) {
+            match response {
+                Ok(_) => committed.push(party),
+                Err(HolochainError::Timeout(_)) => retries.push(party),
+                Err(error) => failures.push(format!("{}: {}", party, error)),
+            }
+        }
+        pending = retries;
+    }
+    (committed, failures)
+}
+
+async fn abort_countersigning(
+    parties: Vec<Address>,
+    entry_address: &Address,
+    timeout: &Timeout,
+    context: &Arc<Context>,
+) {
+    join_all(parties.into_iter().map(|party| {
+        send_and_wait(
+            party,
+            DirectMessage::CountersignAbort(entry_address.clone()),
+            timeout.clone(),
+            context.clone(),
+        )
+    }))
+    .await;
+}
+
+/// The proposer of a countersigned entry asks us to sign it.
+/// We do if we are one of its parties and the entry is valid for us,
+/// and then wait for the proposer to send us the signatures of all parties.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn handle_countersign_request(
+    from_agent_id: Address,
+    msg_id: String,
+    request: CountersigningRequest,
+    context: Arc<Context>,
+) {
+    let result = sign_countersigning_request(&from_agent_id, &request, &context)
+        .await
+        .map(|provenance| String::from(JsonString::from(provenance)));
+    if let Err(ref error) = result {
+        log_warn!(
+            context,
+            "workflow/countersign: Not signing {} for {}: {}",
+            request.entry.address(),
+            from_agent_id,
+            error
+        );
+    }
+    respond(from_agent_id, msg_id, result, &context);
+}
+
+/// The proposer sends us the signatures of all parties of an entry we signed,
+/// so we commit it onto our chain before the proposer does.
+/// If it is already on our chain, the proposer didn't get our answer and sent it again.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn handle_countersign_commit(
+    from_agent_id: Address,
+    msg_id: String,
+    entry: Entry,
+    provenances: Vec<Provenance>,
+    context: Arc<Context>,
+) {
+    let result = commit_countersigned_entry(&from_agent_id, &entry, provenances, &context)
+        .await
+        .map(|address| address.to_string());
+    match result {
+        Ok(_) => dispatch_action(
+            context.action_channel(),
+            ActionWrapper::new(Action::EndCountersigning(entry.address())),
+        ),
+        Err(ref error) => log_warn!(
+            context,
+            "workflow/countersign: Not committing {} from {}: {}",
+            entry.address(),
+            from_agent_id,
+            error
+        ),
+    }
+    respond(from_agent_id, msg_id, result, &context);
+}
+
+/// The proposer gave up on an entry we signed.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn handle_countersign_abort(
+    from_agent_id: Address,
+    msg_id: String,
+    entry_address: Address,
+    context: Arc<Context>,
+) {
+    let result = countersigning_session(&from_agent_id, &entry_address, &context).map(|_| {
+        log_debug!(
+            context,
+            "workflow/countersign: {} aborted countersigning of {}",
+            from_agent_id,
+            entry_address
+        );
+        dispatch_action(
+            context.action_channel(),
+            ActionWrapper::new(Action::EndCountersigning(entry_address.clone())),
+        );
+        entry_address.to_string()
+    });
+    respond(from_agent_id, msg_id, result, &context);
+}
+
+async fn sign_countersigning_request(
+    from_agent_id: &Address,
+    request: &CountersigningRequest,
+    context: &Arc<Context>,
+) -> Result<Provenance, HolochainError> {
+    if request.parties.first() != Some(from_agent_id) {
+        return Err(HolochainError::ErrorGeneric(
+            "Only the proposer can ask for countersignatures".to_string(),
+        ));
+    }
+    if !request.parties.contains(&context.agent_id.address()) {
+        return Err(HolochainError::ErrorGeneric(
+            "We are not a party of this entry".to_string(),
+        ));
+    }
+    validate_authored_entry(&request.entry, None, context, &Vec::new()).await?;
+    let provenance = sign(
+        countersigning_payload(&request.entry.address(), &request.parties),
+        context,
+    )?;
+    dispatch_action(
+        context.action_channel(),
+        ActionWrapper::new(Action::StartCountersigning(CountersigningSession::new(
+            request,
+        ))),
+    );
+    Ok(provenance)
+}
+
+async fn commit_countersigned_entry(
+    from_agent_id: &Address,
+    entry: &Entry,
+    provenances: Vec<Provenance>,
+    context: &Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let session = match countersigning_session(from_agent_id, &entry.address(), context) {
+        Ok(session) => session,
+        Err(error) => {
+            return if is_on_our_chain(&entry.address(), context) {
+                Ok(entry.address())
+            } else {
+                Err(error)
+            }
+        }
+    };
+    let mut signers: Vec<Address> = provenances
+        .iter()
+        .map(|provenance| provenance.source())
+        .collect();
+    signers.sort();
+    let mut parties = session.parties().clone();
+    parties.sort();
+    if signers != parties {
+        return Err(HolochainError::ErrorGeneric(
+            "The signatures don't match the parties of the entry".to_string(),
+        ));
+    }
+    author_countersigned_entry(entry, provenances, context).await
+}
+
+/// Commits and publishes a countersigned entry with our own provenance first,
+/// as that is the one the rest of the system takes as the author.
+async fn author_countersigned_entry(
+    entry: &Entry,
+    mut provenances: Vec<Provenance>,
+    context: &Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let me = context.agent_id.address();
+    provenances.sort_by_key(|provenance| provenance.source() != me);
+    validate_authored_entry(entry, None, context, &provenances).await?;
+    let address = commit_entry_with_provenances(entry.clone(), None, provenances, context).await?;
+    publish_authored_entry(entry, context).await?;
+    Ok(address)
+}
+
+fn countersigning_session(
+    from_agent_id: &Address,
+    entry_address: &Address,
+    context: &Arc<Context>,
+) -> Result<CountersigningSession, HolochainError> {
+    let session = context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("State not initialized".to_string()))?
+        .agent()
+        .countersigning_session(entry_address)
+        .cloned()
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!(
+                "No countersigning of {} in progress",
+                entry_address
+            ))
+        })?;
+    if session.proposer() != Some(from_agent_id) {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Countersigning of {} was proposed by another agent",
+            entry_address
+        )));
+    }
+    Ok(session)
+}
+
+fn is_on_our_chain(entry_address: &Address, context: &Arc<Context>) -> bool {
+    context
+        .state()
+        .map(|state| {
+            state
+                .agent()
+                .iter_chain()
+                .any(|header| header.entry_address() == entry_address)
+        })
+        .unwrap_or(false)
+}
+
+fn sign(payload: String, context: &Arc<Context>) -> Result<Provenance, HolochainError> {
+    let signature = context.conductor_api.execute(payload, CryptoMethod::Sign)?;
+    Ok(Provenance::new(
+        context.agent_id.address(),
+        Signature::from(signature),
+    ))
+}
+
+fn check_countersignature(
+    party: &Address,
+    payload: &str,
+    response: String,
+) -> Result<Provenance, HolochainError> {
+    let provenance = Provenance::try_from(JsonString::from_json(&response))?;
+    if &provenance.source() != party {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Signed by {} instead",
+            provenance.source()
+        )));
+    }
+    if !provenance.verify(payload.to_string())? {
+        return Err(HolochainError::ErrorGeneric(
+            "Invalid countersignature".to_string(),
+        ));
+    }
+    Ok(provenance)
+}
+
+fn respond(
+    to_agent: Address,
+    msg_id: String,
+    result: Result<String, HolochainError>,
+    context: &Arc<Context>,
+) {
+    let direct_message_data = DirectMessageData {
+        address: to_agent,
+        message: DirectMessage::CountersignResponse(result.map_err(|error| error.to_string())),
+        msg_id,
+        is_response: true,
+    };
+    let action_wrapper = ActionWrapper::new(Action::SendDirectMessage((direct_message_data, None)));
+    dispatch_action(context.action_channel(), action_wrapper);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        agent::countersigning::tests::test_countersigning_session, instance::tests::test_context,
+        state::StateWrapper,
+    };
+    use holochain_core_types::entry::test_entry;
+    use holochain_locksmith::RwLock;
+
+    #[test]
+    fn only_signatures_of_the_party_over_the_payload_are_accepted() {
+        let context = test_context("alice", None);
+        let alice = context.agent_id.address();
+        let payload = countersigning_payload(&Address::from("QmEntry"), &[alice.clone()]);
+        let response = String::from(JsonString::from(sign(payload.clone(), &context).unwrap()));
+
+        assert!(check_countersignature(&alice, &payload, response.clone()).is_ok());
+        assert!(check_countersignature(&Address::from("bob"), &payload, response.clone()).is_err());
+        assert!(check_countersignature(&alice, "something else", response).is_err());
+    }
+
+    #[test]
+    fn commits_arriving_after_the_session_expired_are_refused() {
+        let alice = Address::from("alice");
+        let bob = test_context("bob", None);
+        let mut context = (*bob).clone();
+        let state = StateWrapper::new(bob.clone()).reduce(ActionWrapper::new(
+            Action::StartCountersigning(test_countersigning_session(0)),
+        ));
+        context.set_state(Arc::new(RwLock::new(state)));
+        let context = Arc::new(context);
+        std::thread::sleep(Duration::from_millis(5));
+
+        let provenances = vec![
+            Provenance::new(alice.clone(), Signature::from("alice's signature")),
+            Provenance::new(
+                context.agent_id.address(),
+                Signature::from("bob's signature"),
+            ),
+        ];
+        let result = context.block_on(commit_countersigned_entry(
+            &alice,
+            &test_entry(),
+            provenances,
+            &context,
+        ));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("No countersigning of"));
+    }
+}
@@ -5,18 +5,21 @@ use crate::{
     nucleus::validation::{validate_entry, ValidationContext},
 };
 
-use crate::{nucleus::validation::process_validation_err, workflows::validation_package};
+use crate::{
+    nucleus::validation::process_validation_err,
+    workflows::{validation_package, validation_receipt::send_validation_receipt},
+};
 use holochain_core_types::{
     error::HolochainError,
     network::entry_aspect::EntryAspect,
     validation::{EntryLifecycle, ValidationData},
 };
 use holochain_persistence_api::cas::content::AddressableContent;
-use snowflake::ProcessUniqueId;
 use std::sync::Arc;
+use uuid::Uuid;
 
 pub async fn hold_content_aspect(
-    pending_id: &ProcessUniqueId,
+    pending_id: &Uuid,
     entry_with_header: &EntryWithHeader,
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
@@ -36,7 +39,7 @@ pub async fn hold_content_aspect(
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 pub async fn hold_entry_workflow(
-    pending_id: &ProcessUniqueId,
+    pending_id: &Uuid,
     entry_with_header: &EntryWithHeader,
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
@@ -59,6 +62,7 @@ pub async fn hold_entry_workflow(
     let validation_data = ValidationData {
         package: validation_package,
         lifecycle: EntryLifecycle::Dht,
+        chain_forks: Vec::new(),
     };
 
     // 3. Validate the entry
@@ -86,7 +90,16 @@ pub async fn hold_entry_workflow(
     );
 
     // 4. If valid store the entry's content aspect in the local DHT shard
-    hold_content_aspect(pending_id, entry_with_header, context).await?;
+    hold_content_aspect(pending_id, entry_with_header, context.clone()).await?;
+
+    // 5. Let the author know that we validated and hold their entry
+    if let Err(error) = send_validation_receipt(&entry_with_header.header, context.clone()) {
+        log_error!(
+            context,
+            "workflow/hold_entry: Could not send validation receipt: {}",
+            error
+        );
+    }
 
     Ok(())
 }
@@ -11,12 +11,12 @@ use holochain_core_types::{
     validation::{EntryLifecycle, ValidationData},
 };
 use holochain_persistence_api::cas::content::AddressableContent;
-use snowflake::ProcessUniqueId;
 use std::sync::Arc;
+use uuid::Uuid;
 
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
 pub async fn hold_update_workflow(
-    pending_id: &ProcessUniqueId,
+    pending_id: &Uuid,
     entry_with_header: &EntryWithHeader,
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
@@ -43,6 +43,7 @@ pub async fn hold_update_workflow(
     let validation_data = ValidationData {
         package: validation_package,
         lifecycle: EntryLifecycle::Meta,
+        chain_forks: Vec::new(),
     };
 
     // 3. Validate the entry
@@ -0,0 +1,74 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    dht::actions::hold_header::hold_authored_header,
+    instance::dispatch_action,
+    nucleus::validation::validate_header_signatures,
+};
+use holochain_core_types::{chain_header::ChainHeader, error::HolochainError};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::sync::Arc;
+
+/// The network asks us to hold a header that got published to the address of its author.
+/// We index it by its predecessor, which lets us notice if the author's chain forks.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn hold_header_workflow(
+    author: Address,
+    header: ChainHeader,
+    context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    // 1. Check that the header is one the author signed
+    if !header
+        .provenances()
+        .iter()
+        .any(|provenance| provenance.source() == author)
+    {
+        return Err(HolochainError::ValidationFailed(format!(
+            "Header {} was not signed by {}",
+            header.address(),
+            author
+        )));
+    }
+    validate_header_signatures(&header).map_err(|error| {
+        HolochainError::ValidationFailed(format!(
+            "Header {} has an invalid signature: {:?}",
+            header.address(),
+            error
+        ))
+    })?;
+
+    // 2. The first header of a chain has no predecessor to fork from
+    if header.link().is_none() {
+        return Ok(());
+    }
+    hold_authored_header(author.clone(), header.clone(), context.clone()).await?;
+    log_debug!(
+        context,
+        "workflow/hold_header: holding header {} of {}",
+        header.address(),
+        author
+    );
+
+    // 3. The header we now hold may share its predecessor with another one of its author
+    match context.state()?.dht().fork_of(&header, &author) {
+        Ok(Some(fork)) => {
+            log_warn!(
+                context,
+                "workflow/hold_header: chain of {} forks after header {}",
+                fork.author,
+                fork.predecessor
+            );
+            dispatch_action(
+                context.action_channel(),
+                ActionWrapper::new(Action::FlagChainFork(fork)),
+            );
+        }
+        Ok(None) => (),
+        Err(error) => log_error!(
+            context,
+            "workflow/hold_header: Could not check for chain fork: {}",
+            error
+        ),
+    }
+    Ok(())
+}
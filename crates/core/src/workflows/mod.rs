@@ -1,8 +1,12 @@
 #[autotrace]
 pub mod application;
 #[autotrace]
+pub mod author_bundle;
+#[autotrace]
 pub mod author_entry;
 #[autotrace]
+pub mod countersign;
+#[autotrace]
 pub mod get_entry_result;
 #[autotrace]
 pub mod get_link_result;
@@ -17,11 +21,17 @@ pub mod hold_entry_remove;
 #[autotrace]
 pub mod hold_entry_update;
 #[autotrace]
+pub mod hold_header;
+#[autotrace]
 pub mod hold_link;
 #[autotrace]
 pub mod remove_link;
 #[autotrace]
 pub mod respond_validation_package_request;
+#[autotrace]
+pub mod validation_receipt;
+#[autotrace]
+pub mod warrant;
 
 use crate::{
     context::Context,
@@ -39,7 +49,7 @@ use crate::{
     workflows::{
         hold_entry::hold_entry_workflow, hold_entry_remove::hold_remove_workflow,
         hold_entry_update::hold_update_workflow, hold_link::hold_link_workflow,
-        remove_link::remove_link_workflow,
+        remove_link::remove_link_workflow, warrant::issue_warrant,
     },
 };
 use holochain_core_types::{
@@ -226,10 +236,14 @@ async fn validation_package(
 pub mod tests {
     use super::validation_package;
     use crate::{
-        network::entry_with_header::EntryWithHeader, nucleus::actions::tests::*,
+        network::entry_with_header::EntryWithHeader,
+        nucleus::{
+            actions::tests::*,
+            validation::{validate_entry, ValidationContext},
+        },
         workflows::author_entry::author_entry,
     };
-    use holochain_core_types::entry::Entry;
+    use holochain_core_types::{entry::Entry, validation::ValidationData};
     use holochain_json_api::json::JsonString;
     use std::{thread, time};
 
@@ -277,15 +291,65 @@ pub mod tests {
             3
         );
     }
+
+    #[test]
+    fn holders_accept_entries_whose_package_only_has_the_header() {
+        let mut dna = test_dna();
+        dna.uuid = "holders_accept_entries_whose_package_only_has_the_header".to_string();
+        let netname = Some("holders_accept_entries_whose_package_only_has_the_header");
+        let (_instance1, context1) = instance_by_name("jill", dna.clone(), netname);
+        let (_instance2, context2) = instance_by_name("jack", dna, netname);
+
+        // package_entry's validation package definition is Entry
+        let entry = Entry::App(
+            "package_entry".into(),
+            JsonString::from_json("{\"stuff\":\"test entry value\"}"),
+        );
+        context2
+            .block_on(author_entry(&entry, None, &context2, &vec![]))
+            .unwrap();
+        let header = context2
+            .state()
+            .unwrap()
+            .agent()
+            .iter_chain()
+            .next()
+            .expect("Must be able to get header for just published entry");
+        let entry_with_header = EntryWithHeader {
+            entry: entry.clone(),
+            header,
+        };
+
+        let package = context1
+            .block_on(validation_package(&entry_with_header, context1.clone()))
+            .expect("Could not build validation package as holder")
+            .expect("No validation package");
+        assert_eq!(package.source_chain_headers, None);
+        let validation_data = ValidationData {
+            package,
+            ..Default::default()
+        };
+        assert_eq!(
+            context1.block_on(validate_entry(
+                entry,
+                None,
+                validation_data,
+                &context1,
+                ValidationContext::Holding,
+            )),
+            Ok(())
+        );
+    }
 }
 
 /// Runs the given pending validation using the right holding workflow
 /// as specified by PendingValidationStruct::workflow.
+/// Issues a warrant against the author if the entry turns out to be invalid.
 pub async fn run_holding_workflow(
     pending: PendingValidation,
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
-    match pending.workflow {
+    let result = match pending.workflow {
         ValidatingWorkflow::HoldLink => {
             hold_link_workflow(&pending.uuid, &pending.entry_with_header, context.clone()).await
         }
@@ -301,5 +365,15 @@ pub async fn run_holding_workflow(
         ValidatingWorkflow::RemoveEntry => {
             hold_remove_workflow(&pending.uuid, &pending.entry_with_header, context.clone()).await
         }
+    };
+    if let Err(HolochainError::ValidationFailed(reason)) = &result {
+        if let Err(error) = issue_warrant(&pending, reason.clone(), context.clone()).await {
+            log_error!(
+                context,
+                "workflow/warrant: Could not issue warrant: {}",
+                error
+            );
+        }
     }
+    result
 }
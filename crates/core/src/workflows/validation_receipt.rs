@@ -0,0 +1,142 @@
+use crate::{
+    action::{Action, ActionWrapper, DirectMessageData},
+    content_store::GetContent,
+    context::Context,
+    instance::dispatch_action,
+    network::{direct_message::DirectMessage, validation_receipt::ValidationReceipt},
+};
+
+use holochain_core_types::{chain_header::ChainHeader, error::HolochainError};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+pub const VALIDATION_RECEIPT_MESSAGE_TIMEOUT_MS: u64 = 30000;
+
+/// Sends a signed receipt for the entry of the given header to its author after we
+/// validated and stored it. Nothing gets sent for our own entries.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn send_validation_receipt(
+    header: &ChainHeader,
+    context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    let author = header
+        .provenances()
+        .first()
+        .map(|provenance| provenance.source())
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("Header {} has no provenance", header.address()))
+        })?;
+    if author == context.agent_id.address() {
+        return Ok(());
+    }
+
+    let receipt = ValidationReceipt::sign(header, &context)?;
+    log_debug!(
+        context,
+        "workflow/validation_receipt: Sending receipt for entry {} to author {}",
+        receipt.entry_address,
+        author
+    );
+    let direct_message_data = DirectMessageData {
+        address: author,
+        message: DirectMessage::ValidationReceipt(receipt),
+        msg_id: nanoid::simple(),
+        is_response: false,
+    };
+    let timeout = (
+        SystemTime::now(),
+        Duration::from_millis(VALIDATION_RECEIPT_MESSAGE_TIMEOUT_MS),
+    );
+    let action_wrapper = ActionWrapper::new(Action::SendDirectMessage((
+        direct_message_data,
+        Some(timeout),
+    )));
+    dispatch_action(context.action_channel(), action_wrapper);
+    Ok(())
+}
+
+/// A holder sent us a receipt for one of our entries.
+/// We store it if it is signed by the sender and refers to a header of our chain,
+/// and acknowledge it either way so the holder can close the connection.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub fn handle_validation_receipt(
+    from_agent_id: Address,
+    msg_id: String,
+    receipt: ValidationReceipt,
+    context: Arc<Context>,
+) {
+    match check_validation_receipt(&from_agent_id, &receipt, &context) {
+        Ok(()) => {
+            log_debug!(
+                context,
+                "workflow/validation_receipt: Got receipt for entry {} from {}",
+                receipt.entry_address,
+                from_agent_id
+            );
+            dispatch_action(
+                context.action_channel(),
+                ActionWrapper::new(Action::AddValidationReceipt(receipt)),
+            );
+        }
+        Err(error) => log_warn!(
+            context,
+            "workflow/validation_receipt: Ignoring receipt from {}: {}",
+            from_agent_id,
+            error
+        ),
+    }
+
+    let direct_message_data = DirectMessageData {
+        address: from_agent_id,
+        message: DirectMessage::ValidationReceiptReceived,
+        msg_id,
+        is_response: true,
+    };
+    let action_wrapper = ActionWrapper::new(Action::SendDirectMessage((direct_message_data, None)));
+    dispatch_action(context.action_channel(), action_wrapper);
+}
+
+fn check_validation_receipt(
+    from_agent_id: &Address,
+    receipt: &ValidationReceipt,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
+    if &receipt.validator.source() != from_agent_id {
+        return Err(HolochainError::ErrorGeneric(
+            "Receipt is not signed by its sender".to_string(),
+        ));
+    }
+    if !receipt.verify() {
+        return Err(HolochainError::ErrorGeneric(
+            "Invalid receipt signature".to_string(),
+        ));
+    }
+    let content = context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("State not initialized".to_string()))?
+        .agent()
+        .chain_store()
+        .get_raw(&receipt.header_address)?
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!(
+                "Header {} is not in our chain",
+                receipt.header_address
+            ))
+        })?;
+    let header = ChainHeader::try_from_content(&content)?;
+    let authored_by_us = header
+        .provenances()
+        .first()
+        .map(|provenance| provenance.source() == context.agent_id.address())
+        .unwrap_or(false);
+    if !authored_by_us || header.entry_address() != &receipt.entry_address {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Header {} does not belong to our entry {}",
+            receipt.header_address, receipt.entry_address
+        )));
+    }
+    Ok(())
+}
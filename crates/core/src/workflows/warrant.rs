@@ -0,0 +1,180 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    dht::{
+        actions::hold_aspect::hold_aspect,
+        pending_validations::{PendingValidation, ValidatingWorkflow},
+    },
+    instance::dispatch_action,
+    network::entry_with_header::EntryWithHeader,
+    nucleus::validation::{
+        validate_entry, validate_signatures, ValidationContext, ValidationError,
+    },
+    workflows::validation_package,
+};
+use holochain_core_types::{
+    error::HolochainError,
+    network::{entry_aspect::EntryAspect, warrant::Warrant},
+    signature::{Provenance, Signature},
+    validation::{EntryLifecycle, ValidationData},
+};
+use holochain_dpki::utils::Verify;
+use holochain_persistence_api::cas::content::AddressableContent;
+use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
+use std::{convert::TryFrom, sync::Arc};
+use uuid::Uuid;
+
+/// The given pending validation failed, so we sign a warrant against the author of the
+/// entry, block the author ourselves and publish the warrant to the author's neighborhood.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn issue_warrant(
+    pending: &PendingValidation,
+    reason: String,
+    context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    let EntryWithHeader { entry, header } = &pending.entry_with_header;
+    let accused = header
+        .provenances()
+        .first()
+        .map(|provenance| provenance.source())
+        .ok_or_else(|| HolochainError::ErrorGeneric("No source found in ChainHeader".into()))?;
+    if accused == context.agent_id.address() {
+        return Ok(());
+    }
+    // An entry that doesn't match its header or a header with a broken signature could have
+    // been tampered with on its way to us, which says nothing about the author.
+    if validate_signatures(entry, header).is_err() {
+        return Err(HolochainError::ValidationFailed(format!(
+            "Not issuing a warrant against {} for entry {} it didn't sign",
+            accused,
+            entry.address()
+        )));
+    }
+
+    let workflow: String = pending.workflow.clone().into();
+    let signature = context.conductor_api.execute(
+        Warrant::signed_data(header, entry, &workflow, &reason),
+        CryptoMethod::Sign,
+    )?;
+    let warrant = Warrant {
+        header: header.clone(),
+        entry: entry.clone(),
+        workflow,
+        reason,
+        issuer: Provenance::new(context.agent_id.address(), Signature::from(signature)),
+    };
+    log_warn!(
+        context,
+        "workflow/warrant: Issuing warrant against {} for invalid entry {}",
+        accused,
+        entry.address()
+    );
+    hold_aspect(
+        &Uuid::new_v4(),
+        EntryAspect::Warrant(warrant.clone()),
+        context.clone(),
+    )
+    .await?;
+    dispatch_action(
+        context.action_channel(),
+        ActionWrapper::new(Action::PublishWarrant(warrant)),
+    );
+    Ok(())
+}
+
+/// The network asks us to hold a warrant against an agent in our neighborhood.
+/// We only hold it, and with that block the agent, if the signatures check out and
+/// validating the entry ourselves fails too.
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_CORE)]
+pub async fn hold_warrant_workflow(
+    warrant: Warrant,
+    context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    let accused = warrant
+        .accused()
+        .ok_or_else(|| HolochainError::ErrorGeneric("No source found in ChainHeader".into()))?;
+    if accused == context.agent_id.address() {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Not holding warrant from {} against ourselves",
+            warrant.issuer.source()
+        )));
+    }
+
+    // 1. Check that the issuer signed the warrant and the accused signed the header
+    let issuer_signed = warrant
+        .issuer
+        .verify(Warrant::signed_data(
+            &warrant.header,
+            &warrant.entry,
+            &warrant.workflow,
+            &warrant.reason,
+        ))
+        .unwrap_or(false);
+    let accused_signed = validate_signatures(&warrant.entry, &warrant.header).is_ok();
+    if !issuer_signed || !accused_signed {
+        return Err(HolochainError::ValidationFailed(String::from(
+            "Warrant or warranted header has an invalid signature",
+        )));
+    }
+    let entry_with_header =
+        EntryWithHeader::try_from_entry_and_header(warrant.entry.clone(), warrant.header.clone())?;
+
+    // 2. Validate the entry the same way the issuer did
+    revalidate(
+        &entry_with_header,
+        ValidatingWorkflow::try_from(warrant.workflow.clone())?,
+        context.clone(),
+    )
+    .await?;
+
+    // 3. The entry is invalid indeed, so hold the warrant which blocks the agent
+    log_warn!(
+        context,
+        "workflow/warrant: Confirmed warrant from {} against {}, blocking the agent",
+        warrant.issuer.source(),
+        accused
+    );
+    hold_aspect(&Uuid::new_v4(), EntryAspect::Warrant(warrant), context).await
+}
+
+/// Succeeds only if validation of the warranted entry fails
+async fn revalidate(
+    entry_with_header: &EntryWithHeader,
+    workflow: ValidatingWorkflow,
+    context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    let package = validation_package(entry_with_header, context.clone())
+        .await?
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric("Could not get validation package".to_string())
+        })?;
+    let (lifecycle, link) = match workflow {
+        ValidatingWorkflow::HoldEntry => (EntryLifecycle::Dht, None),
+        ValidatingWorkflow::UpdateEntry => (
+            EntryLifecycle::Meta,
+            entry_with_header.header.link_update_delete(),
+        ),
+        _ => (EntryLifecycle::Meta, None),
+    };
+    let validation_data = ValidationData {
+        package,
+        lifecycle,
+        chain_forks: Vec::new(),
+    };
+    match validate_entry(
+        entry_with_header.entry.clone(),
+        link,
+        validation_data,
+        &context,
+        ValidationContext::Holding,
+    )
+    .await
+    {
+        Err(ValidationError::Fail(_)) => Ok(()),
+        Ok(()) => Err(HolochainError::ValidationFailed(format!(
+            "Warranted entry {} is valid",
+            entry_with_header.entry.address()
+        ))),
+        Err(error) => Err(error.into()),
+    }
+}
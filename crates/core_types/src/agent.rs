@@ -1,4 +1,4 @@
-use crate::{entry::Entry, error::HcResult};
+use crate::{entry::Entry, error::HcResult, signature::Signature};
 
 use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
 
@@ -22,8 +22,22 @@ pub struct AgentId {
     pub nick: String,
     /// the encoded public signing key of this agent (the magnifier)
     pub pub_sign_key: Base32,
-    // TODO: Add the encoded public encrypting key (the safe / padlock)
-    // pub pub_enc_key: Base32,
+    /// the encoded public encrypting key of this agent (the safe / padlock),
+    /// needed to encrypt entries for this agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pub_enc_key: Option<Base32>,
+    /// signature of `pub_enc_key` by the signing key. The address doesn't cover the
+    /// encrypting key, so this is what ties it to the agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enc_key_signature: Option<Signature>,
+    /// proof that this agent may join the DNA's network, e.g. an invite signed by
+    /// an existing member. Checked by the DNA's agent validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub membrane_proof: Option<String>,
+    /// signature of `membrane_proof` by the signing key, so that nobody passing the
+    /// AgentId on can swap in another proof.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub membrane_proof_signature: Option<Signature>,
 }
 
 impl AgentId {
@@ -51,9 +65,29 @@ impl AgentId {
         AgentId {
             nick: nick.to_string(),
             pub_sign_key: key_b32,
+            pub_enc_key: None,
+            enc_key_signature: None,
+            membrane_proof: None,
+            membrane_proof_signature: None,
         }
     }
 
+    /// Sets the HCID encoded public encrypting key of this agent, together with
+    /// the signature of it by this agent's signing key.
+    pub fn with_enc_key(mut self, enc_key_b32: Base32, signature: Signature) -> Self {
+        self.pub_enc_key = Some(enc_key_b32);
+        self.enc_key_signature = Some(signature);
+        self
+    }
+
+    /// Sets the proof that this agent may join the network, usually a JSON string,
+    /// together with the signature of it by this agent's signing key.
+    pub fn with_membrane_proof(mut self, membrane_proof: String, signature: Signature) -> Self {
+        self.membrane_proof = Some(membrane_proof);
+        self.membrane_proof_signature = Some(signature);
+        self
+    }
+
     /// Get the key decoded with HCID
     pub fn decoded_key(&self) -> HcResult<String> {
         let codec = HcidEncoding::with_kind("hcs0")?;
@@ -137,6 +171,41 @@ mod tests {
         assert!(maybe_key.is_err());
     }
 
+    #[test]
+    fn it_serializes_enc_key_only_if_set() {
+        let agent_id = test_agent_id().with_enc_key("HcKenc".to_string(), Signature::from("sig"));
+        assert_eq!(
+            JsonString::from(agent_id.clone()),
+            JsonString::from_json(&format!(
+                "{{\"nick\":\"bob\",\"pub_sign_key\":\"{}\",\"pub_enc_key\":\"HcKenc\",\"enc_key_signature\":\"sig\"}}",
+                GOOD_ID
+            ))
+        );
+        assert_eq!(
+            AgentId::try_from(JsonString::from(agent_id.clone())).unwrap(),
+            agent_id
+        );
+        // the address only depends on the signing key
+        assert_eq!(agent_id.address(), test_agent_id().address());
+    }
+
+    #[test]
+    fn it_serializes_membrane_proof_only_if_set() {
+        let agent_id = test_agent_id().with_membrane_proof(
+            "{\"invite\":\"sig\"}".to_string(),
+            Signature::from("proof sig"),
+        );
+        assert_eq!(
+            AgentId::try_from(JsonString::from(agent_id.clone())).unwrap(),
+            agent_id
+        );
+        assert_eq!(agent_id.address(), test_agent_id().address());
+        assert_ne!(
+            JsonString::from(agent_id),
+            JsonString::from(test_agent_id())
+        );
+    }
+
     #[test]
     /// show ToString implementation for Agent
     fn agent_to_string_test() {
@@ -0,0 +1,17 @@
+//! A ChainFork records that an agent published more than one header following the same
+//! predecessor, i.e. that its source chain has more than one branch.
+//! The holders of an agent's address flag forks when they index the headers the agent
+//! published there by predecessor.
+
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::Address;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, DefaultJson)]
+pub struct ChainFork {
+    /// Agent whose chain forked
+    pub author: Address,
+    /// Header that the branches share as their `link`
+    pub predecessor: Address,
+    /// First headers of the branches, at least two
+    pub headers: Vec<Address>,
+}
@@ -1,4 +1,86 @@
-//! NOT YET AVAILABLE
+//! ChainMigrate is the system entry that closes a source chain when its agent moves on
+//! to a new version of the DNA.
+//! It links the closed chain to its successor by carrying the addresses of both DNAs,
+//! so that the history of an agent can be followed across DNA upgrades.
+//! After a ChainMigrate entry got committed no further entries can be added to the chain.
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-pub struct ChainMigrate {}
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::Address;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, DefaultJson)]
+pub struct ChainMigrate {
+    /// DNA of the chain that gets closed
+    old_dna_address: Address,
+    /// DNA of the successor chain
+    new_dna_address: Address,
+    /// Agent that closes the chain
+    agent: Address,
+    reason: String,
+}
+
+impl ChainMigrate {
+    pub fn new(
+        old_dna_address: Address,
+        new_dna_address: Address,
+        agent: Address,
+        reason: String,
+    ) -> Self {
+        ChainMigrate {
+            old_dna_address,
+            new_dna_address,
+            agent,
+            reason,
+        }
+    }
+
+    pub fn old_dna_address(&self) -> &Address {
+        &self.old_dna_address
+    }
+
+    pub fn new_dna_address(&self) -> &Address {
+        &self.new_dna_address
+    }
+
+    pub fn agent(&self) -> &Address {
+        &self.agent
+    }
+
+    pub fn reason(&self) -> &String {
+        &self.reason
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::entry::{entry_type::EntryType, Entry};
+    use std::convert::TryFrom;
+
+    pub fn test_chain_migrate() -> ChainMigrate {
+        ChainMigrate::new(
+            Address::from("old-dna"),
+            Address::from("new-dna"),
+            Address::from("agent"),
+            "upgrade to v2".to_string(),
+        )
+    }
+
+    #[test]
+    fn chain_migrate_smoke_test() {
+        let migrate = test_chain_migrate();
+        assert_eq!(migrate.old_dna_address(), &Address::from("old-dna"));
+        assert_eq!(migrate.new_dna_address(), &Address::from("new-dna"));
+        assert_eq!(migrate.agent(), &Address::from("agent"));
+        assert_eq!(migrate.reason(), "upgrade to v2");
+    }
+
+    #[test]
+    fn chain_migrate_entry_round_trip() {
+        let entry = Entry::ChainMigrate(test_chain_migrate());
+        assert_eq!(entry.entry_type(), EntryType::ChainMigrate);
+        assert_eq!(
+            Entry::try_from(JsonString::from(entry.clone())).unwrap(),
+            entry
+        );
+    }
+}
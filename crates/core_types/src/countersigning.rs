@@ -0,0 +1,51 @@
+//! Countersigned entries are entries that several agents commit together, each onto its
+//! own source chain, with the signatures of all of them as the provenances of the header.
+//! Every party signs the entry address together with the list of all parties, so a
+//! provenance can't be dropped from or added to the header without invalidating the others.
+
+use crate::{entry::Entry, time::Timeout};
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::Address;
+
+/// What the proposer of a countersigned entry asks the other parties to sign
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, DefaultJson)]
+pub struct CountersigningRequest {
+    pub entry: Entry,
+    /// All agents that have to sign, the proposer first
+    pub parties: Vec<Address>,
+    /// How long a party waits for the signatures of the others after it signed
+    pub timeout: Timeout,
+}
+
+/// The data every party of a countersigned entry signs.
+/// It doesn't depend on the order of the parties.
+pub fn countersigning_payload(entry_address: &Address, parties: &[Address]) -> String {
+    let mut parties: Vec<String> = parties.iter().map(|party| party.to_string()).collect();
+    parties.sort();
+    parties.dedup();
+    format!("countersigning:{}:{}", entry_address, parties.join(","))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_covers_the_set_of_parties() {
+        let entry_address = Address::from("QmEntry");
+        let alice = Address::from("alice");
+        let bob = Address::from("bob");
+        assert_eq!(
+            countersigning_payload(&entry_address, &[alice.clone(), bob.clone()]),
+            countersigning_payload(&entry_address, &[bob.clone(), alice.clone()]),
+        );
+        assert_ne!(
+            countersigning_payload(&entry_address, &[alice.clone(), bob.clone()]),
+            countersigning_payload(&entry_address, &[alice.clone()]),
+        );
+        assert_ne!(
+            countersigning_payload(&entry_address, &[alice.clone(), bob.clone()]),
+            countersigning_payload(&Address::from("QmOther"), &[alice, bob]),
+        );
+    }
+}
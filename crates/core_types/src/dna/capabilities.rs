@@ -27,6 +27,14 @@ impl CapabilityRequest {
     }
 }
 
+/// The payload that the caller of a zome function signs for the provenance of its
+/// capability request.
+/// Shared by core, which verifies the signature, and the HDK, which creates it for
+/// calls made from inside a zome.
+pub fn encode_call_data_for_signing<J: Into<JsonString>>(function: &str, parameters: J) -> String {
+    base64::encode(&format!("{}:{}", function, parameters.into()))
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -47,4 +55,12 @@ pub mod tests {
             cap_call
         );
     }
+
+    #[test]
+    fn test_encode_call_data_for_signing() {
+        assert_eq!(
+            encode_call_data_for_signing("some_fn", JsonString::from_json("{}")),
+            base64::encode("some_fn:{}"),
+        );
+    }
 }
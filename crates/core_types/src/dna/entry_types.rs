@@ -137,6 +137,11 @@ pub struct EntryTypeDef {
     /// An array of link definitions for links pointing to entries of this type
     #[serde(default)]
     pub linked_from: Vec<LinkedFrom>,
+
+    /// Agents (by address) that entries of this type get encrypted for,
+    /// in addition to the author, if sharing is encrypted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<Address>,
 }
 
 fn empty_properties() -> JsonString {
@@ -150,6 +155,7 @@ impl Default for EntryTypeDef {
             sharing: Sharing::default(),
             links_to: Vec::default(),
             linked_from: Vec::default(),
+            recipients: Vec::default(),
         }
     }
 }
@@ -46,6 +46,10 @@ pub enum Attribute {
     RemovedLink(Address, String, String),
     PendingEntry,
     Target,
+    ValidationReceipt,
+    Warrant,
+    NextHeader,
+    ChainFork,
 }
 
 impl Default for Attribute {
@@ -86,6 +90,10 @@ impl fmt::Display for Attribute {
             ),
             Attribute::PendingEntry => write!(f, "pending-entry"),
             Attribute::Target => write!(f, "target"),
+            Attribute::ValidationReceipt => write!(f, "validation-receipt"),
+            Attribute::Warrant => write!(f, "warrant"),
+            Attribute::NextHeader => write!(f, "next-header"),
+            Attribute::ChainFork => write!(f, "chain-fork"),
         }
     }
 }
@@ -119,6 +127,10 @@ impl TryFrom<&str> for Attribute {
                 "link" => Ok(Link),
                 "pending-entry" => Ok(PendingEntry),
                 "target" => Ok(Target),
+                "validation-receipt" => Ok(ValidationReceipt),
+                "warrant" => Ok(Warrant),
+                "next-header" => Ok(NextHeader),
+                "chain-fork" => Ok(ChainFork),
                 a => Err(AttributeError::Unrecognized(a.to_string())),
             }
         }
@@ -1,4 +1,4 @@
-use crate::{entry::Entry, error::HolochainError};
+use crate::{entry::Entry, error::HolochainError, time::Iso8601};
 
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
 
@@ -85,12 +85,66 @@ impl CapTokenClaim {
     }
 }
 
+/// Optional restrictions on when and how often a capability grant can be used.
+/// A grant without any limits can be used until it gets revoked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, DefaultJson, Eq, Default)]
+pub struct CapGrantLimits {
+    /// The grant can't be used before this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<Iso8601>,
+    /// The grant can't be used after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<Iso8601>,
+    /// The grant can't be used for more than this many zome calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+}
+
+impl CapGrantLimits {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_not_before(mut self, not_before: Iso8601) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    pub fn with_not_after(mut self, not_after: Iso8601) -> Self {
+        self.not_after = Some(not_after);
+        self
+    }
+
+    pub fn with_max_uses(mut self, max_uses: u32) -> Self {
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        *self == CapGrantLimits::default()
+    }
+
+    /// True if the given time lies within the validity period of these limits
+    pub fn is_active_at(&self, time: &Iso8601) -> bool {
+        self.not_before.map(|start| *time >= start).unwrap_or(true)
+            && self.not_after.map(|end| *time <= end).unwrap_or(true)
+    }
+
+    /// True if a grant with these limits can be used again after the given number of uses
+    pub fn allows_use_after(&self, uses: u32) -> bool {
+        self.max_uses.map(|max| uses < max).unwrap_or(true)
+    }
+}
+
 /// System entry to hold a capabilities granted by the callee
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, DefaultJson, Eq)]
 pub struct CapTokenGrant {
     id: String,
     assignees: Option<Vec<Address>>,
     functions: CapFunctions,
+    // skipped if empty so that grants without limits keep their token
+    #[serde(default, skip_serializing_if = "CapGrantLimits::is_unlimited")]
+    limits: CapGrantLimits,
 }
 
 impl CapTokenGrant {
@@ -99,9 +153,17 @@ impl CapTokenGrant {
             id: String::from(id),
             assignees,
             functions,
+            limits: CapGrantLimits::default(),
         }
     }
 
+    /// Restricts the grant to the given limits.
+    /// As the limits are part of the grant entry, this changes the token.
+    pub fn with_limits(mut self, limits: CapGrantLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn create(
         id: &str,
         cap_type: CapabilityType,
@@ -169,6 +231,10 @@ impl CapTokenGrant {
     pub fn functions(&self) -> CapFunctions {
         self.functions.clone()
     }
+
+    pub fn limits(&self) -> &CapGrantLimits {
+        &self.limits
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +288,41 @@ pub mod tests {
         assert_eq!(grant.assignees().unwrap()[0], test_address)
     }
 
+    #[test]
+    fn test_cap_grant_limits() {
+        let limits = CapGrantLimits::new()
+            .with_not_before(Iso8601::from(100))
+            .with_not_after(Iso8601::from(200))
+            .with_max_uses(2);
+        assert!(!limits.is_active_at(&Iso8601::from(99)));
+        assert!(limits.is_active_at(&Iso8601::from(100)));
+        assert!(limits.is_active_at(&Iso8601::from(200)));
+        assert!(!limits.is_active_at(&Iso8601::from(201)));
+        assert!(limits.allows_use_after(1));
+        assert!(!limits.allows_use_after(2));
+
+        let unlimited = CapGrantLimits::new();
+        assert!(unlimited.is_unlimited());
+        assert!(unlimited.is_active_at(&Iso8601::from(0)));
+        assert!(unlimited.allows_use_after(u32::max_value()));
+    }
+
+    #[test]
+    fn test_cap_grant_limits_change_token() {
+        let grant = CapTokenGrant::new("foo", None, CapFunctions::new());
+        let limited = grant
+            .clone()
+            .with_limits(CapGrantLimits::new().with_max_uses(1));
+        assert_eq!(
+            grant.token(),
+            CapTokenGrant::new("foo", None, CapFunctions::new())
+                .with_limits(CapGrantLimits::new())
+                .token()
+        );
+        assert_ne!(grant.token(), limited.token());
+        assert_eq!(limited.limits().max_uses, Some(1));
+    }
+
     #[test]
     fn test_cap_grant_valid() {
         assert!(CapTokenGrant::valid(CapabilityType::Public, None).is_ok());
@@ -0,0 +1,85 @@
+use crate::{agent::Base32, entry::entry_type::AppEntryType};
+use holochain_json_api::{error::JsonError, json::JsonString};
+use std::collections::BTreeMap;
+
+//-------------------------------------------------------------------------------------------------
+// SealedData
+//-------------------------------------------------------------------------------------------------
+
+/// Data encrypted for a set of recipients.
+///
+/// The data is encrypted with a random content key, and that key is encrypted for every
+/// recipient with a key derived from the sender's and the recipient's encrypting key pairs.
+/// All binary values are base64 encoded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, DefaultJson, Eq)]
+pub struct SealedData {
+    /// Public encrypting key of the sender
+    pub sender: Base32,
+    /// The content key encrypted for each recipient, by public encrypting key of the recipient
+    pub keys: BTreeMap<Base32, String>,
+    /// The encrypted data
+    pub cipher: String,
+}
+
+impl SealedData {
+    /// True if the holder of the given public encrypting key can decrypt this
+    pub fn is_recipient(&self, enc_key: &str) -> bool {
+        self.keys.contains_key(enc_key)
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// EncryptedEntry
+//-------------------------------------------------------------------------------------------------
+
+/// An app entry of an entry type with `Sharing::Encrypted`, as it gets stored
+/// and published. Only the entry type is visible to holders.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, DefaultJson, Eq)]
+pub struct EncryptedEntry {
+    entry_type: AppEntryType,
+    sealed: SealedData,
+}
+
+impl EncryptedEntry {
+    pub fn new(entry_type: AppEntryType, sealed: SealedData) -> Self {
+        EncryptedEntry { entry_type, sealed }
+    }
+
+    pub fn entry_type(&self) -> &AppEntryType {
+        &self.entry_type
+    }
+
+    pub fn sealed(&self) -> &SealedData {
+        &self.sealed
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::entry::{entry_type::EntryType, Entry};
+
+    pub fn test_encrypted_entry() -> EncryptedEntry {
+        let mut keys = BTreeMap::new();
+        keys.insert("HcKrecipient".to_string(), "a2V5".to_string());
+        EncryptedEntry::new(
+            AppEntryType::from("testEntryType"),
+            SealedData {
+                sender: "HcKsender".to_string(),
+                keys,
+                cipher: "Y2lwaGVy".to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn encrypted_entry_smoke_test() {
+        let encrypted = test_encrypted_entry();
+        assert!(encrypted.sealed().is_recipient("HcKrecipient"));
+        assert!(!encrypted.sealed().is_recipient("HcKsender"));
+        assert_eq!(
+            Entry::Encrypted(encrypted).entry_type(),
+            EntryType::App(AppEntryType::from("testEntryType"))
+        );
+    }
+}
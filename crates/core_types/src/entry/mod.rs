@@ -5,11 +5,13 @@
 
 pub mod cap_entries;
 pub mod deletion_entry;
+pub mod encrypted_entry;
 pub mod entry_type;
 
 use self::{
     cap_entries::{CapTokenClaim, CapTokenGrant},
     deletion_entry::DeletionEntry,
+    encrypted_entry::EncryptedEntry,
 };
 use agent::{test_agent_id, AgentId};
 use chain_header::ChainHeader;
@@ -77,6 +79,7 @@ pub enum Entry {
     ChainMigrate(ChainMigrate),
     CapTokenClaim(CapTokenClaim),
     CapTokenGrant(CapTokenGrant),
+    Encrypted(EncryptedEntry),
 }
 
 impl Entry {
@@ -93,6 +96,7 @@ impl Entry {
             Entry::ChainMigrate(_) => EntryType::ChainMigrate,
             Entry::CapTokenClaim(_) => EntryType::CapTokenClaim,
             Entry::CapTokenGrant(_) => EntryType::CapTokenGrant,
+            Entry::Encrypted(encrypted) => EntryType::App(encrypted.entry_type().to_owned()),
         }
     }
 }
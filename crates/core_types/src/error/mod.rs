@@ -118,6 +118,7 @@ pub enum HolochainError {
     DnaHashMismatch(HashString, HashString),
     EntryNotFoundLocally,
     EntryIsPrivate,
+    WasmLimitExceeded(String),
     List(Vec<HolochainError>),
 }
 
@@ -169,6 +170,7 @@ impl fmt::Display for HolochainError {
                 f,
                 "The requested entry is private and should not be shared via gossip"
             ),
+            WasmLimitExceeded(reason) => write!(f, "WASM limit exceeded: {}", reason),
             List(list) => {
                 //most windows system know that \n is a newline so we should be good.
                 let error_list = list
@@ -424,6 +426,10 @@ mod tests {
                 HolochainError::EntryIsPrivate,
                 "The requested entry is private and should not be shared via gossip",
             ),
+            (
+                HolochainError::WasmLimitExceeded("call took longer than 10 ms".to_string()),
+                "WASM limit exceeded: call took longer than 10 ms",
+            ),
         ] {
             assert_eq!(output, &input.to_string());
         }
@@ -209,6 +209,7 @@ impl From<HolochainError> for RibosomeErrorCode {
             HolochainError::DnaHashMismatch(_, _) => RibosomeErrorCode::Unspecified,
             HolochainError::EntryNotFoundLocally => RibosomeErrorCode::Unspecified,
             HolochainError::EntryIsPrivate => RibosomeErrorCode::Unspecified,
+            HolochainError::WasmLimitExceeded(_) => RibosomeErrorCode::CallbackFailed,
             HolochainError::List(_) => RibosomeErrorCode::Unspecified,
         }
     }
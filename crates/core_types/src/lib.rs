@@ -47,7 +47,9 @@ pub mod error;
 extern crate objekt;
 pub mod agent;
 pub mod bits_n_pieces;
+pub mod chain_fork;
 pub mod chain_migrate;
+pub mod countersigning;
 pub mod dna;
 pub mod hdk_version;
 pub mod link;
@@ -1,5 +1,6 @@
 use crate::{
     chain_header::ChainHeader, entry::Entry, error::HolochainError, link::link_data::LinkData,
+    network::warrant::Warrant,
 };
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
@@ -31,7 +32,9 @@ pub enum EntryAspect {
 
     // Communicating only the header makes sense if an
     // entry was deleted but we need to remember that
-    // there was an entry that got deleted (sacrileged)
+    // there was an entry that got deleted (sacrileged).
+    // Authors publish their headers to their own address
+    // so its holders can notice forks in their chain.
     Header(ChainHeader),
 
     // This is the meta item for adding a link.
@@ -72,6 +75,12 @@ pub enum EntryAspect {
     // `Entry::Deletion(address)`.
     // Deletion(Address, ChainHeader),
     Deletion(ChainHeader),
+
+    // A holder's signed claim that the author of the
+    // contained header published an invalid entry.
+    // It is published to the author's agent address
+    // so the author's neighborhood learns about it.
+    Warrant(Warrant),
 }
 
 impl EntryAspect {
@@ -83,6 +92,7 @@ impl EntryAspect {
             EntryAspect::LinkRemove(_, _) => String::from("link_remove"),
             EntryAspect::Update(_, _) => String::from("update"),
             EntryAspect::Deletion(_) => String::from("deletion"),
+            EntryAspect::Warrant(_) => String::from("warrant"),
         }
     }
     pub fn header(&self) -> &ChainHeader {
@@ -93,6 +103,7 @@ impl EntryAspect {
             EntryAspect::LinkRemove(_, header) => header,
             EntryAspect::Update(_, header) => header,
             EntryAspect::Deletion(header) => header,
+            EntryAspect::Warrant(warrant) => &warrant.header,
         }
     }
     /// NB: this is the inverse function of entry_to_meta_aspect,
@@ -112,9 +123,15 @@ impl EntryAspect {
                     ))
                 })?
             }
-            // EntryAspect::Header is currently unused,
-            // but this is what it will be when we do use it
+            // EntryAspect::Header gets published to the address of its author,
+            // which is not held through this mapping
             EntryAspect::Header(header) => header.address(),
+            EntryAspect::Warrant(warrant) => warrant.accused().ok_or_else(|| {
+                HolochainError::ErrorGeneric(format!(
+                    "no provenance on warranted header. Header: {:?}",
+                    warrant.header
+                ))
+            })?,
         })
     }
 }
@@ -166,6 +183,13 @@ impl fmt::Debug for EntryAspect {
             EntryAspect::Deletion(header) => {
                 write!(f, "EntryAspect::Deletion({})", format_header(header))
             }
+            EntryAspect::Warrant(warrant) => write!(
+                f,
+                "EntryAspect::Warrant({}, {}, issuer: {})",
+                warrant.entry.address(),
+                format_header(&warrant.header),
+                warrant.issuer.source()
+            ),
         }
     }
 }
@@ -1,2 +1,3 @@
 pub mod entry_aspect;
 pub mod query;
+pub mod warrant;
@@ -0,0 +1,56 @@
+use crate::{chain_header::ChainHeader, entry::Entry, signature::Provenance};
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
+use std::convert::TryFrom;
+
+/// A holder's signed claim that the author of `header` published an invalid entry.
+/// Warrants get published to the neighborhood of the author's agent address, and
+/// nodes that confirm them stop holding and accepting data from that agent.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, DefaultJson)]
+pub struct Warrant {
+    /// The header of the invalid entry, signed by its author
+    pub header: ChainHeader,
+    pub entry: Entry,
+    /// The holding workflow that rejected the entry, e.g. `HoldEntry`
+    pub workflow: String,
+    /// Why validation failed
+    pub reason: String,
+    /// The holder that found the entry invalid and its signature of this warrant
+    pub issuer: Provenance,
+}
+
+impl Warrant {
+    /// The agent that authored the invalid entry, if its header has any provenance
+    pub fn accused(&self) -> Option<Address> {
+        self.header
+            .provenances()
+            .first()
+            .map(|provenance| provenance.source())
+    }
+
+    /// The data the issuer signs
+    pub fn signed_data(
+        header: &ChainHeader,
+        entry: &Entry,
+        workflow: &str,
+        reason: &str,
+    ) -> String {
+        format!(
+            "warrant:{}:{}:{}:{}",
+            header.address(),
+            entry.address(),
+            workflow,
+            reason
+        )
+    }
+}
+
+impl AddressableContent for Warrant {
+    fn content(&self) -> Content {
+        self.to_owned().into()
+    }
+
+    fn try_from_content(content: &Content) -> Result<Self, JsonError> {
+        Self::try_from(content.to_owned())
+    }
+}
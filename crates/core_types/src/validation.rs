@@ -3,6 +3,7 @@
 //! agent actions between Holochain and Zomes.
 
 use crate::{
+    chain_fork::ChainFork,
     chain_header::ChainHeader,
     entry::{
         entry_type::{AppEntryType, EntryType},
@@ -38,6 +39,8 @@ impl ValidationPackage {
     }
 }
 
+/// Packages built by the author also carry all source chain headers before the entry,
+/// so holders can check that the chain was not closed by a ChainMigrate entry.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, DefaultJson)]
 pub enum ValidationPackageDefinition {
     /// send the header for the entry, along with the entry
@@ -127,6 +130,10 @@ pub struct ValidationData {
     /// In which lifecycle of the entry creation are we running
     /// this validation callback?
     pub lifecycle: EntryLifecycle,
+    /// Forks in the chains of the entry's sources that the
+    /// validating node has seen so far.
+    #[serde(default)]
+    pub chain_forks: Vec<ChainFork>,
 }
 
 impl Default for ValidationData {
@@ -139,6 +146,7 @@ impl Default for ValidationData {
                 custom: None,
             },
             lifecycle: EntryLifecycle::default(),
+            chain_forks: Vec::new(),
         }
     }
 }
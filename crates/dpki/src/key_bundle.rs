@@ -7,9 +7,14 @@ use crate::{
     seed::{Seed, SeedType},
     utils, SEED_SIZE,
 };
-use holochain_core_types::{agent::Base32, error::HcResult};
+use holochain_core_types::{
+    agent::Base32,
+    entry::encrypted_entry::SealedData,
+    error::{HcResult, HolochainError},
+    signature::Signature,
+};
 use serde_json::json;
-use std::str;
+use std::{collections::BTreeMap, str};
 
 use serde_derive::{Deserialize, Serialize};
 
@@ -60,6 +65,16 @@ impl KeyBundle {
         self.sign_keys.sign(data)
     }
 
+    /// sign the public encrypting key with the signing private key,
+    /// which ties the encrypting key to the identity of this bundle
+    /// @return {Signature} base64 encoded signature of the encrypting key
+    pub fn sign_enc_key(&mut self) -> HcResult<Signature> {
+        let mut data = SecBuf::with_insecure_from_string(self.enc_keys.public.clone());
+        let mut signature = self.sign(&mut data)?;
+        let signature = signature.read_lock();
+        Ok(Signature::from(base64::encode(&**signature)))
+    }
+
     pub fn encrypt(&mut self, data: &mut SecBuf) -> HcResult<SecBuf> {
         let mut encrypted_data = SecBuf::with_insecure(
             data.len() + lib3h_sodium::aead::ABYTES + lib3h_sodium::aead::NONCEBYTES,
@@ -76,6 +91,44 @@ impl KeyBundle {
         Ok(decrypted_data)
     }
 
+    /// Encrypt data so that the holders of the given public encrypting keys, and only they,
+    /// can decrypt it with `decrypt_sealed()`.
+    /// The data gets encrypted with a random content key, which in turn gets encrypted
+    /// for each recipient.
+    pub fn encrypt_for(
+        &mut self,
+        data: &mut SecBuf,
+        recipients: &[Base32],
+    ) -> HcResult<SealedData> {
+        let mut content_key = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        content_key.randomize();
+        let cipher = utils::symmetric_encrypt(data, &mut content_key)?;
+
+        let mut keys = BTreeMap::new();
+        for recipient in recipients {
+            let mut shared_key = self.enc_keys.shared_key_for(recipient)?;
+            let encrypted_key = utils::symmetric_encrypt(&mut content_key, &mut shared_key)?;
+            keys.insert(recipient.clone(), base64::encode(&encrypted_key));
+        }
+
+        Ok(SealedData {
+            sender: self.enc_keys.public.clone(),
+            keys,
+            cipher: base64::encode(&cipher),
+        })
+    }
+
+    /// Decrypt data that was encrypted for us with `encrypt_for()`
+    pub fn decrypt_sealed(&mut self, sealed: &SealedData) -> HcResult<SecBuf> {
+        let encrypted_key = sealed.keys.get(&self.enc_keys.public).ok_or_else(|| {
+            HolochainError::ErrorGeneric("Data was not encrypted for this agent".to_string())
+        })?;
+        let mut shared_key = self.enc_keys.shared_key_from(&sealed.sender)?;
+        let mut content_key =
+            utils::symmetric_decrypt(&base64::decode(encrypted_key)?, &mut shared_key)?;
+        utils::symmetric_decrypt(&base64::decode(&sealed.cipher)?, &mut content_key)
+    }
+
     /// verify data that was signed with our private signing key
     /// @param {SecBuf} data buffer to verify
     /// @param {SecBuf} signature candidate for that data buffer
@@ -157,6 +210,14 @@ pub(crate) mod tests {
         assert!(!succeeded);
     }
 
+    #[test]
+    fn keybundle_should_sign_its_enc_key() {
+        let mut bundle = test_generate_random_bundle();
+        let signature = bundle.sign_enc_key().unwrap();
+        let source = bundle.get_id().into();
+        assert!(utils::verify(source, bundle.enc_keys.public.clone(), signature).unwrap());
+    }
+
     #[test]
 
     fn keybundle_should_encrypt_and_decrypt() {
@@ -182,4 +243,24 @@ pub(crate) mod tests {
         //check if decrypted message equals original message
         assert_eq!(message_read_lock[0..16], decrypted_read_lock[0..16])
     }
+
+    #[test]
+    fn keybundle_should_encrypt_for_recipients() {
+        let mut alice = test_generate_random_bundle();
+        let mut bob = test_generate_random_bundle();
+        let mut eve = test_generate_random_bundle();
+
+        let mut message = SecBuf::with_insecure_from_string("secret message".to_string());
+        let recipients = vec![alice.enc_keys.public.clone(), bob.enc_keys.public.clone()];
+        let sealed = alice.encrypt_for(&mut message, &recipients).unwrap();
+        assert_eq!(sealed.sender, alice.enc_keys.public);
+        assert!(sealed.is_recipient(&bob.enc_keys.public));
+
+        for recipient in vec![&mut alice, &mut bob] {
+            let mut decrypted = recipient.decrypt_sealed(&sealed).unwrap();
+            let decrypted = decrypted.read_lock();
+            assert_eq!(&**decrypted, "secret message".as_bytes());
+        }
+        assert!(eve.decrypt_sealed(&sealed).is_err());
+    }
 }
@@ -206,6 +206,40 @@ impl EncryptingKeyPair {
         Ok(())
     }
 
+    /// Derive the key to encrypt data for the holder of the given (HCID encoded) public
+    /// encrypting key. The recipient derives the same key with `shared_key_from()`.
+    pub fn shared_key_for(&mut self, recipient: &Base32) -> HcResult<SecBuf> {
+        let mut own_pub_key = self.decode_pub_key_into_secbuf();
+        let mut recipient_pub_key = utils::decode_pub_key(recipient.clone(), Self::codec())?;
+        let mut rx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        let mut tx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        kx::client_session(
+            &mut own_pub_key,
+            &mut self.private,
+            &mut recipient_pub_key,
+            &mut rx,
+            &mut tx,
+        )?;
+        Ok(tx)
+    }
+
+    /// Derive the key to decrypt data the holder of the given (HCID encoded) public
+    /// encrypting key encrypted for us with `shared_key_for()`.
+    pub fn shared_key_from(&mut self, sender: &Base32) -> HcResult<SecBuf> {
+        let mut own_pub_key = self.decode_pub_key_into_secbuf();
+        let mut sender_pub_key = utils::decode_pub_key(sender.clone(), Self::codec())?;
+        let mut rx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        let mut tx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        kx::server_session(
+            &mut own_pub_key,
+            &mut self.private,
+            &mut sender_pub_key,
+            &mut rx,
+            &mut tx,
+        )?;
+        Ok(rx)
+    }
+
     /// decrypt some arbitrary data with the signing private key
     /// @param {SecBuf} cipher - the data to decrypt
     /// @param{SecBuf} data - the decrypted data
@@ -302,6 +336,22 @@ mod tests {
         assert!(keys.new_from_self().unwrap().is_same(&mut keys));
     }
 
+    #[test]
+    fn keypair_should_derive_shared_keys() {
+        let mut alice = test_generate_random_enc_keypair();
+        let mut bob = test_generate_random_enc_keypair();
+        let mut eve = test_generate_random_enc_keypair();
+
+        let bob_pub = bob.public();
+        let alice_pub = alice.public();
+        let mut alice_to_bob = alice.shared_key_for(&bob_pub).unwrap();
+        let mut bob_from_alice = bob.shared_key_from(&alice_pub).unwrap();
+        let mut eve_from_alice = eve.shared_key_from(&alice_pub).unwrap();
+
+        assert_eq!(0, alice_to_bob.compare(&mut bob_from_alice));
+        assert_ne!(0, alice_to_bob.compare(&mut eve_from_alice));
+    }
+
     #[test]
     fn keypair_should_sign_message_and_verify() {
         let mut sign_keys = test_generate_random_sign_keypair();
@@ -9,7 +9,7 @@ use holochain_core_types::{
     signature::{Provenance, Signature},
 };
 use holochain_persistence_api::cas::content::Address;
-use lib3h_sodium::{kdf, secbuf::SecBuf, sign};
+use lib3h_sodium::{aead, kdf, secbuf::SecBuf, sign};
 use std::str;
 
 /// a trait for things that have a provenance that can be verified
@@ -129,6 +129,36 @@ pub fn generate_random_seed_buf() -> SecBuf {
     generate_random_buf(SEED_SIZE)
 }
 
+/// encrypt data with a symmetric key
+/// @return the cipher followed by the random nonce it was encrypted with
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_DPKI)]
+pub(crate) fn symmetric_encrypt(data: &mut SecBuf, key: &mut SecBuf) -> HcResult<Vec<u8>> {
+    let mut nonce = generate_random_buf(aead::NONCEBYTES);
+    let mut cipher = SecBuf::with_insecure(data.len() + aead::ABYTES);
+    aead::enc(data, key, None, &mut nonce, &mut cipher)?;
+    let cipher = cipher.read_lock();
+    let nonce = nonce.read_lock();
+    Ok(cipher.iter().chain(nonce.iter()).cloned().collect())
+}
+
+/// decrypt data encrypted with `symmetric_encrypt()`
+#[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_DPKI)]
+pub(crate) fn symmetric_decrypt(cipher_with_nonce: &[u8], key: &mut SecBuf) -> HcResult<SecBuf> {
+    if cipher_with_nonce.len() < aead::ABYTES + aead::NONCEBYTES {
+        return Err(HolochainError::ErrorGeneric(
+            "Cipher is too short".to_string(),
+        ));
+    }
+    let cipher_length = cipher_with_nonce.len() - aead::NONCEBYTES;
+    let mut cipher = SecBuf::with_insecure(cipher_length);
+    cipher.from_array(&cipher_with_nonce[..cipher_length])?;
+    let mut nonce = SecBuf::with_insecure(aead::NONCEBYTES);
+    nonce.from_array(&cipher_with_nonce[cipher_length..])?;
+    let mut data = SecBuf::with_secure(cipher_length - aead::ABYTES);
+    aead::dec(&mut data, key, None, &mut nonce, &mut cipher)?;
+    Ok(data)
+}
+
 /// encrypt and base64 encode a secbuf
 #[holochain_tracing_macros::newrelic_autotrace(HOLOCHAIN_DPKI)]
 pub fn encrypt_with_passphrase_buf(
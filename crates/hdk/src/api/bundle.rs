@@ -1,14 +1,56 @@
-use crate::{
-    error::{ZomeApiError, ZomeApiResult},
-    BundleOnClose,
+use crate::{error::ZomeApiResult, Dispatch};
+use holochain_core_types::time::Timeout;
+use holochain_json_api::json::JsonString;
+use holochain_wasm_utils::api_serialization::bundle::{
+    BundleOnClose, CloseBundleArgs, StartBundleArgs,
 };
 
-/// NOT YET AVAILABLE
-pub fn start_bundle(_timeout: usize, _user_param: serde_json::Value) -> ZomeApiResult<()> {
-    Err(ZomeApiError::FunctionNotImplemented)
+/// Opens a bundle for the currently running zome function call.
+/// Until the bundle gets closed, entries and links committed by this call
+/// ([commit_entry](fn.commit_entry.html), [link_entries](fn.link_entries.html),
+/// [update_entry](fn.update_entry.html), [remove_entry](fn.remove_entry.html) and
+/// [remove_link](fn.remove_link.html)) are not written to the source chain right away
+/// but staged in the bundle. Those functions still return the addresses of the staged entries.
+///
+/// Closing the bundle with `BundleOnClose::Commit` validates all staged entries and only
+/// if every one of them is valid commits them to the source chain and publishes them.
+/// If the bundle is still open after `timeout` milliseconds, or the zome function returns
+/// without closing it, the bundle gets discarded.
+///
+/// Only one bundle can be open per instance at a time.
+/// # Examples
+/// ```rust
+/// # extern crate hdk;
+/// # extern crate serde_json;
+/// # use hdk::error::ZomeApiResult;
+/// # use hdk::holochain_core_types::error::RibosomeEncodingBits;
+/// # use hdk::holochain_core_types::error::RibosomeEncodedValue;
+/// # use hdk::BundleOnClose;
+/// # #[no_mangle]
+/// # pub fn hc_start_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_close_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+///
+/// pub fn handle_create_post_with_tags(content: String) -> ZomeApiResult<()> {
+///     hdk::start_bundle(10000, serde_json::Value::Null)?;
+///     // commit the post, the tags and the links between them
+///     // ...
+///     hdk::close_bundle(BundleOnClose::Commit)
+/// }
+/// # fn main() {}
+/// ```
+pub fn start_bundle(timeout: usize, user_param: serde_json::Value) -> ZomeApiResult<()> {
+    Dispatch::StartBundle.with_input(StartBundleArgs {
+        timeout: Timeout::new(timeout),
+        user_param: JsonString::from(user_param),
+    })
 }
 
-/// NOT YET AVAILABLE
-pub fn close_bundle(_action: BundleOnClose) -> ZomeApiResult<()> {
-    Err(ZomeApiError::FunctionNotImplemented)
+/// Closes the bundle opened by [start_bundle](fn.start_bundle.html) in this zome function call.
+/// With `BundleOnClose::Commit` all staged entries get validated and committed together,
+/// with `BundleOnClose::Discard` they get thrown away.
+/// Returns an error if no bundle is open, if it timed out or if any of the entries is invalid.
+/// In all those cases nothing of the bundle is written to the source chain.
+pub fn close_bundle(action: BundleOnClose) -> ZomeApiResult<()> {
+    Dispatch::CloseBundle.with_input(CloseBundleArgs { action })
 }
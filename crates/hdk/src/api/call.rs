@@ -1,4 +1,8 @@
-use crate::{error::ZomeApiResult, Dispatch};
+use crate::{api::sign, error::ZomeApiResult, Dispatch, AGENT_ADDRESS};
+use holochain_core_types::{
+    dna::capabilities::encode_call_data_for_signing,
+    signature::{Provenance, Signature},
+};
 use holochain_json_api::json::JsonString;
 use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::ZomeFnCallArgs;
@@ -243,5 +247,41 @@ pub fn call<S: Into<String>>(
         cap_token,
         fn_name: fn_name.into(),
         fn_args: String::from(fn_args),
+        provenance: None,
     })
 }
+
+/// Like [call](fn.call.html), but the call carries the given provenance instead of getting
+/// signed by the calling instance's agent.
+/// The callee checks the signature and, for `Assigned` grants, that the signer is an assignee.
+/// This can be used to pass on a call signed by this agent with [sign_call](fn.sign_call.html)
+/// or the provenance of a call a client made, as found in `CAPABILITY_REQ`.
+pub fn call_with_provenance<S: Into<String>>(
+    instance_handle: S,
+    zome_name: S,
+    cap_token: Address,
+    fn_name: S,
+    fn_args: JsonString,
+    provenance: Provenance,
+) -> ZomeApiResult<JsonString> {
+    Dispatch::Call.with_input(ZomeFnCallArgs {
+        instance_handle: instance_handle.into(),
+        zome_name: zome_name.into(),
+        cap_token,
+        fn_name: fn_name.into(),
+        fn_args: String::from(fn_args),
+        provenance: Some(provenance),
+    })
+}
+
+/// Signs a call of the given function with the given arguments with this agent's key.
+/// Returns the provenance to pass to [call_with_provenance](fn.call_with_provenance.html).
+/// The arguments have to be exactly the same for the signature to verify.
+pub fn sign_call<S: Into<String>>(fn_name: S, fn_args: JsonString) -> ZomeApiResult<Provenance> {
+    let payload = encode_call_data_for_signing(&fn_name.into(), fn_args);
+    let signature = sign(payload)?;
+    Ok(Provenance::new(
+        AGENT_ADDRESS.clone(),
+        Signature::from(signature),
+    ))
+}
@@ -1,5 +1,5 @@
 use crate::{error::ZomeApiResult, Dispatch};
-use holochain_core_types::entry::cap_entries::{CapFunctions, CapabilityType};
+use holochain_core_types::entry::cap_entries::{CapFunctions, CapGrantLimits, CapabilityType};
 use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::capabilities::{
     CommitCapabilityClaimArgs, CommitCapabilityGrantArgs,
@@ -17,6 +17,27 @@ pub fn commit_capability_grant<S: Into<String>>(
         cap_type,
         assignees,
         functions,
+        limits: CapGrantLimits::default(),
+    })
+}
+
+/// Adds a capability grant to the local chain that can only be used within the
+/// validity period and for the number of calls given by `limits`.
+/// Like any grant it can be revoked early with [update_entry](fn.update_entry.html)
+/// or [remove_entry](fn.remove_entry.html).
+pub fn commit_capability_grant_with_limits<S: Into<String>>(
+    id: S,
+    cap_type: CapabilityType,
+    assignees: Option<Vec<Address>>,
+    functions: CapFunctions,
+    limits: CapGrantLimits,
+) -> ZomeApiResult<Address> {
+    Dispatch::CommitCapabilityGrant.with_input(CommitCapabilityGrantArgs {
+        id: id.into(),
+        cap_type,
+        assignees,
+        functions,
+        limits,
     })
 }
 
@@ -0,0 +1,33 @@
+use crate::{error::ZomeApiResult, Dispatch};
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::chain_migrate::CloseChainArgs;
+
+/// Closes the source chain of this agent in favour of a chain on the DNA with address
+/// `new_dna_address`, e.g. a newer version of this app.
+/// Commits a `ChainMigrate` entry that records the old and new DNA addresses, the agent and the
+/// given reason, and returns its address.
+///
+/// This is final: once the chain is closed, any further commit to it fails validation.
+/// The agent is expected to continue on the new DNA, which can point back to the old chain
+/// by looking up the `ChainMigrate` entry.
+/// # Examples
+/// ```rust
+/// # extern crate hdk;
+/// # use hdk::error::ZomeApiResult;
+/// # use hdk::holochain_core_types::error::RibosomeEncodingBits;
+/// # use hdk::holochain_core_types::error::RibosomeEncodedValue;
+/// # use hdk::holochain_persistence_api::cas::content::Address;
+/// # #[no_mangle]
+/// # pub fn hc_close_chain(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+///
+/// pub fn handle_upgrade(new_dna_address: Address) -> ZomeApiResult<Address> {
+///     hdk::close_chain(new_dna_address, "moving to v2".into())
+/// }
+/// # fn main() {}
+/// ```
+pub fn close_chain(new_dna_address: Address, reason: String) -> ZomeApiResult<Address> {
+    Dispatch::CloseChain.with_input(CloseChainArgs {
+        new_dna_address,
+        reason,
+    })
+}
@@ -0,0 +1,43 @@
+use crate::{error::ZomeApiResult, Dispatch};
+use holochain_core_types::{entry::Entry, time::Timeout};
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::countersigning::CountersignEntryArgs;
+
+/// Commits the given entry together with the agents in `parties`, each onto their own
+/// source chain, and returns its address.
+///
+/// The other parties get asked to sign the entry over direct messages and validate it like
+/// any entry they commit themselves. Once all of them signed, every other party commits the
+/// identical entry with the signatures of all parties as its provenances, so holders can
+/// check that nobody got added or left out, and the proposing agent commits it last.
+/// If a party refuses or doesn't answer within `timeout`, countersigning gets aborted
+/// and nothing is committed. If a party doesn't commit within another `timeout`, we don't
+/// commit either and the error lists the parties that did.
+/// # Examples
+/// ```rust
+/// # extern crate hdk;
+/// # use hdk::error::ZomeApiResult;
+/// # use hdk::holochain_core_types::entry::Entry;
+/// # use hdk::holochain_core_types::error::RibosomeEncodingBits;
+/// # use hdk::holochain_core_types::error::RibosomeEncodedValue;
+/// # use hdk::holochain_core_types::time::Timeout;
+/// # use hdk::holochain_persistence_api::cas::content::Address;
+/// # #[no_mangle]
+/// # pub fn hc_countersign_entry(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+///
+/// pub fn handle_trade(trade: Entry, seller: Address) -> ZomeApiResult<Address> {
+///     hdk::countersign_entry(&trade, vec![seller], Timeout::new(30000))
+/// }
+/// # fn main() {}
+/// ```
+pub fn countersign_entry(
+    entry: &Entry,
+    parties: Vec<Address>,
+    timeout: Timeout,
+) -> ZomeApiResult<Address> {
+    Dispatch::CountersignEntry.with_input(CountersignEntryArgs {
+        entry: entry.clone(),
+        parties,
+        timeout,
+    })
+}
@@ -10,7 +10,7 @@ use holochain_core_types::{
     dna::capabilities::CapabilityRequest,
     error::{RibosomeEncodedAllocation, RibosomeEncodingBits, ZomeApiInternalResult},
 };
-pub use holochain_wasm_utils::api_serialization::validation::*;
+pub use holochain_wasm_utils::api_serialization::{bundle::BundleOnClose, validation::*};
 use holochain_wasm_utils::{
     api_serialization::ZomeApiGlobals,
     memory::{ribosome::load_ribosome_encoded_json, stack::WasmStack},
@@ -22,7 +22,9 @@ use std::convert::{TryFrom, TryInto};
 mod bundle;
 mod call;
 mod capability;
+mod chain_migrate;
 mod commit_entry;
+mod countersign;
 mod debug;
 mod decrypt;
 mod emit_signal;
@@ -40,13 +42,18 @@ mod send;
 mod sign;
 mod sleep;
 mod update_remove;
+mod validation_receipts;
 mod version;
 
 pub use self::{
     bundle::{close_bundle, start_bundle},
-    call::call,
-    capability::{commit_capability_claim, commit_capability_grant},
+    call::{call, call_with_provenance, sign_call},
+    capability::{
+        commit_capability_claim, commit_capability_grant, commit_capability_grant_with_limits,
+    },
+    chain_migrate::close_chain,
     commit_entry::{commit_entry, commit_entry_result},
+    countersign::countersign_entry,
     debug::debug,
     decrypt::decrypt,
     emit_signal::emit_signal,
@@ -70,6 +77,7 @@ pub use self::{
     sign::{sign, sign_one_time, verify_signature},
     sleep::sleep,
     update_remove::{remove_entry, update_agent, update_entry},
+    validation_receipts::get_validation_receipt_count,
     version::{version, version_hash},
 };
 
@@ -136,8 +144,6 @@ macro_rules! def_api_fns {
         #[allow(dead_code)]
         extern "C" {
             pub(crate) fn hc_property(_: RibosomeEncodingBits) -> RibosomeEncodingBits;
-            pub(crate) fn hc_start_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits;
-            pub(crate) fn hc_close_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits;
             $( pub(crate) fn $function_name (_: RibosomeEncodingBits) -> RibosomeEncodingBits;) *
         }
 
@@ -196,6 +202,11 @@ def_api_fns! {
     hc_commit_capability_grant, CommitCapabilityGrant;
     hc_commit_capability_claim, CommitCapabilityClaim;
     hc_emit_signal, EmitSignal;
+    hc_start_bundle, StartBundle;
+    hc_close_bundle, CloseBundle;
+    hc_close_chain, CloseChain;
+    hc_get_validation_receipt_count, GetValidationReceiptCount;
+    hc_countersign_entry, CountersignEntry;
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -364,9 +375,3 @@ impl Default for GetEntryMask {
 //        Commit,
 //    }
 //}
-
-/// Allowed input for close_bundle()
-pub enum BundleOnClose {
-    Commit,
-    Discard,
-}
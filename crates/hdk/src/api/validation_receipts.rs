@@ -0,0 +1,34 @@
+use crate::{error::ZomeApiResult, Dispatch};
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::validation_receipts::{
+    ValidationReceiptCount, ValidationReceiptCountArgs,
+};
+
+/// Returns the number of distinct validators that confirmed holding the entry at
+/// `entry_address`, which must be an entry this agent authored.
+///
+/// Holders send a signed validation receipt to the author once they validated and stored
+/// an entry, so apps can wait for a couple of receipts before telling users that
+/// a published entry is safely stored in the DHT.
+/// # Examples
+/// ```rust
+/// # extern crate hdk;
+/// # use hdk::error::ZomeApiResult;
+/// # use hdk::holochain_core_types::error::RibosomeEncodingBits;
+/// # use hdk::holochain_core_types::error::RibosomeEncodedValue;
+/// # use hdk::holochain_persistence_api::cas::content::Address;
+/// # #[no_mangle]
+/// # pub fn hc_get_validation_receipt_count(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+///
+/// pub fn handle_is_post_stored(post_address: Address) -> ZomeApiResult<bool> {
+///     Ok(hdk::get_validation_receipt_count(&post_address)? >= 3)
+/// }
+/// # fn main() {}
+/// ```
+pub fn get_validation_receipt_count(entry_address: &Address) -> ZomeApiResult<usize> {
+    Dispatch::GetValidationReceiptCount
+        .with_input(ValidationReceiptCountArgs {
+            entry_address: entry_address.clone(),
+        })
+        .map(|receipts: ValidationReceiptCount| receipts.count)
+}
@@ -457,6 +457,22 @@ mod tests {
         suite(l, MemConnectConfig::default());
     }
 
+    #[test]
+    fn tls_works_pem_certificate() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let mut config = TlsBindConfig::new(TcpBindConfig::default());
+        config.tls_certificate = Some(
+            TlsCertificate::from_pem(
+                cert.serialize_pem().unwrap().as_bytes(),
+                cert.serialize_private_key_pem().as_bytes(),
+            )
+            .unwrap(),
+        );
+        let l: InStreamListenerTls<InStreamListenerTcp> =
+            InStreamListenerTls::bind(&url2!("{}://127.0.0.1:0", SCHEME), config).unwrap();
+        suite(l, TcpConnectConfig::default());
+    }
+
     #[test]
     fn tls_works_tcp() {
         let config = TlsBindConfig::new(TcpBindConfig::default()).fake_certificate();
@@ -35,6 +35,44 @@ impl TlsCertificate {
         }
     }
 
+    /// build a certificate from PEM encoded data, e.g. as issued by a CA
+    /// `certificate_pem` may contain a whole chain, leaf certificate first
+    pub fn from_pem(certificate_pem: &[u8], key_pem: &[u8]) -> std::io::Result<Self> {
+        let to_io = |e: openssl::error::ErrorStack| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}", e))
+        };
+        let key = openssl::pkey::PKey::private_key_from_pem(key_pem).map_err(to_io)?;
+        let mut chain = openssl::x509::X509::stack_from_pem(certificate_pem)
+            .map_err(to_io)?
+            .into_iter();
+        let cert = chain.next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "no certificate in pem")
+        })?;
+        let mut ca = openssl::stack::Stack::new().map_err(to_io)?;
+        for intermediate in chain {
+            ca.push(intermediate).map_err(to_io)?;
+        }
+
+        let passphrase = nanoid::simple();
+        let pkcs12 = openssl::pkcs12::Pkcs12::builder()
+            .ca(ca)
+            .build(&passphrase, "in_stream_tls", &key, &cert)
+            .map_err(to_io)?;
+
+        Ok(Self {
+            pkcs12_data: pkcs12.to_der().map_err(to_io)?,
+            passphrase,
+        })
+    }
+
+    /// read a PEM encoded certificate (chain) and private key from files
+    pub fn from_pem_files<P: AsRef<std::path::Path>>(
+        certificate_file: P,
+        key_file: P,
+    ) -> std::io::Result<Self> {
+        Self::from_pem(&std::fs::read(certificate_file)?, &std::fs::read(key_file)?)
+    }
+
     /// WARNING - do not use this with any sensitive data
     ///         - the private key is PUBLIC
     /// use a pre-generated fake certificate
@@ -52,6 +52,11 @@ impl AspectMap {
             .insert(entry_aspect_address);
     }
 
+    /// Removes the entry with all its aspects, returning the aspects if it was there
+    pub fn remove(&mut self, entry_address: &EntryHash) -> Option<AspectSet> {
+        self.0.remove(entry_address)
+    }
+
     pub fn entry_addresses(&self) -> impl Iterator<Item = &EntryHash> {
         self.0.keys()
     }
@@ -31,6 +31,7 @@ pub mod in_memory;
 pub mod lib3h_worker;
 pub mod p2p_config;
 pub mod p2p_network;
+pub mod sharding;
 pub mod sim2h_worker;
 pub mod tweetlog;
 
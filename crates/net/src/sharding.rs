@@ -0,0 +1,58 @@
+//! Locations of agents and entries in the sharding arc, computed the same way
+//! sim2h does so that a node can tell which of the entries it holds are furthest
+//! away from its own location.
+
+use lib3h::rrdht_util::{calc_location_for_id, Location};
+use lib3h_crypto_api::CryptoSystem;
+use lib3h_protocol::types::EntryHash;
+
+lazy_static! {
+    static ref CRYPTO: Box<dyn CryptoSystem> = Box::new(lib3h_sodium::SodiumCryptoSystem::new());
+}
+
+/// The location of an agent id or an entry address. Ids that lib3h can't
+/// convert get hashed into an hcid first, like sim2h does for entry addresses.
+pub fn location(id: &str) -> Location {
+    match calc_location_for_id(&CRYPTO, id) {
+        Ok(loc) => loc,
+        Err(_) => {
+            let mut hash = CRYPTO.buf_new_insecure(CRYPTO.hash_sha256_bytes());
+            let r: Box<dyn lib3h_crypto_api::Buffer> = Box::new(id.as_bytes().to_vec());
+            CRYPTO.hash_sha256(&mut hash, &r).unwrap();
+            calc_location_for_id(
+                &CRYPTO,
+                &hcid::HcidEncoding::with_kind("hcs0")
+                    .unwrap()
+                    .encode(&hash)
+                    .unwrap(),
+            )
+            .unwrap()
+        }
+    }
+}
+
+/// How far forward the entry is from the agent in the arc. sim2h asks the agents
+/// with the smallest distances to hold an entry.
+pub fn entry_distance(agent_id: &str, entry_address: &EntryHash) -> u32 {
+    let entry_address: String = entry_address.clone().into();
+    location(agent_id).forward_distance_to(location(&entry_address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_distance() {
+        let agent_id = "HcScic3VAmEP9ucmrw4MMFKVARIvvdn43k6xC5r5FYe7qkrnY6X7tpUm8jkbxbr";
+        assert_eq!(entry_distance("test", &EntryHash::from("test")), 0);
+        assert_eq!(
+            entry_distance(agent_id, &EntryHash::from("test")),
+            entry_distance(agent_id, &EntryHash::from("test"))
+        );
+        assert_ne!(
+            entry_distance(agent_id, &EntryHash::from("test")),
+            entry_distance(agent_id, &EntryHash::from("other"))
+        );
+    }
+}
@@ -825,7 +825,13 @@ fn spawn_handle_message_list_data(
     // just iter/send we don't need a new task for this
 
     for (entry_hash, aspects) in list_data.address_map {
+        // an empty aspect list means the agent evicted the entry
         if aspects.is_empty() {
+            sim2h_handle.state().spawn_agent_drops_entry(
+                (&*space_hash).clone(),
+                signer.clone(),
+                entry_hash,
+            );
             continue;
         }
 
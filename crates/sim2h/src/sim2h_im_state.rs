@@ -72,6 +72,15 @@ enum AolEntry {
         aspects: im::HashSet<AspectHash>,
     },
 
+    // this agent told us it no longer holds any aspect of this entry
+    // (i.e. it evicted the entry to stay within its storage quota)
+    AgentDropsEntry {
+        aol_idx: u64,
+        space_hash: SpaceHash,
+        agent_id: AgentId,
+        entry_hash: EntryHash,
+    },
+
     // our owner is ready to do a round of gossip
     // check to see if any of our agents are ready to check
     // for missing aspects they need to be holding
@@ -460,6 +469,15 @@ impl Space {
         }
     }
 
+    fn agent_drops_entry(&mut self, agent_id: &AgentId, entry_hash: &EntryHash) {
+        let agent_id = self.get_mono_agent_id(agent_id);
+        if let Some(entry) = self.entry_to_all_aspects.get_mut(entry_hash) {
+            for holding_set in entry.aspects.iter_mut() {
+                holding_set.remove(&agent_id);
+            }
+        }
+    }
+
     fn clear_holding(&mut self, agent_id: &AgentId) {
         for entry in self.entry_to_all_aspects.iter_mut() {
             for holding_set in entry.aspects.iter_mut() {
@@ -615,6 +633,12 @@ impl Store {
                 aspects,
                 ..
             } => self.agent_holds_aspects(space_hash, agent_id, entry_hash, aspects),
+            AolEntry::AgentDropsEntry {
+                space_hash,
+                agent_id,
+                entry_hash,
+                ..
+            } => self.agent_drops_entry(space_hash, agent_id, entry_hash),
             AolEntry::CheckGossip { response, .. } => self.check_gossip(response),
             AolEntry::CheckDisconnected { response, .. } => self.check_disconnected(response),
         }
@@ -694,6 +718,16 @@ impl Store {
             .agent_holds_aspects(&agent_id, &entry_hash, &aspects);
     }
 
+    fn agent_drops_entry(
+        &mut self,
+        space_hash: SpaceHash,
+        agent_id: AgentId,
+        entry_hash: EntryHash,
+    ) {
+        self.get_space_mut(space_hash)
+            .agent_drops_entry(&agent_id, &entry_hash);
+    }
+
     #[tracing::instrument(skip(self, response))]
     fn check_gossip(&mut self, response: tokio::sync::oneshot::Sender<CheckGossipData>) {
         let mut check_gossip_data = CheckGossipData::new();
@@ -1014,6 +1048,43 @@ impl StoreHandle {
         tokio::task::spawn(f);
     }
 
+    #[must_use]
+    #[allow(clippy::redundant_pattern_matching)]
+    pub fn agent_drops_entry(
+        &self,
+        space_hash: SpaceHash,
+        agent_id: AgentId,
+        entry_hash: EntryHash,
+    ) -> BoxFuture<'static, ()> {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        if let Err(_) = self.send_mut.send(StoreProto::Mutate(
+            AolEntry::AgentDropsEntry {
+                aol_idx: self.con_incr.inc(),
+                space_hash,
+                agent_id,
+                entry_hash,
+            },
+            sender,
+        )) {
+            error!("failed to send im store message - shutting down?");
+            return async {}.boxed();
+        }
+        async move {
+            let _ = receiver.await;
+        }
+        .boxed()
+    }
+
+    pub fn spawn_agent_drops_entry(
+        &self,
+        space_hash: SpaceHash,
+        agent_id: AgentId,
+        entry_hash: EntryHash,
+    ) {
+        let f = self.agent_drops_entry(space_hash, agent_id, entry_hash);
+        tokio::task::spawn(f);
+    }
+
     #[tracing::instrument(skip(self))]
     #[allow(clippy::redundant_pattern_matching)]
     pub async fn check_gossip(&self) -> CheckGossipData {
@@ -1230,6 +1301,73 @@ mod tests {
         async_run(async_same_aspect_in_differing_entries_test().boxed());
     }
 
+    async fn async_agent_drops_entry_test() {
+        let aid1 = gen_agent();
+
+        let space_hash: SpaceHash = "abcd".into();
+        let entry_hash_1: EntryHash = "test1".into();
+        let entry_hash_2: EntryHash = "test2".into();
+        let aspect_hash_1: AspectHash = "one".into();
+        let aspect_hash_2: AspectHash = "two".into();
+        let uri1: Lib3hUri = url::Url::parse("ws://yada1").unwrap().into();
+
+        let crypto = Box::new(lib3h_sodium::SodiumCryptoSystem::new());
+        let store = Store::new(
+            crypto,
+            0,       // FULL SYNC
+            Some(6), // set a nice/short 6ms gossip interval for testing : )
+        );
+
+        store
+            .new_connection(space_hash.clone(), aid1.clone(), uri1.clone())
+            .await;
+
+        store
+            .agent_holds_aspects(
+                space_hash.clone(),
+                aid1.clone(),
+                entry_hash_1.clone(),
+                im::hashset! {aspect_hash_1.clone()},
+            )
+            .await;
+
+        store
+            .agent_holds_aspects(
+                space_hash.clone(),
+                aid1.clone(),
+                entry_hash_2.clone(),
+                im::hashset! {aspect_hash_2.clone()},
+            )
+            .await;
+
+        store
+            .agent_drops_entry(space_hash.clone(), aid1.clone(), entry_hash_1.clone())
+            .await;
+
+        let state = store.get_clone().await;
+        debug!("GOT: {:#?}", state);
+
+        let space = state.spaces.get(&space_hash).unwrap();
+
+        let holding = |entry_hash: &EntryHash, aspect_hash: &AspectHash| {
+            space
+                .entry_to_all_aspects
+                .get(entry_hash)
+                .unwrap()
+                .aspects
+                .get(aspect_hash)
+                .unwrap()
+                .contains(&aid1)
+        };
+        assert!(!holding(&entry_hash_1, &aspect_hash_1));
+        assert!(holding(&entry_hash_2, &aspect_hash_2));
+    }
+
+    #[test]
+    fn agent_drops_entry_test() {
+        async_run(async_agent_drops_entry_test().boxed());
+    }
+
     async fn async_gossip_test() {
         let aid1 = gen_agent();
         let aid2 = gen_agent();
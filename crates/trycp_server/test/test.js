@@ -67,6 +67,7 @@ trace = true
 admin = true
 id = "someadminid"
 instances = []
+bind_address = "0.0.0.0"
     [interfaces.driver]
     type = "websocket"
     port = 1112
@@ -75,6 +76,7 @@ instances = []
 admin = true
 id = "somednaid"
 instances = []
+bind_address = "0.0.0.0"
     [interfaces.driver]
     type = "websocket"
     port = 1111
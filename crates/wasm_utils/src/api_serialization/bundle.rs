@@ -0,0 +1,27 @@
+use holochain_core_types::time::Timeout;
+use holochain_json_api::{error::JsonError, json::*};
+
+/// Struct for input data received when Zome API function start_bundle() is invoked
+#[derive(Deserialize, Clone, PartialEq, Debug, Serialize, DefaultJson)]
+pub struct StartBundleArgs {
+    /// How long the bundle may stay open. Closing it with `BundleOnClose::Commit`
+    /// after that fails and discards the bundle.
+    pub timeout: Timeout,
+    /// Arbitrary data the zome wants to keep with the bundle
+    pub user_param: JsonString,
+}
+
+/// Allowed input for close_bundle()
+#[derive(Deserialize, Clone, PartialEq, Debug, Serialize, DefaultJson)]
+pub enum BundleOnClose {
+    /// Validate all bundled entries and commit them if (and only if) every one of them is valid
+    Commit,
+    /// Throw away all bundled entries
+    Discard,
+}
+
+/// Struct for input data received when Zome API function close_bundle() is invoked
+#[derive(Deserialize, Clone, PartialEq, Debug, Serialize, DefaultJson)]
+pub struct CloseBundleArgs {
+    pub action: BundleOnClose,
+}
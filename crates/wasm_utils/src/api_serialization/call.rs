@@ -1,3 +1,4 @@
+use holochain_core_types::signature::Provenance;
 use holochain_json_api::{error::JsonError, json::*};
 use holochain_persistence_api::cas::content::Address;
 
@@ -11,4 +12,8 @@ pub struct ZomeFnCallArgs {
     pub cap_token: Address,
     pub fn_name: String,
     pub fn_args: String,
+    /// Signature of the caller over function name and arguments (see `hdk::sign_call()`).
+    /// Without it the call gets signed by the agent of the calling instance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
 }
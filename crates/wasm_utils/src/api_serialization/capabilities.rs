@@ -1,7 +1,7 @@
 use holochain_json_api::{error::JsonError, json::*};
 use holochain_persistence_api::cas::content::Address;
 
-use holochain_core_types::entry::cap_entries::{CapFunctions, CapabilityType};
+use holochain_core_types::entry::cap_entries::{CapFunctions, CapGrantLimits, CapabilityType};
 
 // arguments required for calling commit_capability_grant
 #[derive(Deserialize, Default, Debug, Serialize, DefaultJson)]
@@ -10,6 +10,8 @@ pub struct CommitCapabilityGrantArgs {
     pub cap_type: CapabilityType,
     pub assignees: Option<Vec<Address>>,
     pub functions: CapFunctions,
+    #[serde(default)]
+    pub limits: CapGrantLimits,
 }
 
 // arguments required for calling commit_capability_claim
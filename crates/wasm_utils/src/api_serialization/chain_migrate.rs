@@ -0,0 +1,10 @@
+use holochain_json_api::{error::JsonError, json::*};
+use holochain_persistence_api::cas::content::Address;
+
+/// Struct for input data received when Zome API function close_chain() is invoked
+#[derive(Deserialize, Clone, PartialEq, Debug, Serialize, DefaultJson)]
+pub struct CloseChainArgs {
+    /// Address of the DNA the agent continues with
+    pub new_dna_address: Address,
+    pub reason: String,
+}
@@ -7,23 +7,42 @@ use holochain_persistence_api::cas::content::Address;
 #[derive(Deserialize, Debug, Serialize, DefaultJson, PartialEq, Clone)]
 pub struct CommitEntryOptions {
     pub provenance: Vec<Provenance>,
+    /// Agents an entry of an encrypted entry type gets encrypted for,
+    /// in addition to the author and the recipients given in the entry definition
+    #[serde(default)]
+    pub recipients: Vec<Address>,
 }
 
 impl Default for CommitEntryOptions {
     /// The default CommitEntryOptions has no additional provenance.
     fn default() -> Self {
-        CommitEntryOptions { provenance: vec![] }
+        CommitEntryOptions {
+            provenance: vec![],
+            recipients: vec![],
+        }
     }
 }
 
 impl CommitEntryOptions {
     pub fn new(provenance: Vec<Provenance>) -> Self {
-        Self { provenance }
+        Self {
+            provenance,
+            recipients: vec![],
+        }
+    }
+
+    pub fn with_recipients(mut self, recipients: Vec<Address>) -> Self {
+        self.recipients = recipients;
+        self
     }
 
     pub fn provenance(&self) -> Vec<Provenance> {
         self.provenance.clone()
     }
+
+    pub fn recipients(&self) -> Vec<Address> {
+        self.recipients.clone()
+    }
 }
 
 /// The arguments required to execute a commit_entry_result() call.
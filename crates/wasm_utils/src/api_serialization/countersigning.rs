@@ -0,0 +1,13 @@
+use holochain_core_types::{entry::Entry, time::Timeout};
+use holochain_json_api::{error::JsonError, json::*};
+use holochain_persistence_api::cas::content::Address;
+
+/// Struct for input data received when Zome API function countersign_entry() is invoked
+#[derive(Deserialize, Clone, PartialEq, Debug, Serialize, DefaultJson)]
+pub struct CountersignEntryArgs {
+    pub entry: Entry,
+    /// The other agents that have to sign and commit the entry
+    pub parties: Vec<Address>,
+    /// How long to wait for the other parties
+    pub timeout: Timeout,
+}
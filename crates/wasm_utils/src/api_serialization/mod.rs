@@ -9,8 +9,11 @@ mod call;
 ///
 /// For the case of HDK-rust we can use the exact same types by
 /// importing this module.
+pub mod bundle;
 pub mod capabilities;
+pub mod chain_migrate;
 pub mod commit_entry;
+pub mod countersigning;
 pub mod crypto;
 pub mod emit_signal;
 pub mod get_entry;
@@ -24,6 +27,7 @@ pub mod send;
 pub mod sign;
 mod update_entry;
 pub mod validation;
+pub mod validation_receipts;
 pub mod verify_signature;
 mod zome_api_globals;
 
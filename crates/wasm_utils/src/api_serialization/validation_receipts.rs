@@ -0,0 +1,15 @@
+use holochain_json_api::{error::JsonError, json::*};
+use holochain_persistence_api::cas::content::Address;
+
+/// Struct for input data received when Zome API function get_validation_receipt_count() is invoked
+#[derive(Deserialize, Clone, PartialEq, Debug, Serialize, DefaultJson)]
+pub struct ValidationReceiptCountArgs {
+    /// Address of an entry this agent authored
+    pub entry_address: Address,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug, Serialize, DefaultJson)]
+pub struct ValidationReceiptCount {
+    /// Number of distinct validators that sent a receipt for the entry
+    pub count: usize,
+}
@@ -1,4 +1,4 @@
-use holochain_core_types::agent::AgentId;
+use holochain_core_types::{agent::AgentId, entry::encrypted_entry::SealedData};
 use holochain_dpki::{key_bundle::KeyBundle, SEED_SIZE};
 use holochain_locksmith::Mutex;
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
@@ -30,8 +30,10 @@ pub fn registered_test_agent<S: Into<String>>(nick: S) -> AgentId {
         .expect("SecBuf must be writeable");
 
     // Create KeyBundle from seed
-    let keybundle = KeyBundle::new_from_seed_buf(&mut seed).unwrap();
-    let agent_id = AgentId::new(&nick, keybundle.get_id());
+    let mut keybundle = KeyBundle::new_from_seed_buf(&mut seed).unwrap();
+    let enc_key_signature = keybundle.sign_enc_key().unwrap();
+    let agent_id = AgentId::new(&nick, keybundle.get_id())
+        .with_enc_key(keybundle.enc_keys.public.clone(), enc_key_signature);
 
     // Register key in static TEST_AGENT_KEYS
     TEST_AGENT_KEYBUNDLES
@@ -148,6 +150,44 @@ pub fn mock_decrypt(payload: String, agent_id: &AgentId) -> String {
         .unwrap()
 }
 
+fn with_test_keybundle<T, F: FnOnce(&mut KeyBundle) -> T>(agent_id: &AgentId, f: F) -> T {
+    TEST_AGENT_KEYBUNDLES
+        .lock()
+        .unwrap()
+        .get(&agent_id.address())
+        .expect(
+            format!(
+                "Agent {:?} not found in mock registry. \
+                 Test agent keys need to be registered first.",
+                agent_id
+            )
+            .as_str(),
+        )
+        .lock()
+        .map(|mut keybundle| f(&mut keybundle))
+        .unwrap()
+}
+
+/// This is a local mock for the `agent/encrypt_for` conductor API function.
+pub fn mock_encrypt_for(payload: String, recipients: &[String], agent_id: &AgentId) -> SealedData {
+    with_test_keybundle(agent_id, |keybundle| {
+        let mut message = SecBuf::with_insecure_from_string(payload);
+        keybundle
+            .encrypt_for(&mut message, recipients)
+            .expect("Mock encryption failed.")
+    })
+}
+
+/// This is a local mock for the `agent/decrypt_sealed` conductor API function.
+pub fn mock_decrypt_sealed(sealed: &SealedData, agent_id: &AgentId) -> Option<String> {
+    with_test_keybundle(agent_id, |keybundle| {
+        keybundle.decrypt_sealed(sealed).ok().map(|mut decrypted| {
+            let decrypted = decrypted.read_lock();
+            std::str::from_utf8(&*decrypted).unwrap().to_string()
+        })
+    })
+}
+
 /// Wraps `fn mock_signer(String) -> String` in an `IoHandler` to mock the conductor API
 /// in a way that core can safely assume the conductor API to be present with at least
 /// the `agent/sign` method.
@@ -223,5 +263,31 @@ pub fn mock_conductor_api(agent_id: AgentId) -> IoHandler {
 
         Ok(json!({"payload": payload, "message": mock_decrypt(payload, &agent_id)}))
     });
+
+    let encrypt_for_agent = agent_id.clone();
+    handler.add_method("agent/encrypt_for", move |params: Params| {
+        let params: serde_json::Value = params.parse()?;
+        let payload = params["payload"]
+            .as_str()
+            .and_then(|payload| base64::decode(payload).ok())
+            .and_then(|payload| String::from_utf8(payload).ok())
+            .ok_or(jsonrpc_core::Error::invalid_params("invalid `payload`"))?;
+        let recipients: Vec<String> = serde_json::from_value(params["recipients"].clone())
+            .map_err(|_| jsonrpc_core::Error::invalid_params("invalid `recipients`"))?;
+        let sealed = mock_encrypt_for(payload, &recipients, &encrypt_for_agent);
+        Ok(json!({ "sealed": serde_json::to_string(&sealed).unwrap() }))
+    });
+
+    let decrypt_sealed_agent = agent_id.clone();
+    handler.add_method("agent/decrypt_sealed", move |params: Params| {
+        let params: serde_json::Value = params.parse()?;
+        let sealed: SealedData = params["sealed"]
+            .as_str()
+            .and_then(|sealed| serde_json::from_str(sealed).ok())
+            .ok_or(jsonrpc_core::Error::invalid_params("invalid `sealed`"))?;
+        let message = mock_decrypt_sealed(&sealed, &decrypt_sealed_agent)
+            .ok_or(jsonrpc_core::Error::invalid_params("not a recipient"))?;
+        Ok(json!({ "message": message }))
+    });
     handler
 }